@@ -0,0 +1,227 @@
+//! A robot that patrols between waypoints, returning to its dock to recharge
+//! whenever its battery runs low.
+//!
+//! This example shows how a reactive guard can preempt a long-running branch,
+//! how trees can be split into reusable subtrees, how nodes can pass values to
+//! each other and react to them through the blackboard, and how the monitoring
+//! tools can report on a running tree.
+#[macro_use]
+extern crate aspen;
+
+use aspen::{
+    blackboard::Blackboard,
+    monitor::{preorder, BatchingMonitor, HeartbeatMonitor, Transition},
+    node::{Node, Tickable},
+    stats::LatencyMonitor,
+    std_nodes::{InlineAction, Sequence},
+    BehaviorTree, Status,
+};
+use std::time::Duration;
+
+/// The position of the charging dock.
+const DOCK: i32 = 0;
+
+/// The battery level below which the robot goes back to the dock.
+const LOW_BATTERY: u32 = 30;
+
+/// How much battery a single step of movement uses.
+const STEP_COST: u32 = 2;
+
+/// How much battery a single tick on the dock restores.
+const CHARGE_RATE: u32 = 25;
+
+/// The blackboard entry holding the position the robot is driving to.
+const GOAL: &str = "goal";
+
+/// The state of the robot.
+#[derive(Debug)]
+struct Robot {
+    /// Position along the patrol route.
+    position: i32,
+
+    /// Battery charge, as a percentage.
+    battery: u32,
+
+    /// Whether or not the robot has committed to recharging.
+    charging: bool,
+
+    /// The number of times the robot has recharged.
+    recharges: u32,
+
+    /// The number of new goals the robot has announced.
+    announced: u32,
+
+    /// Values shared between the nodes of the tree.
+    blackboard: Blackboard,
+}
+impl AsRef<Blackboard> for Robot {
+    fn as_ref(&self) -> &Blackboard {
+        &self.blackboard
+    }
+}
+impl AsMut<Blackboard> for Robot {
+    fn as_mut(&mut self) -> &mut Blackboard {
+        &mut self.blackboard
+    }
+}
+
+fn main() {
+    // The guard is checked on every tick. While the battery is fine it
+    // succeeds immediately and the patrol continues, but once the battery is
+    // low it preempts the patrol until the robot has recharged. Before either,
+    // the robot announces any goal which was set on the previous tick
+    let root = ActiveSequence! {
+        announce_goal(),
+        battery_guard(),
+        Repeat! { 2, patrol(&[4, -4, DOCK]) }.named(Some("Patrol"))
+    }
+    .into_node();
+    let mut tree = BehaviorTree::new(root).named(Some("patrol-and-recharge"));
+    println!("{}\n", tree);
+
+    // Every read of the goal is matched by a write
+    let warnings = tree.validate();
+    assert!(warnings.is_empty(), "{:?}", warnings);
+
+    // Map node identifiers to names so that the transitions are readable. Only
+    // the nodes which were explicitly named are reported
+    let names: Vec<Option<String>> = preorder(tree.root())
+        .into_iter()
+        .map(|n| Some(n.name().to_string()).filter(|name| name != n.type_name()))
+        .collect();
+
+    let mut transitions = BatchingMonitor::new(Duration::from_secs(0), |batch: &[Transition]| {
+        for t in batch {
+            if let Some(ref name) = names[t.node] {
+                println!("  {}: {:?} -> {:?}", name, t.from, t.to);
+            }
+        }
+    });
+    let mut heartbeat = HeartbeatMonitor::new(Duration::from_millis(1), |h: &_| {
+        println!("Heartbeat: {:?}", h);
+    });
+    let mut latencies = LatencyMonitor::new();
+
+    tree.on_complete(|summary| {
+        println!(
+            "\nMission finished after {} ticks: {:?}",
+            summary.ticks, summary.status
+        );
+    });
+
+    let mut robot = Robot {
+        position: DOCK,
+        battery: 40,
+        charging: false,
+        recharges: 0,
+        announced: 0,
+        blackboard: Blackboard::new(),
+    };
+    let status = tree.run(
+        f64::INFINITY,
+        &mut robot,
+        Some(|t: &BehaviorTree<Robot>| {
+            transitions.observe(t);
+            heartbeat.observe(t);
+            latencies.observe(t);
+        }),
+    );
+    drop(transitions);
+    drop(tree);
+
+    println!("{:?}", robot);
+    for (node, histogram) in latencies.types() {
+        println!(
+            "{:>14}: {:>4} ticks, p99 {:?}",
+            node,
+            histogram.count(),
+            histogram.quantile(0.99).unwrap()
+        );
+    }
+
+    // This example doubles as an integration test
+    assert_eq!(status, Status::Succeeded);
+    assert!(robot.recharges >= 1);
+    assert_eq!(robot.position, DOCK);
+    assert!(robot.announced >= 6);
+    assert_eq!(robot.blackboard.get::<i32>(GOAL), Some(&DOCK));
+}
+
+/// A subtree that always succeeds, announcing the goal whenever it changes.
+fn announce_goal() -> Node<'static, Robot> {
+    AlwaysSucceed! {
+        ActiveSequence! {
+            OnBlackboardChange!(GOAL),
+            InlineAction!{ "Announce", |r: &mut Robot| {
+                match r.blackboard.get::<i32>(GOAL) {
+                    Some(goal) => println!("New goal: {}", goal),
+                    None => println!("Goal cleared"),
+                }
+                r.announced += 1;
+                Status::Succeeded
+            } }
+        }
+        .into_node()
+    }
+    .named(Some("AnnounceGoal"))
+}
+
+/// A subtree that succeeds while the battery is fine, and otherwise drives
+/// the robot to its dock and charges it until the battery is full.
+fn battery_guard() -> Node<'static, Robot> {
+    Selector! {
+        Condition!{ "BatteryOk", |r: &Robot| !r.charging && r.battery >= LOW_BATTERY },
+        Sequence! {
+            InlineAction!{ |r: &mut Robot| { r.charging = true; Status::Succeeded } },
+            go_to(DOCK),
+            InlineAction!{ "Recharge", recharge }
+        }
+    }
+    .named(Some("BatteryGuard"))
+}
+
+/// A subtree that visits each of the waypoints in order.
+fn patrol(waypoints: &[i32]) -> Node<'static, Robot> {
+    Sequence::new(waypoints.iter().map(|&w| go_to(w)).collect()).named(Some("Lap"))
+}
+
+/// A subtree that sets `target` as the goal, then drives there.
+fn go_to(target: i32) -> Node<'static, Robot> {
+    let set_goal = InlineAction::new(move |r: &mut Robot| {
+        r.blackboard.set(GOAL, target);
+        Status::Succeeded
+    })
+    .writes::<i32, _>(GOAL);
+    let drive = InlineAction::new(drive).reads::<i32, _>(GOAL);
+    Sequence::new(vec![set_goal, drive]).named(Some(format!("GoTo({})", target)))
+}
+
+/// Moves the robot one step towards the goal on the blackboard each tick.
+fn drive(r: &mut Robot) -> Status {
+    let target = match r.blackboard.get::<i32>(GOAL) {
+        Some(&target) => target,
+        None => return Status::Failed,
+    };
+    if r.position == target {
+        return Status::Succeeded;
+    }
+    if r.battery < STEP_COST {
+        return Status::Failed;
+    }
+
+    r.position += (target - r.position).signum();
+    r.battery -= STEP_COST;
+    Status::Running
+}
+
+/// Charges the robot until the battery is full.
+fn recharge(r: &mut Robot) -> Status {
+    r.battery = (r.battery + CHARGE_RATE).min(100);
+    if r.battery < 100 {
+        return Status::Running;
+    }
+
+    r.charging = false;
+    r.recharges += 1;
+    Status::Succeeded
+}
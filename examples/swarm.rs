@@ -0,0 +1,224 @@
+//! A thousand game agents driven by a single tree definition.
+//!
+//! Every agent gets its own `BehaviorTree` built from the same definition
+//! function. The expensive, read-only parts of the definition (here the tuning
+//! parameters) are shared between all of the trees behind an `Arc`, so each
+//! instance only carries the state it needs to resume between ticks. The
+//! example reports how much memory each instance needs and how long it takes
+//! to tick the whole swarm, and fails if an instance grows beyond a fixed
+//! budget.
+//!
+//! Run it in release mode for meaningful timings:
+//!
+//! ```text
+//! cargo run --release --example swarm
+//! ```
+#[macro_use]
+extern crate aspen;
+
+use aspen::{
+    node::{Node, Tickable},
+    std_nodes::InlineAction,
+    BehaviorTree, Status,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// The number of agents in the swarm.
+const AGENTS: usize = 1_000;
+
+/// The number of frames to simulate.
+const FRAMES: usize = 600;
+
+/// The most memory, in bytes, that a single tree instance may use.
+const INSTANCE_BUDGET: usize = 4 * 1024;
+
+/// An allocator which keeps track of how many bytes are currently allocated.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Parameters shared by every agent.
+#[derive(Debug)]
+struct Tuning {
+    /// Hunger above which an agent goes looking for food.
+    hungry: u32,
+
+    /// Distance at which an agent notices the predator.
+    sight: i32,
+
+    /// The position of the food.
+    food: i32,
+}
+
+/// The state of a single agent.
+#[derive(Debug, Default)]
+struct Agent {
+    /// Position in the world.
+    position: i32,
+
+    /// How hungry the agent is.
+    hunger: u32,
+
+    /// The position of the predator, as seen by this agent.
+    predator: i32,
+
+    /// State for the agent's random walk.
+    rng: u32,
+
+    /// How many times the agent has eaten.
+    meals: u32,
+}
+impl Agent {
+    /// Creates an agent with its own random seed.
+    fn new(seed: u32) -> Self {
+        Agent {
+            position: (seed % 200) as i32 - 100,
+            rng: seed.wrapping_mul(2_654_435_761) | 1,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a pseudo-random step of -1, 0, or 1.
+    fn random_step(&mut self) -> i32 {
+        // Xorshift
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        (self.rng % 3) as i32 - 1
+    }
+
+    /// Advances the parts of the world that the agent does not control.
+    fn update(&mut self, frame: usize) {
+        self.hunger += 1;
+        self.predator = ((frame as i32 / 4) % 300) - 150;
+    }
+}
+
+/// The tree definition shared by every agent.
+///
+/// Each call builds a new instance. The closures only capture a pointer to the
+/// shared tuning, so the per-instance cost is the nodes themselves.
+fn definition(tuning: &Arc<Tuning>) -> Node<'static, Agent> {
+    let (t1, t2, t3, t4) = (
+        Arc::clone(tuning),
+        Arc::clone(tuning),
+        Arc::clone(tuning),
+        Arc::clone(tuning),
+    );
+    Selector! {
+        // Fleeing lasts only as long as the predator is in sight
+        ActiveSequence! {
+            Condition!{ move |a: &Agent| (a.predator - a.position).abs() < t1.sight },
+            InlineAction!{ |a: &mut Agent| {
+                a.position -= (a.predator - a.position).signum();
+                Status::Running
+            }}
+        }
+        .into_node(),
+        ActiveSequence! {
+            Condition!{ move |a: &Agent| a.hunger > t2.hungry },
+            move_to(move |_| t3.food),
+            InlineAction!{ |a: &mut Agent| {
+                a.hunger = 0;
+                a.meals += 1;
+                Status::Succeeded
+            }}
+        }
+        .into_node(),
+        InlineAction!{ move |a: &mut Agent| {
+            a.position += a.random_step();
+            if a.position == t4.food {
+                a.position += 1;
+            }
+            Status::Running
+        }}
+    }
+}
+
+/// A leaf that moves the agent one step per tick towards a target.
+fn move_to<F>(target: F) -> Node<'static, Agent>
+where
+    F: Fn(&Agent) -> i32 + 'static,
+{
+    InlineAction::new(move |a: &mut Agent| {
+        let target = target(a);
+        if a.position == target {
+            return Status::Succeeded;
+        }
+
+        a.position += (target - a.position).signum();
+        Status::Running
+    })
+}
+
+fn main() {
+    let tuning = Arc::new(Tuning {
+        hungry: 150,
+        sight: 10,
+        food: 0,
+    });
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let mut trees: Vec<_> = (0..AGENTS)
+        .map(|_| BehaviorTree::new(definition(&tuning)))
+        .collect();
+    let per_instance = (ALLOCATED.load(Ordering::Relaxed) - before) / AGENTS;
+    println!("{}\n", trees[0]);
+    println!("{} bytes per tree instance", per_instance);
+
+    let mut agents: Vec<_> = (0..AGENTS as u32).map(Agent::new).collect();
+
+    let start = Instant::now();
+    for frame in 0..FRAMES {
+        for (tree, agent) in trees.iter_mut().zip(&mut agents) {
+            agent.update(frame);
+            // An agent which finishes a meal goes back to its normal routine
+            if tree.tick(agent).is_done() {
+                tree.reset();
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let ticks = AGENTS * FRAMES;
+    let meals: u32 = agents.iter().map(|a| a.meals).sum();
+    println!(
+        "{} ticks in {:?} ({:.0} ns per agent, {:.2} ms per frame)",
+        ticks,
+        elapsed,
+        elapsed.as_nanos() as f64 / ticks as f64,
+        elapsed.as_secs_f64() * 1e3 / FRAMES as f64,
+    );
+    println!("{} meals eaten", meals);
+
+    // This example doubles as a regression test for the size of an instance
+    assert!(
+        per_instance <= INSTANCE_BUDGET,
+        "a tree instance uses {} bytes, which is over the budget of {}",
+        per_instance,
+        INSTANCE_BUDGET
+    );
+    assert!(meals > 0);
+}
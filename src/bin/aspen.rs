@@ -0,0 +1,137 @@
+//! Tools for working with behavior trees.
+//!
+//! ```text
+//! aspen replay <trace> [--speed <factor>x] [--render tui|dot-frames] [--out <dir>]
+//! ```
+//!
+//! The `replay` command re-animates a trace written by a `TraceRecorder`. The
+//! `tui` renderer, which is the default, redraws the tree in the terminal at
+//! the recorded pace, divided by the speed. The `dot-frames` renderer writes
+//! one DOT graph per tick into the output directory, which defaults to
+//! `frames`, for turning into a video with Graphviz.
+use aspen::trace::Trace;
+use std::{env, fs, path::PathBuf, process, thread, time::Duration};
+
+/// The time between ticks of traces which did not record it.
+const DEFAULT_PERIOD: Duration = Duration::from_millis(100);
+
+/// How a replay is rendered.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Render {
+    /// Redraw the tree in the terminal.
+    Tui,
+
+    /// Write a DOT graph for each tick.
+    DotFrames,
+}
+
+/// The options of the `replay` command.
+struct Replay {
+    /// The trace file.
+    trace: PathBuf,
+
+    /// How many times faster than recorded to replay.
+    speed: f64,
+
+    /// How to render the replay.
+    render: Render,
+
+    /// Where DOT frames are written.
+    out: PathBuf,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("replay") => parse_replay(&args[1..]).and_then(|r| replay(&r)),
+        _ => Err(usage()),
+    };
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        process::exit(2);
+    }
+}
+
+/// Returns the usage message.
+fn usage() -> String {
+    "usage: aspen replay <trace> [--speed <factor>x] [--render tui|dot-frames] [--out <dir>]"
+        .to_owned()
+}
+
+/// Reads the options of the `replay` command.
+fn parse_replay(args: &[String]) -> Result<Replay, String> {
+    let mut trace = None;
+    let mut replay = Replay {
+        trace: PathBuf::new(),
+        speed: 1.0,
+        render: Render::Tui,
+        out: PathBuf::from("frames"),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(usage);
+        match arg.as_str() {
+            "--speed" => {
+                let speed = value()?;
+                replay.speed = match speed.trim_end_matches('x').parse() {
+                    Ok(s) if s > 0.0 => s,
+                    _ => return Err(format!("invalid speed {}", speed)),
+                };
+            }
+            "--render" => {
+                replay.render = match value()?.as_str() {
+                    "tui" => Render::Tui,
+                    "dot-frames" => Render::DotFrames,
+                    other => return Err(format!("unknown renderer {}", other)),
+                };
+            }
+            "--out" => replay.out = PathBuf::from(value()?),
+            path if trace.is_none() && !path.starts_with("--") => trace = Some(path),
+            _ => return Err(usage()),
+        }
+    }
+
+    replay.trace = PathBuf::from(trace.ok_or_else(usage)?);
+    Ok(replay)
+}
+
+/// Replays a trace.
+fn replay(options: &Replay) -> Result<(), String> {
+    let path = options.trace.display();
+    let text = fs::read_to_string(&options.trace).map_err(|e| format!("{}: {}", path, e))?;
+    let trace = Trace::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+    let frames = trace.frames();
+
+    if options.render == Render::DotFrames {
+        fs::create_dir_all(&options.out).map_err(|e| e.to_string())?;
+        for (i, frame) in frames.iter().enumerate() {
+            let file = options.out.join(format!("frame-{:06}.dot", i));
+            fs::write(&file, trace.render_dot(frame))
+                .map_err(|e| format!("{}: {}", file.display(), e))?;
+        }
+        println!("Wrote {} frames to {}", frames.len(), options.out.display());
+        return Ok(());
+    }
+
+    let mut previous = None;
+    for frame in &frames {
+        if let Some(previous) = previous {
+            let gap = match (previous, frame.elapsed()) {
+                (Some(a), Some(b)) if b > a => b - a,
+                _ => DEFAULT_PERIOD,
+            };
+            thread::sleep(gap.div_f64(options.speed));
+        }
+        previous = Some(frame.elapsed());
+
+        // Clear the screen and move to the top left before redrawing
+        print!("\x1b[2J\x1b[H");
+        match frame.elapsed() {
+            Some(t) => println!("tick {} at {:.3}s\n", frame.tick(), t.as_secs_f64()),
+            None => println!("tick {}\n", frame.tick()),
+        }
+        print!("{}", trace.render_text(frame));
+    }
+    Ok(())
+}
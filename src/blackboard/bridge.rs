@@ -0,0 +1,88 @@
+//! Keeping fields of a world struct in step with blackboard entries.
+use super::Blackboard;
+
+/// A world whose fields are mirrored by blackboard entries.
+///
+/// This lets a tree whose nodes were written against a world struct adopt the
+/// blackboard one subtree at a time: the `SyncBlackboard` node writes the
+/// bridged fields to the blackboard before ticking its child, and reads back
+/// the entries which were written during the tick. It is usually implemented
+/// with the `blackboard_bridge!` macro.
+pub trait BlackboardBridge {
+    /// Returns the keys of the entries which mirror fields.
+    fn bridged_keys(&self) -> &'static [&'static str];
+
+    /// Writes the fields which differ from their entries to the blackboard.
+    ///
+    /// Entries are only written when they change, so that nodes watching them
+    /// only see real changes.
+    fn export(&self, blackboard: &mut Blackboard);
+
+    /// Reads the entry with the given key back into its field.
+    ///
+    /// Nothing happens if the key is not bridged, or if the entry is missing or
+    /// of another type than the field.
+    fn import(&mut self, key: &str, blackboard: &Blackboard);
+}
+
+/// Implements `BlackboardBridge` for a struct, mirroring each of the given
+/// fields with the entry with the given key.
+///
+/// The fields must be `Clone`, `PartialEq` and `Send`.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate aspen;
+/// # use aspen::blackboard::{Blackboard, BlackboardBridge};
+/// # fn main() {
+/// struct Robot {
+///     battery: f32,
+///     docked: bool,
+/// }
+/// blackboard_bridge!(Robot {
+///     battery: "battery",
+///     docked: "docked",
+/// });
+///
+/// let mut robot = Robot {
+///     battery: 0.8,
+///     docked: false,
+/// };
+/// let mut blackboard = Blackboard::new();
+/// robot.export(&mut blackboard);
+/// assert_eq!(blackboard.get::<f32>("battery"), Some(&0.8));
+///
+/// blackboard.set("docked", true);
+/// robot.import("docked", &blackboard);
+/// assert!(robot.docked);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! blackboard_bridge {
+    ($world:ty { $($field:ident : $key:expr),* $(,)? }) => {
+        impl $crate::blackboard::BlackboardBridge for $world {
+            fn bridged_keys(&self) -> &'static [&'static str] {
+                &[$($key),*]
+            }
+
+            fn export(&self, blackboard: &mut $crate::blackboard::Blackboard) {
+                $(
+                    if blackboard.get($key) != Some(&self.$field) {
+                        blackboard.set($key, ::std::clone::Clone::clone(&self.$field));
+                    }
+                )*
+            }
+
+            fn import(&mut self, key: &str, blackboard: &$crate::blackboard::Blackboard) {
+                $(
+                    if key == $key {
+                        if let Some(value) = blackboard.get(key) {
+                            self.$field = ::std::clone::Clone::clone(value);
+                        }
+                    }
+                )*
+            }
+        }
+    };
+}
@@ -0,0 +1,273 @@
+//! Saving blackboard entries to a file.
+use super::Blackboard;
+use crate::json::{from_json, push_string};
+use std::{
+    any::Any,
+    fmt, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Converts a value on the blackboard to text, if it has the expected type.
+type EncodeFn = fn(&(dyn Any + Send)) -> Option<String>;
+
+/// Converts text back into a value for the blackboard.
+type DecodeFn = fn(&str) -> Option<Box<dyn Any + Send>>;
+
+/// A blackboard entry which is saved to the file.
+struct Key {
+    /// The key of the entry.
+    name: String,
+
+    /// Converts the value to text.
+    encode: EncodeFn,
+
+    /// Converts text back into a value.
+    decode: DecodeFn,
+
+    /// The revision of the entry when it was last saved or loaded.
+    saved: Option<u64>,
+}
+
+/// A file which selected blackboard entries are saved to and restored from.
+///
+/// Long-lived facts, such as map identifiers or calibration values, can be
+/// kept across restarts by loading the file when the program starts and
+/// saving it at suitable checkpoints, for example with a `SaveBlackboard` node.
+///
+/// Only the keys registered with `persist` are saved. Values are stored as
+/// text using their `Display` and `FromStr` implementations, in a JSON object
+/// mapping keys to strings. The file is written to a temporary file first and
+/// then renamed over the old one, so an interrupted save never leaves a
+/// truncated file behind.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::blackboard::{Blackboard, BlackboardFile};
+/// # let dir = std::env::temp_dir().join(format!("aspen-doc-{}", std::process::id()));
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("facts.json");
+/// let mut file = BlackboardFile::new(&path).persist::<u32>("map_id");
+///
+/// let mut blackboard = Blackboard::new();
+/// blackboard.set("map_id", 42u32);
+/// blackboard.set("pose", (1.0, 2.0));
+/// file.save(&blackboard).unwrap();
+///
+/// let mut restored = Blackboard::new();
+/// assert_eq!(file.load(&mut restored).unwrap(), 1);
+/// assert_eq!(restored.get::<u32>("map_id"), Some(&42));
+/// assert!(!restored.contains("pose"));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub struct BlackboardFile {
+    /// The path of the file.
+    path: PathBuf,
+
+    /// The entries which are saved.
+    keys: Vec<Key>,
+}
+impl BlackboardFile {
+    /// Creates a new `BlackboardFile` at the given path, which does not save
+    /// any entries yet.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        BlackboardFile {
+            path: path.as_ref().to_owned(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Adds the entry with the given key, holding a `T`, to the entries which
+    /// are saved.
+    pub fn persist<T>(mut self, key: &str) -> Self
+    where
+        T: fmt::Display + FromStr + Send + 'static,
+    {
+        self.keys.push(Key {
+            name: key.to_owned(),
+            encode: encode::<T>,
+            decode: decode::<T>,
+            saved: None,
+        });
+        self
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `true` if any of the saved entries changed since they were
+    /// last saved or loaded.
+    pub fn is_dirty(&self, blackboard: &Blackboard) -> bool {
+        self.keys
+            .iter()
+            .any(|k| blackboard.revision(&k.name) != k.saved)
+    }
+
+    /// Writes the saved entries to the file.
+    ///
+    /// Entries which are missing or which do not hold the registered type are
+    /// left out.
+    pub fn save(&mut self, blackboard: &Blackboard) -> io::Result<()> {
+        let mut entries = Vec::with_capacity(self.keys.len());
+        for key in &self.keys {
+            if let Some(text) = blackboard.get_any(&key.name).and_then(key.encode) {
+                entries.push((key.name.as_str(), text));
+            }
+        }
+
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, to_json(&entries))?;
+        fs::rename(&tmp, &self.path)?;
+        debug!(
+            "Saved {} blackboard entries to {:?}",
+            entries.len(),
+            self.path
+        );
+
+        for key in &mut self.keys {
+            key.saved = blackboard.revision(&key.name);
+        }
+        Ok(())
+    }
+
+    /// Writes the saved entries to the file if any of them changed since they
+    /// were last saved or loaded, returning `true` if the file was written.
+    pub fn save_if_dirty(&mut self, blackboard: &Blackboard) -> io::Result<bool> {
+        if !self.is_dirty(blackboard) {
+            return Ok(false);
+        }
+
+        self.save(blackboard).map(|_| true)
+    }
+
+    /// Restores the saved entries from the file, returning how many were
+    /// restored.
+    ///
+    /// A missing file restores nothing. Entries in the file which are not
+    /// registered, or whose value cannot be parsed, are skipped with a warning.
+    pub fn load(&mut self, blackboard: &mut Blackboard) -> io::Result<usize> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let entries = from_json(&text).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{:?} is not a blackboard file", self.path),
+            )
+        })?;
+
+        let mut restored = 0;
+        for (name, text) in entries {
+            let key = match self.keys.iter_mut().find(|k| k.name == name) {
+                Some(key) => key,
+                None => {
+                    warn!("Skipping unknown blackboard entry {}", name);
+                    continue;
+                }
+            };
+            match (key.decode)(&text) {
+                Some(value) => {
+                    blackboard.set_boxed(name, value);
+                    key.saved = blackboard.revision(&key.name);
+                    restored += 1;
+                }
+                None => warn!("Skipping blackboard entry {} with invalid value", name),
+            }
+        }
+        Ok(restored)
+    }
+}
+impl fmt::Debug for BlackboardFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlackboardFile")
+            .field("path", &self.path)
+            .field(
+                "keys",
+                &self.keys.iter().map(|k| &k.name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Converts a value to text if it is a `T`.
+fn encode<T: fmt::Display + 'static>(value: &(dyn Any + Send)) -> Option<String> {
+    value.downcast_ref::<T>().map(T::to_string)
+}
+
+/// Parses a `T` from text.
+fn decode<T: FromStr + Send + 'static>(text: &str) -> Option<Box<dyn Any + Send>> {
+    let value: T = text.parse().ok()?;
+    Some(Box::new(value))
+}
+
+/// Writes a JSON object mapping keys to strings.
+fn to_json(entries: &[(&str, String)]) -> String {
+    let mut json = String::from("{\n");
+    for (i, (key, value)) in entries.iter().enumerate() {
+        json.push_str("  ");
+        push_string(&mut json, key);
+        json.push_str(": ");
+        push_string(&mut json, value);
+        json.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+    }
+    json.push('}');
+    json.push('\n');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_json, BlackboardFile};
+    use crate::{blackboard::Blackboard, json::from_json};
+    use std::{env, fs};
+
+    #[test]
+    fn json_round_trip() {
+        let entries = vec![
+            ("a \"b\"", "line\nbreak\u{1}".to_owned()),
+            ("c", String::new()),
+        ];
+        let parsed = from_json(&to_json(&entries)).unwrap();
+        assert_eq!(parsed[0], ("a \"b\"".to_owned(), entries[0].1.clone()));
+        assert_eq!(parsed[1], ("c".to_owned(), String::new()));
+
+        assert_eq!(from_json(" { } "), Some(Vec::new()));
+        assert_eq!(from_json("{\"a\": 1}"), None);
+        assert_eq!(from_json("{\"a\": \"1\"} x"), None);
+    }
+
+    #[test]
+    fn saves_only_changes() {
+        let dir = env::temp_dir().join(format!("aspen-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut file = BlackboardFile::new(dir.join("bb.json"))
+            .persist::<f64>("offset")
+            .persist::<String>("map");
+
+        let mut blackboard = Blackboard::new();
+        blackboard.set("offset", 0.25f64);
+        blackboard.set("map", "lab".to_owned());
+        assert!(file.save_if_dirty(&blackboard).unwrap());
+        assert!(!file.save_if_dirty(&blackboard).unwrap());
+
+        blackboard.set("offset", 0.5f64);
+        assert!(file.is_dirty(&blackboard));
+        file.save(&blackboard).unwrap();
+
+        let mut restored = Blackboard::new();
+        assert_eq!(file.load(&mut restored).unwrap(), 2);
+        assert_eq!(restored.get::<f64>("offset"), Some(&0.5));
+        assert_eq!(
+            restored.get::<String>("map").map(String::as_str),
+            Some("lab")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+//! Shared, dynamically typed storage for the state of a tree.
+//!
+//! A `Blackboard` maps string keys to values of any type. It allows nodes that
+//! were written independently to exchange data without agreeing on a single
+//! world type: a node which needs the blackboard only requires that the world
+//! implements `AsRef<Blackboard>` (or `AsMut<Blackboard>` to write to it). A
+//! `Blackboard` can also be used as the world directly.
+//!
+//! Every time an entry is written it is given a new revision number, which
+//! allows nodes to detect when an entry has changed. Selected entries can be
+//! kept across restarts with a `BlackboardFile`.
+//!
+//! A subtree can be given its own scope, layered over the blackboard of the
+//! rest of the tree. Reads fall through to the parent scope, but writes stay in
+//! the subtree's scope unless they are promoted, so subtrees written by
+//! different teams cannot clobber each other's keys. The `ScopedBlackboard`
+//! node manages a scope for its child.
+//!
+//! Nodes can declare the keys they read and write, and the types of their
+//! values, so that `validate` can check that the producers and consumers of
+//! every key agree before the tree is run.
+//!
+//! Trees whose nodes were written against a world struct can adopt the
+//! blackboard gradually, by mirroring selected fields of the world with
+//! entries using `BlackboardBridge`.
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet},
+    fmt, mem,
+};
+
+mod bridge;
+pub use self::bridge::BlackboardBridge;
+
+mod file;
+pub use self::file::BlackboardFile;
+
+mod schema;
+pub use self::schema::{Access, KeyUse};
+
+/// A single value on the blackboard.
+struct Entry {
+    /// The value itself.
+    value: Box<dyn Any + Send>,
+
+    /// The revision at which the value was written.
+    revision: u64,
+}
+
+/// Storage for values of any type, identified by string keys.
+///
+/// While a scope has been entered with `enter_scope`, the blackboard holds the
+/// entries of that scope, and its parent holds the entries of the enclosing
+/// one. Reads of keys which aren't in the scope fall through to the parent,
+/// while writes, in-place modifications and removals only affect the scope.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::blackboard::Blackboard;
+/// let mut blackboard = Blackboard::new();
+/// blackboard.set("battery", 85u32);
+///
+/// assert_eq!(blackboard.get::<u32>("battery"), Some(&85));
+/// assert_eq!(blackboard.get::<f32>("battery"), None);
+/// assert_eq!(blackboard.get::<u32>("speed"), None);
+/// ```
+#[derive(Default)]
+pub struct Blackboard {
+    /// The values, by key.
+    entries: HashMap<String, Entry>,
+
+    /// The revision given to the last write.
+    revision: u64,
+
+    /// The enclosing scope, if a scope has been entered.
+    parent: Option<Box<Blackboard>>,
+}
+impl Blackboard {
+    /// Creates an empty blackboard.
+    pub fn new() -> Self {
+        Blackboard::default()
+    }
+
+    /// Writes a value, replacing any existing value with the same key.
+    pub fn set<K, T>(&mut self, key: K, value: T)
+    where
+        K: Into<String>,
+        T: Any + Send,
+    {
+        self.set_boxed(key, Box::new(value));
+    }
+
+    /// Writes a boxed value, replacing any existing value with the same key.
+    pub(crate) fn set_boxed<K: Into<String>>(&mut self, key: K, value: Box<dyn Any + Send>) {
+        self.revision += 1;
+        let entry = Entry {
+            value,
+            revision: self.revision,
+        };
+        self.entries.insert(key.into(), entry);
+    }
+
+    /// Returns the entry with the given key, from this scope or the nearest
+    /// enclosing scope which has it.
+    fn entry(&self, key: &str) -> Option<&Entry> {
+        match self.entries.get(key) {
+            Some(entry) => Some(entry),
+            None => self.parent.as_ref()?.entry(key),
+        }
+    }
+
+    /// Returns the value with the given key.
+    ///
+    /// Returns `None` if there is no such value, or if it is not a `T`.
+    pub fn get<T: Any>(&self, key: &str) -> Option<&T> {
+        self.entry(key)?.value.downcast_ref()
+    }
+
+    /// Returns the value with the given key, whatever its type.
+    pub(crate) fn get_any(&self, key: &str) -> Option<&(dyn Any + Send)> {
+        self.entry(key).map(|e| &*e.value)
+    }
+
+    /// Returns the type of the value with the given key.
+    pub(crate) fn type_of(&self, key: &str) -> Option<TypeId> {
+        self.entry(key).map(|e| Any::type_id(&*e.value))
+    }
+
+    /// Returns the value with the given key as text, if it has one of the
+    /// common types.
+    pub(crate) fn get_text(&self, key: &str) -> Option<String> {
+        self.get_any(key).and_then(to_text)
+    }
+
+    /// Returns a mutable reference to the value with the given key.
+    ///
+    /// Returns `None` if there is no such value, or if it is not a `T`. The
+    /// value is given a new revision, whether or not it is modified. Values
+    /// which are only in an enclosing scope can't be modified; `set` a value
+    /// to shadow them instead.
+    pub fn get_mut<T: Any>(&mut self, key: &str) -> Option<&mut T> {
+        let entry = self.entries.get_mut(key)?;
+        if !entry.value.is::<T>() {
+            return None;
+        }
+
+        self.revision += 1;
+        entry.revision = self.revision;
+        entry.value.downcast_mut()
+    }
+
+    /// Removes the value with the given key from this scope, returning `true`
+    /// if there was one.
+    ///
+    /// A value with the same key in an enclosing scope is visible again
+    /// afterwards.
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    /// Returns `true` if there is a value with the given key, in this scope or
+    /// an enclosing one.
+    pub fn contains(&self, key: &str) -> bool {
+        self.entry(key).is_some()
+    }
+
+    /// Returns the revision at which the value with the given key was last
+    /// written, or `None` if there is no such value.
+    ///
+    /// Revisions increase with every write to the blackboard, so a value has
+    /// changed if and only if its revision has.
+    pub fn revision(&self, key: &str) -> Option<u64> {
+        self.entry(key).map(|e| e.revision)
+    }
+
+    /// Returns an iterator over the keys on the blackboard, including those
+    /// of enclosing scopes, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let mut keys = HashSet::new();
+        let mut scope = Some(self);
+        while let Some(s) = scope {
+            keys.extend(s.entries.keys().map(String::as_str));
+            scope = s.parent.as_deref();
+        }
+        keys.into_iter()
+    }
+
+    /// Enters a new scope, holding the entries of `scope`.
+    ///
+    /// The current entries become the parent scope, which reads fall through
+    /// to. Any scope `scope` was itself in is ignored.
+    pub fn enter_scope(&mut self, mut scope: Blackboard) {
+        // Keep revisions increasing across scopes, so a shadowed value is seen
+        // as a change
+        scope.revision = scope.revision.max(self.revision);
+        let parent = mem::replace(self, scope);
+        self.parent = Some(Box::new(parent));
+    }
+
+    /// Leaves the current scope, returning its entries, or `None` if no scope
+    /// has been entered.
+    pub fn exit_scope(&mut self) -> Option<Blackboard> {
+        let parent = self.parent.take()?;
+        let scope = mem::replace(self, *parent);
+        self.revision = self.revision.max(scope.revision);
+        Some(scope)
+    }
+
+    /// Moves the value with the given key from this scope to the enclosing
+    /// one, returning `true` if there was one to move.
+    ///
+    /// The value is given a new revision. Nothing happens if no scope has been
+    /// entered.
+    pub fn promote(&mut self, key: &str) -> bool {
+        let parent = match self.parent {
+            Some(ref mut parent) => parent,
+            None => return false,
+        };
+        match self.entries.remove_entry(key) {
+            Some((key, entry)) => {
+                parent.revision = parent.revision.max(self.revision);
+                parent.set_boxed(key, entry.value);
+                self.revision = parent.revision;
+                true
+            }
+            None => false,
+        }
+    }
+}
+impl AsRef<Blackboard> for Blackboard {
+    fn as_ref(&self) -> &Blackboard {
+        self
+    }
+}
+impl AsMut<Blackboard> for Blackboard {
+    fn as_mut(&mut self) -> &mut Blackboard {
+        self
+    }
+}
+impl fmt::Debug for Blackboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blackboard")
+            .field("keys", &self.entries.keys().collect::<Vec<_>>())
+            .field("revision", &self.revision)
+            .field("parent", &self.parent)
+            .finish()
+    }
+}
+
+/// Converts a blackboard value to text, if it has one of the common types.
+fn to_text(value: &(dyn Any + Send)) -> Option<String> {
+    macro_rules! try_types {
+        ($($t:ty),*) => {
+            $(if let Some(v) = value.downcast_ref::<$t>() {
+                return Some(v.to_string());
+            })*
+        };
+    }
+
+    try_types!(String, &'static str, bool, char);
+    try_types!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Blackboard;
+
+    #[test]
+    fn writes_change_the_revision() {
+        let mut blackboard = Blackboard::new();
+        assert_eq!(blackboard.revision("a"), None);
+
+        blackboard.set("a", 1u8);
+        let first = blackboard.revision("a").unwrap();
+        blackboard.set("b", 1u8);
+        assert_eq!(blackboard.revision("a"), Some(first));
+
+        *blackboard.get_mut::<u8>("a").unwrap() += 1;
+        assert!(blackboard.revision("a").unwrap() > first);
+        assert_eq!(blackboard.get::<u8>("a"), Some(&2));
+    }
+
+    #[test]
+    fn wrong_type_is_not_modified() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set("a", 1u8);
+        let revision = blackboard.revision("a");
+
+        assert!(blackboard.get_mut::<u16>("a").is_none());
+        assert_eq!(blackboard.revision("a"), revision);
+        assert!(blackboard.remove("a"));
+        assert!(!blackboard.contains("a"));
+    }
+
+    #[test]
+    fn scopes_shadow_and_promote() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set("goal", 1u8);
+        blackboard.set("speed", 1u8);
+        let parent_revision = blackboard.revision("goal").unwrap();
+
+        blackboard.enter_scope(Blackboard::new());
+        assert_eq!(blackboard.get::<u8>("goal"), Some(&1));
+        assert!(blackboard.get_mut::<u8>("goal").is_none());
+        blackboard.set("goal", 2u8);
+        blackboard.set("result", 3u8);
+        assert!(blackboard.revision("goal").unwrap() > parent_revision);
+        assert!(blackboard.promote("result"));
+        assert!(!blackboard.promote("speed"));
+        let mut keys: Vec<&str> = blackboard.keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec!["goal", "result", "speed"]);
+
+        let scope = blackboard.exit_scope().unwrap();
+        assert_eq!(scope.get::<u8>("goal"), Some(&2));
+        assert!(!scope.contains("speed"));
+        assert_eq!(blackboard.get::<u8>("goal"), Some(&1));
+        assert_eq!(blackboard.get::<u8>("result"), Some(&3));
+        assert!(blackboard.exit_scope().is_none());
+    }
+}
@@ -0,0 +1,72 @@
+//! Declarations of the blackboard keys which nodes read and write.
+use std::{
+    any::{self, Any, TypeId},
+    borrow::Cow,
+};
+
+/// Whether a node reads or writes a blackboard key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// The node reads the value.
+    Read,
+
+    /// The node writes the value.
+    Write,
+}
+
+/// A blackboard key which a node declares it reads or writes, with the type
+/// of its value.
+///
+/// Keys are declared with `Node::reads` and `Node::writes`. They do not change
+/// how the node behaves, but allow `validate` to check that every key which is
+/// read is also written, as the same type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyUse {
+    /// The key.
+    key: Cow<'static, str>,
+
+    /// Whether the key is read or written.
+    access: Access,
+
+    /// The type of the value.
+    type_id: TypeId,
+
+    /// The name of the type of the value.
+    type_name: &'static str,
+}
+impl KeyUse {
+    /// Creates a declaration that the value with the given key, which is a
+    /// `T`, is read or written.
+    pub fn new<T, K>(key: K, access: Access) -> Self
+    where
+        T: Any,
+        K: Into<Cow<'static, str>>,
+    {
+        KeyUse {
+            key: key.into(),
+            access,
+            type_id: TypeId::of::<T>(),
+            type_name: any::type_name::<T>(),
+        }
+    }
+
+    /// Returns the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns whether the key is read or written.
+    pub fn access(&self) -> Access {
+        self.access
+    }
+
+    /// Returns the type of the value.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Returns the name of the type of the value.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
@@ -4,19 +4,133 @@ use std::{
 };
 
 use crate::{
-    node::{Node, Tickable},
+    blackboard::Blackboard,
+    config::{ConfigGuard, ConfigHandle},
+    node::{self, DryRun, LogPolicy, Node, SendNode, Tickable},
     status::Status,
+    validate::{self, Warning},
 };
 
+/// Callback run when a behavior tree completes.
+type CompletionFn<'a> = dyn FnMut(&RunSummary) + 'a;
+
 /// Main behavior tree struct.
+///
+/// Dropping the tree drops all of its nodes. `Action` nodes with a task still
+/// running will halt it and then join or abandon the worker thread according
+/// to their `DropPolicy`.
+///
+/// A tree is not `Send`, so it must be built on the thread that ticks it. Use
+/// a `SharedTree` to read its state from other threads.
 pub struct BehaviorTree<'a, W> {
     /// Root node of the behavior tree.
     root: Node<'a, W>,
+
+    /// The name of the tree, used to identify it in telemetry.
+    name: Option<String>,
+
+    /// The number of times the tree has been ticked.
+    tick_count: u64,
+
+    /// When the current run started, and the tick count at that time.
+    run_start: Option<(Instant, u64)>,
+
+    /// Callbacks to run whenever the tree completes.
+    on_complete: Vec<Box<CompletionFn<'a>>>,
+
+    /// How the nodes of the tree log their ticks.
+    log_policy: LogPolicy,
+
+    /// The config made current while the tree is ticked.
+    config: Option<ConfigHandle>,
 }
 impl<'a, W> BehaviorTree<'a, W> {
     /// Create a new behavior tree with the supplied `Node` as the root.
     pub fn new(root: Node<'a, W>) -> BehaviorTree<'a, W> {
-        BehaviorTree { root }
+        BehaviorTree {
+            root,
+            name: None,
+            tick_count: 0,
+            run_start: None,
+            on_complete: Vec::new(),
+            log_policy: LogPolicy::default(),
+            config: None,
+        }
+    }
+
+    /// Registers a callback to be run every time the tree completes.
+    ///
+    /// The callback is run exactly once each time the root node finishes with
+    /// either `Succeeded` or `Failed`, from within the tick that finished it.
+    /// Callbacks are run in the order they were registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::{BehaviorTree, Status};
+    /// let mut tree = BehaviorTree::new(Repeat::with_limit(3, AlwaysFail::new()));
+    /// let mut code = None;
+    /// tree.on_complete(|summary| code = Some(summary.exit_code()));
+    ///
+    /// tree.run(0.0, &mut (), None::<fn(&BehaviorTree<()>)>);
+    /// assert_eq!(tree.tick(&mut ()), Status::Running);
+    /// assert_eq!(tree.tick(&mut ()), Status::Succeeded);
+    /// # drop(tree);
+    /// assert_eq!(code, Some(0));
+    /// ```
+    pub fn on_complete<F>(&mut self, callback: F)
+    where
+        F: FnMut(&RunSummary) + 'a,
+    {
+        self.on_complete.push(Box::new(callback));
+    }
+
+    /// Sets the name of this tree.
+    pub fn named<T: Into<String>>(mut self, name: Option<T>) -> BehaviorTree<'a, W> {
+        self.name = name.map(Into::into);
+        self
+    }
+
+    /// Sets how the nodes of this tree log their ticks.
+    ///
+    /// By default, every tick of every node is logged at trace level.
+    pub fn with_log_policy(mut self, policy: LogPolicy) -> BehaviorTree<'a, W> {
+        self.log_policy = policy;
+        self
+    }
+
+    /// Returns how the nodes of this tree log their ticks.
+    pub fn log_policy(&self) -> LogPolicy {
+        self.log_policy
+    }
+
+    /// Sets the config that the nodes of this tree read while they are ticked.
+    ///
+    /// A snapshot of the config is made current through `Config::current` for
+    /// the duration of every tick, so replacing or reloading it through the
+    /// handle takes effect from the next tick.
+    pub fn with_config(mut self, config: ConfigHandle) -> BehaviorTree<'a, W> {
+        self.config = Some(config);
+        self
+    }
+
+    /// Returns the config of this tree, if it has one.
+    pub fn config(&self) -> Option<&ConfigHandle> {
+        self.config.as_ref()
+    }
+
+    /// Returns the name of this tree, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the number of times this tree has been ticked.
+    ///
+    /// Unlike the status of the nodes, this count is not affected by resetting
+    /// the tree.
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
     }
 
     /// Returns a reference to the root node.
@@ -24,28 +138,181 @@ impl<'a, W> BehaviorTree<'a, W> {
         &self.root
     }
 
+    /// Returns the nodes that the next tick would run, without running it.
+    ///
+    /// Conditions and the routing of composite nodes are evaluated against the
+    /// world as usual, but actions are never run: they, and any other node
+    /// whose outcome cannot be predicted, are assumed to succeed. The nodes are
+    /// returned in the order they would be ticked, identified by their
+    /// position in a pre-order traversal of the tree.
+    ///
+    /// Neither the tree nor the world are modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::{BehaviorTree, Status};
+    /// let tree = BehaviorTree::new(Selector::new(vec![
+    ///     Condition::new(|&battery: &u32| battery > 20),
+    ///     InlineAction::new(|_| panic!("not run during a dry run")),
+    /// ]));
+    /// assert_eq!(tree.dry_run(&50), vec![0, 1]);
+    /// assert_eq!(tree.dry_run(&10), vec![0, 1, 2]);
+    /// ```
+    pub fn dry_run(&self, world: &W) -> Vec<usize> {
+        self.dry_run_with(world, |_| Status::Succeeded)
+    }
+
+    /// Returns the nodes that the next tick would run, using `stand_in` to
+    /// decide the status of the nodes whose outcome cannot be predicted.
+    ///
+    /// See `BehaviorTree::dry_run` for details.
+    pub fn dry_run_with<F>(&self, world: &W, stand_in: F) -> Vec<usize>
+    where
+        F: Fn(&Node<'_, W>) -> Status,
+    {
+        let after_reset = self.root.status().map_or(false, |s| s.is_done());
+        let mut run = DryRun::new(&self.root, after_reset, &stand_in);
+        run.tick(&self.root, world);
+        run.into_path()
+    }
+
     /// Tick the behavior tree a single time.
     ///
     /// If the tree has already been completed, ticking it again will reset it.
     /// When the tree is reset, it will return an `Initialized` status a single
     /// time.
+    ///
+    /// Since ticking takes `&mut self`, a tree cannot be ticked again from
+    /// within its own tick, such as by one of its nodes or completion
+    /// callbacks. A tree shared behind a `RefCell` or `Mutex` would have to be
+    /// borrowed or locked for the tick, which already rules it out.
+    ///
+    /// If a tick panics and the panic is caught, the tree can be ticked again.
+    /// Its nodes may have been left partway through their tick logic, though,
+    /// so it is usually best to reset it first.
     pub fn tick(&mut self, world: &mut W) -> Status {
-        match self.root.status() {
+        if self.run_start.is_none() {
+            self.run_start = Some((Instant::now(), self.tick_count));
+        }
+        self.tick_count = self.tick_count.wrapping_add(1);
+
+        let policy = LogPolicyGuard::set(self.log_policy);
+        let config = self.config.as_ref().map(|c| ConfigGuard::set(c.get()));
+        let status = match self.root.status() {
             None | Some(Status::Running) => self.root.tick(world),
             Some(Status::Failed) | Some(Status::Succeeded) => {
                 debug!("Tree reset via ticking");
                 self.root.reset();
                 self.root.tick(world)
             }
+        };
+
+        if node::cleanups_pending() {
+            node::tick_cleanups(&self.root, world);
+        }
+        drop(config);
+        drop(policy);
+
+        if status.is_done() {
+            self.complete(status);
+        }
+
+        status
+    }
+
+    /// Checks the tree for likely authoring mistakes, without ticking it.
+    ///
+    /// See the `validate` module for the checks which are made.
+    pub fn validate(&self) -> Vec<Warning> {
+        validate::validate(&self.root)
+    }
+
+    /// Checks the tree for likely authoring mistakes, given the blackboard it
+    /// will start with.
+    ///
+    /// Keys on the initial blackboard count as written when checking the keys
+    /// that nodes declare they read.
+    pub fn validate_with_blackboard(&self, initial: &Blackboard) -> Vec<Warning> {
+        validate::validate_with_blackboard(&self.root, initial)
+    }
+
+    /// Tick the behavior tree a single time, letting its nodes know when the
+    /// tick should finish.
+    ///
+    /// Nodes can read the deadline through `TickContext` to bound their work.
+    /// Nothing stops a tick from overrunning it.
+    pub fn tick_with_deadline(&mut self, world: &mut W, deadline: Instant) -> Status {
+        let _guard = DeadlineGuard::set(Some(deadline));
+        self.tick(world)
+    }
+
+    /// Tick the behavior tree `n` times, returning the status of the last
+    /// tick.
+    ///
+    /// The tree is ticked `n` times regardless of its status, so a tree that
+    /// completes part way through will be reset and ticked again. Returns
+    /// `None` if `n` is zero.
+    pub fn tick_n(&mut self, n: usize, world: &mut W) -> Option<Status> {
+        let mut status = None;
+        for _ in 0..n {
+            status = Some(self.tick(world));
+        }
+        status
+    }
+
+    /// Returns an iterator which ticks the tree each time it is advanced.
+    ///
+    /// The iterator yields the status of every tick and ends after the tree
+    /// completes, so it never resets a completed tree. If the tree never
+    /// completes, neither does the iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::{BehaviorTree, Status};
+    /// let mut tree = BehaviorTree::new(Repeat::with_limit(3, AlwaysSucceed::new()));
+    /// let statuses: Vec<_> = tree.ticks(&mut ()).collect();
+    /// assert_eq!(
+    ///     statuses,
+    ///     vec![Status::Running, Status::Running, Status::Succeeded]
+    /// );
+    /// ```
+    pub fn ticks<'t>(&'t mut self, world: &'t mut W) -> Ticks<'t, 'a, W> {
+        Ticks {
+            tree: self,
+            world,
+            done: false,
         }
     }
 
     /// Reset the tree to a state identical to before it had ran.
     pub fn reset(&mut self) {
         trace!("Tree reset");
+        self.run_start = None;
         self.root.reset();
     }
 
+    /// Ends the current run and notifies the completion callbacks.
+    fn complete(&mut self, status: Status) {
+        let (start, start_ticks) = self
+            .run_start
+            .take()
+            .expect("run started before the tree completed");
+        let summary = RunSummary {
+            status,
+            ticks: self.tick_count.wrapping_sub(start_ticks),
+            elapsed: start.elapsed(),
+        };
+
+        debug!("Tree completed: {:?}", summary);
+        for callback in &mut self.on_complete {
+            callback(&summary);
+        }
+    }
+
     /// Run the behavior tree until it either succeeds or fails.
     ///
     /// This makes no guarantees that it will run at the specified frequency. If
@@ -58,7 +325,59 @@ impl<'a, W> BehaviorTree<'a, W> {
     /// NOTE: The only time this will return `Status::Running` is if the
     /// frequency is zero and the behavior tree is running after the first
     /// tick.
-    pub fn run<F>(&mut self, freq: f64, world: &mut W, mut hook: Option<F>) -> Status
+    pub fn run<F>(&mut self, freq: f64, world: &mut W, hook: Option<F>) -> Status
+    where
+        F: FnMut(&BehaviorTree<'a, W>),
+    {
+        self.run_until(None, freq, world, hook)
+    }
+
+    /// Run the behavior tree until it either completes or the given amount of
+    /// time has passed.
+    ///
+    /// This behaves like `run`, except that it returns `Status::Running` if
+    /// the tree is still running at the deadline. The tree is not reset, so a
+    /// later call will pick up where this one left off. The tree is always
+    /// ticked at least once, and the deadline is only checked between ticks:
+    /// a slow tick can overrun it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::{BehaviorTree, Status};
+    /// # use std::time::Duration;
+    /// let mut tree = BehaviorTree::new(Repeat::with_limit(1000, AlwaysSucceed::new()));
+    /// let status = tree.run_for(
+    ///     Duration::from_millis(20),
+    ///     100.0,
+    ///     &mut (),
+    ///     None::<fn(&BehaviorTree<()>)>,
+    /// );
+    /// assert_eq!(status, Status::Running);
+    /// ```
+    pub fn run_for<F>(
+        &mut self,
+        duration: Duration,
+        freq: f64,
+        world: &mut W,
+        hook: Option<F>,
+    ) -> Status
+    where
+        F: FnMut(&BehaviorTree<'a, W>),
+    {
+        self.run_until(Some(Instant::now() + duration), freq, world, hook)
+    }
+
+    /// Run the behavior tree at the given frequency until it completes or the
+    /// deadline, if any, passes.
+    fn run_until<F>(
+        &mut self,
+        deadline: Option<Instant>,
+        freq: f64,
+        world: &mut W,
+        mut hook: Option<F>,
+    ) -> Status
     where
         F: FnMut(&BehaviorTree<'a, W>),
     {
@@ -87,36 +406,244 @@ impl<'a, W> BehaviorTree<'a, W> {
             let now = Instant::now();
 
             trace!("Ticking tree");
-            status = self.tick(world);
+            status = if freq.is_finite() {
+                let tick_deadline = now + cycle_dur;
+                self.tick_with_deadline(
+                    world,
+                    deadline.map_or(tick_deadline, |d| d.min(tick_deadline)),
+                )
+            } else {
+                match deadline {
+                    Some(d) => self.tick_with_deadline(world, d),
+                    None => self.tick(world),
+                }
+            };
             if let Some(ref mut f) = hook {
                 f(self);
             }
 
             let elapsed = now.elapsed();
 
+            if status.is_done() {
+                break;
+            }
+
+            // Don't start another tick after the deadline
+            let remaining = match deadline {
+                Some(d) => match d.checked_duration_since(Instant::now()) {
+                    Some(r) if r > Duration::from_secs(0) => Some(r),
+                    _ => {
+                        debug!("Deadline reached, tree still running");
+                        break;
+                    }
+                },
+                None => None,
+            };
+
+            // Really, the Duration would take care of the case where the
+            // frequency is infinite. However, specifying a frequency of
+            // infinity means running as fast a possible. In that case, I do
+            // not want to give this thread an opportunity to sleep at all
+            if !freq.is_finite() {
+                continue;
+            }
+
             // Sleep for the remaining amount of time
-            if !status.is_done() && freq.is_finite() && elapsed < cycle_dur {
-                if elapsed < cycle_dur {
-                    // Really, the Duration would take care of the case where the
-                    // frequency is infinite. However, specifying a frequency of
-                    // infinity means running as fast a possible. In that case, I
-                    // do not want to give this thread an opportunity to sleep at
-                    // all
-                    thread::sleep(cycle_dur - elapsed);
-                } else {
-                    warn!(
-                        "Unable to tick at desired frequency: Expected {:?}, elapsed {:?}",
-                        cycle_dur, elapsed
-                    );
+            if elapsed < cycle_dur {
+                let sleep = cycle_dur - elapsed;
+                match remaining {
+                    // Waking up after the deadline would just waste the time
+                    Some(r) if r < sleep => {
+                        thread::sleep(r);
+                        break;
+                    }
+                    _ => thread::sleep(sleep),
                 }
+            } else {
+                warn!(
+                    "Unable to tick at desired frequency: Expected {:?}, elapsed {:?}",
+                    cycle_dur, elapsed
+                );
             }
         }
 
         status
     }
 }
+
+/// A behavior tree which can be moved to another thread.
+///
+/// A `BehaviorTree` is not `Send`, since its nodes and completion callbacks are
+/// type-erased. A `SendTree` is built from a `SendNode` and only accepts
+/// completion callbacks which are `Send`, so it is `Send` itself, and can be
+/// kept in an `Arc<Mutex<_>>` to tick it from whichever thread holds the lock.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::node::SendNode;
+/// # use aspen::std_nodes::typed::Condition;
+/// # use aspen::{SendTree, Status};
+/// # use std::sync::{Arc, Mutex};
+/// # use std::thread;
+/// let tree = SendTree::new(SendNode::new(Condition::new(|&n: &u32| n > 0)));
+/// let tree = Arc::new(Mutex::new(tree));
+///
+/// let shared = Arc::clone(&tree);
+/// let status = thread::spawn(move || shared.lock().unwrap().tick(&mut 1));
+/// assert_eq!(status.join().unwrap(), Status::Succeeded);
+/// assert_eq!(tree.lock().unwrap().tree().tick_count(), 1);
+/// ```
+pub struct SendTree<'a, W>(BehaviorTree<'a, W>);
+// Safety: the root is a `SendNode`, every completion callback is `Send`, and
+// the rest of the tree is plain data and a `ConfigHandle`. No `&mut
+// BehaviorTree` is handed out, so neither can be replaced.
+unsafe impl<'a, W> Send for SendTree<'a, W> {}
+impl<'a, W> SendTree<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new tree with the given root.
+    pub fn new(root: SendNode<'a, W>) -> Self {
+        SendTree(BehaviorTree::new(root.into_node()))
+    }
+
+    /// Registers a callback to be run every time the tree completes.
+    ///
+    /// See `BehaviorTree::on_complete`.
+    pub fn on_complete<F>(&mut self, callback: F)
+    where
+        F: FnMut(&RunSummary) + Send + 'a,
+    {
+        self.0.on_complete(callback)
+    }
+
+    /// Sets the name of this tree.
+    pub fn named<T: Into<String>>(self, name: Option<T>) -> Self {
+        SendTree(self.0.named(name))
+    }
+
+    /// Sets how the nodes of this tree log their ticks.
+    pub fn with_log_policy(self, policy: LogPolicy) -> Self {
+        SendTree(self.0.with_log_policy(policy))
+    }
+
+    /// Sets the config that the nodes of this tree read while they are ticked.
+    pub fn with_config(self, config: ConfigHandle) -> Self {
+        SendTree(self.0.with_config(config))
+    }
+
+    /// Returns a reference to the tree.
+    pub fn tree(&self) -> &BehaviorTree<'a, W> {
+        &self.0
+    }
+
+    /// Tick the behavior tree a single time.
+    ///
+    /// See `BehaviorTree::tick`.
+    pub fn tick(&mut self, world: &mut W) -> Status {
+        self.0.tick(world)
+    }
+
+    /// Tick the behavior tree a single time, letting its nodes know when the
+    /// tick should finish.
+    pub fn tick_with_deadline(&mut self, world: &mut W, deadline: Instant) -> Status {
+        self.0.tick_with_deadline(world, deadline)
+    }
+
+    /// Reset the tree to a state identical to before it had ran.
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    /// Returns the tree, which is no longer `Send`.
+    pub fn into_inner(self) -> BehaviorTree<'a, W> {
+        self.0
+    }
+}
+
+/// Restores the previous tick deadline of the thread when dropped.
+struct DeadlineGuard(Option<Instant>);
+impl DeadlineGuard {
+    /// Sets the tick deadline of the thread until the guard is dropped.
+    fn set(deadline: Option<Instant>) -> Self {
+        DeadlineGuard(node::set_tick_deadline(deadline))
+    }
+}
+impl Drop for DeadlineGuard {
+    fn drop(&mut self) {
+        node::set_tick_deadline(self.0);
+    }
+}
+
+/// Restores the previous log policy of the thread when dropped, including
+/// when a tick panics.
+struct LogPolicyGuard(LogPolicy);
+impl LogPolicyGuard {
+    /// Sets the log policy of the thread until the guard is dropped.
+    fn set(policy: LogPolicy) -> Self {
+        LogPolicyGuard(node::set_log_policy(policy))
+    }
+}
+impl Drop for LogPolicyGuard {
+    fn drop(&mut self) {
+        node::set_log_policy(self.0);
+    }
+}
+
+/// An iterator which ticks a behavior tree until it completes.
+///
+/// This struct is created by `BehaviorTree::ticks`.
+pub struct Ticks<'t, 'a, W> {
+    /// The tree being ticked.
+    tree: &'t mut BehaviorTree<'a, W>,
+
+    /// The world the tree is ticked with.
+    world: &'t mut W,
+
+    /// Whether or not the tree has completed.
+    done: bool,
+}
+impl<'t, 'a, W> Iterator for Ticks<'t, 'a, W> {
+    type Item = Status;
+
+    fn next(&mut self) -> Option<Status> {
+        if self.done {
+            return None;
+        }
+
+        let status = self.tree.tick(self.world);
+        self.done = status.is_done();
+        Some(status)
+    }
+}
+
+/// A summary of a single run of a behavior tree, from its first tick until it
+/// completed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RunSummary {
+    /// The final status of the tree.
+    pub status: Status,
+
+    /// The number of ticks the run took.
+    pub ticks: u64,
+
+    /// The time between the start of the first tick and the end of the last.
+    pub elapsed: Duration,
+}
+impl RunSummary {
+    /// Returns a process exit code for the run: zero if the tree succeeded and
+    /// one if it failed.
+    pub fn exit_code(&self) -> i32 {
+        match self.status {
+            Status::Succeeded => 0,
+            _ => 1,
+        }
+    }
+}
+
 impl<'a, W> fmt::Display for BehaviorTree<'a, W> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             "BehaviorTree:( status = {:?}, root = {} )",
@@ -125,3 +652,215 @@ impl<'a, W> fmt::Display for BehaviorTree<'a, W> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::{self, LogPolicy, SendNode, TickContext, Tickable},
+        std_nodes::{
+            typed, AlwaysFail, AlwaysRunning, Condition, InlineAction, NoTick, Repeat, Sequence,
+        },
+        BehaviorTree, SendTree, Status,
+    };
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    fn assert_send<T: Send>() {}
+
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn send_trees_move_between_threads() {
+        assert_send::<SendNode<'static, u32>>();
+        assert_send::<SendTree<'static, u32>>();
+        assert_sync::<Mutex<SendTree<'static, u32>>>();
+
+        let (sender, receiver) = mpsc::channel();
+        let mut tree = SendTree::new(SendNode::selector(vec![
+            SendNode::new(typed::Condition::new(|&n: &u32| n >= 3)),
+            SendNode::new(typed::InlineAction::new(|n: &mut u32| {
+                *n += 1;
+                Status::Running
+            }))
+            .named(Some("Count")),
+        ]));
+        tree.on_complete(move |s| sender.send(s.ticks).unwrap());
+        let tree = Arc::new(Mutex::new(tree));
+
+        let ticker = Arc::clone(&tree);
+        thread::spawn(move || {
+            let mut count = 0;
+            while ticker.lock().unwrap().tick(&mut count) == Status::Running {}
+        })
+        .join()
+        .unwrap();
+
+        let tree = tree.lock().unwrap();
+        assert_eq!(tree.tree().root().children()[1].name(), "Count");
+        assert_eq!(receiver.try_recv(), Ok(4));
+    }
+
+    #[test]
+    fn nodes_see_the_tick_deadline() {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut seen = Vec::new();
+        let mut tree = BehaviorTree::new(InlineAction::with_context(
+            |_: &mut (), ctx: &TickContext| {
+                seen.push(ctx.deadline());
+                Status::Succeeded
+            },
+        ));
+
+        tree.tick(&mut ());
+        tree.tick_with_deadline(&mut (), deadline);
+        drop(tree);
+        assert_eq!(seen, vec![None, Some(deadline)]);
+        assert_eq!(TickContext::current().deadline(), None);
+    }
+
+    #[test]
+    fn on_complete_runs_once_per_run() {
+        let mut summaries = Vec::new();
+        let mut tree = BehaviorTree::new(Repeat::with_limit(2, AlwaysFail::new()));
+        tree.on_complete(|s| summaries.push((s.status, s.ticks, s.exit_code())));
+
+        for _ in 0..5 {
+            tree.tick(&mut ());
+        }
+        drop(tree);
+
+        assert_eq!(
+            summaries,
+            vec![(Status::Succeeded, 2, 0), (Status::Succeeded, 2, 0)]
+        );
+    }
+
+    #[test]
+    fn reset_restarts_the_run() {
+        let mut ticks = 0;
+        let mut tree = BehaviorTree::new(Repeat::with_limit(2, AlwaysFail::new()));
+        tree.on_complete(|s| ticks = s.ticks);
+
+        tree.tick(&mut ());
+        tree.reset();
+        tree.tick(&mut ());
+        tree.tick(&mut ());
+        drop(tree);
+
+        assert_eq!(ticks, 2);
+    }
+
+    #[test]
+    fn run_for_stops_at_deadline() {
+        let mut tree = BehaviorTree::new(AlwaysRunning::new());
+        let start = Instant::now();
+        let status = tree.run_for(
+            Duration::from_millis(50),
+            100.0,
+            &mut (),
+            None::<fn(&BehaviorTree<()>)>,
+        );
+        assert_eq!(status, Status::Running);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(tree.tick_count() <= 6);
+    }
+
+    #[test]
+    fn run_for_returns_early_when_done() {
+        let mut tree = BehaviorTree::new(Repeat::with_limit(3, AlwaysFail::new()));
+        let status = tree.run_for(
+            Duration::from_secs(60),
+            f64::INFINITY,
+            &mut (),
+            None::<fn(&BehaviorTree<()>)>,
+        );
+        assert_eq!(status, Status::Succeeded);
+        assert_eq!(tree.tick_count(), 3);
+    }
+
+    #[test]
+    fn tick_n() {
+        let mut tree = BehaviorTree::new(Repeat::with_limit(2, AlwaysFail::new()));
+        assert_eq!(tree.tick_n(0, &mut ()), None);
+        assert_eq!(tree.tick_n(2, &mut ()), Some(Status::Succeeded));
+        assert_eq!(tree.tick_n(3, &mut ()), Some(Status::Running));
+        assert_eq!(tree.tick_count(), 5);
+    }
+
+    #[test]
+    fn ticks_is_unbounded_for_running_trees() {
+        let mut tree = BehaviorTree::new(AlwaysRunning::new());
+        assert_eq!(tree.ticks(&mut ()).take(10).count(), 10);
+    }
+
+    #[test]
+    fn running_tree_never_completes() {
+        let mut completed = false;
+        let mut tree = BehaviorTree::new(AlwaysRunning::new());
+        tree.on_complete(|_| completed = true);
+        tree.tick(&mut ());
+        drop(tree);
+        assert!(!completed);
+    }
+
+    #[test]
+    fn dry_run_resumes_from_current_state() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            InlineAction::new(|_| Status::Succeeded),
+            InlineAction::new(|_| Status::Running),
+            Condition::new(|_| true),
+        ]));
+        assert_eq!(tree.dry_run(&()), vec![0, 1, 2, 3]);
+
+        tree.tick(&mut ());
+        assert_eq!(tree.dry_run(&()), vec![0, 2, 3]);
+        assert_eq!(tree.dry_run_with(&(), |_| Status::Failed), vec![0, 2]);
+    }
+
+    #[test]
+    fn dry_run_does_not_tick_actions() {
+        let tree = BehaviorTree::new(Sequence::new(vec![NoTick::new(), NoTick::new()]));
+        assert_eq!(tree.dry_run(&()), vec![0, 1, 2]);
+        assert_eq!(tree.root().status(), None);
+    }
+
+    #[test]
+    fn dry_run_after_completion_starts_over() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            InlineAction::new(|_| Status::Succeeded),
+            InlineAction::new(|_| Status::Succeeded),
+        ]));
+        tree.tick(&mut ());
+        assert_eq!(tree.dry_run(&()), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn panicking_tick_can_be_ticked_again() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![InlineAction::new(
+            |panicked: &mut bool| {
+                if !*panicked {
+                    *panicked = true;
+                    panic!("interrupted");
+                }
+                Status::Succeeded
+            },
+        )
+        .named(Some("Faulty"))]))
+        .named(Some("mission"))
+        .with_log_policy(LogPolicy::transitions_only());
+
+        let mut panicked = false;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| tree.tick(&mut panicked)));
+        assert!(result.is_err());
+        assert_eq!(
+            node::set_log_policy(LogPolicy::every_tick()),
+            LogPolicy::every_tick()
+        );
+
+        assert_eq!(tree.tick(&mut panicked), Status::Succeeded);
+    }
+}
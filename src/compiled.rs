@@ -0,0 +1,364 @@
+//! Trees lowered to flat tables for hard real-time execution.
+//!
+//! A `Spec` describes a tree whose leaves are plain function pointers, so it
+//! holds no closures or trait objects. `compile` lowers it into a `Program`: a
+//! table of operations in pre-order, with the state of every node kept in
+//! arrays which are allocated once, up front. Ticking a program never
+//! allocates, visits each operation at most once, and recurses no deeper than
+//! the tree, so the worst-case execution time of a tick is bounded by
+//! `Program::len` calls to the leaves plus bookkeeping.
+//!
+//! The nodes have the semantics of their counterparts in `std_nodes`: a
+//! `Sequence` resumes from the child it stopped at, while a `Selector` starts
+//! from its first child on every tick and resets the children after the one
+//! which did not fail.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::compiled::{compile, Spec};
+//! # use aspen::Status;
+//! fn is_charged(battery: &u32) -> bool {
+//!     *battery >= 80
+//! }
+//!
+//! fn charge(battery: &mut u32) -> Status {
+//!     *battery += 10;
+//!     if *battery >= 80 {
+//!         Status::Succeeded
+//!     } else {
+//!         Status::Running
+//!     }
+//! }
+//!
+//! let mut program = compile(&Spec::Selector(vec![
+//!     Spec::Condition(is_charged),
+//!     Spec::Action(charge),
+//! ]));
+//!
+//! let mut battery = 60;
+//! assert_eq!(program.tick(&mut battery), Status::Running);
+//! assert_eq!(program.tick(&mut battery), Status::Succeeded);
+//! assert_eq!(program.tick(&mut battery), Status::Succeeded);
+//! assert_eq!(program.len(), 3);
+//! ```
+use crate::status::Status;
+use std::fmt;
+
+/// A description of a tree which can be compiled into a `Program`.
+pub enum Spec<W> {
+    /// Ticks its children in order while they succeed.
+    Sequence(Vec<Spec<W>>),
+
+    /// Ticks its children in order while they fail.
+    Selector(Vec<Spec<W>>),
+
+    /// Inverts the status of its child.
+    Invert(Box<Spec<W>>),
+
+    /// Succeeds if the function returns `true`, and fails otherwise.
+    Condition(fn(&W) -> bool),
+
+    /// Returns the status returned by the function.
+    Action(fn(&mut W) -> Status),
+}
+impl<W> Clone for Spec<W> {
+    fn clone(&self) -> Self {
+        match self {
+            Spec::Sequence(c) => Spec::Sequence(c.clone()),
+            Spec::Selector(c) => Spec::Selector(c.clone()),
+            Spec::Invert(c) => Spec::Invert(c.clone()),
+            Spec::Condition(f) => Spec::Condition(*f),
+            Spec::Action(f) => Spec::Action(*f),
+        }
+    }
+}
+impl<W> fmt::Debug for Spec<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Spec::Sequence(c) => f.debug_tuple("Sequence").field(c).finish(),
+            Spec::Selector(c) => f.debug_tuple("Selector").field(c).finish(),
+            Spec::Invert(c) => f.debug_tuple("Invert").field(c).finish(),
+            Spec::Condition(_) => f.write_str("Condition"),
+            Spec::Action(_) => f.write_str("Action"),
+        }
+    }
+}
+
+/// Compiles a spec into a program.
+pub fn compile<W>(spec: &Spec<W>) -> Program<W> {
+    let mut ops = Vec::new();
+    let depth = lower(spec, &mut ops);
+    let len = ops.len();
+    Program {
+        ops,
+        cursor: (1..=len).collect(),
+        status: vec![None; len],
+        depth,
+    }
+}
+
+/// Appends the operations for `spec` to `ops`, returning its depth.
+fn lower<W>(spec: &Spec<W>, ops: &mut Vec<Op<W>>) -> usize {
+    let index = ops.len();
+    let (kind, children): (_, &[Spec<W>]) = match spec {
+        Spec::Sequence(c) => (Kind::Sequence, c),
+        Spec::Selector(c) => (Kind::Selector, c),
+        Spec::Invert(c) => (Kind::Invert, std::slice::from_ref(&**c)),
+        Spec::Condition(f) => (Kind::Condition(*f), &[]),
+        Spec::Action(f) => (Kind::Action(*f), &[]),
+    };
+    ops.push(Op { kind, end: 0 });
+
+    let depth = children.iter().map(|c| lower(c, ops)).max().unwrap_or(0);
+    ops[index].end = ops.len();
+    depth + 1
+}
+
+/// A tree lowered to a flat table of operations.
+pub struct Program<W> {
+    /// The operations, in pre-order.
+    ops: Vec<Op<W>>,
+
+    /// For each sequence, the index of the child to tick next.
+    cursor: Vec<usize>,
+
+    /// The status of each operation from its last tick.
+    status: Vec<Option<Status>>,
+
+    /// The depth of the tree.
+    depth: usize,
+}
+impl<W> Program<W> {
+    /// Ticks the program a single time.
+    ///
+    /// If the program has already completed, it is reset first, in the same
+    /// way as a `BehaviorTree`.
+    pub fn tick(&mut self, world: &mut W) -> Status {
+        if self.status[0].map_or(false, |s| s.is_done()) {
+            self.reset();
+        }
+        self.run(0, world)
+    }
+
+    /// Resets every node to its initial state.
+    pub fn reset(&mut self) {
+        self.reset_range(0, self.ops.len());
+    }
+
+    /// Returns the status of the root from its last tick.
+    pub fn status(&self) -> Option<Status> {
+        self.status[0]
+    }
+
+    /// Returns the statuses of all of the nodes, in pre-order.
+    pub fn statuses(&self) -> &[Option<Status>] {
+        &self.status
+    }
+
+    /// Returns the number of nodes, which bounds the number of leaves called
+    /// in a tick.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns `true` if the program has no nodes, which is never the case.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Returns the depth of the tree, which bounds the recursion of a tick.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Ticks the node with the given index.
+    fn run(&mut self, index: usize, world: &mut W) -> Status {
+        let status = match self.ops[index].kind {
+            Kind::Condition(f) => {
+                if f(world) {
+                    Status::Succeeded
+                } else {
+                    Status::Failed
+                }
+            }
+            Kind::Action(f) => f(world),
+            Kind::Invert => match self.run(index + 1, world) {
+                Status::Succeeded => Status::Failed,
+                Status::Failed => Status::Succeeded,
+                s => s,
+            },
+            Kind::Sequence => self.run_sequence(index, world),
+            Kind::Selector => self.run_selector(index, world),
+        };
+
+        self.status[index] = Some(status);
+        status
+    }
+
+    /// Ticks the children of a sequence while they succeed, starting from the
+    /// one it stopped at.
+    fn run_sequence(&mut self, index: usize, world: &mut W) -> Status {
+        let end = self.ops[index].end;
+        let mut status = Status::Succeeded;
+        while self.cursor[index] < end && status == Status::Succeeded {
+            let child = self.cursor[index];
+            status = self.run(child, world);
+
+            if status.is_done() {
+                self.cursor[index] = self.ops[child].end;
+            }
+        }
+
+        status
+    }
+
+    /// Ticks the children of a selector while they fail, then resets the rest.
+    fn run_selector(&mut self, index: usize, world: &mut W) -> Status {
+        let end = self.ops[index].end;
+        let mut status = Status::Failed;
+        let mut child = index + 1;
+        while child < end {
+            let next = self.ops[child].end;
+            if status == Status::Failed {
+                status = self.run(child, world);
+            } else {
+                self.reset_range(child, next);
+            }
+            child = next;
+        }
+
+        status
+    }
+
+    /// Resets the nodes with indices in `start..end`, which must be whole
+    /// subtrees.
+    fn reset_range(&mut self, start: usize, end: usize) {
+        for i in start..end {
+            self.status[i] = None;
+            self.cursor[i] = i + 1;
+        }
+    }
+}
+impl<W> fmt::Debug for Program<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Program")
+            .field("len", &self.ops.len())
+            .field("depth", &self.depth)
+            .field("status", &self.status[0])
+            .finish()
+    }
+}
+
+/// A single node of a program.
+struct Op<W> {
+    /// What the node does.
+    kind: Kind<W>,
+
+    /// One past the index of the node's last descendant.
+    end: usize,
+}
+
+/// The kinds of node in a program.
+enum Kind<W> {
+    /// A `Spec::Sequence`.
+    Sequence,
+
+    /// A `Spec::Selector`.
+    Selector,
+
+    /// A `Spec::Invert`.
+    Invert,
+
+    /// A `Spec::Condition`.
+    Condition(fn(&W) -> bool),
+
+    /// A `Spec::Action`.
+    Action(fn(&mut W) -> Status),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, Spec};
+    use crate::{
+        node::Node,
+        std_nodes::{Condition, InlineAction, Invert, Selector, Sequence},
+        BehaviorTree, Status,
+    };
+
+    /// A world which counts down, and records the leaves which were called.
+    #[derive(Default)]
+    struct World {
+        count: u32,
+        calls: Vec<u8>,
+    }
+
+    fn even(w: &World) -> bool {
+        w.count % 2 == 0
+    }
+
+    fn step(w: &mut World) -> Status {
+        w.calls.push(1);
+        match w.count.checked_sub(1) {
+            Some(n) => {
+                w.count = n;
+                Status::Running
+            }
+            None => Status::Succeeded,
+        }
+    }
+
+    fn fail(w: &mut World) -> Status {
+        w.calls.push(2);
+        Status::Failed
+    }
+
+    fn spec() -> Spec<World> {
+        Spec::Selector(vec![
+            Spec::Sequence(vec![Spec::Condition(even), Spec::Action(fail)]),
+            Spec::Sequence(vec![
+                Spec::Invert(Box::new(Spec::Action(fail))),
+                Spec::Action(step),
+            ]),
+        ])
+    }
+
+    fn dynamic() -> Node<'static, World> {
+        Selector::new(vec![
+            Sequence::new(vec![Condition::new(even), InlineAction::new(fail)]),
+            Sequence::new(vec![
+                Invert::new(InlineAction::new(fail)),
+                InlineAction::new(step),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn matches_the_dynamic_tree() {
+        for count in 0..6 {
+            let mut program = compile(&spec());
+            let mut tree = BehaviorTree::new(dynamic());
+            let mut a = World {
+                count,
+                ..World::default()
+            };
+            let mut b = World {
+                count,
+                ..World::default()
+            };
+
+            for _ in 0..10 {
+                assert_eq!(program.tick(&mut a), tree.tick(&mut b));
+                assert_eq!(a.calls, b.calls);
+            }
+        }
+    }
+
+    #[test]
+    fn layout_is_preorder() {
+        let program = compile(&spec());
+        assert_eq!(program.len(), 8);
+        assert_eq!(program.depth(), 4);
+        assert!(program.statuses().iter().all(Option::is_none));
+    }
+}
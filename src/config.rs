@@ -0,0 +1,279 @@
+//! Read-only tuning constants, kept apart from the world.
+//!
+//! Thresholds, speeds and timeouts are properties of a deployment rather than
+//! state, so they do not belong in the world that nodes mutate. A `Config`
+//! holds them as text, parsed into the expected type when they are read, and
+//! is usually loaded from a file. Nodes built in code can read it when they
+//! are constructed, trees loaded from files can use its values in `${name}`
+//! placeholders through `Loader::config`, and closures can read it while they
+//! are ticked through `Config::current`.
+//!
+//! A `ConfigHandle` shares a config between trees and threads and lets it be
+//! replaced or reloaded from its file while the trees run. Each tick of a tree
+//! given a handle with `BehaviorTree::with_config` sees a single snapshot, so a
+//! reload takes effect from the next tick without changing any state.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::config::{Config, ConfigHandle};
+//! # use aspen::std_nodes::*;
+//! # use aspen::{BehaviorTree, Status};
+//! let mut config = Config::new();
+//! config.set("min_battery", 0.2);
+//! let handle = ConfigHandle::new(config);
+//!
+//! let low_battery = Condition::new(|&battery: &f64| {
+//!     battery < Config::current().map_or(0.0, |c| c.get_or("min_battery", 0.0))
+//! });
+//! let mut tree = BehaviorTree::new(low_battery).with_config(handle.clone());
+//! assert_eq!(tree.tick(&mut 0.3), Status::Failed);
+//!
+//! let mut config = Config::new();
+//! config.set("min_battery", 0.4);
+//! handle.replace(config);
+//! assert_eq!(tree.tick(&mut 0.3), Status::Succeeded);
+//! ```
+use crate::json::{from_json, push_string};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+thread_local! {
+    /// The config of the tree being ticked on this thread.
+    static CURRENT: RefCell<Option<Arc<Config>>> = RefCell::new(None);
+}
+
+/// A set of named tuning constants.
+///
+/// Values are stored as text and parsed with `FromStr` when they are read. On
+/// file, a config is a JSON object mapping keys to strings, the same as a
+/// `BlackboardFile`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The values, as text.
+    values: HashMap<String, String>,
+}
+impl Config {
+    /// Creates an empty config.
+    pub fn new() -> Self {
+        Config::default()
+    }
+
+    /// Parses a config from the text of a config file.
+    pub fn parse(text: &str) -> Option<Self> {
+        let values = from_json(text)?.into_iter().collect();
+        Some(Config { values })
+    }
+
+    /// Loads a config from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        Config::parse(&text).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{:?} is not a config file", path),
+            )
+        })
+    }
+
+    /// Returns the config of the tree being ticked on this thread, if it was
+    /// given one.
+    ///
+    /// This is a snapshot taken when the tick started, so it does not change
+    /// during the tick even if the config is reloaded.
+    pub fn current() -> Option<Arc<Config>> {
+        CURRENT.with(|c| c.borrow().clone())
+    }
+
+    /// Sets the value with the given key.
+    pub fn set<K: Into<String>, T: ToString>(&mut self, key: K, value: T) {
+        self.values.insert(key.into(), value.to_string());
+    }
+
+    /// Returns the value with the given key as text.
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns the value with the given key, parsed as a `T`.
+    ///
+    /// Returns `None` if there is no such value, or if it cannot be parsed, in
+    /// which case a warning is logged.
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        let text = self.values.get(key)?;
+        let value = text.parse().ok();
+        if value.is_none() {
+            warn!("Config value {} = {:?} is not valid", key, text);
+        }
+        value
+    }
+
+    /// Returns the value with the given key, or `default` if it is missing or
+    /// invalid.
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// Returns `true` if there is a value with the given key.
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Returns the keys of the values, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// Writes the config in the format read by `parse`, with sorted keys.
+    pub fn to_text(&self) -> String {
+        let mut keys: Vec<_> = self.values.keys().collect();
+        keys.sort();
+
+        let mut json = String::from("{\n");
+        for (i, key) in keys.iter().enumerate() {
+            json.push_str("  ");
+            push_string(&mut json, key);
+            json.push_str(": ");
+            push_string(&mut json, &self.values[*key]);
+            json.push_str(if i + 1 < keys.len() { ",\n" } else { "\n" });
+        }
+        json.push_str("}\n");
+        json
+    }
+}
+
+/// A shared, replaceable config.
+///
+/// Clones of a handle share the same config. Readers take a snapshot with
+/// `get`, which stays valid however the config is later replaced.
+#[derive(Clone)]
+pub struct ConfigHandle {
+    /// The current config.
+    config: Arc<RwLock<Arc<Config>>>,
+
+    /// The file the config is reloaded from, if any.
+    path: Option<PathBuf>,
+}
+impl ConfigHandle {
+    /// Creates a handle to the given config.
+    pub fn new(config: Config) -> Self {
+        ConfigHandle {
+            config: Arc::new(RwLock::new(Arc::new(config))),
+            path: None,
+        }
+    }
+
+    /// Creates a handle to the config in a file, which `reload` reads again.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut handle = ConfigHandle::new(Config::load(path)?);
+        handle.path = Some(path.to_owned());
+        Ok(handle)
+    }
+
+    /// Returns a snapshot of the current config.
+    pub fn get(&self) -> Arc<Config> {
+        match self.config.read() {
+            Ok(config) => config.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Replaces the config for every clone of this handle.
+    pub fn replace(&self, config: Config) {
+        let config = Arc::new(config);
+        match self.config.write() {
+            Ok(mut current) => *current = config,
+            Err(poisoned) => *poisoned.into_inner() = config,
+        }
+    }
+
+    /// Returns the file the config is reloaded from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Reads the config file again, replacing the config.
+    ///
+    /// If the file cannot be read or parsed, the config is left as it was and
+    /// the error is returned. Returns an error of kind `NotFound` if the handle
+    /// was not created from a file.
+    pub fn reload(&self) -> io::Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "config has no file"))?;
+        let config = Config::load(path)?;
+        debug!("Reloaded config from {:?}", path);
+        self.replace(config);
+        Ok(())
+    }
+}
+impl fmt::Debug for ConfigHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigHandle")
+            .field("config", &self.get())
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Makes a config current on this thread until it is dropped.
+pub(crate) struct ConfigGuard(Option<Arc<Config>>);
+impl ConfigGuard {
+    /// Sets the current config of the thread until the guard is dropped.
+    pub(crate) fn set(config: Arc<Config>) -> Self {
+        ConfigGuard(CURRENT.with(|c| c.replace(Some(config))))
+    }
+}
+impl Drop for ConfigGuard {
+    fn drop(&mut self) {
+        let outer = self.0.take();
+        CURRENT.with(|c| *c.borrow_mut() = outer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ConfigHandle};
+    use std::{env, fs};
+
+    #[test]
+    fn reload_keeps_snapshots() {
+        let dir = env::temp_dir().join(format!("aspen-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+
+        let mut config = Config::new();
+        config.set("speed", 0.5);
+        config.set("name", "rover");
+        fs::write(&path, config.to_text()).unwrap();
+
+        let handle = ConfigHandle::from_file(&path).unwrap();
+        let before = handle.get();
+        assert_eq!(before.get::<f64>("speed"), Some(0.5));
+        assert_eq!(before.get::<f64>("name"), None);
+        assert_eq!(before.get_or("retries", 3), 3);
+
+        fs::write(&path, "{\"speed\": \"1.5\"}").unwrap();
+        handle.clone().reload().unwrap();
+        assert_eq!(handle.get().get::<f64>("speed"), Some(1.5));
+        assert_eq!(before.get::<f64>("speed"), Some(0.5));
+
+        // A broken file leaves the config as it was
+        fs::write(&path, "{\"speed\": 2}").unwrap();
+        assert!(handle.reload().is_err());
+        assert_eq!(handle.get().text("speed"), Some("1.5"));
+
+        assert!(ConfigHandle::new(Config::new()).reload().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+//! A gRPC server for controlling a running tree.
+//!
+//! `ControlService` implements the `TreeControl` service described by
+//! `proto/control.proto` in the repository, by forwarding each call to a
+//! `Controller`. It is served with tonic, usually from a thread of its own
+//! while the tree is ticked on another, since the controller is the only part
+//! which needs to be shared. The messages and the server are in `pb`, which is
+//! generated from the same file. Consoles generate their clients from it too.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use aspen::blackboard::Blackboard;
+//! # use aspen::control::{grpc::ControlService, ControlledTree};
+//! # use aspen::std_nodes::*;
+//! # use aspen::BehaviorTree;
+//! # use std::thread;
+//! let mut tree = ControlledTree::new(BehaviorTree::new(AlwaysRunning::new()));
+//! let service = ControlService::new(tree.controller());
+//!
+//! thread::spawn(move || {
+//!     let runtime = tokio::runtime::Builder::new_current_thread()
+//!         .enable_all()
+//!         .build()
+//!         .unwrap();
+//!     runtime.block_on(
+//!         tonic::transport::Server::builder()
+//!             .add_service(service.into_server())
+//!             .serve("0.0.0.0:50051".parse().unwrap()),
+//!     )
+//! });
+//!
+//! let mut blackboard = Blackboard::new();
+//! loop {
+//!     tree.tick(&mut blackboard);
+//!     # break;
+//! }
+//! ```
+use super::Controller;
+use crate::status::Status;
+use std::time::Duration;
+use tokio::{sync::mpsc, time};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response};
+
+/// The messages of the service, and its generated server.
+#[allow(missing_docs, clippy::all, clippy::pedantic)]
+#[rustfmt::skip]
+pub mod pb;
+use self::pb::tree_control_server::{TreeControl, TreeControlServer};
+
+/// The default time between checks for new ticks when streaming statuses.
+const DEFAULT_WATCH_PERIOD: Duration = Duration::from_millis(100);
+
+/// The `TreeControl` gRPC service, backed by a `Controller`.
+#[derive(Clone)]
+pub struct ControlService {
+    /// The controller the calls are forwarded to.
+    controller: Controller,
+
+    /// How often streams of statuses check for new ticks.
+    watch_period: Duration,
+}
+impl ControlService {
+    /// Creates a service which controls a tree through `controller`.
+    pub fn new(controller: Controller) -> Self {
+        ControlService {
+            controller,
+            watch_period: DEFAULT_WATCH_PERIOD,
+        }
+    }
+
+    /// Sets how often streams of statuses check for new ticks.
+    ///
+    /// A stream sends the statuses at most once per period, so ticks in
+    /// between are not seen. The default is 100ms.
+    pub fn with_watch_period(mut self, period: Duration) -> Self {
+        self.watch_period = period;
+        self
+    }
+
+    /// Wraps the service in a server, ready to be added to a tonic router.
+    pub fn into_server(self) -> TreeControlServer<Self> {
+        TreeControlServer::new(self)
+    }
+}
+
+/// Returns the statuses of the tree as a message.
+fn statuses(controller: &Controller) -> pb::Statuses {
+    pb::Statuses {
+        tick_count: controller.tick_count(),
+        paused: controller.is_paused(),
+        statuses: controller
+            .statuses()
+            .into_iter()
+            .map(|s| status_code(s) as i32)
+            .collect(),
+    }
+}
+
+/// Converts the status of a node to its message form.
+fn status_code(status: Option<Status>) -> pb::Status {
+    match status {
+        None => pb::Status::NotTicked,
+        Some(Status::Running) => pb::Status::Running,
+        Some(Status::Succeeded) => pb::Status::Succeeded,
+        Some(Status::Failed) => pb::Status::Failed,
+    }
+}
+
+/// The result of a call.
+type Reply<T> = Result<Response<T>, tonic::Status>;
+
+#[tonic::async_trait]
+impl TreeControl for ControlService {
+    async fn get_structure(&self, _: Request<pb::Empty>) -> Reply<pb::Structure> {
+        let nodes = self
+            .controller
+            .structure()
+            .into_iter()
+            .map(|n| pb::Node {
+                id: n.id() as u32,
+                parent: n.parent().map(|p| p as u32),
+                name: n.name().to_owned(),
+                type_name: n.type_name().to_owned(),
+            })
+            .collect();
+        Ok(Response::new(pb::Structure { nodes }))
+    }
+
+    async fn get_statuses(&self, _: Request<pb::Empty>) -> Reply<pb::Statuses> {
+        Ok(Response::new(statuses(&self.controller)))
+    }
+
+    type WatchStatusesStream = ReceiverStream<Result<pb::Statuses, tonic::Status>>;
+
+    async fn watch_statuses(&self, _: Request<pb::Empty>) -> Reply<Self::WatchStatusesStream> {
+        let (sender, receiver) = mpsc::channel(4);
+        let controller = self.controller.clone();
+        let mut interval = time::interval(self.watch_period);
+        tokio::spawn(async move {
+            let mut last = None;
+            loop {
+                interval.tick().await;
+                let tick_count = controller.tick_count();
+                if last == Some(tick_count) {
+                    continue;
+                }
+                last = Some(tick_count);
+                if sender.send(Ok(statuses(&controller))).await.is_err() {
+                    // The client went away
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn pause(&self, _: Request<pb::Empty>) -> Reply<pb::Empty> {
+        self.controller.pause();
+        Ok(Response::new(pb::Empty {}))
+    }
+
+    async fn resume(&self, _: Request<pb::Empty>) -> Reply<pb::Empty> {
+        self.controller.resume();
+        Ok(Response::new(pb::Empty {}))
+    }
+
+    async fn reset(&self, _: Request<pb::Empty>) -> Reply<pb::Empty> {
+        self.controller.reset();
+        Ok(Response::new(pb::Empty {}))
+    }
+
+    async fn set_enabled(&self, request: Request<pb::Override>) -> Reply<pb::Empty> {
+        let request = request.into_inner();
+        if self.controller.set_enabled(&request.name, request.enabled) {
+            Ok(Response::new(pb::Empty {}))
+        } else {
+            Err(tonic::Status::not_found(format!(
+                "no node is exposed as {}",
+                request.name
+            )))
+        }
+    }
+
+    async fn read_blackboard(&self, _: Request<pb::Empty>) -> Reply<pb::Blackboard> {
+        let entries = self
+            .controller
+            .read_blackboard()
+            .into_iter()
+            .map(|(key, value)| pb::Entry { key, value })
+            .collect();
+        Ok(Response::new(pb::Blackboard { entries }))
+    }
+
+    async fn write_blackboard(&self, request: Request<pb::Entry>) -> Reply<pb::Empty> {
+        let entry = request.into_inner();
+        self.controller.write_blackboard(entry.key, entry.value);
+        Ok(Response::new(pb::Empty {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pb, pb::tree_control_server::TreeControl, ControlService};
+    use crate::{
+        blackboard::Blackboard,
+        control::ControlledTree,
+        std_nodes::{AlwaysRunning, AlwaysSucceed, Sequence},
+        BehaviorTree,
+    };
+    use std::time::Duration;
+    use tokio_stream::StreamExt;
+    use tonic::{Code, Request};
+
+    #[test]
+    fn calls_are_forwarded_to_the_controller() {
+        let mut tree = ControlledTree::new(BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new(),
+            AlwaysRunning::new(),
+        ])));
+        let service =
+            ControlService::new(tree.controller()).with_watch_period(Duration::from_millis(1));
+        let mut blackboard = Blackboard::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let empty = || Request::new(pb::Empty {});
+
+        let structure = runtime
+            .block_on(service.get_structure(empty()))
+            .unwrap()
+            .into_inner();
+        assert_eq!(structure.nodes[2].parent, Some(0));
+        assert_eq!(structure.nodes[2].type_name, "AlwaysRunning");
+
+        let mut watch = runtime
+            .block_on(service.watch_statuses(empty()))
+            .unwrap()
+            .into_inner();
+        let first = runtime.block_on(watch.next()).unwrap().unwrap();
+        assert_eq!(first.tick_count, 0);
+
+        let entry = pb::Entry {
+            key: "goal".to_owned(),
+            value: "dock".to_owned(),
+        };
+        runtime
+            .block_on(service.write_blackboard(Request::new(entry)))
+            .unwrap();
+        tree.tick(&mut blackboard);
+        let read = runtime
+            .block_on(service.read_blackboard(empty()))
+            .unwrap()
+            .into_inner();
+        assert_eq!(read.entries[0].value, "dock");
+
+        let next = runtime.block_on(watch.next()).unwrap().unwrap();
+        assert_eq!(next.tick_count, 1);
+        let running = pb::Status::Running as i32;
+        assert_eq!(
+            next.statuses,
+            vec![running, pb::Status::Succeeded as i32, running]
+        );
+
+        runtime.block_on(service.pause(empty())).unwrap();
+        assert_eq!(tree.tick(&mut blackboard), None);
+
+        let unknown = pb::Override {
+            name: "Dock".to_owned(),
+            enabled: false,
+        };
+        let error = runtime
+            .block_on(service.set_enabled(Request::new(unknown)))
+            .unwrap_err();
+        assert_eq!(error.code(), Code::NotFound);
+    }
+}
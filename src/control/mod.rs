@@ -0,0 +1,371 @@
+//! Controlling and monitoring a running tree from other threads.
+//!
+//! A `ControlledTree` stays on the thread that ticks it, like a `SharedTree`,
+//! and hands out `Controller`s which can be used from any thread. A controller
+//! can read the structure of the tree and the latest statuses, pause, resume
+//! and reset the tree, switch exposed nodes on and off, and read and write the
+//! blackboard. Commands are applied at the start of the next tick, so the tree
+//! is never changed while it is being ticked.
+//!
+//! This is the surface of an operator console. `proto/control.proto` in the
+//! repository describes the same operations as a gRPC service, which the
+//! `grpc` module serves with tonic when the `grpc` feature is enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::blackboard::Blackboard;
+//! # use aspen::control::ControlledTree;
+//! # use aspen::std_nodes::*;
+//! # use aspen::{BehaviorTree, Status};
+//! let mut tree = ControlledTree::new(BehaviorTree::new(Sequence::new(vec![
+//!     AlwaysSucceed::new(),
+//!     AlwaysRunning::new(),
+//! ])));
+//! let controller = tree.controller();
+//! let mut blackboard = Blackboard::new();
+//!
+//! controller.write_blackboard("goal", "dock");
+//! assert_eq!(tree.tick(&mut blackboard), Some(Status::Running));
+//! assert_eq!(blackboard.get::<String>("goal").unwrap(), "dock");
+//! assert_eq!(controller.structure()[2].type_name(), "AlwaysRunning");
+//!
+//! controller.pause();
+//! assert_eq!(tree.tick(&mut blackboard), None);
+//! assert!(controller.is_paused());
+//! ```
+use crate::{
+    blackboard::Blackboard,
+    monitor,
+    node::{EnableSwitch, Node, Tickable},
+    BehaviorTree, Status,
+};
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+/// A node of a controlled tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeSummary {
+    /// The index of the node in pre-order.
+    id: usize,
+
+    /// The ID of the parent of the node.
+    parent: Option<usize>,
+
+    /// The name of the node.
+    name: String,
+
+    /// The type of the node.
+    type_name: String,
+}
+impl NodeSummary {
+    /// Returns the index of the node in pre-order.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the ID of the parent of the node, which is `None` for the root.
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// Returns the name of the node.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type of the node.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+}
+
+/// A change requested by a controller, applied before the next tick.
+enum Command {
+    /// Reset the tree.
+    Reset,
+
+    /// Set a blackboard entry to a string.
+    Write(String, String),
+
+    /// Remove a blackboard entry.
+    Remove(String),
+}
+
+/// The state shared between a tree and its controllers.
+#[derive(Default)]
+struct State {
+    /// The nodes of the tree, in pre-order.
+    structure: Vec<NodeSummary>,
+
+    /// The status of every node, in pre-order.
+    statuses: Vec<Option<Status>>,
+
+    /// The number of times the tree had been ticked.
+    tick_count: u64,
+
+    /// Whether ticking is paused.
+    paused: bool,
+
+    /// The blackboard entries with a text form, sorted by key.
+    blackboard: Vec<(String, String)>,
+
+    /// Commands which have not yet been applied.
+    commands: Vec<Command>,
+
+    /// The switches of the exposed nodes, by name.
+    switches: HashMap<String, EnableSwitch>,
+}
+
+/// A behavior tree which can be controlled from other threads.
+pub struct ControlledTree<'a, W> {
+    /// The tree itself.
+    tree: BehaviorTree<'a, W>,
+
+    /// The state shared with the controllers.
+    state: Arc<Mutex<State>>,
+}
+impl<'a, W> ControlledTree<'a, W>
+where
+    W: AsMut<Blackboard>,
+{
+    /// Wraps `tree`, publishing its structure and current state.
+    pub fn new(tree: BehaviorTree<'a, W>) -> Self {
+        let mut state = State::default();
+        summarise(tree.root(), None, &mut state.structure);
+        monitor::collect_statuses(tree.root(), &mut state.statuses);
+        ControlledTree {
+            tree,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Returns a new controller for the tree.
+    pub fn controller(&self) -> Controller {
+        Controller {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    /// Lets controllers enable and disable a node by name.
+    ///
+    /// The switch is usually taken with `Node::enable_switch` while the tree
+    /// is built.
+    pub fn expose<N: Into<String>>(&mut self, name: N, switch: EnableSwitch) {
+        lock(&self.state).switches.insert(name.into(), switch);
+    }
+
+    /// Returns a reference to the tree.
+    pub fn tree(&self) -> &BehaviorTree<'a, W> {
+        &self.tree
+    }
+
+    /// Applies the pending commands, then ticks the tree once unless it is
+    /// paused, and publishes its new state.
+    ///
+    /// Returns the status of the tree, or `None` if it is paused.
+    pub fn tick(&mut self, world: &mut W) -> Option<Status> {
+        let (commands, paused) = {
+            let mut state = lock(&self.state);
+            (mem::take(&mut state.commands), state.paused)
+        };
+
+        for command in commands {
+            let blackboard = world.as_mut();
+            match command {
+                Command::Reset => self.tree.reset(),
+                Command::Write(key, value) => blackboard.set(key, value),
+                Command::Remove(key) => {
+                    blackboard.remove(&key);
+                }
+            }
+        }
+
+        let status = if paused {
+            None
+        } else {
+            Some(self.tree.tick(world))
+        };
+        self.publish(world.as_mut());
+        status
+    }
+
+    /// Returns the wrapped tree.
+    pub fn into_inner(self) -> BehaviorTree<'a, W> {
+        self.tree
+    }
+
+    /// Copies the state of the tree and the blackboard for the controllers.
+    fn publish(&self, blackboard: &Blackboard) {
+        let mut entries: Vec<_> = blackboard
+            .keys()
+            .filter_map(|k| Some((k.to_owned(), blackboard.get_text(k)?)))
+            .collect();
+        entries.sort_unstable();
+
+        let mut state = lock(&self.state);
+        state.tick_count = self.tree.tick_count();
+        monitor::collect_statuses(self.tree.root(), &mut state.statuses);
+        state.blackboard = entries;
+    }
+}
+
+/// A handle which controls a `ControlledTree`, and can be sent to and shared
+/// between threads.
+///
+/// Nodes are identified by their index in a pre-order traversal of the tree,
+/// so the root node has ID zero.
+#[derive(Clone)]
+pub struct Controller {
+    /// The state shared with the tree.
+    state: Arc<Mutex<State>>,
+}
+impl Controller {
+    /// Returns the nodes of the tree, in pre-order.
+    pub fn structure(&self) -> Vec<NodeSummary> {
+        lock(&self.state).structure.clone()
+    }
+
+    /// Returns the status of every node as of the latest tick, in
+    /// pre-order.
+    pub fn statuses(&self) -> Vec<Option<Status>> {
+        lock(&self.state).statuses.clone()
+    }
+
+    /// Returns the number of times the tree had been ticked when its state
+    /// was published.
+    pub fn tick_count(&self) -> u64 {
+        lock(&self.state).tick_count
+    }
+
+    /// Stops the tree from being ticked until `resume` is called.
+    pub fn pause(&self) {
+        lock(&self.state).paused = true;
+    }
+
+    /// Lets the tree be ticked again after `pause`.
+    pub fn resume(&self) {
+        lock(&self.state).paused = false;
+    }
+
+    /// Returns `true` if the tree is paused.
+    pub fn is_paused(&self) -> bool {
+        lock(&self.state).paused
+    }
+
+    /// Resets the tree before its next tick.
+    pub fn reset(&self) {
+        lock(&self.state).commands.push(Command::Reset);
+    }
+
+    /// Enables or disables an exposed node, returning `false` if no node was
+    /// exposed with the given name.
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        match lock(&self.state).switches.get(name) {
+            Some(switch) => {
+                switch.set(enabled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the names of the exposed nodes, in alphabetical order.
+    pub fn exposed(&self) -> Vec<String> {
+        let mut names: Vec<_> = lock(&self.state).switches.keys().cloned().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the blackboard entries which have a text form, sorted by key,
+    /// as of the latest tick.
+    pub fn read_blackboard(&self) -> Vec<(String, String)> {
+        lock(&self.state).blackboard.clone()
+    }
+
+    /// Sets a blackboard entry to a `String` before the next tick.
+    pub fn write_blackboard<K: Into<String>, V: Into<String>>(&self, key: K, value: V) {
+        let command = Command::Write(key.into(), value.into());
+        lock(&self.state).commands.push(command);
+    }
+
+    /// Removes a blackboard entry before the next tick.
+    pub fn remove_blackboard<K: Into<String>>(&self, key: K) {
+        lock(&self.state).commands.push(Command::Remove(key.into()));
+    }
+}
+
+/// Appends summaries of `node` and its descendants, in pre-order.
+fn summarise<W>(node: &Node<'_, W>, parent: Option<usize>, nodes: &mut Vec<NodeSummary>) {
+    let id = nodes.len();
+    nodes.push(NodeSummary {
+        id,
+        parent,
+        name: node.name().to_owned(),
+        type_name: node.type_name().to_owned(),
+    });
+    for child in node.children() {
+        summarise(child, Some(id), nodes);
+    }
+}
+
+/// Locks the shared state.
+///
+/// The state is always left consistent, so a panic while it was locked does
+/// not matter.
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ControlledTree, Controller};
+    use crate::{
+        blackboard::Blackboard,
+        std_nodes::{AlwaysRunning, AlwaysSucceed, Selector},
+        BehaviorTree, Status,
+    };
+    use std::thread;
+
+    #[test]
+    fn controllers_work_from_other_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Controller>();
+
+        let mut first = AlwaysRunning::new().named(Some("first"));
+        let switch = first.enable_switch();
+        let mut tree = ControlledTree::new(BehaviorTree::new(Selector::new(vec![
+            first,
+            AlwaysSucceed::new(),
+        ])));
+        tree.expose("first", switch);
+        let controller = tree.controller();
+        let mut blackboard = Blackboard::new();
+        blackboard.set("battery", 80u32);
+
+        assert_eq!(tree.tick(&mut blackboard), Some(Status::Running));
+        thread::spawn(move || {
+            assert_eq!(controller.structure()[1].parent(), Some(0));
+            assert_eq!(
+                controller.read_blackboard(),
+                vec![("battery".to_owned(), "80".to_owned())]
+            );
+            assert!(controller.set_enabled("first", false));
+            assert!(!controller.set_enabled("second", false));
+            controller.remove_blackboard("battery");
+            controller.reset();
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(tree.tick(&mut blackboard), Some(Status::Succeeded));
+        assert!(!blackboard.contains("battery"));
+        assert_eq!(tree.tree().tick_count(), 2);
+    }
+}
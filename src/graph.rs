@@ -0,0 +1,239 @@
+//! Drawing trees as graphs.
+//!
+//! A `TreeGraph` captures the structure of a tree, and renders it as a DOT
+//! graph for Graphviz or as a Mermaid flowchart for documentation and code
+//! review. The graph can optionally be overlaid with the blackboard dataflow
+//! declared with `Node::reads` and `Node::writes`: each key is drawn once,
+//! with dashed edges from the nodes which write it and to the nodes which read
+//! it. This makes coupling between distant branches visible, which the shape
+//! of the tree alone hides.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::std_nodes::*;
+//! # use aspen::graph::TreeGraph;
+//! # use aspen::BehaviorTree;
+//! let tree: BehaviorTree<()> = BehaviorTree::new(Sequence::new(vec![
+//!     AlwaysSucceed::new()
+//!         .named(Some("Plan"))
+//!         .writes::<Vec<u32>, _>("path"),
+//!     AlwaysSucceed::new()
+//!         .named(Some("Follow"))
+//!         .reads::<Vec<u32>, _>("path"),
+//! ]));
+//!
+//! let mermaid = TreeGraph::new(tree.root()).with_dataflow().to_mermaid();
+//! assert!(mermaid.contains("n1 -.->|writes| k0"));
+//! assert!(mermaid.contains("k0 -.->|reads| n2"));
+//! ```
+use crate::{
+    blackboard::Access,
+    node::{Node, Tickable},
+};
+
+/// A node of a graphed tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct GraphNode {
+    /// The name of the node.
+    name: String,
+
+    /// The pre-order index of the parent of the node.
+    parent: Option<usize>,
+}
+
+/// A blackboard key, and the nodes which use it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct GraphKey {
+    /// The key.
+    key: String,
+
+    /// The pre-order indices of the nodes which use the key, in pre-order.
+    uses: Vec<(usize, Access)>,
+}
+
+/// The structure of a tree, ready to be drawn.
+///
+/// Nodes are identified in the output as `n` followed by their pre-order
+/// index, and blackboard keys as `k` followed by the order in which they are
+/// first used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeGraph {
+    /// The nodes of the tree, in pre-order.
+    nodes: Vec<GraphNode>,
+
+    /// The blackboard keys used by the nodes.
+    keys: Vec<GraphKey>,
+
+    /// Whether the blackboard dataflow is drawn.
+    dataflow: bool,
+}
+impl TreeGraph {
+    /// Captures the structure of the tree with the given root.
+    pub fn new<W>(root: &Node<'_, W>) -> Self {
+        let mut graph = TreeGraph {
+            nodes: Vec::new(),
+            keys: Vec::new(),
+            dataflow: false,
+        };
+        graph.push_nodes(root, None);
+        graph
+    }
+
+    /// Draws the blackboard dataflow over the tree.
+    pub fn with_dataflow(mut self) -> Self {
+        self.dataflow = true;
+        self
+    }
+
+    /// Appends `node` and its descendants, in pre-order.
+    fn push_nodes<W>(&mut self, node: &Node<'_, W>, parent: Option<usize>) {
+        let id = self.nodes.len();
+        self.nodes.push(GraphNode {
+            name: node.name().to_owned(),
+            parent,
+        });
+        for used in node.key_uses() {
+            let index = match self.keys.iter().position(|k| k.key == used.key()) {
+                Some(index) => index,
+                None => {
+                    self.keys.push(GraphKey {
+                        key: used.key().to_owned(),
+                        uses: Vec::new(),
+                    });
+                    self.keys.len() - 1
+                }
+            };
+            let uses = &mut self.keys[index].uses;
+            if !uses.contains(&(id, used.access())) {
+                uses.push((id, used.access()));
+            }
+        }
+
+        for child in node.children() {
+            self.push_nodes(child, Some(id));
+        }
+    }
+
+    /// Returns the dataflow edges as (from, to, label), if they are drawn.
+    fn dataflow_edges(&self) -> Vec<(String, String, &'static str)> {
+        if !self.dataflow {
+            return Vec::new();
+        }
+
+        let mut edges = Vec::new();
+        for (k, key) in self.keys.iter().enumerate() {
+            for &(id, access) in &key.uses {
+                edges.push(match access {
+                    Access::Write => (format!("n{}", id), format!("k{}", k), "writes"),
+                    Access::Read => (format!("k{}", k), format!("n{}", id), "reads"),
+                });
+            }
+        }
+        edges
+    }
+
+    /// Renders the tree as a DOT graph.
+    ///
+    /// Blackboard keys are drawn as ellipses, outside of the ranking of the
+    /// tree so that they do not distort its layout.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph tree {\n  node [shape=box];\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            dot.push_str(&format!(
+                "  n{} [label=\"{}\"];\n",
+                id,
+                dot_escape(&node.name)
+            ));
+            if let Some(parent) = node.parent {
+                dot.push_str(&format!("  n{} -> n{};\n", parent, id));
+            }
+        }
+
+        if self.dataflow {
+            for (k, key) in self.keys.iter().enumerate() {
+                dot.push_str(&format!(
+                    "  k{} [label=\"{}\", shape=ellipse, color=blue, fontcolor=blue];\n",
+                    k,
+                    dot_escape(&key.key)
+                ));
+            }
+        }
+        for (from, to, label) in self.dataflow_edges() {
+            dot.push_str(&format!(
+                "  {} -> {} [label={}, style=dashed, color=blue, constraint=false];\n",
+                from, to, label
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the tree as a Mermaid flowchart, from top to bottom.
+    ///
+    /// Blackboard keys are drawn as cylinders.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart TD\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            mermaid.push_str(&format!("  n{}[\"{}\"]\n", id, mermaid_escape(&node.name)));
+            if let Some(parent) = node.parent {
+                mermaid.push_str(&format!("  n{} --> n{}\n", parent, id));
+            }
+        }
+
+        if self.dataflow {
+            for (k, key) in self.keys.iter().enumerate() {
+                mermaid.push_str(&format!("  k{}[(\"{}\")]\n", k, mermaid_escape(&key.key)));
+            }
+        }
+        for (from, to, label) in self.dataflow_edges() {
+            mermaid.push_str(&format!("  {} -.->|{}| {}\n", from, label, to));
+        }
+        mermaid
+    }
+}
+
+/// Escapes text for use in a quoted DOT string.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes text for use in a quoted Mermaid label.
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TreeGraph;
+    use crate::std_nodes::{AlwaysSucceed, Selector, Sequence};
+
+    #[test]
+    fn dataflow_is_only_drawn_when_asked() {
+        let root = Selector::new(vec![
+            Sequence::new(vec![
+                AlwaysSucceed::new().writes::<u32, _>("goal"),
+                AlwaysSucceed::new().named(Some("Say \"hi\"")),
+            ]),
+            AlwaysSucceed::new()
+                .reads::<u32, _>("goal")
+                .reads::<bool, _>("ready"),
+        ]);
+        let graph = TreeGraph::new::<()>(&root);
+
+        let plain = graph.to_dot();
+        assert!(plain.contains("  n3 [label=\"Say \\\"hi\\\"\"];\n"));
+        assert!(plain.contains("  n0 -> n4;\n"));
+        assert!(!plain.contains("k0"));
+
+        let dot = graph.clone().with_dataflow().to_dot();
+        assert!(dot.contains("  k1 [label=\"ready\""));
+        assert!(dot.contains("  n2 -> k0 [label=writes"));
+        assert!(dot.contains("  k0 -> n4 [label=reads"));
+
+        let mermaid = graph.with_dataflow().to_mermaid();
+        assert!(mermaid.contains("  n3[\"Say #quot;hi#quot;\"]\n"));
+        assert!(mermaid.contains("  k0[(\"goal\")]\n"));
+        assert!(mermaid.contains("  k1 -.->|reads| n4\n"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Helpers for writing JSON by hand.
+
+/// Appends a quoted and escaped JSON string.
+pub(crate) fn push_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Parses a JSON object mapping keys to strings.
+pub(crate) fn from_json(json: &str) -> Option<Vec<(String, String)>> {
+    let mut chars = json.chars().peekable();
+    let mut entries = Vec::new();
+
+    skip_whitespace(&mut chars);
+    if chars.next()? != '{' {
+        return None;
+    }
+    skip_whitespace(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_whitespace(&mut chars);
+            let key = parse_string(&mut chars)?;
+            skip_whitespace(&mut chars);
+            if chars.next()? != ':' {
+                return None;
+            }
+            skip_whitespace(&mut chars);
+            let value = parse_string(&mut chars)?;
+            entries.push((key, value));
+
+            skip_whitespace(&mut chars);
+            match chars.next()? {
+                ',' => continue,
+                '}' => break,
+                _ => return None,
+            }
+        }
+    }
+
+    skip_whitespace(&mut chars);
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(entries)
+}
+
+/// Skips JSON whitespace.
+fn skip_whitespace<I: Iterator<Item = char>>(chars: &mut std::iter::Peekable<I>) {
+    while let Some(' ') | Some('\n') | Some('\r') | Some('\t') = chars.peek() {
+        chars.next();
+    }
+}
+
+/// Parses a quoted JSON string.
+fn parse_string<I: Iterator<Item = char>>(chars: &mut I) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+
+    let mut s = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(s),
+            '\\' => s.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                '/' => '/',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    std::char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                _ => return None,
+            }),
+            c => s.push(c),
+        }
+    }
+}
@@ -8,16 +8,115 @@
 //! 1: Marzinotto, Alejandro, et al. "Towards a unified behavior trees
 //! framework for robot control." Robotics and Automation (ICRA), 2014 IEEE
 //! International Conference on. IEEE, 2014.
+//!
+//! # Features
+//!
+//! - `action` (default): the `Action` node, which runs its task in a separate
+//!   thread.
+//! - `monitoring` (default): the monitors in `monitor`, the `stats`, `trace`
+//!   and `control` modules, and `SharedTree`, which observe running trees and
+//!   share their state with other threads.
+//! - `grpc`: the `control::grpc` module, a tonic server which exposes the
+//!   `control` module to operator consoles over gRPC.
+//! - `gpio`: the `GpioCondition` node, which checks the level of a digital
+//!   input pin.
+//! - `serial`: the `SerialCommandAction` node, which writes a command to a
+//!   serial port and waits for an acknowledgement.
+//! - `remote`: the `RemoteAction` and `RemoteCondition` nodes, which run leaves
+//!   on other processes or robots, such as teammates in a fleet.
+//! - `robotics`: common robot guards, such as `BatteryAbove` and `EStopClear`.
+//! - `chaos`: the `Chaos` decorator, which randomly injects faults into its
+//!   child for robustness testing.
+//! - `static-alloc`: the `static_alloc` module, nodes and trees for
+//!   microcontrollers which never allocate.
+//! - `plugins`: `NodeRegistry::load_plugin`, which adds node constructors from
+//!   a shared library.
+//! - `test-utils`: the `testing` module, which generates random trees for
+//!   property-based testing, including with `proptest` and `arbitrary`, and
+//!   runs scripted scenarios.
+//!
+//! ## Static allocation
+//!
+//! The core of the crate depends on nothing but `log`. For small targets,
+//! including WASM, disable the default features and build trees only from
+//! cooperative nodes such as `InlineAction`. Nodes named with a `&'static str`
+//! keep a reference to it rather than an owned copy.
+//!
+//! A `Node` still boxes its internals, though. For microcontrollers without a
+//! heap, enable the `static-alloc` feature instead and build trees from the
+//! `static_alloc` module: its nodes hold their internals inline, have
+//! `&'static str` names and keep their children in fixed-capacity
+//! `heapless::Vec`s. Composites with an exact number of children
+//! (`SequenceN`, `SelectorN`) and the nodes in `std_nodes::typed` never
+//! allocate either, and can be used alongside them.
+//!
+//! Where the worst-case execution time of a tick has to be bounded,
+//! `compiled::compile` lowers a tree of function pointers into a flat table
+//! which is ticked without allocating.
+
+// Node constructors deliberately return the type-erased `Node` rather than
+// `Self`, so that trees can be assembled inline.
+#![allow(clippy::new_ret_no_self)]
 
 #[macro_use]
 extern crate log;
 
 mod bt;
-pub use crate::bt::BehaviorTree;
+pub use crate::bt::{BehaviorTree, RunSummary, SendTree, Ticks};
+
+pub mod blackboard;
+
+pub mod clock;
+
+pub mod compiled;
+
+pub mod config;
+
+#[cfg(feature = "monitoring")]
+pub mod control;
+
+pub mod graph;
+
+mod json;
+
+pub mod loader;
+
+pub mod monitor;
 
 pub mod node;
 
+#[cfg(feature = "plugins")]
+pub mod plugin;
+
+pub mod registry;
+
+mod rng;
+
+#[cfg(feature = "action")]
+mod sched;
+
+#[cfg(feature = "monitoring")]
+mod shared;
+#[cfg(feature = "monitoring")]
+pub use crate::shared::{SharedTree, TreeHandle};
+
+#[cfg(feature = "static-alloc")]
+pub mod static_alloc;
+
+#[cfg(feature = "monitoring")]
+pub mod stats;
+
 mod status;
 pub use crate::status::Status;
 
 pub mod std_nodes;
+
+#[cfg(feature = "test-utils")]
+pub mod testing;
+
+#[cfg(feature = "monitoring")]
+pub mod trace;
+
+pub mod validate;
+
+pub mod world;
@@ -0,0 +1,69 @@
+//! Conditional `branch` elements, which are kept or dropped depending on the
+//! capabilities of the robot.
+use super::{Element, LoadError};
+use std::{collections::HashSet, mem};
+
+/// Replaces each `branch` element among the descendants of `element` with its
+/// children if its condition holds, and removes it otherwise.
+pub(crate) fn resolve(
+    element: &mut Element,
+    capabilities: &HashSet<String>,
+) -> Result<(), LoadError> {
+    let children = mem::take(&mut element.children);
+    for mut child in children {
+        if child.name != "branch" {
+            resolve(&mut child, capabilities)?;
+            element.children.push(child);
+            continue;
+        }
+
+        if let Some((key, _)) = child.attributes.iter().find(|(k, _)| k != "if") {
+            return Err(child.error(format!("unexpected attribute {} on branch", key)));
+        }
+        let condition = child.required("if")?;
+        if holds(condition, capabilities).map_err(|e| child.error(e))? {
+            resolve(&mut child, capabilities)?;
+            element.children.append(&mut child.children);
+        } else {
+            debug!("Skipping branch which needs {}", condition);
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if the condition holds for the given capabilities.
+///
+/// A condition is a comma separated list of capabilities which are all
+/// needed, where `!` before a capability means that it must be missing.
+fn holds(condition: &str, capabilities: &HashSet<String>) -> Result<bool, String> {
+    let mut holds = true;
+    for term in condition.split(',').map(str::trim) {
+        let (name, wanted) = match term.strip_prefix('!') {
+            Some(name) => (name.trim(), false),
+            None => (term, true),
+        };
+        if name.is_empty() {
+            return Err(format!("invalid condition {:?}", condition));
+        }
+        holds &= capabilities.contains(name) == wanted;
+    }
+    Ok(holds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::holds;
+    use std::collections::HashSet;
+
+    #[test]
+    fn conditions_match_capabilities() {
+        let capabilities: HashSet<_> = vec!["arm".to_owned(), "lidar".to_owned()]
+            .into_iter()
+            .collect();
+        assert_eq!(holds("arm", &capabilities), Ok(true));
+        assert_eq!(holds("arm, lidar", &capabilities), Ok(true));
+        assert_eq!(holds("arm, !lidar", &capabilities), Ok(false));
+        assert_eq!(holds("!camera", &capabilities), Ok(true));
+        assert!(holds("arm,,lidar", &capabilities).is_err());
+    }
+}
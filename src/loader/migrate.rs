@@ -0,0 +1,196 @@
+//! Upgrading tree files written for older versions of the format.
+use super::{Element, LoadError, SCHEMA_VERSION};
+use std::fmt;
+
+/// Function which upgrades a document from one version of the format to a
+/// later one.
+///
+/// It is given the root element, and does not need to update its `format`
+/// attribute.
+pub type MigrationFn = dyn Fn(&mut Element) -> Result<(), LoadError>;
+
+/// A single registered migration.
+struct Migration {
+    /// The version the migration upgrades from.
+    from: u32,
+
+    /// The version the migration upgrades to.
+    to: u32,
+
+    /// Upgrades the document.
+    apply: Box<MigrationFn>,
+}
+
+/// Upgrades documents to the current version of the tree file format.
+///
+/// When a file is loaded, the migrations are applied in turn from the version
+/// the file was written for until it reaches `SCHEMA_VERSION`. Where more than
+/// one migration starts from the same version, the one which goes furthest
+/// without passing `SCHEMA_VERSION` is used.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::loader::{Loader, Migrator};
+/// // Suppose that version 0 of the format called sequences `Seq`
+/// let mut migrator = Migrator::new();
+/// migrator.register(0, 1, |document| {
+///     document.visit_mut(&mut |e| {
+///         if e.name() == "Seq" {
+///             e.set_name("Sequence");
+///         }
+///     });
+///     Ok(())
+/// });
+///
+/// let definition = Loader::new()
+///     .migrator(migrator)
+///     .load_str(r#"<root format="0"><BehaviorTree ID="Main"><Seq /></BehaviorTree></root>"#)?;
+/// assert_eq!(definition.document().attribute("format"), Some("1"));
+/// # Ok::<(), aspen::loader::LoadError>(())
+/// ```
+#[derive(Default)]
+pub struct Migrator {
+    /// The migrations, in the order they were registered.
+    migrations: Vec<Migration>,
+}
+impl Migrator {
+    /// Creates a migrator with the migrations built into aspen.
+    pub fn new() -> Self {
+        Migrator::default()
+    }
+
+    /// Registers a migration from one version of the format to a later one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `to` is not later than `from`.
+    pub fn register<F>(&mut self, from: u32, to: u32, migration: F)
+    where
+        F: Fn(&mut Element) -> Result<(), LoadError> + 'static,
+    {
+        assert!(
+            to > from,
+            "a migration must upgrade to a later version, not from {} to {}",
+            from,
+            to
+        );
+        self.migrations.push(Migration {
+            from,
+            to,
+            apply: Box::new(migration),
+        });
+    }
+
+    /// Upgrades a document to the current version of the format, returning the
+    /// version it was written for.
+    pub fn migrate(&self, document: &mut Element) -> Result<u32, LoadError> {
+        let original = match document.attribute("format") {
+            Some(v) => v
+                .parse()
+                .map_err(|_| document.error(format!("invalid format version {}", v)))?,
+            None => 1,
+        };
+        if original > SCHEMA_VERSION {
+            return Err(document.error(format!(
+                "format version {} is newer than the supported version {}",
+                original, SCHEMA_VERSION
+            )));
+        }
+
+        let mut version = original;
+        while version < SCHEMA_VERSION {
+            let migration = self
+                .migrations
+                .iter()
+                .filter(|m| m.from == version && m.to <= SCHEMA_VERSION)
+                .max_by_key(|m| m.to)
+                .ok_or_else(|| {
+                    document.error(format!(
+                        "no migration from format version {} to {}",
+                        version, SCHEMA_VERSION
+                    ))
+                })?;
+
+            (migration.apply)(document).map_err(|e| {
+                LoadError::new(format!(
+                    "migrating from format version {} to {}: {}",
+                    migration.from,
+                    migration.to,
+                    e.message()
+                ))
+                .within(e.span())
+            })?;
+            debug!(
+                "Migrated tree file from format version {} to {}",
+                version, migration.to
+            );
+            version = migration.to;
+            document.set_attribute("format", version.to_string());
+        }
+
+        Ok(original)
+    }
+}
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrator")
+            .field(
+                "migrations",
+                &self
+                    .migrations
+                    .iter()
+                    .map(|m| (m.from, m.to))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Migrator;
+    use crate::loader::{Element, LoadError};
+
+    #[test]
+    fn migrations_are_chained() {
+        let mut migrator = Migrator::new();
+        migrator.register(0, 1, |d| {
+            d.set_attribute("seen", "0");
+            Ok(())
+        });
+        migrator.register(u32::MAX - 1, u32::MAX, |_| Err(LoadError::new("unused")));
+
+        let mut document = Element::new("root");
+        document.set_attribute("format", "0");
+        assert_eq!(migrator.migrate(&mut document), Ok(0));
+        assert_eq!(document.attribute("format"), Some("1"));
+        assert_eq!(document.attribute("seen"), Some("0"));
+
+        let mut current = Element::new("root");
+        assert_eq!(migrator.migrate(&mut current), Ok(1));
+        assert_eq!(current.attribute("seen"), None);
+    }
+
+    #[test]
+    fn unsupported_versions_are_reported() {
+        let mut document = Element::new("root");
+        document.set_attribute("format", "0");
+        assert_eq!(
+            Migrator::new()
+                .migrate(&mut document)
+                .unwrap_err()
+                .message(),
+            "no migration from format version 0 to 1"
+        );
+
+        document.set_attribute("format", "2");
+        assert_eq!(
+            Migrator::new()
+                .migrate(&mut document)
+                .unwrap_err()
+                .message(),
+            "format version 2 is newer than the supported version 1"
+        );
+    }
+}
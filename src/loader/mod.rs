@@ -0,0 +1,1162 @@
+//! Loading trees from XML files.
+//!
+//! Tree files use a format similar to that of BehaviorTree.CPP. The root
+//! element is `root`, whose `format` attribute gives the version of the format
+//! the file was written for, and which contains one or more `BehaviorTree`
+//! elements with unique `ID`s. Each `BehaviorTree` holds a single node, and
+//! nodes are elements named after their type in a `NodeRegistry`. Their
+//! attributes are passed to the constructor as parameters, except for `name`,
+//! which names the node. A `SubTree` element with an `ID` stands for another
+//! tree.
+//!
+//! An `include` element in the root, with a `path` attribute, adds the trees
+//! of another file, so that a large set of trees can be split into reusable
+//! files. Relative paths are relative to the directory of the including file.
+//! Files may be included more than once, but only their first inclusion has
+//! an effect, and a file which includes itself, directly or indirectly, is an
+//! error.
+//!
+//! A `branch` element with an `if` attribute is replaced by its children if
+//! the robot has the capabilities the condition names, and removed otherwise,
+//! so that one tree can describe a family of robots with different hardware.
+//! The condition is a comma separated list of capabilities which are all
+//! needed, where `!` before a capability means that it must be missing. The
+//! capabilities are given to the `Loader`, and branches may appear anywhere,
+//! including around `include` and `BehaviorTree` elements.
+//!
+//! Errors give the file, line and column they are about and, for misspelled
+//! node types and tree IDs, the closest known name. `LoadError::render`
+//! formats an error together with the line it points at, for tree authors who
+//! are not familiar with the code.
+//!
+//! The tree which is built is the one named by the `main_tree_to_execute`
+//! attribute of the root, or the only tree if there is just one.
+//!
+//! Files written for older versions of the format are upgraded by a
+//! `Migrator` when they are loaded, so they keep working as the format
+//! changes. Files without a `format` attribute are taken to be version 1.
+//! Attribute values can contain `${name}` placeholders, which are filled in by
+//! the `Loader`. A `ParamServer` fills them in from defaults, overridden by the
+//! model of robot and then by the robot itself, so that one set of files
+//! serves a mixed fleet.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::loader::Loader;
+//! # use aspen::registry::NodeRegistry;
+//! # use aspen::Status;
+//! # use aspen::node::Tickable;
+//! let definition = Loader::new().load_str(
+//!     r#"
+//!     <root format="1" main_tree_to_execute="Main">
+//!       <BehaviorTree ID="Main">
+//!         <Sequence name="Patrol">
+//!           <SubTree ID="Check" />
+//!           <AlwaysSucceed />
+//!         </Sequence>
+//!       </BehaviorTree>
+//!       <BehaviorTree ID="Check">
+//!         <Invert><AlwaysFail /></Invert>
+//!       </BehaviorTree>
+//!     </root>
+//!     "#,
+//! )?;
+//!
+//! let registry = NodeRegistry::with_std_nodes();
+//! let mut root = definition.build(&registry)?;
+//! assert_eq!(root.name(), "Patrol");
+//! assert_eq!(root.tick(&mut ()), Status::Succeeded);
+//! # Ok::<(), aspen::loader::LoadError>(())
+//! ```
+use crate::{
+    blackboard::Blackboard,
+    config::Config,
+    node::Node,
+    registry::{NodeRegistry, Params},
+    BehaviorTree,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt, fs, mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+mod branch;
+
+mod migrate;
+pub use self::migrate::{MigrationFn, Migrator};
+
+mod params;
+pub use self::params::{ParamLayer, ParamServer};
+
+mod prototype;
+use self::prototype::Plan;
+pub use self::prototype::Prototype;
+
+mod substitute;
+
+mod suggest;
+use self::substitute::Sources;
+
+pub(crate) mod xml;
+
+/// The version of the tree file format which this version of aspen reads.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Loads tree definitions from text or files.
+///
+/// Attribute values can contain `${name}` placeholders, which are replaced
+/// when the file is loaded so that one file can be deployed with different
+/// settings on each robot. A placeholder takes the value given to `param` if
+/// there is one, then the value in the `config`, then the entry on the
+/// `blackboard` if it holds a string, boolean or number, and then the
+/// environment variable, if `environment` is enabled. `${name:-default}`
+/// falls back to `default` if none of these has a value, and `$$` stands for
+/// a single `$`.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::loader::Loader;
+/// # use aspen::blackboard::Blackboard;
+/// let mut blackboard = Blackboard::new();
+/// blackboard.set("robot", "rover1".to_owned());
+///
+/// let definition = Loader::new()
+///     .param("retries", "3")
+///     .blackboard(&blackboard)
+///     .load_str(
+///         r#"
+///         <root>
+///           <BehaviorTree ID="Main">
+///             <Repeat name="${robot}" limit="${retries}"><AlwaysSucceed /></Repeat>
+///           </BehaviorTree>
+///         </root>
+///         "#,
+///     )?;
+///
+/// let node = &definition.document().children()[0].children()[0];
+/// assert_eq!(node.attribute("name"), Some("rover1"));
+/// assert_eq!(node.attribute("limit"), Some("3"));
+/// # Ok::<(), aspen::loader::LoadError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Loader<'l> {
+    /// Upgrades files written for older versions of the format.
+    migrator: Migrator,
+
+    /// The values of placeholders.
+    params: HashMap<String, String>,
+
+    /// A config whose values can be used in placeholders.
+    config: Option<&'l Config>,
+
+    /// A blackboard whose entries can be used in placeholders.
+    blackboard: Option<&'l Blackboard>,
+
+    /// Whether environment variables can be used in placeholders.
+    environment: bool,
+
+    /// The capabilities which `branch` conditions are checked against.
+    capabilities: HashSet<String>,
+}
+impl<'l> Loader<'l> {
+    /// Creates a loader with the migrations built into aspen.
+    pub fn new() -> Self {
+        Loader::default()
+    }
+
+    /// Sets the migrator used to upgrade older files.
+    pub fn migrator(mut self, migrator: Migrator) -> Self {
+        self.migrator = migrator;
+        self
+    }
+
+    /// Sets the value of a placeholder.
+    pub fn param<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.params.insert(key.into(), value.into());
+        self
+    }
+
+    /// Allows the values of a config to be used in placeholders.
+    pub fn config(mut self, config: &'l Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Allows the entries of a blackboard to be used in placeholders.
+    pub fn blackboard(mut self, blackboard: &'l Blackboard) -> Self {
+        self.blackboard = Some(blackboard);
+        self
+    }
+
+    /// Allows environment variables to be used in placeholders.
+    pub fn environment(mut self, enabled: bool) -> Self {
+        self.environment = enabled;
+        self
+    }
+
+    /// Adds a capability of the robot, which `branch` conditions can check
+    /// for.
+    pub fn capability<C: Into<String>>(mut self, capability: C) -> Self {
+        self.capabilities.insert(capability.into());
+        self
+    }
+
+    /// Adds several capabilities of the robot.
+    pub fn capabilities<I>(mut self, capabilities: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.capabilities
+            .extend(capabilities.into_iter().map(Into::into));
+        self
+    }
+
+    /// Loads a tree definition from text.
+    pub fn load_str(&self, text: &str) -> Result<TreeDefinition, LoadError> {
+        self.load(text, None)
+    }
+
+    /// Loads a tree definition from a file.
+    pub fn load_file<P: AsRef<Path>>(&self, path: P) -> Result<TreeDefinition, LoadError> {
+        let path = path.as_ref();
+        let text =
+            fs::read_to_string(path).map_err(|e| LoadError::new(e.to_string()).in_file(path))?;
+        self.load(&text, Some(path)).map_err(|e| e.in_file(path))
+    }
+
+    /// Loads a tree definition from text, which was read from `file`.
+    fn load(&self, text: &str, file: Option<&Path>) -> Result<TreeDefinition, LoadError> {
+        let mut stack = Vec::new();
+        if let Some(file) = file {
+            let canonical = file
+                .canonicalize()
+                .map_err(|e| LoadError::new(e.to_string()))?;
+            stack.push((canonical, file.to_owned()));
+        }
+        let mut included = stack.iter().map(|(c, _)| c.clone()).collect();
+
+        let document = self.load_document(text, file, &mut stack, &mut included)?;
+        TreeDefinition::new(document, file.map(Path::to_owned))
+    }
+
+    /// Parses, upgrades and fills in a document, resolves its branches, then
+    /// replaces its includes with the trees of the files they name.
+    ///
+    /// The stack holds the canonical and given paths of the files being
+    /// loaded, to detect files which include themselves, and `included` holds
+    /// the canonical paths of all of the files loaded so far, so that each is
+    /// only included once.
+    fn load_document(
+        &self,
+        text: &str,
+        file: Option<&Path>,
+        stack: &mut Vec<(PathBuf, PathBuf)>,
+        included: &mut Vec<PathBuf>,
+    ) -> Result<Element, LoadError> {
+        let mut document = xml::parse(text)?;
+        if let Some(file) = file {
+            let file: Arc<Path> = Arc::from(file);
+            document.visit_mut(&mut |e| e.file = Some(file.clone()));
+        }
+        self.migrator.migrate(&mut document)?;
+        substitute::substitute(
+            &mut document,
+            Sources {
+                params: &self.params,
+                config: self.config,
+                blackboard: self.blackboard,
+                environment: self.environment,
+            },
+        )?;
+        branch::resolve(&mut document, &self.capabilities)?;
+
+        let children = mem::take(&mut document.children);
+        for child in children {
+            if child.name != "include" {
+                document.children.push(child);
+                continue;
+            }
+
+            // Relative paths are relative to the including file, or to the
+            // working directory for text
+            let path = child.required("path")?;
+            let resolved = match file.and_then(Path::parent) {
+                Some(dir) => dir.join(path),
+                None => PathBuf::from(path),
+            };
+            let canonical = resolved
+                .canonicalize()
+                .map_err(|e| child.error(format!("failed to include {}: {}", path, e)))?;
+            if stack.iter().any(|(c, _)| *c == canonical) {
+                let mut cycle: Vec<_> = stack
+                    .iter()
+                    .skip_while(|(c, _)| *c != canonical)
+                    .map(|(_, p)| p.display().to_string())
+                    .collect();
+                cycle.push(resolved.display().to_string());
+                return Err(child.error(format!("include cycle: {}", cycle.join(" > "))));
+            }
+            if included.contains(&canonical) {
+                continue;
+            }
+
+            let text = fs::read_to_string(&resolved)
+                .map_err(|e| child.error(format!("failed to include {}: {}", path, e)))?;
+            debug!("Including tree file {}", resolved.display());
+            included.push(canonical.clone());
+            stack.push((canonical, resolved.clone()));
+            let included_document = self
+                .load_document(&text, Some(&resolved), stack, included)
+                .map_err(|e| e.in_file(&resolved))?;
+            stack.pop();
+
+            document.children.extend(
+                included_document
+                    .children
+                    .into_iter()
+                    .filter(|c| c.name == "BehaviorTree"),
+            );
+        }
+
+        Ok(document)
+    }
+}
+
+/// A tree file which has been loaded, and from which trees can be built.
+#[derive(Clone, Debug)]
+pub struct TreeDefinition {
+    /// The document, upgraded to the current version of the format.
+    document: Element,
+
+    /// The file the definition was loaded from.
+    file: Option<PathBuf>,
+}
+impl TreeDefinition {
+    /// Checks the structure of a document.
+    fn new(document: Element, file: Option<PathBuf>) -> Result<Self, LoadError> {
+        if document.name != "root" {
+            return Err(document.error(format!("expected a root element, found {}", document.name)));
+        }
+
+        let mut ids: Vec<&str> = Vec::new();
+        for child in &document.children {
+            match child.name.as_str() {
+                "BehaviorTree" => {}
+                // Written by Groot, and not needed to build trees
+                "TreeNodesModel" => continue,
+                other => return Err(child.error(format!("unexpected element {}", other))),
+            }
+
+            let id = child.required("ID")?;
+            if ids.contains(&id) {
+                return Err(child.error(format!("duplicate tree {}", id)));
+            }
+            ids.push(id);
+            if child.children.len() != 1 {
+                return Err(child.error(format!("tree {} must contain exactly one node", id)));
+            }
+        }
+
+        Ok(TreeDefinition { document, file })
+    }
+
+    /// Returns the file the definition was loaded from, if any.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the document, upgraded to the current version of the format.
+    pub fn document(&self) -> &Element {
+        &self.document
+    }
+
+    /// Returns the IDs of the trees in the definition.
+    pub fn trees(&self) -> impl Iterator<Item = &str> {
+        self.tree_elements().filter_map(|t| t.attribute("ID"))
+    }
+
+    /// Returns the ID of the tree which `build` builds, if there is one.
+    pub fn main_tree(&self) -> Option<&str> {
+        match self.document.attribute("main_tree_to_execute") {
+            Some(id) => Some(id),
+            None => {
+                let mut trees = self.trees();
+                match (trees.next(), trees.next()) {
+                    (Some(id), None) => Some(id),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Builds the main tree.
+    pub fn build<W: 'static>(
+        &self,
+        registry: &NodeRegistry<W>,
+    ) -> Result<Node<'static, W>, LoadError> {
+        self.build_tree(self.main_id()?, registry)
+    }
+
+    /// Builds the tree with the given ID.
+    pub fn build_tree<W: 'static>(
+        &self,
+        id: &str,
+        registry: &NodeRegistry<W>,
+    ) -> Result<Node<'static, W>, LoadError> {
+        self.resolve(id, registry)?.construct()
+    }
+
+    /// Builds the main tree, named after its ID.
+    pub fn instantiate<W: 'static>(
+        &self,
+        registry: &NodeRegistry<W>,
+    ) -> Result<BehaviorTree<'static, W>, LoadError> {
+        let id = self.main_id()?;
+        let root = self.build_tree(id, registry)?;
+        Ok(BehaviorTree::new(root).named(Some(id)))
+    }
+
+    /// Resolves the main tree into a prototype, from which copies of it can be
+    /// built without going back to the definition.
+    ///
+    /// A copy is built to check the parameters of the nodes, so errors from
+    /// constructors are reported here.
+    pub fn prototype<W: 'static>(
+        &self,
+        registry: &NodeRegistry<W>,
+    ) -> Result<Prototype<W>, LoadError> {
+        self.prototype_tree(self.main_id()?, registry)
+    }
+
+    /// Resolves the tree with the given ID into a prototype.
+    pub fn prototype_tree<W: 'static>(
+        &self,
+        id: &str,
+        registry: &NodeRegistry<W>,
+    ) -> Result<Prototype<W>, LoadError> {
+        let root = self.resolve(id, registry)?;
+        root.construct()?;
+        Ok(Prototype::new(id.to_owned(), root))
+    }
+
+    /// Returns the ID of the main tree, or an error if there is none.
+    fn main_id(&self) -> Result<&str, LoadError> {
+        self.main_tree().ok_or_else(|| {
+            self.located(LoadError::new(
+                "no main tree, set main_tree_to_execute on the root element",
+            ))
+        })
+    }
+
+    /// Resolves the tree with the given ID.
+    fn resolve<W: 'static>(
+        &self,
+        id: &str,
+        registry: &NodeRegistry<W>,
+    ) -> Result<Plan<W>, LoadError> {
+        let mut stack = Vec::new();
+        let mut path = Vec::new();
+        self.resolve_subtree(id, registry, &mut stack, &mut path, self.document.span)
+            .map_err(|e| self.located(e))
+    }
+
+    /// Returns the `BehaviorTree` elements.
+    fn tree_elements(&self) -> impl Iterator<Item = &Element> {
+        self.document
+            .children
+            .iter()
+            .filter(|c| c.name == "BehaviorTree")
+    }
+
+    /// Resolves the tree with the given ID, which is referred to at `span`.
+    ///
+    /// The stack holds the IDs of the trees being resolved, to detect trees
+    /// which contain themselves.
+    fn resolve_subtree<'d, W: 'static>(
+        &'d self,
+        id: &str,
+        registry: &NodeRegistry<W>,
+        stack: &mut Vec<&'d str>,
+        path: &mut Vec<String>,
+        span: Option<Span>,
+    ) -> Result<Plan<W>, LoadError> {
+        let tree = self
+            .tree_elements()
+            .find(|t| t.attribute("ID") == Some(id))
+            .ok_or_else(|| {
+                LoadError::new(format!("no tree with ID {}", id))
+                    .within(span)
+                    .suggest(suggest::closest(id, self.trees()))
+            })?;
+        let id = tree.required("ID")?;
+        if stack.contains(&id) {
+            stack.push(id);
+            return Err(LoadError::new(format!(
+                "tree {} contains itself ({})",
+                id,
+                stack.join(" > ")
+            ))
+            .within(span));
+        }
+
+        stack.push(id);
+        path.push(id.to_owned());
+        let plan = self.resolve_node(&tree.children[0], registry, stack, path)?;
+        path.pop();
+        stack.pop();
+        Ok(plan)
+    }
+
+    /// Resolves the node described by an element.
+    ///
+    /// The path holds the IDs of the trees and the names of the nodes from the
+    /// root to the parent of the element.
+    fn resolve_node<'d, W: 'static>(
+        &'d self,
+        element: &'d Element,
+        registry: &NodeRegistry<W>,
+        stack: &mut Vec<&'d str>,
+        path: &mut Vec<String>,
+    ) -> Result<Plan<W>, LoadError> {
+        let name = element.attribute("name").map(str::to_owned);
+        if element.name == "SubTree" {
+            if let Some((key, _)) = element
+                .attributes
+                .iter()
+                .find(|(k, _)| k != "ID" && k != "name")
+            {
+                return Err(element.error(format!("unexpected attribute {} on SubTree", key)));
+            }
+            let id = element.required("ID")?;
+            let mut plan = self.resolve_subtree(id, registry, stack, path, element.span)?;
+            if name.is_some() {
+                plan.name = name;
+            }
+            return Ok(plan);
+        }
+
+        let constructor = match registry.constructor(&element.name) {
+            Some(constructor) => constructor,
+            None => {
+                let candidates = registry.type_names().into_iter().chain(Some("SubTree"));
+                let mut error = element
+                    .error(format!("unknown node type {}", element.name))
+                    .suggest(suggest::closest(&element.name, candidates));
+                error.details.path = path.clone();
+                return Err(error);
+            }
+        };
+
+        let mut params = Params::new();
+        for (key, value) in element.attributes() {
+            if key == "name" {
+                params.insert_optional(key, value);
+            } else {
+                params.insert(key, value);
+            }
+        }
+        path.push(name.clone().unwrap_or_else(|| element.name.clone()));
+        let children = element
+            .children
+            .iter()
+            .map(|c| self.resolve_node(c, registry, stack, path))
+            .collect::<Result<_, _>>()?;
+        let plan = Plan {
+            constructor,
+            params,
+            name,
+            children,
+            span: element.span,
+            file: element.file.clone(),
+            path: path.clone(),
+        };
+        path.pop();
+        Ok(plan)
+    }
+
+    /// Adds the file of the definition to an error.
+    fn located(&self, error: LoadError) -> LoadError {
+        match self.file {
+            Some(ref file) => error.in_file(file),
+            None => error,
+        }
+    }
+}
+
+/// A position in a tree file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// The line, starting from one.
+    line: usize,
+
+    /// The column, in characters, starting from one.
+    column: usize,
+}
+impl Position {
+    /// Returns the line, starting from one.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column, in characters, starting from one.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A range of text in a tree file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// The position of the first character.
+    start: Position,
+
+    /// The position after the last character.
+    end: Position,
+}
+impl Span {
+    /// Returns the position of the first character.
+    pub fn start(&self) -> Position {
+        self.start
+    }
+
+    /// Returns the position after the last character.
+    pub fn end(&self) -> Position {
+        self.end
+    }
+}
+
+/// An element of a tree file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Element {
+    /// The name of the element.
+    name: String,
+
+    /// The attributes, in the order they were written.
+    attributes: Vec<(String, String)>,
+
+    /// The child elements.
+    children: Vec<Element>,
+
+    /// The start tag of the element, if it was read from a file.
+    span: Option<Span>,
+
+    /// The file the element was read from, if any.
+    file: Option<Arc<Path>>,
+}
+impl Element {
+    /// Creates an element with no attributes or children.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Element {
+            name: name.into(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            span: None,
+            file: None,
+        }
+    }
+
+    /// Returns the name of the element.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames the element.
+    pub fn set_name<N: Into<String>>(&mut self, name: N) {
+        self.name = name.into();
+    }
+
+    /// Returns the value of the attribute with the given key.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the attributes, in the order they were written.
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Sets an attribute, replacing any existing one with the same key.
+    pub fn set_attribute<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        let key = key.into();
+        let value = value.into();
+        match self.attributes.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.attributes.push((key, value)),
+        }
+    }
+
+    /// Removes an attribute, returning its value.
+    pub fn remove_attribute(&mut self, key: &str) -> Option<String> {
+        let index = self.attributes.iter().position(|(k, _)| k == key)?;
+        Some(self.attributes.remove(index).1)
+    }
+
+    /// Returns the child elements.
+    pub fn children(&self) -> &[Element] {
+        &self.children
+    }
+
+    /// Returns the child elements, for modification.
+    pub fn children_mut(&mut self) -> &mut Vec<Element> {
+        &mut self.children
+    }
+
+    /// Returns where the element starts, if it was read from a file.
+    pub fn position(&self) -> Option<Position> {
+        self.span.map(|s| s.start)
+    }
+
+    /// Returns the span of the start tag of the element, if it was read from a
+    /// file.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Returns the file the element was read from, if any.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the line the element starts on, if it was read from a file.
+    pub fn line(&self) -> Option<usize> {
+        self.span.map(|s| s.start.line)
+    }
+
+    /// Calls the function on this element and all of its descendants, in
+    /// pre-order.
+    pub fn visit_mut<F: FnMut(&mut Element)>(&mut self, f: &mut F) {
+        f(self);
+        for child in &mut self.children {
+            child.visit_mut(f);
+        }
+    }
+
+    /// Returns the attribute with the given key, or an error if it is missing.
+    fn required(&self, key: &str) -> Result<&str, LoadError> {
+        self.attribute(key)
+            .ok_or_else(|| self.error(format!("{} is missing the {} attribute", self.name, key)))
+    }
+
+    /// Returns an error at the position of the element.
+    fn error<M: Into<String>>(&self, message: M) -> LoadError {
+        let error = LoadError::new(message).within(self.span);
+        match self.file {
+            Some(ref file) => error.in_file(file),
+            None => error,
+        }
+    }
+}
+
+/// An error from loading a tree file or building a tree from it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadError {
+    /// The details of the error, boxed to keep results small.
+    details: Box<Details>,
+}
+
+/// The details of a `LoadError`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Details {
+    /// What went wrong.
+    message: String,
+
+    /// The file the error is in.
+    file: Option<PathBuf>,
+
+    /// Where in the file the error is.
+    span: Option<Span>,
+
+    /// The path to the node the error is about.
+    path: Vec<String>,
+
+    /// What was probably meant instead.
+    suggestion: Option<String>,
+}
+impl LoadError {
+    /// Creates an error with the given message.
+    pub fn new<M: Into<String>>(message: M) -> Self {
+        LoadError {
+            details: Box::new(Details {
+                message: message.into(),
+                file: None,
+                span: None,
+                path: Vec::new(),
+                suggestion: None,
+            }),
+        }
+    }
+
+    /// Sets where in the file the error is.
+    pub fn at(mut self, position: Position) -> Self {
+        self.details.span = Some(Span {
+            start: position,
+            end: position,
+        });
+        self
+    }
+
+    /// Returns what went wrong.
+    pub fn message(&self) -> &str {
+        &self.details.message
+    }
+
+    /// Returns the file the error is in, if known.
+    pub fn file(&self) -> Option<&Path> {
+        self.details.file.as_deref()
+    }
+
+    /// Returns where in the file the error is, if known.
+    pub fn position(&self) -> Option<Position> {
+        self.details.span.map(|s| s.start)
+    }
+
+    /// Returns the span of text the error is about, if known.
+    pub fn span(&self) -> Option<Span> {
+        self.details.span
+    }
+
+    /// Returns the IDs of the trees and the names of the nodes from the root
+    /// to the node the error is about, or nothing if it is not about a node.
+    pub fn path(&self) -> &[String] {
+        &self.details.path
+    }
+
+    /// Returns what was probably meant instead, such as the name of a node
+    /// type close to a misspelled one.
+    pub fn suggestion(&self) -> Option<&str> {
+        self.details.suggestion.as_deref()
+    }
+
+    /// Formats the error with the line of the source it is about, marking the
+    /// span of the error, in the style of the Rust compiler.
+    ///
+    /// The source should be the text of the file the error is in.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = String::from("error: ");
+        if !self.details.path.is_empty() {
+            out.push_str(&self.details.path.join(" > "));
+            out.push_str(": ");
+        }
+        out.push_str(&self.details.message);
+        out.push('\n');
+
+        let span = match self.details.span {
+            Some(span) => span,
+            None => {
+                if let Some(ref file) = self.details.file {
+                    out.push_str(&format!(" --> {}\n", file.display()));
+                }
+                return self.render_help(out);
+            }
+        };
+        let file = self
+            .details
+            .file
+            .as_ref()
+            .map_or_else(|| "<text>".to_owned(), |f| f.display().to_string());
+        out.push_str(&format!(" --> {}:{}\n", file, span.start));
+
+        if let Some(line) = source.lines().nth(span.start.line - 1) {
+            let number = span.start.line.to_string();
+            let gutter = " ".repeat(number.len());
+            let line_len = line.chars().count();
+            let end = if span.end.line == span.start.line {
+                span.end.column
+            } else {
+                line_len + 1
+            };
+            let width = end.saturating_sub(span.start.column).max(1);
+            out.push_str(&format!("{} |\n", gutter));
+            out.push_str(&format!("{} | {}\n", number, line));
+            out.push_str(&format!(
+                "{} | {}{}\n",
+                gutter,
+                " ".repeat(span.start.column - 1),
+                "^".repeat(width)
+            ));
+        }
+        self.render_help(out)
+    }
+
+    /// Appends the suggestion to a rendered error.
+    fn render_help(&self, mut out: String) -> String {
+        if let Some(ref suggestion) = self.details.suggestion {
+            out.push_str(&format!(" = help: did you mean {}?\n", suggestion));
+        }
+        out
+    }
+
+    /// Sets where in the file the error is, if known and not already set.
+    fn within(mut self, span: Option<Span>) -> Self {
+        self.details.span = self.details.span.or(span);
+        self
+    }
+
+    /// Sets what was probably meant instead.
+    fn suggest(mut self, suggestion: Option<&str>) -> Self {
+        self.details.suggestion = suggestion.map(str::to_owned);
+        self
+    }
+
+    /// Sets the file the error is in, if not already set.
+    fn in_file(mut self, file: &Path) -> Self {
+        if self.details.file.is_none() {
+            self.details.file = Some(file.to_owned());
+        }
+        self
+    }
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref file) = self.details.file {
+            write!(f, "{}:", file.display())?;
+        }
+        if let Some(span) = self.details.span {
+            write!(f, "{}:", span.start)?;
+        }
+        if self.details.file.is_some() || self.details.span.is_some() {
+            f.write_str(" ")?;
+        }
+        if !self.details.path.is_empty() {
+            write!(f, "{}: ", self.details.path.join(" > "))?;
+        }
+        f.write_str(&self.details.message)?;
+        if let Some(ref suggestion) = self.details.suggestion {
+            write!(f, " (did you mean {}?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+impl Error for LoadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::Loader;
+    use crate::{blackboard::Blackboard, node::Tickable, registry::NodeRegistry, Status};
+    use std::{env, fs, time::Instant};
+
+    #[test]
+    fn builds_the_main_tree() {
+        let definition = Loader::new()
+            .load_str(
+                "<root main_tree_to_execute='B'><BehaviorTree \
+                 ID='A'><AlwaysFail/></BehaviorTree><BehaviorTree ID='B'><Selector><SubTree \
+                 ID='A' name='Inner'/><AlwaysSucceed/></Selector></BehaviorTree></root>",
+            )
+            .unwrap();
+        assert_eq!(definition.trees().collect::<Vec<_>>(), ["A", "B"]);
+
+        let registry = NodeRegistry::with_std_nodes();
+        let mut root = definition.build(&registry).unwrap();
+        assert_eq!(root.children()[0].name(), "Inner");
+        assert_eq!(root.tick(&mut ()), Status::Succeeded);
+    }
+
+    #[test]
+    fn builds_blackboard_nodes() {
+        let definition = Loader::new()
+            .load_str(
+                "<root><BehaviorTree ID='Search'><Sequence><StartTimer \
+                 name='search'/><CheckTimer name='search' \
+                 ms='300000'/></Sequence></BehaviorTree></root>",
+            )
+            .unwrap();
+
+        let registry = NodeRegistry::with_blackboard_nodes();
+        let mut root = definition.build(&registry).unwrap();
+        let mut blackboard = Blackboard::new();
+        assert_eq!(root.tick(&mut blackboard), Status::Succeeded);
+        assert!(blackboard.get::<Instant>("search").is_some());
+    }
+
+    #[test]
+    fn branches_depend_on_capabilities() {
+        let text = "<root><BehaviorTree ID='Main'><Sequence><branch if='arm'><AlwaysSucceed \
+                    name='Grasp'/><AlwaysSucceed name='Lift'/></branch><branch \
+                    if='!arm'><AlwaysSucceed \
+                    name='Push'/></branch></Sequence></BehaviorTree></root>";
+        let names = |loader: Loader<'_>| {
+            let definition = loader.load_str(text).unwrap();
+            let root = definition
+                .build(&NodeRegistry::<()>::with_std_nodes())
+                .unwrap();
+            root.children()
+                .iter()
+                .map(|c| c.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Loader::new().capability("arm")), ["Grasp", "Lift"]);
+        assert_eq!(names(Loader::new().capabilities(vec!["lidar"])), ["Push"]);
+    }
+
+    #[test]
+    fn prototypes_check_parameters_up_front() {
+        let registry = NodeRegistry::<()>::with_std_nodes();
+        let definition = Loader::new()
+            .load_str(
+                "<root><BehaviorTree ID='Job'><Timeout \
+                 ms='soon'><AlwaysRunning/></Timeout></BehaviorTree></root>",
+            )
+            .unwrap();
+        let error = definition.prototype(&registry).unwrap_err();
+        assert_eq!(error.path(), ["Job", "Timeout"]);
+
+        let definition = Loader::new()
+            .load_str("<root><BehaviorTree ID='Job'><AlwaysRunning/></BehaviorTree></root>")
+            .unwrap();
+        let prototype = definition.prototype(&registry).unwrap();
+        let mut first = prototype.instantiate().unwrap();
+        let second = prototype.clone().instantiate().unwrap();
+        first.tick(&mut ());
+        assert_eq!(first.tick_count(), 1);
+        assert_eq!(second.tick_count(), 0);
+    }
+
+    #[test]
+    fn suggests_fixes_for_misspellings() {
+        let text = "<root>\n\x20 <BehaviorTree ID='Main'>\n\x20   <Sequense name='Patrol'/>\n\x20 \
+                    </BehaviorTree>\n</root>\n";
+        let definition = Loader::new().load_str(text).unwrap();
+        let error = definition
+            .build(&NodeRegistry::<()>::with_std_nodes())
+            .err()
+            .unwrap();
+        assert_eq!(error.suggestion(), Some("Sequence"));
+        assert_eq!(
+            error.to_string(),
+            "3:5: Main: unknown node type Sequense (did you mean Sequence?)"
+        );
+        assert_eq!(
+            error.render(text),
+            "error: Main: unknown node type Sequense\n\x20--> <text>:3:5\n\x20 |\n3 |     \
+             <Sequense name='Patrol'/>\n\x20 |     ^^^^^^^^^^^^^^^^^^^^^^^^^\n\x20= help: did you \
+             mean Sequence?\n"
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_their_location() {
+        let dir = env::temp_dir().join(format!("aspen-loader-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tree.xml");
+        fs::write(
+            &path,
+            "<root>\n\x20 <BehaviorTree ID='Main'>\n\x20   <Sequence>\n\x20     <SubTree \
+             ID='Main' />\n\x20     <Invert />\n\x20   </Sequence>\n\x20 \
+             </BehaviorTree>\n</root>\n",
+        )
+        .unwrap();
+
+        let definition = Loader::new().load_file(&path).unwrap();
+        let registry = NodeRegistry::<()>::with_std_nodes();
+        let error = definition.build(&registry).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{}:4:7: tree Main contains itself (Main > Main)",
+                path.display()
+            )
+        );
+
+        let mut document = definition.document().clone();
+        document.children_mut()[0].children_mut()[0]
+            .children_mut()
+            .remove(0);
+        let error = super::TreeDefinition::new(document, None)
+            .unwrap()
+            .build(&registry)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{}:5:7: Main > Sequence > Invert: expected one child, found 0",
+                path.display()
+            )
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn includes_are_relative_to_the_including_file() {
+        let dir = env::temp_dir().join(format!("aspen-include-{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        let tree = |id: &str, body: &str| {
+            format!(
+                "<BehaviorTree ID='{}'><Sequence>{}</Sequence></BehaviorTree>",
+                id, body
+            )
+        };
+        fs::write(
+            dir.join("main.xml"),
+            format!(
+                "<root main_tree_to_execute='Main'><include path='lib/nav.xml'/><include \
+                 path='common.xml'/>{}</root>",
+                tree("Main", "<SubTree ID='Nav'/><SubTree ID='Common'/>")
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("lib/nav.xml"),
+            format!(
+                "<root><include path='../common.xml'/>{}</root>",
+                tree("Nav", "<SubTree ID='Common'/>")
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("common.xml"),
+            format!("<root>{}</root>", tree("Common", "<AlwaysSucceed/>")),
+        )
+        .unwrap();
+
+        let definition = Loader::new().load_file(dir.join("main.xml")).unwrap();
+        assert_eq!(
+            definition.trees().collect::<Vec<_>>(),
+            ["Common", "Nav", "Main"]
+        );
+        let registry = NodeRegistry::with_std_nodes();
+        let mut root = definition.build(&registry).unwrap();
+        assert_eq!(root.tick(&mut ()), Status::Succeeded);
+
+        fs::write(
+            dir.join("common.xml"),
+            format!(
+                "<root><include path='lib/nav.xml'/>{}</root>",
+                tree("Common", "<AlwaysSucceed/>")
+            ),
+        )
+        .unwrap();
+        let error = Loader::new().load_file(dir.join("main.xml")).unwrap_err();
+        let lib = dir.join("lib");
+        assert_eq!(
+            error.message(),
+            format!(
+                "include cycle: {} > {} > {}",
+                lib.join("nav.xml").display(),
+                lib.join("../common.xml").display(),
+                lib.join("../lib/nav.xml").display()
+            )
+        );
+        assert_eq!(error.file(), Some(&*lib.join("../common.xml")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+//! Layered parameters for a fleet of robots.
+use super::{suggest, LoadError, Loader};
+use crate::config::Config;
+use std::collections::HashMap;
+
+/// The layer a parameter was resolved from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParamLayer {
+    /// The defaults shared by every robot.
+    Default,
+
+    /// The overrides for a model of robot.
+    Model,
+
+    /// The overrides for a single robot.
+    Unit,
+}
+
+/// A robot, and the model it is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Unit {
+    /// The model of the robot.
+    model: String,
+
+    /// The overrides for the robot.
+    params: Config,
+}
+
+/// Resolves the parameters of each robot in a fleet from layers of overrides.
+///
+/// A parameter takes the value given for the robot itself if there is one,
+/// then the value given for its model, and then the default. This lets one
+/// set of tree files serve robots of different models, and robots of the same
+/// model which differ in calibration, by filling in `${name}` placeholders
+/// with the parameters of the robot the tree is loaded for.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::config::Config;
+/// # use aspen::loader::{ParamLayer, ParamServer};
+/// let mut defaults = Config::new();
+/// defaults.set("max_speed", 1.0);
+/// defaults.set("retries", 3);
+/// let mut heavy = Config::new();
+/// heavy.set("max_speed", 0.5);
+/// let mut rover7 = Config::new();
+/// rover7.set("retries", 5);
+///
+/// let server = ParamServer::new(defaults)
+///     .with_model("heavy", heavy)
+///     .with_unit("rover7", "heavy", rover7);
+///
+/// let definition = server.loader("rover7")?.load_str(
+///     r#"
+///     <root>
+///       <BehaviorTree ID="Main">
+///         <Repeat name="${max_speed}" limit="${retries}"><AlwaysSucceed /></Repeat>
+///       </BehaviorTree>
+///     </root>
+///     "#,
+/// )?;
+/// let node = &definition.document().children()[0].children()[0];
+/// assert_eq!(node.attribute("name"), Some("0.5"));
+/// assert_eq!(node.attribute("limit"), Some("5"));
+///
+/// assert_eq!(server.lookup("rover7", "max_speed"), Some(("0.5", ParamLayer::Model)));
+/// # Ok::<(), aspen::loader::LoadError>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParamServer {
+    /// The defaults shared by every robot.
+    defaults: Config,
+
+    /// The overrides for each model.
+    models: HashMap<String, Config>,
+
+    /// The robots, by identifier.
+    units: HashMap<String, Unit>,
+}
+impl ParamServer {
+    /// Creates a server with the given defaults.
+    pub fn new(defaults: Config) -> Self {
+        ParamServer {
+            defaults,
+            models: HashMap::new(),
+            units: HashMap::new(),
+        }
+    }
+
+    /// Adds a model of robot, with the parameters it overrides.
+    pub fn with_model<M: Into<String>>(mut self, model: M, params: Config) -> Self {
+        self.models.insert(model.into(), params);
+        self
+    }
+
+    /// Adds a robot of the given model, with the parameters it overrides.
+    ///
+    /// The model must also be added with `with_model`, even if it overrides
+    /// nothing, so that misspelled models are caught.
+    pub fn with_unit<U, M>(mut self, unit: U, model: M, params: Config) -> Self
+    where
+        U: Into<String>,
+        M: Into<String>,
+    {
+        let model = model.into();
+        self.units.insert(unit.into(), Unit { model, params });
+        self
+    }
+
+    /// Returns the model of the given robot, if it is known.
+    pub fn model(&self, unit: &str) -> Option<&str> {
+        self.units.get(unit).map(|u| u.model.as_str())
+    }
+
+    /// Returns the value of a parameter for the given robot, and the layer it
+    /// came from.
+    ///
+    /// Returns `None` if the robot or its model is unknown, or if no layer has
+    /// a value for the parameter.
+    pub fn lookup(&self, unit: &str, key: &str) -> Option<(&str, ParamLayer)> {
+        let (unit, model) = self.layers(unit).ok()?;
+        unit.text(key)
+            .map(|v| (v, ParamLayer::Unit))
+            .or_else(|| model.text(key).map(|v| (v, ParamLayer::Model)))
+            .or_else(|| self.defaults.text(key).map(|v| (v, ParamLayer::Default)))
+    }
+
+    /// Returns every parameter of the given robot, with the overrides applied.
+    pub fn resolve(&self, unit: &str) -> Result<Config, LoadError> {
+        let (unit, model) = self.layers(unit)?;
+        let mut resolved = self.defaults.clone();
+        for layer in &[model, unit] {
+            for key in layer.keys() {
+                if let Some(value) = layer.text(key) {
+                    resolved.set(key, value);
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Returns a loader which fills in placeholders with the parameters of
+    /// the given robot.
+    ///
+    /// The parameters are given to the loader with `Loader::param`, so they
+    /// take precedence over any config or blackboard it is later given.
+    pub fn loader(&self, unit: &str) -> Result<Loader<'static>, LoadError> {
+        let (unit, model) = self.layers(unit)?;
+        let mut loader = Loader::new();
+        for layer in &[&self.defaults, model, unit] {
+            for key in layer.keys() {
+                if let Some(value) = layer.text(key) {
+                    loader = loader.param(key, value);
+                }
+            }
+        }
+        Ok(loader)
+    }
+
+    /// Returns the overrides of the given robot and of its model.
+    fn layers(&self, unit: &str) -> Result<(&Config, &Config), LoadError> {
+        let overrides = self.units.get(unit).ok_or_else(|| {
+            let units = self.units.keys().map(String::as_str);
+            LoadError::new(format!("no robot {}", unit)).suggest(suggest::closest(unit, units))
+        })?;
+        let model = self.models.get(&overrides.model).ok_or_else(|| {
+            LoadError::new(format!(
+                "robot {} is of unknown model {}",
+                unit, overrides.model
+            ))
+            .suggest(suggest::closest(
+                &overrides.model,
+                self.models.keys().map(String::as_str),
+            ))
+        })?;
+        Ok((&overrides.params, model))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParamLayer, ParamServer};
+    use crate::config::Config;
+
+    #[test]
+    fn units_override_models_override_defaults() {
+        let mut defaults = Config::new();
+        defaults.set("speed", 1.0);
+        defaults.set("lidar", "front");
+        defaults.set("retries", 3);
+        let mut heavy = Config::new();
+        heavy.set("speed", 0.5);
+        heavy.set("retries", 4);
+        let mut unit = Config::new();
+        unit.set("retries", 5);
+
+        let server = ParamServer::new(defaults)
+            .with_model("heavy", heavy)
+            .with_model("light", Config::new())
+            .with_unit("rover1", "heavy", unit)
+            .with_unit("rover2", "light", Config::new())
+            .with_unit("rover3", "hevy", Config::new());
+
+        let resolved = server.resolve("rover1").unwrap();
+        assert_eq!(resolved.get::<f64>("speed"), Some(0.5));
+        assert_eq!(resolved.get::<u32>("retries"), Some(5));
+        assert_eq!(resolved.text("lidar"), Some("front"));
+        assert_eq!(
+            server.lookup("rover2", "speed"),
+            Some(("1", ParamLayer::Default))
+        );
+        assert_eq!(
+            server.lookup("rover1", "retries"),
+            Some(("5", ParamLayer::Unit))
+        );
+        assert_eq!(server.lookup("rover1", "missing"), None);
+
+        let error = server.resolve("rover4").unwrap_err();
+        assert_eq!(error.message(), "no robot rover4");
+        let error = server.loader("rover3").unwrap_err();
+        assert_eq!(error.message(), "robot rover3 is of unknown model hevy");
+        assert_eq!(error.suggestion(), Some("heavy"));
+    }
+}
@@ -0,0 +1,125 @@
+//! Trees resolved once, from which independent copies can be built quickly.
+use super::{LoadError, Span};
+use crate::{
+    node::Node,
+    registry::{self, ConstructorFn, Params},
+    BehaviorTree,
+};
+use std::{fmt, path::Path, sync::Arc};
+
+/// A node of a resolved tree, with everything needed to construct it.
+pub(crate) struct Plan<W> {
+    /// The constructor for the type of the node.
+    pub(crate) constructor: Arc<ConstructorFn<W>>,
+
+    /// The parameters of the node.
+    pub(crate) params: Params,
+
+    /// The name of the node.
+    pub(crate) name: Option<String>,
+
+    /// The children of the node.
+    pub(crate) children: Vec<Plan<W>>,
+
+    /// The start tag of the element the node was defined by.
+    pub(crate) span: Option<Span>,
+
+    /// The file the node was defined in.
+    pub(crate) file: Option<Arc<Path>>,
+
+    /// The IDs of the trees and the names of the nodes from the root to the
+    /// node.
+    pub(crate) path: Vec<String>,
+}
+impl<W: 'static> Plan<W> {
+    /// Constructs the node and its children.
+    pub(crate) fn construct(&self) -> Result<Node<'static, W>, LoadError> {
+        let children = self
+            .children
+            .iter()
+            .map(Plan::construct)
+            .collect::<Result<_, _>>()?;
+
+        let node = registry::call(&*self.constructor, &self.params, children).map_err(|e| {
+            let mut error = LoadError::new(e.message()).within(self.span);
+            if let Some(ref file) = self.file {
+                error = error.in_file(file);
+            }
+            error.details.path = self.path.clone();
+            error
+        })?;
+        Ok(node.named(self.name.clone()))
+    }
+}
+
+/// A tree resolved from a `TreeDefinition`, from which any number of
+/// independent copies can be built.
+///
+/// Resolving a tree looks up the constructor of every node and expands its
+/// subtrees once, so building a copy only calls the constructors. This suits
+/// programs which start a new tree for every job or agent. Prototypes are
+/// cheap to clone, and clones share the resolved tree.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::loader::Loader;
+/// # use aspen::registry::NodeRegistry;
+/// # use aspen::Status;
+/// let definition = Loader::new().load_str(
+///     r#"<root><BehaviorTree ID="Job"><Repeat limit="2"><AlwaysSucceed /></Repeat></BehaviorTree></root>"#,
+/// )?;
+/// let prototype = definition.prototype(&NodeRegistry::with_std_nodes())?;
+///
+/// let mut first = prototype.instantiate()?;
+/// let mut second = prototype.instantiate()?;
+/// assert_eq!(first.name(), Some("Job"));
+/// assert_eq!(first.tick(&mut ()), Status::Running);
+/// assert_eq!(first.tick(&mut ()), Status::Succeeded);
+/// assert_eq!(second.tick(&mut ()), Status::Running);
+/// # Ok::<(), aspen::loader::LoadError>(())
+/// ```
+pub struct Prototype<W> {
+    /// The ID of the tree.
+    id: String,
+
+    /// The root of the resolved tree.
+    root: Arc<Plan<W>>,
+}
+impl<W: 'static> Prototype<W> {
+    /// Creates a prototype of the tree with the given ID and root.
+    pub(crate) fn new(id: String, root: Plan<W>) -> Self {
+        Prototype {
+            id,
+            root: Arc::new(root),
+        }
+    }
+
+    /// Returns the ID of the tree.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Builds a new copy of the root node of the tree.
+    pub fn build(&self) -> Result<Node<'static, W>, LoadError> {
+        self.root.construct()
+    }
+
+    /// Builds a new copy of the tree, named after its ID.
+    pub fn instantiate(&self) -> Result<BehaviorTree<'static, W>, LoadError> {
+        Ok(BehaviorTree::new(self.build()?).named(Some(self.id.clone())))
+    }
+}
+impl<W> Clone for Prototype<W> {
+    fn clone(&self) -> Self {
+        Prototype {
+            id: self.id.clone(),
+            root: Arc::clone(&self.root),
+        }
+    }
+}
+impl<W> fmt::Debug for Prototype<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prototype").field("id", &self.id).finish()
+    }
+}
@@ -0,0 +1,150 @@
+//! Substitution of `${name}` placeholders in attribute values.
+use super::{Element, LoadError};
+use crate::{blackboard::Blackboard, config::Config};
+use std::{collections::HashMap, env};
+
+/// Where the values of placeholders come from, in order of precedence.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Sources<'l> {
+    /// Values given to the loader.
+    pub(crate) params: &'l HashMap<String, String>,
+
+    /// A config whose values can be used.
+    pub(crate) config: Option<&'l Config>,
+
+    /// A blackboard whose entries can be used.
+    pub(crate) blackboard: Option<&'l Blackboard>,
+
+    /// Whether environment variables can be used.
+    pub(crate) environment: bool,
+}
+impl<'l> Sources<'l> {
+    /// Returns the value for the placeholder with the given name.
+    fn lookup(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.params.get(name) {
+            return Some(value.clone());
+        }
+        if let Some(value) = self.config.and_then(|c| c.text(name)) {
+            return Some(value.to_owned());
+        }
+        if let Some(value) = self.blackboard.and_then(|b| b.get_text(name)) {
+            return Some(value);
+        }
+        if self.environment {
+            return env::var(name).ok();
+        }
+        None
+    }
+}
+
+/// Replaces the placeholders in the attributes of the element and its
+/// descendants.
+pub(crate) fn substitute(document: &mut Element, sources: Sources<'_>) -> Result<(), LoadError> {
+    let mut result = Ok(());
+    document.visit_mut(&mut |element| {
+        if result.is_err() {
+            return;
+        }
+        for i in 0..element.attributes.len() {
+            match expand(&element.attributes[i].1, sources) {
+                Ok(Some(value)) => element.attributes[i].1 = value,
+                Ok(None) => {}
+                Err(message) => {
+                    let key = &element.attributes[i].0;
+                    result = Err(element.error(format!("in attribute {}: {}", key, message)));
+                    return;
+                }
+            }
+        }
+    });
+    result
+}
+
+/// Expands the placeholders in a value, returning `None` if it has none.
+///
+/// `${name}` is replaced by the value of `name`, and `${name:-default}` falls
+/// back to `default` if `name` has no value. `$$` stands for a single `$`.
+fn expand(value: &str, sources: Sources<'_>) -> Result<Option<String>, String> {
+    if !value.contains('$') {
+        return Ok(None);
+    }
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+
+        let body = rest
+            .strip_prefix('{')
+            .ok_or_else(|| "expected { after $, or $$ for a literal $".to_owned())?;
+        let end = body
+            .find('}')
+            .ok_or_else(|| "unterminated placeholder".to_owned())?;
+        let (name, default) = match body[..end].find(":-") {
+            Some(i) => (&body[..i], Some(&body[i + 2..end])),
+            None => (&body[..end], None),
+        };
+        if name.is_empty() {
+            return Err("empty placeholder".to_owned());
+        }
+
+        match sources.lookup(name) {
+            Some(v) => expanded.push_str(&v),
+            None => match default {
+                Some(d) => expanded.push_str(d),
+                None => return Err(format!("no value for ${{{}}}", name)),
+            },
+        }
+        rest = &body[end + 1..];
+    }
+    expanded.push_str(rest);
+
+    Ok(Some(expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, Sources};
+    use crate::{blackboard::Blackboard, config::Config};
+    use std::collections::HashMap;
+
+    #[test]
+    fn expands_placeholders() {
+        let mut params = HashMap::new();
+        params.insert("speed".to_owned(), "0.5".to_owned());
+        let mut blackboard = Blackboard::new();
+        blackboard.set("speed", 2.0f64);
+        blackboard.set("robot", "rover1".to_owned());
+        let mut config = Config::new();
+        config.set("robot", "rover2");
+        config.set("topic", "cmd_vel");
+        let sources = Sources {
+            params: &params,
+            config: Some(&config),
+            blackboard: Some(&blackboard),
+            environment: false,
+        };
+
+        let expand = |v| expand(v, sources);
+        assert_eq!(expand("plain"), Ok(None));
+        assert_eq!(
+            expand("/${robot}/${topic} at ${speed} m/s"),
+            Ok(Some("/rover2/cmd_vel at 0.5 m/s".to_owned()))
+        );
+        assert_eq!(expand("${timeout:-30}$$"), Ok(Some("30$".to_owned())));
+        assert_eq!(
+            expand("${timeout}"),
+            Err("no value for ${timeout}".to_owned())
+        );
+        assert_eq!(
+            expand("${robot"),
+            Err("unterminated placeholder".to_owned())
+        );
+    }
+}
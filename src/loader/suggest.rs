@@ -0,0 +1,61 @@
+//! Suggestions for misspelled names.
+
+/// Returns the candidate closest to `name`, if any is close enough to be a
+/// likely misspelling of it.
+///
+/// Names which differ only in case always match. Otherwise, the edit distance
+/// must be at most a third of the length of the name.
+pub(crate) fn closest<'c, I>(name: &str, candidates: I) -> Option<&'c str>
+where
+    I: IntoIterator<Item = &'c str>,
+{
+    let lower = name.to_lowercase();
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|c| *c != name)
+        .map(|c| {
+            let distance = if c.to_lowercase() == lower {
+                0
+            } else {
+                distance(&lower, &c.to_lowercase())
+            };
+            (distance, c)
+        })
+        .filter(|&(d, _)| d <= limit)
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, c)| c)
+}
+
+/// Returns the Levenshtein distance between two strings, in characters.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest, distance};
+
+    #[test]
+    fn suggests_close_names() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+
+        let names = vec!["Sequence", "Selector", "Parallel", "Invert"];
+        assert_eq!(closest("Sequense", names.clone()), Some("Sequence"));
+        assert_eq!(closest("selector", names.clone()), Some("Selector"));
+        assert_eq!(closest("Inverter", names.clone()), Some("Invert"));
+        assert_eq!(closest("MoveTo", names), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! A parser for the subset of XML used by tree files.
+//!
+//! This handles elements, attributes, comments, the XML declaration and the
+//! predefined and numeric entities. Text content is ignored, as tree files do
+//! not use it, and there is no support for namespaces or DTDs.
+use super::{Element, LoadError, Position, Span};
+use std::{iter::Peekable, str::Chars};
+
+/// Parses a document, returning its root element.
+pub(crate) fn parse(text: &str) -> Result<Element, LoadError> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        position: Position { line: 1, column: 1 },
+    };
+
+    parser.skip_misc()?;
+    let root = match parser.peek() {
+        Some('<') => parser.element()?,
+        Some(_) => return Err(parser.error("expected an element")),
+        None => return Err(parser.error("the document is empty")),
+    };
+    parser.skip_misc()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected content after the root element"));
+    }
+    Ok(root)
+}
+
+/// Appends text with the characters which are special in XML escaped, so
+/// that it can be used as an attribute value or as text content.
+pub(crate) fn push_escaped(xml: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => xml.push_str("&lt;"),
+            '>' => xml.push_str("&gt;"),
+            '&' => xml.push_str("&amp;"),
+            '"' => xml.push_str("&quot;"),
+            '\'' => xml.push_str("&apos;"),
+            c => xml.push(c),
+        }
+    }
+}
+
+/// The state of the parser.
+struct Parser<'t> {
+    /// The remaining text.
+    chars: Peekable<Chars<'t>>,
+
+    /// The position of the next character.
+    position: Position,
+}
+impl<'t> Parser<'t> {
+    /// Returns the next character without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    /// Consumes the next character.
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Consumes the given text, which must come next.
+    fn expect(&mut self, text: &str) -> Result<(), LoadError> {
+        for expected in text.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error(format!("expected {}", text)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error at the current position.
+    fn error<M: Into<String>>(&self, message: M) -> LoadError {
+        LoadError::new(message).at(self.position)
+    }
+
+    /// Skips whitespace.
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    /// Skips whitespace, comments and processing instructions.
+    fn skip_misc(&mut self) -> Result<(), LoadError> {
+        loop {
+            self.skip_whitespace();
+            let mut ahead = self.chars.clone();
+            match (ahead.next(), ahead.next()) {
+                (Some('<'), Some('!')) => self.comment()?,
+                (Some('<'), Some('?')) => self.skip_until("?>")?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips a comment.
+    fn comment(&mut self) -> Result<(), LoadError> {
+        self.expect("<!--")?;
+        self.skip_until("-->")
+    }
+
+    /// Skips past the next occurrence of the given text.
+    fn skip_until(&mut self, end: &str) -> Result<(), LoadError> {
+        let start = self.position;
+        let mut seen = String::new();
+        while !seen.ends_with(end) {
+            match self.next() {
+                Some(c) => seen.push(c),
+                None => {
+                    return Err(
+                        LoadError::new(format!("unterminated markup, expected {}", end)).at(start),
+                    )
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a name.
+    fn name(&mut self) -> Result<String, LoadError> {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':') {
+                name.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        if name.is_empty() {
+            Err(self.error("expected a name"))
+        } else {
+            Ok(name)
+        }
+    }
+
+    /// Parses an element and its contents.
+    fn element(&mut self) -> Result<Element, LoadError> {
+        let position = self.position;
+        self.expect("<")?;
+        let mut element = Element::new(self.name()?);
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('/') => {
+                    self.expect("/>")?;
+                    element.span = Some(Span {
+                        start: position,
+                        end: self.position,
+                    });
+                    return Ok(element);
+                }
+                Some('>') => {
+                    self.next();
+                    element.span = Some(Span {
+                        start: position,
+                        end: self.position,
+                    });
+                    break;
+                }
+                Some(_) => {
+                    let key = self.name()?;
+                    if element.attribute(&key).is_some() {
+                        return Err(self.error(format!("duplicate attribute {}", key)));
+                    }
+                    self.skip_whitespace();
+                    self.expect("=")?;
+                    self.skip_whitespace();
+                    let value = self.quoted()?;
+                    element.attributes.push((key, value));
+                }
+                None => return Err(self.error("unterminated element")),
+            }
+        }
+
+        loop {
+            let mut ahead = self.chars.clone();
+            match (ahead.next(), ahead.next()) {
+                (Some('<'), Some('/')) => {
+                    let end = self.position;
+                    self.expect("</")?;
+                    let name = self.name()?;
+                    if name != element.name {
+                        return Err(LoadError::new(format!(
+                            "expected </{}>, found </{}>",
+                            element.name, name
+                        ))
+                        .at(end));
+                    }
+                    self.skip_whitespace();
+                    self.expect(">")?;
+                    return Ok(element);
+                }
+                (Some('<'), Some('!')) => self.comment()?,
+                (Some('<'), _) => element.children.push(self.element()?),
+                (Some(_), _) => {
+                    self.next();
+                }
+                (None, _) => {
+                    return Err(
+                        LoadError::new(format!("unterminated element {}", element.name))
+                            .at(position),
+                    )
+                }
+            }
+        }
+    }
+
+    /// Parses a quoted attribute value.
+    fn quoted(&mut self) -> Result<String, LoadError> {
+        let quote = match self.next() {
+            Some(c @ '"') | Some(c @ '\'') => c,
+            _ => return Err(self.error("expected a quoted value")),
+        };
+
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some(c) if c == quote => return Ok(value),
+                Some('&') => value.push(self.entity()?),
+                Some('<') => return Err(self.error("unexpected < in attribute value")),
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated attribute value")),
+            }
+        }
+    }
+
+    /// Parses an entity, after its `&`.
+    fn entity(&mut self) -> Result<char, LoadError> {
+        let mut entity = String::new();
+        loop {
+            match self.next() {
+                Some(';') => break,
+                Some(c) if entity.len() < 10 => entity.push(c),
+                _ => return Err(self.error("unterminated entity")),
+            }
+        }
+
+        let c = match entity.as_str() {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            e if e.starts_with("#x") => u32::from_str_radix(&e[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            e if e.starts_with('#') => e[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        };
+        c.ok_or_else(|| self.error(format!("unknown entity &{};", entity)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn parses_elements_and_attributes() {
+        let root = parse(
+            "<?xml version=\"1.0\"?>\n<!-- a comment -->\n<root a='1 &lt; 2'>\n\x20 <Child \
+             b=\"&#x41;&amp;\" />\n\x20 <!-- another -->\n\x20 <Other></Other>\n</root>\n",
+        )
+        .unwrap();
+        assert_eq!(root.name(), "root");
+        assert_eq!(root.attribute("a"), Some("1 < 2"));
+        assert_eq!(root.children().len(), 2);
+        assert_eq!(root.children()[0].attribute("b"), Some("A&"));
+        assert_eq!(root.children()[1].line(), Some(6));
+    }
+
+    #[test]
+    fn reports_positions() {
+        let error = parse("<root>\n  <Child>\n</root>").unwrap_err();
+        assert_eq!(error.to_string(), "3:1: expected </Child>, found </root>");
+
+        let error = parse("<root a=\"1\" a=\"2\"/>").unwrap_err();
+        assert_eq!(error.to_string(), "1:14: duplicate attribute a");
+    }
+}
@@ -0,0 +1,281 @@
+//! A lightweight status beacon sent over UDP.
+//!
+//! Each beacon is a single datagram holding the structure hash of the tree,
+//! its tick count and the status of every node packed into two bits, which
+//! keeps a tree of a hundred nodes under 64 bytes. This suits links where a
+//! full telemetry stack is too heavy, and can be sent to a multicast group so
+//! that any number of listeners can watch the tree.
+//!
+//! A beacon is laid out as follows, with integers in little-endian order:
+//!
+//! | Bytes  | Contents                                        |
+//! |--------|-------------------------------------------------|
+//! | 4      | The magic bytes `ASPB`                          |
+//! | 1      | The format version, which is 1                  |
+//! | 8      | The structure hash, as from `structure_hash`    |
+//! | 8      | The tick count of the tree                      |
+//! | 4      | The number of nodes                             |
+//! | n / 4  | The statuses, four to a byte, lowest bits first |
+//!
+//! Each status is 0 if the node has not been ticked, 1 if it is running, 2 if
+//! it succeeded and 3 if it failed. Nodes are in pre-order.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use aspen::std_nodes::*;
+//! # use aspen::monitor::beacon::Beacon;
+//! # use aspen::BehaviorTree;
+//! # use std::time::Duration;
+//! let mut tree = BehaviorTree::new(AlwaysRunning::new());
+//! let mut beacon = Beacon::new("239.255.42.1:7400", Duration::from_millis(200)).unwrap();
+//! tree.run(
+//!     10.0,
+//!     &mut (),
+//!     Some(|t: &BehaviorTree<()>| beacon.observe(t)),
+//! );
+//! ```
+use super::{collect_statuses, structure_hash};
+use crate::{bt::BehaviorTree, status::Status};
+use std::{
+    convert::TryInto,
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+/// The bytes every beacon starts with.
+const MAGIC: &[u8; 4] = b"ASPB";
+
+/// The version of the layout.
+const VERSION: u8 = 1;
+
+/// The length of everything before the statuses.
+const HEADER_LEN: usize = 25;
+
+/// The contents of a beacon.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BeaconPacket {
+    /// The hash of the tree's structure, as computed by `structure_hash`.
+    pub structure_hash: u64,
+
+    /// The number of times the tree had been ticked.
+    pub tick_count: u64,
+
+    /// The status of every node, in pre-order.
+    pub statuses: Vec<Option<Status>>,
+}
+impl BeaconPacket {
+    /// Reads a beacon, returning `None` if it is not one.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return None;
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        let count = u32::from_le_bytes(bytes[21..25].try_into().unwrap()) as usize;
+        let packed = &bytes[HEADER_LEN..];
+        if packed.len() != (count + 3) / 4 {
+            return None;
+        }
+
+        let statuses = (0..count)
+            .map(|i| match packed[i / 4] >> (i % 4 * 2) & 0b11 {
+                1 => Some(Status::Running),
+                2 => Some(Status::Succeeded),
+                3 => Some(Status::Failed),
+                _ => None,
+            })
+            .collect();
+        Some(BeaconPacket {
+            structure_hash: u64_at(5),
+            tick_count: u64_at(13),
+            statuses,
+        })
+    }
+
+    /// Writes the beacon into `buffer`.
+    ///
+    /// The buffer is cleared before being written to.
+    pub fn encode(&self, buffer: &mut Vec<u8>) {
+        encode(self.structure_hash, self.tick_count, &self.statuses, buffer);
+    }
+}
+
+/// Writes a beacon into `buffer`, which is cleared first.
+fn encode(structure_hash: u64, tick_count: u64, statuses: &[Option<Status>], buffer: &mut Vec<u8>) {
+    buffer.clear();
+    buffer.extend_from_slice(MAGIC);
+    buffer.push(VERSION);
+    buffer.extend_from_slice(&structure_hash.to_le_bytes());
+    buffer.extend_from_slice(&tick_count.to_le_bytes());
+    buffer.extend_from_slice(&(statuses.len() as u32).to_le_bytes());
+    for chunk in statuses.chunks(4) {
+        let byte = chunk.iter().enumerate().fold(0, |byte, (i, status)| {
+            let code = match status {
+                None => 0,
+                Some(Status::Running) => 1,
+                Some(Status::Succeeded) => 2,
+                Some(Status::Failed) => 3,
+            };
+            byte | code << (i * 2)
+        });
+        buffer.push(byte);
+    }
+}
+
+/// A publisher that sends a beacon for a tree over UDP at a limited rate.
+///
+/// The buffers are reused between beacons, and the structure hash is only
+/// computed again when the number of nodes changes.
+pub struct Beacon {
+    /// The socket the beacons are sent from.
+    socket: UdpSocket,
+
+    /// Where the beacons are sent.
+    target: SocketAddr,
+
+    /// The minimum amount of time between beacons.
+    period: Duration,
+
+    /// The last time a beacon was sent.
+    last_sent: Option<Instant>,
+
+    /// The structure hash of the tree, as of the last beacon.
+    structure_hash: u64,
+
+    /// The statuses of the nodes, as of the last beacon.
+    statuses: Vec<Option<Status>>,
+
+    /// The encoded beacon.
+    packet: Vec<u8>,
+}
+impl Beacon {
+    /// Creates a beacon which is sent to `target` at most once every
+    /// `period`, from a socket bound to any free port.
+    ///
+    /// The target may be a multicast group, in which case the beacons do not
+    /// leave the local network unless the time to live of the socket is
+    /// raised.
+    pub fn new<A: ToSocketAddrs>(target: A, period: Duration) -> io::Result<Self> {
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no target address"))?;
+        let any: SocketAddr = if target.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        Ok(Beacon::with_socket(UdpSocket::bind(any)?, target, period))
+    }
+
+    /// Creates a beacon which is sent to `target` from `socket`.
+    pub fn with_socket(socket: UdpSocket, target: SocketAddr, period: Duration) -> Self {
+        Beacon {
+            socket,
+            target,
+            period,
+            last_sent: None,
+            structure_hash: 0,
+            statuses: Vec::new(),
+            packet: Vec::new(),
+        }
+    }
+
+    /// Returns the socket the beacons are sent from, so that options such as
+    /// the multicast time to live can be set.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Sends a beacon if at least `period` has passed since the last one.
+    ///
+    /// Failures to send are logged rather than returned, so that this can be
+    /// called from the hook passed to `BehaviorTree::run`.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        let due = match self.last_sent {
+            Some(t) => t.elapsed() >= self.period,
+            None => true,
+        };
+        if due {
+            if let Err(e) = self.send(tree) {
+                warn!("Unable to send beacon to {}: {}", self.target, e);
+            }
+        }
+    }
+
+    /// Immediately sends a beacon.
+    pub fn send<W>(&mut self, tree: &BehaviorTree<'_, W>) -> io::Result<()> {
+        let count = self.statuses.len();
+        collect_statuses(tree.root(), &mut self.statuses);
+        if self.statuses.len() != count || self.last_sent.is_none() {
+            self.structure_hash = structure_hash(tree.root());
+        }
+
+        encode(
+            self.structure_hash,
+            tree.tick_count(),
+            &self.statuses,
+            &mut self.packet,
+        );
+        self.last_sent = Some(Instant::now());
+        self.socket.send_to(&self.packet, self.target).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Beacon, BeaconPacket};
+    use crate::{
+        monitor::structure_hash,
+        std_nodes::{AlwaysFail, AlwaysRunning, AlwaysSucceed, Selector, Sequence},
+        BehaviorTree, Status,
+    };
+    use std::{net::UdpSocket, time::Duration};
+
+    #[test]
+    fn beacons_round_trip() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut beacon =
+            Beacon::new(receiver.local_addr().unwrap(), Duration::from_secs(60)).unwrap();
+
+        let mut tree = BehaviorTree::new(Selector::new(vec![
+            AlwaysFail::new(),
+            Sequence::new(vec![AlwaysSucceed::new(), AlwaysRunning::new()]),
+            AlwaysSucceed::new(),
+        ]));
+        tree.tick(&mut ());
+        beacon.observe(&tree);
+        // Not due again yet
+        beacon.observe(&tree);
+
+        let mut bytes = [0; 64];
+        let len = receiver.recv(&mut bytes).unwrap();
+        assert_eq!(len, 25 + 2);
+        let packet = BeaconPacket::decode(&bytes[..len]).unwrap();
+        assert_eq!(packet.structure_hash, structure_hash(tree.root()));
+        assert_eq!(packet.tick_count, 1);
+        assert_eq!(
+            packet.statuses,
+            vec![
+                Some(Status::Running),
+                Some(Status::Failed),
+                Some(Status::Running),
+                Some(Status::Succeeded),
+                Some(Status::Running),
+                None,
+            ]
+        );
+
+        let mut encoded = Vec::new();
+        packet.encode(&mut encoded);
+        assert_eq!(&encoded[..], &bytes[..len]);
+        assert_eq!(BeaconPacket::decode(&bytes[..len - 1]), None);
+
+        receiver.set_nonblocking(true).unwrap();
+        assert!(receiver.recv(&mut bytes).is_err());
+    }
+}
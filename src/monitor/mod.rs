@@ -0,0 +1,226 @@
+//! Telemetry for running behavior trees.
+//!
+//! Nodes are identified by their position in a pre-order traversal of the tree,
+//! with the root node at index zero. As long as the structure of the tree does
+//! not change these identifiers are stable, which allows telemetry to refer to
+//! nodes without sending their names on every update.
+//!
+//! The monitors in this module need the `monitoring` feature, and are designed
+//! to be driven from the hook passed to `BehaviorTree::run`:
+//!
+//! ```
+//! # #[cfg(feature = "monitoring")] {
+//! # use aspen::std_nodes::*;
+//! # use aspen::monitor::{BatchingMonitor, Transition};
+//! # use aspen::BehaviorTree;
+//! # use std::time::Duration;
+//! let mut tree = BehaviorTree::new(Sequence::new(vec![
+//!     AlwaysSucceed::new(),
+//!     AlwaysSucceed::new(),
+//! ]));
+//!
+//! let mut published = Vec::new();
+//! let mut monitor = BatchingMonitor::new(Duration::from_millis(100), |batch: &[Transition]| {
+//!     published.extend_from_slice(batch)
+//! });
+//!
+//! tree.run(
+//!     0.0,
+//!     &mut (),
+//!     Some(|t: &BehaviorTree<()>| monitor.observe(t)),
+//! );
+//! monitor.flush();
+//! # drop(monitor);
+//! assert_eq!(published.len(), 3);
+//! # }
+//! ```
+use crate::{
+    node::{Node, NodeName, Tickable},
+    status::Status,
+};
+
+#[cfg(feature = "monitoring")]
+pub mod beacon;
+
+#[cfg(feature = "monitoring")]
+pub mod proto;
+
+#[cfg(feature = "monitoring")]
+mod telemetry;
+#[cfg(feature = "monitoring")]
+pub use self::telemetry::{
+    BatchingMonitor, Coverage, Heartbeat, HeartbeatMonitor, ProgressMonitor, ProgressUpdate,
+    Publisher, StatusFrame, Transition,
+};
+
+/// Returns every node in the tree rooted at `root`, in pre-order.
+///
+/// The position of a node in the returned vector is its identifier.
+pub fn preorder<'n, W>(root: &'n Node<'_, W>) -> Vec<&'n Node<'n, W>> {
+    let mut nodes = Vec::new();
+    push_nodes(root, &mut nodes);
+    nodes
+}
+
+/// Recursively appends `node` and its descendants.
+fn push_nodes<'n, W>(node: &'n Node<'n, W>, nodes: &mut Vec<&'n Node<'n, W>>) {
+    nodes.push(node);
+    for child in node.children() {
+        push_nodes(child, nodes);
+    }
+}
+
+/// Writes the status of every node in the tree into `buffer`, in pre-order.
+///
+/// The buffer is cleared before being written to, which allows it to be reused
+/// between calls without reallocating.
+pub fn collect_statuses<W>(root: &Node<'_, W>, buffer: &mut Vec<Option<Status>>) {
+    buffer.clear();
+    push_statuses(root, buffer);
+}
+
+/// Recursively appends the status of `node` and its descendants.
+fn push_statuses<W>(node: &Node<'_, W>, buffer: &mut Vec<Option<Status>>) {
+    buffer.push(node.status());
+    for child in node.children() {
+        push_statuses(child, buffer);
+    }
+}
+
+/// Writes the name of every node in the tree into `buffer`, in pre-order.
+///
+/// This maps the identifiers of the nodes to their names. The names are shared
+/// rather than copied, so the map can be kept alongside the statuses and only
+/// needs to be collected again when the structure of the tree changes.
+pub fn collect_names<W>(root: &Node<'_, W>, buffer: &mut Vec<NodeName>) {
+    buffer.clear();
+    push_names(root, buffer);
+}
+
+/// Recursively appends the name of `node` and its descendants.
+fn push_names<W>(node: &Node<'_, W>, buffer: &mut Vec<NodeName>) {
+    buffer.push(node.shared_name());
+    for child in node.children() {
+        push_names(child, buffer);
+    }
+}
+
+/// Writes the phase of every node in the tree into `buffer`, in pre-order.
+///
+/// A node belongs to the phase it was tagged with by `Node::in_phase`, or else
+/// to the phase of its nearest tagged ancestor. This maps the identifiers in
+/// telemetry to phases, so that transitions can be rolled up by phase as well
+/// as by node.
+pub fn collect_phases<W>(root: &Node<'_, W>, buffer: &mut Vec<Option<NodeName>>) {
+    buffer.clear();
+    push_phases(root, None, buffer);
+}
+
+/// Recursively appends the phase of `node` and its descendants, given the
+/// phase of its parent.
+fn push_phases<W>(node: &Node<'_, W>, parent: Option<&str>, buffer: &mut Vec<Option<NodeName>>) {
+    let phase = node.phase().or(parent);
+    buffer.push(phase.map(NodeName::intern));
+    for child in node.children() {
+        push_phases(child, phase, buffer);
+    }
+}
+
+/// Computes a hash of the structure of the tree rooted at `root`.
+///
+/// The hash covers the name and number of children of every node, but not
+/// their statuses. It uses 64-bit FNV-1a so that the value is the same across
+/// processes, platforms and compiler versions, allowing an external watchdog to
+/// verify it is watching the tree it expects.
+pub fn structure_hash<W>(root: &Node<'_, W>) -> u64 {
+    let mut hash = FNV_OFFSET;
+    hash_node(root, &mut hash);
+    hash
+}
+
+/// The FNV-1a 64-bit offset basis.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Feeds the structure of `node` and its descendants into `hash`.
+fn hash_node<W>(node: &Node<'_, W>, hash: &mut u64) {
+    let children = node.children();
+    let count = (children.len() as u64).to_le_bytes();
+    let bytes = node
+        .name()
+        .bytes()
+        // Separate the name from the child count so that the two can't alias
+        .chain(Some(0))
+        .chain(count.iter().cloned());
+    for b in bytes {
+        *hash ^= u64::from(b);
+        *hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    for child in children {
+        hash_node(child, hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        monitor::{collect_names, collect_phases, structure_hash},
+        std_nodes::{AlwaysFail, AlwaysRunning, AlwaysSucceed, Sequence},
+        BehaviorTree,
+    };
+
+    #[test]
+    fn structure_hash_ignores_status() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new(),
+            AlwaysRunning::new(),
+        ]));
+        let before = structure_hash(tree.root());
+        tree.tick(&mut ());
+        assert_eq!(before, structure_hash(tree.root()));
+
+        let other: BehaviorTree<()> =
+            BehaviorTree::new(Sequence::new(vec![AlwaysSucceed::new(), AlwaysFail::new()]));
+        assert_ne!(before, structure_hash(other.root()));
+    }
+
+    #[test]
+    fn names_are_shared() {
+        let tree: BehaviorTree<()> = BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new().named(Some(String::from("first"))),
+            AlwaysSucceed::new(),
+        ]));
+        let mut names = Vec::new();
+        collect_names(tree.root(), &mut names);
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        assert_eq!(names, vec!["Sequence", "first", "AlwaysSucceed"]);
+    }
+
+    #[test]
+    fn phases_are_inherited() {
+        let tree: BehaviorTree<()> = BehaviorTree::new(Sequence::new(vec![
+            Sequence::new(vec![
+                AlwaysSucceed::new(),
+                AlwaysSucceed::new().in_phase("Recovery"),
+            ])
+            .in_phase("Navigation"),
+            AlwaysSucceed::new(),
+        ]));
+        let mut phases = Vec::new();
+        collect_phases(tree.root(), &mut phases);
+        let phases: Vec<Option<&str>> = phases.iter().map(|p| p.as_deref()).collect();
+        assert_eq!(
+            phases,
+            vec![
+                None,
+                Some("Navigation"),
+                Some("Navigation"),
+                Some("Recovery"),
+                None
+            ]
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Protocol buffer encodings of tree telemetry.
+//!
+//! The messages are described by `proto/telemetry.proto` in the repository, so
+//! that clients in any language can generate decoders for them. The encoders
+//! here are written by hand and append to a caller's buffer, which can be
+//! reused between messages without reallocating.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::std_nodes::*;
+//! # use aspen::monitor::{proto, StatusFrame};
+//! # use aspen::BehaviorTree;
+//! let mut tree = BehaviorTree::new(AlwaysSucceed::new());
+//! let mut frame = StatusFrame::new();
+//! let mut buffer = Vec::new();
+//!
+//! tree.tick(&mut ());
+//! if frame.update(&tree) {
+//!     proto::encode_structure(&tree, &mut buffer);
+//!     // Publish the structure...
+//! }
+//! proto::encode_frame(&frame, &mut buffer);
+//! // Publish the statuses...
+//! ```
+use super::{structure_hash, StatusFrame, Transition};
+use crate::{
+    bt::BehaviorTree,
+    node::{Node, Tickable},
+    status::Status,
+};
+
+/// The wire type of varints.
+const VARINT: u8 = 0;
+
+/// The wire type of 64-bit fixed width values.
+const FIXED64: u8 = 1;
+
+/// The wire type of length-delimited values.
+const LENGTH: u8 = 2;
+
+/// Writes a `Structure` message describing the nodes of the tree into
+/// `buffer`.
+///
+/// The buffer is cleared before being written to.
+pub fn encode_structure<W>(tree: &BehaviorTree<'_, W>, buffer: &mut Vec<u8>) {
+    buffer.clear();
+    if let Some(name) = tree.name() {
+        push_string(buffer, 1, name);
+    }
+    push_fixed64(buffer, 2, structure_hash(tree.root()));
+    push_nodes(buffer, tree.root(), None, &mut 0);
+}
+
+/// Writes a `Transitions` message holding a batch of transitions into
+/// `buffer`.
+///
+/// The buffer is cleared before being written to.
+pub fn encode_transitions(
+    structure_hash: u64,
+    tick_count: u64,
+    transitions: &[Transition],
+    buffer: &mut Vec<u8>,
+) {
+    buffer.clear();
+    push_fixed64(buffer, 1, structure_hash);
+    push_varint_field(buffer, 2, tick_count);
+    for transition in transitions {
+        let fields = [
+            (1, transition.node as u64),
+            (2, status_code(transition.from)),
+            (3, status_code(transition.to)),
+        ];
+        let len: usize = fields
+            .iter()
+            .filter(|&&(_, v)| v != 0)
+            .map(|&(_, v)| 1 + varint_len(v))
+            .sum();
+        push_key(buffer, 3, LENGTH);
+        push_varint(buffer, len as u64);
+        for &(field, value) in &fields {
+            push_varint_field(buffer, field, value);
+        }
+    }
+}
+
+/// Writes a `Frame` message holding the status of every node into `buffer`.
+///
+/// The buffer is cleared before being written to.
+pub fn encode_frame(frame: &StatusFrame, buffer: &mut Vec<u8>) {
+    buffer.clear();
+    push_fixed64(buffer, 1, frame.structure_hash());
+    push_varint_field(buffer, 2, frame.tick_count());
+    if !frame.statuses().is_empty() {
+        // Every status code fits in a single byte
+        push_key(buffer, 3, LENGTH);
+        push_varint(buffer, frame.statuses().len() as u64);
+        buffer.extend(frame.statuses().iter().map(|&s| status_code(s) as u8));
+    }
+}
+
+/// Appends a `Node` message for `node` and each of its descendants, in
+/// pre-order.
+fn push_nodes<W>(buffer: &mut Vec<u8>, node: &Node<'_, W>, parent: Option<u64>, next: &mut u64) {
+    let id = *next;
+    *next += 1;
+
+    let (name, type_name) = (node.name(), node.type_name());
+    let mut len = string_len(name) + string_len(type_name);
+    if id != 0 {
+        len += 1 + varint_len(id);
+    }
+    if let Some(parent) = parent {
+        len += 1 + varint_len(parent);
+    }
+    push_key(buffer, 3, LENGTH);
+    push_varint(buffer, len as u64);
+    push_varint_field(buffer, 1, id);
+    if let Some(parent) = parent {
+        push_key(buffer, 2, VARINT);
+        push_varint(buffer, parent);
+    }
+    push_string(buffer, 3, name);
+    push_string(buffer, 4, type_name);
+
+    for child in node.children() {
+        push_nodes(buffer, child, Some(id), next);
+    }
+}
+
+/// Returns the code of a status in the `Status` enum.
+fn status_code(status: Option<Status>) -> u64 {
+    match status {
+        None => 0,
+        Some(Status::Running) => 1,
+        Some(Status::Succeeded) => 2,
+        Some(Status::Failed) => 3,
+    }
+}
+
+/// Appends the key of a field.
+fn push_key(buffer: &mut Vec<u8>, field: u8, wire_type: u8) {
+    buffer.push(field << 3 | wire_type);
+}
+
+/// Appends a varint.
+fn push_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Returns the number of bytes in the varint encoding of `value`.
+fn varint_len(value: u64) -> usize {
+    let bits = 64 - value.leading_zeros() as usize;
+    (bits.max(1) + 6) / 7
+}
+
+/// Appends a varint field, unless it has the default value of zero.
+fn push_varint_field(buffer: &mut Vec<u8>, field: u8, value: u64) {
+    if value != 0 {
+        push_key(buffer, field, VARINT);
+        push_varint(buffer, value);
+    }
+}
+
+/// Appends a fixed width 64-bit field.
+fn push_fixed64(buffer: &mut Vec<u8>, field: u8, value: u64) {
+    push_key(buffer, field, FIXED64);
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a string field, unless it is empty.
+fn push_string(buffer: &mut Vec<u8>, field: u8, value: &str) {
+    if !value.is_empty() {
+        push_key(buffer, field, LENGTH);
+        push_varint(buffer, value.len() as u64);
+        buffer.extend_from_slice(value.as_bytes());
+    }
+}
+
+/// Returns the number of bytes `push_string` appends for `value`.
+fn string_len(value: &str) -> usize {
+    if value.is_empty() {
+        0
+    } else {
+        1 + varint_len(value.len() as u64) + value.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_structure, encode_transitions, varint_len};
+    use crate::{
+        monitor::Transition,
+        std_nodes::{AlwaysSucceed, Sequence},
+        BehaviorTree, Status,
+    };
+
+    #[test]
+    fn messages_match_the_wire_format() {
+        let transitions = [Transition {
+            node: 1,
+            from: None,
+            to: Some(Status::Running),
+        }];
+        let mut buffer = Vec::new();
+        encode_transitions(0x0807_0605_0403_0201, 300, &transitions, &mut buffer);
+        assert_eq!(
+            buffer,
+            vec![
+                0x09, 1, 2, 3, 4, 5, 6, 7, 8, // structure_hash
+                0x10, 0xac, 0x02, // tick_count
+                0x1a, 4, 0x08, 1, 0x18, 1, // transitions
+            ]
+        );
+        assert_eq!(varint_len(0), 1);
+        assert_eq!(varint_len(300), 2);
+
+        let tree: BehaviorTree<()> =
+            BehaviorTree::new(Sequence::new(vec![AlwaysSucceed::new().named(Some("a"))]));
+        encode_structure(&tree, &mut buffer);
+        let root = b"\x1a\x14\x1a\x08Sequence\x22\x08Sequence";
+        let child = b"\x1a\x16\x08\x01\x10\x00\x1a\x01a\x22\x0dAlwaysSucceed";
+        assert_eq!(buffer[0], 0x11);
+        assert_eq!(&buffer[9..9 + root.len()], &root[..]);
+        assert_eq!(&buffer[9 + root.len()..], &child[..]);
+    }
+}
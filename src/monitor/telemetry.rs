@@ -0,0 +1,716 @@
+//! Monitors which publish the state of running trees.
+use super::{collect_names, collect_statuses, preorder, structure_hash};
+use crate::{
+    bt::BehaviorTree,
+    node::{Node, NodeName, Progress, Tickable},
+    status::Status,
+};
+use std::{
+    fmt, mem,
+    time::{Duration, Instant},
+};
+
+/// A change in the status of a single node.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Transition {
+    /// The pre-order index of the node.
+    pub node: usize,
+
+    /// The status of the node before the change.
+    pub from: Option<Status>,
+
+    /// The status of the node after the change.
+    pub to: Option<Status>,
+}
+
+/// A destination for batches of status transitions.
+///
+/// This is implemented for any `FnMut(&[Transition])`, which is usually enough
+/// to forward batches on to a transport such as LCM.
+pub trait Publisher {
+    /// Publishes a batch of transitions, in the order they were observed.
+    fn publish(&mut self, transitions: &[Transition]);
+}
+impl<F> Publisher for F
+where
+    F: FnMut(&[Transition]),
+{
+    fn publish(&mut self, transitions: &[Transition]) {
+        self(transitions)
+    }
+}
+
+/// A monitor that batches status transitions and publishes them at a limited
+/// rate.
+///
+/// Each observation records only the nodes whose status changed since the
+/// previous observation. These transitions are held until at least `period`
+/// has passed since the last publish, at which point the whole batch is handed
+/// to the publisher. Batches are never empty: if nothing changed, nothing is
+/// published.
+///
+/// The first observation of a tree reports every node as a transition from
+/// `None`, as does the first observation after the structure of the tree
+/// changes, as detected by `structure_hash`.
+pub struct BatchingMonitor<P> {
+    /// Where the batches are sent.
+    publisher: P,
+
+    /// The minimum amount of time between publishes.
+    period: Duration,
+
+    /// The last time a batch was published.
+    last_publish: Option<Instant>,
+
+    /// The hash of the structure of the tree seen during the last observation.
+    structure_hash: Option<u64>,
+
+    /// The statuses seen during the last observation.
+    statuses: Vec<Option<Status>>,
+
+    /// Scratch space for the current observation.
+    scratch: Vec<Option<Status>>,
+
+    /// Transitions which have yet to be published.
+    pending: Vec<Transition>,
+}
+impl<P> BatchingMonitor<P>
+where
+    P: Publisher,
+{
+    /// Creates a new monitor which publishes at most once every `period`.
+    ///
+    /// A period of zero will publish after every observation that saw a
+    /// change.
+    pub fn new(period: Duration, publisher: P) -> Self {
+        BatchingMonitor {
+            publisher,
+            period,
+            last_publish: None,
+            structure_hash: None,
+            statuses: Vec::new(),
+            scratch: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Records the transitions in the tree since the last observation and
+    /// publishes them if the period has elapsed.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        collect_statuses(tree.root(), &mut self.scratch);
+
+        let hash = structure_hash(tree.root());
+        let force_full = self.structure_hash != Some(hash);
+        if force_full {
+            trace!("Tree structure changed, reporting all nodes");
+            self.structure_hash = Some(hash);
+            self.statuses.clear();
+            self.statuses.resize(self.scratch.len(), None);
+            self.pending.clear();
+        }
+
+        for (node, (old, new)) in self.statuses.iter().zip(&self.scratch).enumerate() {
+            if old != new || force_full {
+                self.pending.push(Transition {
+                    node,
+                    from: *old,
+                    to: *new,
+                });
+            }
+        }
+        mem::swap(&mut self.statuses, &mut self.scratch);
+
+        let due = match self.last_publish {
+            Some(t) => t.elapsed() >= self.period,
+            None => true,
+        };
+        if due {
+            self.flush();
+        }
+    }
+
+    /// Immediately publishes any pending transitions.
+    pub fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            trace!("Publishing {} transitions", self.pending.len());
+            self.publisher.publish(&self.pending);
+            self.pending.clear();
+        }
+        self.last_publish = Some(Instant::now());
+    }
+
+    /// Returns the number of transitions waiting to be published.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns a reference to the publisher.
+    pub fn publisher(&self) -> &P {
+        &self.publisher
+    }
+}
+
+/// A message holding the status of every node in a tree, which is updated in
+/// place.
+///
+/// Publishers which send the whole state of the tree on every tick, such as an
+/// LCM status channel, can keep one frame and update it after each tick. The
+/// buffers are sized from the structure of the tree on the first update, and
+/// are only resized and refilled with the names of the nodes when the
+/// structure changes, so that updates do not allocate.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::monitor::StatusFrame;
+/// # use aspen::{BehaviorTree, Status};
+/// let mut tree = BehaviorTree::new(Sequence::new(vec![
+///     AlwaysSucceed::new(),
+///     AlwaysRunning::new(),
+/// ]));
+/// let mut frame = StatusFrame::new();
+///
+/// tree.tick(&mut ());
+/// assert!(frame.update(&tree));
+/// assert_eq!(frame.names()[1].as_str(), "AlwaysSucceed");
+///
+/// tree.tick(&mut ());
+/// assert!(!frame.update(&tree));
+/// assert_eq!(frame.statuses()[2], Some(Status::Running));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StatusFrame {
+    /// The hash of the tree's structure, as computed by `structure_hash`.
+    structure_hash: u64,
+
+    /// The number of times the tree had been ticked.
+    tick_count: u64,
+
+    /// The names of the nodes, in pre-order.
+    names: Vec<NodeName>,
+
+    /// The statuses of the nodes, in pre-order.
+    statuses: Vec<Option<Status>>,
+}
+impl StatusFrame {
+    /// Creates an empty frame.
+    pub fn new() -> Self {
+        StatusFrame::default()
+    }
+
+    /// Updates the frame from the tree, returning `true` if the structure of
+    /// the tree changed.
+    ///
+    /// The structure is assumed to be unchanged as long as the number of
+    /// nodes is the same, which holds for trees that are not rebuilt while
+    /// they run.
+    pub fn update<W>(&mut self, tree: &BehaviorTree<'_, W>) -> bool {
+        let count = self.statuses.len();
+        self.tick_count = tree.tick_count();
+        collect_statuses(tree.root(), &mut self.statuses);
+
+        let changed = self.statuses.len() != count || self.names.is_empty();
+        if changed {
+            trace!("Tree structure changed, rebuilding the status frame");
+            collect_names(tree.root(), &mut self.names);
+            self.structure_hash = structure_hash(tree.root());
+        }
+        changed
+    }
+
+    /// Returns the hash of the structure of the tree.
+    pub fn structure_hash(&self) -> u64 {
+        self.structure_hash
+    }
+
+    /// Returns the number of times the tree had been ticked at the last
+    /// update.
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// Returns the names of the nodes, in pre-order.
+    pub fn names(&self) -> &[NodeName] {
+        &self.names
+    }
+
+    /// Returns the statuses of the nodes, in pre-order.
+    pub fn statuses(&self) -> &[Option<Status>] {
+        &self.statuses
+    }
+}
+
+/// A liveness message describing a behavior tree.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Heartbeat<'t> {
+    /// The name of the tree, if it has one.
+    pub name: Option<&'t str>,
+
+    /// The hash of the tree's structure, as computed by `structure_hash`.
+    pub structure_hash: u64,
+
+    /// The number of times the tree has been ticked.
+    pub tick_count: u64,
+
+    /// The status of the root node.
+    pub status: Option<Status>,
+}
+
+/// A monitor that periodically emits a `Heartbeat` for a tree.
+///
+/// External watchdogs can use the heartbeat to detect an executive that has
+/// stopped ticking its tree. Heartbeats are only sent when `observe` or `beat`
+/// is called, so this relies on them being called from a loop which runs
+/// whether or not the tree is ticked, such as the executive's main loop when
+/// ticking is conditional. Then the tick count stops increasing while
+/// heartbeats still arrive, and if heartbeats stop arriving altogether, the
+/// loop itself is likely wedged. If `observe` is only called after each tick,
+/// both stop together.
+pub struct HeartbeatMonitor<F> {
+    /// Called with every heartbeat.
+    callback: F,
+
+    /// The minimum amount of time between heartbeats.
+    period: Duration,
+
+    /// The last time a heartbeat was sent.
+    last_beat: Option<Instant>,
+}
+impl<F> HeartbeatMonitor<F>
+where
+    F: FnMut(&Heartbeat<'_>),
+{
+    /// Creates a new monitor which emits a heartbeat at most once every
+    /// `period`.
+    pub fn new(period: Duration, callback: F) -> Self {
+        HeartbeatMonitor {
+            callback,
+            period,
+            last_beat: None,
+        }
+    }
+
+    /// Emits a heartbeat if at least `period` has passed since the last one.
+    ///
+    /// The first observation always emits a heartbeat.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        let due = match self.last_beat {
+            Some(t) => t.elapsed() >= self.period,
+            None => true,
+        };
+        if due {
+            self.beat(tree);
+        }
+    }
+
+    /// Immediately emits a heartbeat.
+    pub fn beat<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        let heartbeat = Heartbeat {
+            name: tree.name(),
+            structure_hash: structure_hash(tree.root()),
+            tick_count: tree.tick_count(),
+            status: tree.root().status(),
+        };
+        trace!("Heartbeat: {:?}", heartbeat);
+        (self.callback)(&heartbeat);
+        self.last_beat = Some(Instant::now());
+    }
+}
+
+/// A change in the progress reported by a single node.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProgressUpdate<'t> {
+    /// The pre-order index of the node.
+    pub node: usize,
+
+    /// The name of the node.
+    pub name: &'t str,
+
+    /// The progress the node now reports, or `None` if it stopped reporting.
+    pub progress: Option<&'t Progress>,
+}
+
+/// A monitor that reports changes to the progress of nodes, so that user
+/// interfaces can show messages such as "Docking: 65%".
+///
+/// Each observation reports every node whose progress changed since the
+/// previous observation, in a single batch. If nothing changed, nothing is
+/// reported.
+pub struct ProgressMonitor<F> {
+    /// Called with every batch of updates.
+    callback: F,
+
+    /// The progress seen during the last observation.
+    seen: Vec<Option<Progress>>,
+}
+impl<F> ProgressMonitor<F>
+where
+    F: FnMut(&[ProgressUpdate<'_>]),
+{
+    /// Creates a new progress monitor.
+    pub fn new(callback: F) -> Self {
+        ProgressMonitor {
+            callback,
+            seen: Vec::new(),
+        }
+    }
+
+    /// Reports the nodes whose progress changed since the last observation.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        let nodes = preorder(tree.root());
+        self.seen.resize(nodes.len(), None);
+
+        let mut updates = Vec::new();
+        for (id, (node, seen)) in nodes.iter().zip(&mut self.seen).enumerate() {
+            let progress = node.progress();
+            if progress != seen.as_ref() {
+                *seen = progress.cloned();
+                updates.push(ProgressUpdate {
+                    node: id,
+                    name: node.name(),
+                    progress,
+                });
+            }
+        }
+
+        if !updates.is_empty() {
+            trace!("Reporting progress of {} nodes", updates.len());
+            (self.callback)(&updates);
+        }
+    }
+}
+
+/// Which nodes of a tree have ever been ticked.
+///
+/// This is meant for test suites: take the coverage of the tree at the end of
+/// each test, merge the results, and check that every branch, including
+/// rarely-taken recovery branches, was exercised by some test.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::monitor::Coverage;
+/// # use aspen::BehaviorTree;
+/// let build = || {
+///     Selector::new(vec![
+///         Condition::new(|healthy: &bool| *healthy),
+///         AlwaysSucceed::new().named(Some("Recover")),
+///     ])
+/// };
+///
+/// let mut tree = BehaviorTree::new(build());
+/// tree.tick(&mut true);
+/// let mut coverage = Coverage::of(&tree);
+/// assert_eq!(
+///     coverage.uncovered().collect::<Vec<_>>(),
+///     [(2, "Selector > Recover")]
+/// );
+///
+/// let mut tree = BehaviorTree::new(build());
+/// tree.tick(&mut false);
+/// coverage.merge(&Coverage::of(&tree));
+/// assert_eq!(coverage.percentage(), 100.0);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Coverage {
+    /// The structure hash of the tree.
+    structure: u64,
+
+    /// The path of every node, in pre-order.
+    paths: Vec<String>,
+
+    /// Whether every node has been ticked, in pre-order.
+    ticked: Vec<bool>,
+}
+impl Coverage {
+    /// Returns the coverage of the tree so far.
+    ///
+    /// A node counts as covered if it has been ticked since it was created,
+    /// even if it has been reset since.
+    pub fn of<W>(tree: &BehaviorTree<'_, W>) -> Self {
+        let mut coverage = Coverage {
+            structure: structure_hash(tree.root()),
+            paths: Vec::new(),
+            ticked: Vec::new(),
+        };
+        coverage.push(tree.root(), "");
+        coverage
+    }
+
+    /// Recursively records `node`, whose parent has the given path, and its
+    /// descendants.
+    fn push<W>(&mut self, node: &Node<'_, W>, parent: &str) {
+        let path = if parent.is_empty() {
+            node.name().to_owned()
+        } else {
+            format!("{} > {}", parent, node.name())
+        };
+        self.ticked.push(node.tick_count() > 0);
+        self.paths.push(path.clone());
+        for child in node.children() {
+            self.push(child, &path);
+        }
+    }
+
+    /// Adds the nodes covered by `other`, which must be for a tree of the same
+    /// structure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the trees have different structures.
+    pub fn merge(&mut self, other: &Coverage) {
+        assert_eq!(
+            self.structure, other.structure,
+            "cannot merge the coverage of trees with different structures"
+        );
+        for (mine, theirs) in self.ticked.iter_mut().zip(&other.ticked) {
+            *mine |= *theirs;
+        }
+    }
+
+    /// Returns the number of nodes in the tree.
+    pub fn len(&self) -> usize {
+        self.ticked.len()
+    }
+
+    /// Returns `true` if the tree has no nodes, which is never the case.
+    pub fn is_empty(&self) -> bool {
+        self.ticked.is_empty()
+    }
+
+    /// Returns the number of nodes which have been ticked.
+    pub fn covered(&self) -> usize {
+        self.ticked.iter().filter(|&&t| t).count()
+    }
+
+    /// Returns the percentage of nodes which have been ticked.
+    pub fn percentage(&self) -> f64 {
+        100.0 * self.covered() as f64 / self.len() as f64
+    }
+
+    /// Returns the pre-order index and path of every node which has never been
+    /// ticked.
+    pub fn uncovered(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.ticked
+            .iter()
+            .zip(&self.paths)
+            .enumerate()
+            .filter(|(_, (&t, _))| !t)
+            .map(|(i, (_, p))| (i, p.as_str()))
+    }
+}
+impl fmt::Display for Coverage {
+    /// Writes a report of the coverage, listing the nodes which were never
+    /// ticked.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Tree coverage: {}/{} nodes ({:.1}%)",
+            self.covered(),
+            self.len(),
+            self.percentage()
+        )?;
+        for (id, path) in self.uncovered() {
+            write!(f, "\n  never ticked: #{} {}", id, path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        monitor::{BatchingMonitor, Coverage, HeartbeatMonitor, ProgressMonitor, Transition},
+        node::{Node, Progress, Tickable},
+        std_nodes::{AlwaysFail, AlwaysRunning, AlwaysSucceed, Sequence},
+        BehaviorTree, Status,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn coverage_reports_untouched_nodes() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            AlwaysFail::new(),
+            AlwaysSucceed::new().named(Some("Never")),
+        ]));
+        tree.tick(&mut ());
+        tree.reset();
+
+        let coverage = Coverage::of(&tree);
+        assert_eq!(coverage.covered(), 2);
+        assert_eq!(
+            coverage.to_string(),
+            "Tree coverage: 2/3 nodes (66.7%)\n  never ticked: #2 Sequence > Never"
+        );
+    }
+
+    #[test]
+    fn first_observation_reports_everything() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new(),
+            AlwaysRunning::new(),
+        ]));
+        let mut batches: Vec<Vec<Transition>> = Vec::new();
+        let mut monitor = BatchingMonitor::new(Duration::from_secs(0), |b: &[Transition]| {
+            batches.push(b.to_vec())
+        });
+
+        monitor.observe(&tree);
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        drop(monitor);
+
+        // Everything, then only the changes, then nothing changed
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].len(), 3);
+        assert!(batches[0]
+            .iter()
+            .all(|t| t.from.is_none() && t.to.is_none()));
+        assert_eq!(batches[1].len(), 3);
+        assert_eq!(batches[1][0].to, Some(Status::Running));
+    }
+
+    #[test]
+    fn structure_changes_report_everything() {
+        let tree: BehaviorTree<()> = BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new(),
+            AlwaysRunning::new(),
+        ]));
+        let other: BehaviorTree<()> =
+            BehaviorTree::new(Sequence::new(vec![AlwaysSucceed::new(), AlwaysFail::new()]));
+        let mut batches: Vec<Vec<Transition>> = Vec::new();
+        let mut monitor = BatchingMonitor::new(Duration::from_secs(0), |b: &[Transition]| {
+            batches.push(b.to_vec())
+        });
+
+        monitor.observe(&tree);
+        monitor.observe(&other);
+        drop(monitor);
+
+        // The same number of nodes, but not the same nodes
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].len(), 3);
+        assert!(batches[1]
+            .iter()
+            .all(|t| t.from.is_none() && t.to.is_none()));
+    }
+
+    #[test]
+    fn batches_until_period_elapses() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new(),
+            AlwaysRunning::new(),
+        ]));
+        let mut published = 0;
+        let mut monitor = BatchingMonitor::new(Duration::from_secs(3600), |b: &[Transition]| {
+            published += b.len()
+        });
+
+        monitor.observe(&tree);
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        assert_eq!(monitor.pending(), 3);
+
+        monitor.flush();
+        assert_eq!(monitor.pending(), 0);
+        drop(monitor);
+        assert_eq!(published, 6);
+    }
+
+    #[test]
+    fn heartbeat() {
+        let mut tree = BehaviorTree::new(AlwaysRunning::new()).named(Some("patrol"));
+        let mut beats = Vec::new();
+        let mut monitor = HeartbeatMonitor::new(Duration::from_secs(3600), |h: &_| {
+            beats.push((h.name.map(String::from), h.tick_count, h.status))
+        });
+
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        monitor.beat(&tree);
+
+        assert_eq!(
+            beats,
+            vec![
+                (Some("patrol".to_string()), 1, Some(Status::Running)),
+                (Some("patrol".to_string()), 2, Some(Status::Running)),
+            ]
+        );
+    }
+
+    #[test]
+    fn heartbeats_continue_while_ticking_stalls() {
+        let mut tree = BehaviorTree::new(AlwaysRunning::new());
+        let mut beats = Vec::new();
+        let mut monitor =
+            HeartbeatMonitor::new(Duration::from_secs(0), |h: &_| beats.push(h.tick_count));
+
+        // The loop keeps observing after the tree stops being ticked
+        for i in 0..4 {
+            if i < 2 {
+                tree.tick(&mut ());
+            }
+            monitor.observe(&tree);
+        }
+
+        assert_eq!(beats, vec![1, 2, 2, 2]);
+    }
+
+    /// Reports progress in steps of a quarter, one step per tick.
+    struct Stepper(Option<Progress>);
+    impl Tickable<()> for Stepper {
+        fn tick(&mut self, _: &mut ()) -> Status {
+            let done = self.0.as_ref().map_or(0.0, Progress::fraction) + 0.25;
+            self.0 = Some(Progress::new(done, "Docking"));
+            Status::Running
+        }
+
+        fn reset(&mut self) {
+            self.0 = None;
+        }
+
+        fn progress(&self) -> Option<&Progress> {
+            self.0.as_ref()
+        }
+
+        fn type_name(&self) -> &'static str {
+            "Stepper"
+        }
+    }
+
+    #[test]
+    fn progress_reports_changes() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![Node::new(Stepper(None))]));
+        let mut reports = Vec::new();
+        let mut monitor = ProgressMonitor::new(|updates: &[_]| {
+            for u in updates {
+                reports.push((u.node, u.progress.map(ToString::to_string)));
+            }
+        });
+
+        monitor.observe(&tree);
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        monitor.observe(&tree);
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        tree.reset();
+        monitor.observe(&tree);
+        drop(monitor);
+
+        assert_eq!(
+            reports,
+            vec![
+                (1, Some("Docking: 25%".to_string())),
+                (1, Some("Docking: 50%".to_string())),
+                (1, None),
+            ]
+        );
+    }
+}
@@ -1,7 +1,23 @@
 //! Behavior tree nodes and internal node logic.
 
-use crate::status::Status;
-use std::fmt;
+use crate::{
+    blackboard::{Access, KeyUse},
+    monitor::preorder,
+    status::Status,
+};
+use std::{
+    any::Any,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 /// Represents a generic node.
 ///
@@ -12,6 +28,10 @@ use std::fmt;
 ///
 /// This class is largely just a wrapper around an `Tickable` object. This is
 /// to enforce some runtime behavior.
+///
+/// Because the internals are type-erased, a node is never `Send` or `Sync`,
+/// even when its internals are. A `SendNode` keeps track of whether they are,
+/// so that a tree can be moved to another thread.
 pub struct Node<'a, W> {
     /// The status from the last time this node was ticked.
     status: Option<Status>,
@@ -21,8 +41,42 @@ pub struct Node<'a, W> {
 
     /// The name for this node.
     ///
-    /// If present, it will be used instead of the type name.
-    name: Option<String>,
+    /// If present, it will be used instead of the type name. Static names are
+    /// stored without allocating.
+    name: Option<NodeName>,
+
+    /// The number of times this node has been ticked.
+    tick_count: u64,
+
+    /// How long the last tick of this node took.
+    last_tick_duration: Option<Duration>,
+
+    /// The switch controlling whether this node is enabled.
+    ///
+    /// Nodes are always enabled unless a switch has been created.
+    switch: Option<EnableSwitch>,
+
+    /// The status returned by this node while it is disabled.
+    disabled_status: Status,
+
+    /// The longest an activation of this node is expected to take.
+    expected_duration: Option<Duration>,
+
+    /// The phase of the mission this node and its descendants belong to.
+    phase: Option<NodeName>,
+
+    /// The blackboard keys this node declares it reads and writes.
+    keys: Vec<KeyUse>,
+
+    /// When the internals may next be ticked, if they asked to be ticked less
+    /// often.
+    resume_at: Option<Instant>,
+
+    /// When a tick of this node was last logged.
+    last_logged: Option<Instant>,
+
+    /// The number of ticks of this node since the last one which was logged.
+    unlogged_ticks: u64,
 }
 impl<'a, W> Node<'a, W> {
     /// Creates a new `Node` with the given `Tickable`.
@@ -36,6 +90,16 @@ impl<'a, W> Node<'a, W> {
             status: None,
             internals: Box::new(internals),
             name: None,
+            tick_count: 0,
+            last_tick_duration: None,
+            switch: None,
+            disabled_status: Status::Failed,
+            expected_duration: None,
+            phase: None,
+            keys: Vec::new(),
+            resume_at: None,
+            last_logged: None,
+            unlogged_ticks: 0,
         }
     }
 
@@ -46,6 +110,21 @@ impl<'a, W> Node<'a, W> {
         self.status
     }
 
+    /// Returns the number of times this node has been ticked.
+    ///
+    /// This count is not affected by resetting the node.
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// Returns how long the last tick of this node took, including the time
+    /// spent ticking its children.
+    ///
+    /// Returns `None` if the node has never been ticked.
+    pub fn last_tick_duration(&self) -> Option<Duration> {
+        self.last_tick_duration
+    }
+
     /// Returns the name of this node.
     ///
     /// Unless this node was renamed via the `named` method, this will be the
@@ -58,28 +137,549 @@ impl<'a, W> Node<'a, W> {
         }
     }
 
+    /// Returns the name of this node as a `NodeName`, which can be kept without
+    /// copying it.
+    ///
+    /// The type names of unnamed nodes are interned, so this only allocates the
+    /// first time a type name is seen on a thread.
+    pub fn shared_name(&self) -> NodeName {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => NodeName::intern(self.internals.type_name()),
+        }
+    }
+
+    /// Returns the progress last reported by the task behind this node, if it
+    /// reports any.
+    pub fn progress(&self) -> Option<&Progress> {
+        self.internals.progress()
+    }
+
+    /// Returns the event last emitted by the task behind this node, if it is of
+    /// type `T`.
+    pub fn latest_event<T: Any>(&self) -> Option<&T> {
+        let event: &dyn Any = self.internals.latest_event()?;
+        event.downcast_ref()
+    }
+
+    /// Returns when this node last heard from the task behind it, if it runs
+    /// one.
+    pub fn last_heartbeat(&self) -> Option<Instant> {
+        self.internals.last_heartbeat()
+    }
+
+    /// Takes the value produced by the last completed run of this node, if it
+    /// is of type `T`.
+    ///
+    /// A value of any other type is left in place.
+    pub fn take_result<T: Any>(&mut self) -> Option<T> {
+        if !self.internals.result()?.is::<T>() {
+            return None;
+        }
+
+        let value: Box<dyn Any> = self.internals.take_result()?;
+        value.downcast().ok().map(|v| *v)
+    }
+
+    /// Returns the longest an activation of this node is expected to take, if
+    /// one was declared.
+    pub fn expected_duration(&self) -> Option<Duration> {
+        self.expected_duration
+    }
+
+    /// Declares the longest an activation of this node is expected to take.
+    ///
+    /// An activation lasts from the tick that starts the node until the tick
+    /// that completes it. This does not change how the node behaves, but
+    /// activations which take longer are reported by the
+    /// [`SlaMonitor`](crate::stats::SlaMonitor).
+    pub fn with_expected_duration(mut self, max: Duration) -> Node<'a, W> {
+        self.expected_duration = Some(max);
+        self
+    }
+
+    /// Returns the phase this node was tagged with, if any.
+    ///
+    /// This is only the node's own tag. Use
+    /// [`collect_phases`](crate::monitor::collect_phases) to find the phase of
+    /// every node in a tree, including those inherited from their ancestors.
+    pub fn phase(&self) -> Option<&str> {
+        self.phase.as_deref()
+    }
+
+    /// Tags this node and its descendants as a phase of the mission, such as
+    /// "Navigation" or "Recovery".
+    ///
+    /// This does not change how the node behaves, but statistics, traces and
+    /// telemetry can be rolled up by phase, such as by the
+    /// [`PhaseMonitor`](crate::stats::PhaseMonitor). A descendant tagged with
+    /// another phase belongs to that phase instead.
+    pub fn in_phase<T: Into<Cow<'static, str>>>(mut self, phase: T) -> Node<'a, W> {
+        self.phase = Some(NodeName::from(phase.into()));
+        self
+    }
+
+    /// Returns the blackboard keys this node declares it reads and writes.
+    pub fn key_uses(&self) -> &[KeyUse] {
+        &self.keys
+    }
+
+    /// Declares that this node reads the blackboard value with the given key,
+    /// which is a `T`.
+    ///
+    /// This does not change how the node behaves, but `validate` reports keys
+    /// which are read without anything writing them.
+    pub fn reads<T, K>(mut self, key: K) -> Node<'a, W>
+    where
+        T: Any,
+        K: Into<Cow<'static, str>>,
+    {
+        self.keys.push(KeyUse::new::<T, K>(key, Access::Read));
+        self
+    }
+
+    /// Declares that this node writes the blackboard value with the given key,
+    /// which is a `T`.
+    pub fn writes<T, K>(mut self, key: K) -> Node<'a, W>
+    where
+        T: Any,
+        K: Into<Cow<'static, str>>,
+    {
+        self.keys.push(KeyUse::new::<T, K>(key, Access::Write));
+        self
+    }
+
+    /// Returns `true` if this node is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.switch.as_ref().map_or(true, EnableSwitch::is_enabled)
+    }
+
+    /// Enables or disables this node.
+    ///
+    /// A disabled node does not tick its logic or its children. Instead, it
+    /// returns a constant status, which is `Status::Failed` unless changed with
+    /// `Node::set_disabled_status`. If the node is running when it is next
+    /// ticked while disabled, it is reset so that any work in progress is
+    /// stopped.
+    ///
+    /// To switch a node on or off once it is part of a tree, use the handle
+    /// returned by `Node::enable_switch`.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enable_switch().set(enabled);
+    }
+
+    /// Sets the status returned by this node while it is disabled.
+    ///
+    /// A disabled branch of a `Selector` would usually fail, so that its
+    /// siblings are tried instead, while a disabled branch of a `Sequence`
+    /// would usually succeed.
+    pub fn set_disabled_status(&mut self, status: Status) {
+        self.disabled_status = status;
+    }
+
+    /// Returns a handle which can enable or disable this node at runtime.
+    ///
+    /// This allows a branch to be switched off without rebuilding the tree,
+    /// including from another thread.
+    pub fn enable_switch(&mut self) -> EnableSwitch {
+        self.switch
+            .get_or_insert_with(|| EnableSwitch(Arc::new(AtomicBool::new(true))))
+            .clone()
+    }
+
     /// Sets the name for this particular node.
-    pub fn named<T: Into<String>>(mut self, name: Option<T>) -> Node<'a, W> {
+    ///
+    /// Names that are `&'static str` do not require an allocation.
+    pub fn named<T: Into<Cow<'static, str>>>(mut self, name: Option<T>) -> Node<'a, W> {
         // We consume the node and return it to fit better into the current
         // pattern of making trees. By using a reference, named nodes would not
         // be able to be made inline. This also makes the macros look much nicer.
-        let new_name = name.map(Into::into);
+        let new_name = name.map(|n| NodeName::from(n.into()));
         if let Some(ref s) = new_name {
             trace!("Renaming node from {} to {}", self.name(), s);
         } else {
             trace!("Removing name from {}", self.name());
         }
+        self.internals.on_rename(new_name.as_deref());
         self.name = new_name;
         self
     }
+
+    /// Attaches a cleanup node which is run if this node is halted while it
+    /// is running.
+    ///
+    /// This wraps the node in an `OnHalt` decorator, which describes when the
+    /// cleanup is run.
+    pub fn on_halt(self, cleanup: Node<'a, W>) -> Node<'a, W>
+    where
+        W: 'a,
+    {
+        crate::std_nodes::OnHalt::new(self, cleanup)
+    }
+
+    /// Logs a tick starting at `now` if the policy allows it, returning `true`
+    /// if it was logged.
+    fn log_tick(&mut self, policy: LogPolicy, now: Instant) -> bool {
+        let due = match (policy.tick_interval, self.last_logged) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(interval), Some(last)) => now.saturating_duration_since(last) >= interval,
+        };
+        if !due {
+            self.unlogged_ticks = self.unlogged_ticks.saturating_add(1);
+            return false;
+        }
+
+        if self.unlogged_ticks > 0 {
+            trace!(
+                "Ticking node {} ({} ticks not logged)",
+                self.name(),
+                self.unlogged_ticks
+            );
+        } else {
+            trace!("Ticking node {}", self.name());
+        }
+        self.last_logged = Some(now);
+        self.unlogged_ticks = 0;
+        true
+    }
+}
+
+thread_local! {
+    /// The time by which the tick in progress on this thread should finish.
+    static TICK_DEADLINE: Cell<Option<Instant>> = Cell::new(None);
+
+    /// The tick period requested by the internals being ticked on this thread.
+    static TICK_PERIOD: Cell<Option<Duration>> = Cell::new(None);
+
+    /// The number of cleanups on this thread which were started by a halt and
+    /// have not yet finished.
+    static PENDING_CLEANUPS: Cell<usize> = Cell::new(0);
+
+    /// How the nodes ticked on this thread log their ticks.
+    static LOG_POLICY: Cell<LogPolicy> = Cell::new(LogPolicy::every_tick());
+
+    /// The names interned on this thread.
+    static INTERNED: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// The name of a node, which is cheap to clone.
+///
+/// Static names are kept as references, and other names are shared, so that
+/// telemetry can hold on to the names of nodes without copying them on every
+/// tick.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::node::NodeName;
+/// let name = NodeName::intern("Patrol");
+/// assert_eq!(&*name, "Patrol");
+/// assert_eq!(name, NodeName::from("Patrol"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct NodeName(NameRepr);
+
+/// How a `NodeName` is stored.
+#[derive(Clone, Debug)]
+enum NameRepr {
+    /// A static string.
+    Static(&'static str),
+
+    /// A shared string.
+    Shared(Arc<str>),
+}
+impl NodeName {
+    /// Returns the interned copy of `name`, interning it if this is the first
+    /// time it has been seen on this thread.
+    pub fn intern(name: &str) -> Self {
+        INTERNED.with(|names| {
+            let mut names = names.borrow_mut();
+            if let Some(shared) = names.get(name) {
+                return NodeName(NameRepr::Shared(shared.clone()));
+            }
+            let shared: Arc<str> = Arc::from(name);
+            names.insert(shared.clone());
+            NodeName(NameRepr::Shared(shared))
+        })
+    }
+
+    /// Returns the name as a string slice.
+    pub fn as_str(&self) -> &str {
+        match self.0 {
+            NameRepr::Static(name) => name,
+            NameRepr::Shared(ref name) => name,
+        }
+    }
+}
+impl Deref for NodeName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl AsRef<str> for NodeName {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl PartialEq for NodeName {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+impl Eq for NodeName {}
+impl std::hash::Hash for NodeName {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+impl fmt::Display for NodeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+impl From<&'static str> for NodeName {
+    fn from(name: &'static str) -> Self {
+        NodeName(NameRepr::Static(name))
+    }
+}
+impl From<String> for NodeName {
+    fn from(name: String) -> Self {
+        NodeName(NameRepr::Shared(name.into()))
+    }
+}
+impl From<Arc<str>> for NodeName {
+    fn from(name: Arc<str>) -> Self {
+        NodeName(NameRepr::Shared(name))
+    }
+}
+impl From<Cow<'static, str>> for NodeName {
+    fn from(name: Cow<'static, str>) -> Self {
+        match name {
+            Cow::Borrowed(name) => name.into(),
+            Cow::Owned(name) => name.into(),
+        }
+    }
+}
+
+/// Sets how the nodes ticked on this thread log their ticks, returning the
+/// previous policy.
+pub(crate) fn set_log_policy(policy: LogPolicy) -> LogPolicy {
+    LOG_POLICY.with(|p| p.replace(policy))
+}
+
+/// How nodes log their ticks.
+///
+/// Logging every tick at trace level can flood the log of a tree which is
+/// ticked at a high frequency. A policy can instead log each node's ticks at
+/// most once per interval, and log changes of status at debug level so that
+/// they are never lost. Ticks which are not logged are counted, and the count
+/// is included in the next tick which is.
+///
+/// The policy is set on a `BehaviorTree` and applies to all of its nodes.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::node::LogPolicy;
+/// # use aspen::BehaviorTree;
+/// # use std::time::Duration;
+/// let mut tree = BehaviorTree::new(AlwaysRunning::new())
+///     .with_log_policy(LogPolicy::rate_limited(Duration::from_secs(1)));
+/// tree.tick(&mut ());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LogPolicy {
+    /// Whether changes of status are logged.
+    transitions: bool,
+
+    /// The shortest time between logged ticks of the same node, or `None` if
+    /// ticks are not logged.
+    tick_interval: Option<Duration>,
+}
+impl LogPolicy {
+    /// Returns a policy which logs every tick at trace level, and nothing
+    /// else.
+    ///
+    /// This is the default.
+    pub const fn every_tick() -> Self {
+        LogPolicy {
+            transitions: false,
+            tick_interval: Some(Duration::from_secs(0)),
+        }
+    }
+
+    /// Returns a policy which logs changes of status at debug level, and the
+    /// ticks of each node at trace level at most once per `interval`.
+    pub const fn rate_limited(interval: Duration) -> Self {
+        LogPolicy {
+            transitions: true,
+            tick_interval: Some(interval),
+        }
+    }
+
+    /// Returns a policy which only logs changes of status, at debug level.
+    pub const fn transitions_only() -> Self {
+        LogPolicy {
+            transitions: true,
+            tick_interval: None,
+        }
+    }
+
+    /// Returns `true` if changes of status are logged.
+    pub fn logs_transitions(&self) -> bool {
+        self.transitions
+    }
+
+    /// Returns the shortest time between logged ticks of the same node, or
+    /// `None` if ticks are not logged.
+    pub fn tick_interval(&self) -> Option<Duration> {
+        self.tick_interval
+    }
+}
+impl Default for LogPolicy {
+    fn default() -> Self {
+        LogPolicy::every_tick()
+    }
+}
+
+/// Records that a cleanup has been started on this thread.
+pub(crate) fn start_cleanup() {
+    PENDING_CLEANUPS.with(|c| c.set(c.get() + 1));
+}
+
+/// Records that a cleanup on this thread has finished or been dropped.
+pub(crate) fn finish_cleanup() {
+    PENDING_CLEANUPS.with(|c| c.set(c.get().saturating_sub(1)));
+}
+
+/// Returns `true` if any cleanups on this thread have not yet finished.
+pub(crate) fn cleanups_pending() -> bool {
+    PENDING_CLEANUPS.with(Cell::get) > 0
+}
+
+/// Ticks the pending cleanups of `node` and its descendants.
+pub(crate) fn tick_cleanups<W>(node: &Node<'_, W>, world: &mut W) {
+    node.tick_cleanup(world);
+    for child in node.children() {
+        tick_cleanups(child, world);
+    }
+}
+
+/// Sets the deadline of the ticks on this thread, returning the previous one.
+pub(crate) fn set_tick_deadline(deadline: Option<Instant>) -> Option<Instant> {
+    TICK_DEADLINE.with(|d| d.replace(deadline))
+}
+
+/// Information about the tick in progress on this thread.
+///
+/// Nodes which do a bounded amount of work each tick can use the deadline to
+/// decide how much to do, and nodes which do not need to be ticked at the full
+/// rate of the tree can ask to be ticked less often.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::node::TickContext;
+/// # use std::time::{Duration, Instant};
+/// let ctx = TickContext::current();
+/// let budget = ctx.remaining().unwrap_or_else(|| Duration::from_millis(1));
+/// let stop = Instant::now() + budget / 2;
+/// while Instant::now() < stop {
+///     // Do a slice of the work
+/// #   break;
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TickContext {
+    /// The time by which the tick should finish.
+    deadline: Option<Instant>,
+}
+impl TickContext {
+    /// Returns the context of the tick in progress on this thread.
+    pub fn current() -> Self {
+        TickContext {
+            deadline: TICK_DEADLINE.with(Cell::get),
+        }
+    }
+
+    /// Returns the time by which the tick should finish, if the tree was given
+    /// one.
+    ///
+    /// `BehaviorTree::run` and `BehaviorTree::run_for` give every tick a
+    /// deadline of one period of the requested frequency, and
+    /// `BehaviorTree::tick_with_deadline` gives it an explicit one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns how long is left until the deadline, which is zero once it has
+    /// passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// Asks for the node being ticked to be ticked at most once per `period`.
+    ///
+    /// If the node returns `Running` from this tick, its internals are not
+    /// ticked again until `period` has passed since this tick started. Until
+    /// then, ticking the node returns `Running` straight away, which also
+    /// skips the node's whole branch. The request only lasts for one tick, so
+    /// it has to be repeated to keep the lower rate. If it is made more than
+    /// once in a tick, the longest period wins.
+    pub fn request_tick_period(&self, period: Duration) {
+        TICK_PERIOD.with(|p| p.set(Some(p.get().map_or(period, |q| q.max(period)))));
+    }
 }
 
 impl<'a, W> Tickable<W> for Node<'a, W> {
     /// Ticks the node a single time.
     fn tick(&mut self, world: &mut W) -> Status {
         // Tick the internals
-        trace!("Ticking node {}", self.name());
-        self.status = Some(self.internals.tick(world));
+        let start = Instant::now();
+        let policy = LOG_POLICY.with(Cell::get);
+        self.log_tick(policy, start);
+        let previous = self.status;
+        if self.is_enabled() {
+            let throttled = matches!(
+                self.resume_at,
+                Some(at) if self.status == Some(Status::Running) && start < at
+            );
+            if throttled {
+                trace!("Skipping throttled node {}", self.name());
+            } else {
+                let outer = TICK_PERIOD.with(|p| p.take());
+                let status = self.internals.tick(world);
+                let period = TICK_PERIOD.with(|p| p.replace(outer));
+
+                self.resume_at = match period {
+                    Some(period) if status == Status::Running => Some(start + period),
+                    _ => None,
+                };
+                self.status = Some(status);
+            }
+        } else {
+            if self.status == Some(Status::Running) {
+                trace!("Stopping disabled node {}", self.name());
+                self.internals.reset();
+                self.resume_at = None;
+            }
+            self.status = Some(self.disabled_status);
+        }
+        if policy.transitions && self.status != previous {
+            debug!(
+                "Node {} changed from {:?} to {:?}",
+                self.name(),
+                previous,
+                self.status.unwrap()
+            );
+        }
+        self.last_tick_duration = Some(start.elapsed());
+        self.tick_count = self.tick_count.wrapping_add(1);
         self.status.unwrap()
     }
 
@@ -92,6 +692,7 @@ impl<'a, W> Tickable<W> for Node<'a, W> {
         if self.status.is_some() {
             trace!("Resetting node {} ({:?})", self.name(), self.status());
             self.status = None;
+            self.resume_at = None;
             self.internals.reset();
         }
     }
@@ -99,7 +700,7 @@ impl<'a, W> Tickable<W> for Node<'a, W> {
     /// Returns a vector containing references to all of this node's children.
     ///
     /// This is likely the most unstable part of Aspen, use with caution.
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         (*self.internals).children()
     }
 
@@ -108,6 +709,51 @@ impl<'a, W> Tickable<W> for Node<'a, W> {
         self.internals.type_name()
     }
 
+    /// Predicts the status of this node via the dry run.
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(run.tick(self, world))
+    }
+
+    /// Returns the progress reported by the internals.
+    fn progress(&self) -> Option<&Progress> {
+        self.internals.progress()
+    }
+
+    /// Returns the children which the internals can never reach.
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        self.internals.unreachable_children()
+    }
+
+    /// Passes the new name on to the internals.
+    fn on_rename(&mut self, name: Option<&str>) {
+        self.internals.on_rename(name);
+    }
+
+    /// Returns the latest event held by the internals.
+    fn latest_event(&self) -> Option<&(dyn Any + Send)> {
+        self.internals.latest_event()
+    }
+
+    /// Returns the last heartbeat seen by the internals.
+    fn last_heartbeat(&self) -> Option<Instant> {
+        self.internals.last_heartbeat()
+    }
+
+    /// Returns the result held by the internals.
+    fn result(&self) -> Option<&(dyn Any + Send)> {
+        self.internals.result()
+    }
+
+    /// Takes the result held by the internals.
+    fn take_result(&mut self) -> Option<Box<dyn Any + Send>> {
+        self.internals.take_result()
+    }
+
+    /// Ticks the cleanup held by the internals.
+    fn tick_cleanup(&self, world: &mut W) {
+        self.internals.tick_cleanup(world);
+    }
+
     /// Returns a concrete Node.
     ///
     /// ([`Node::into_node`] does precisely nothing)
@@ -119,8 +765,259 @@ impl<'a, W> Tickable<W> for Node<'a, W> {
     }
 }
 
+/// A node whose internals are all `Send`, so that it can be moved to another
+/// thread.
+///
+/// A `Node` cannot be `Send` even when its internals are, since they are
+/// type-erased. A `SendNode` can only be built from internals which are `Send`,
+/// so it is `Send` itself: leaves and statically typed trees from the
+/// `std_nodes::typed` module are wrapped with `new`, and `sequence` and
+/// `selector` combine other `SendNode`s into ordinary composites, whose
+/// children take part in monitoring as usual. A `SendTree` is built from a
+/// `SendNode` in the same way.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::node::SendNode;
+/// # use aspen::std_nodes::typed::{Condition, InlineAction};
+/// # use aspen::Status;
+/// # use std::thread;
+/// let mut node = SendNode::sequence(vec![
+///     SendNode::new(Condition::new(|&n: &u32| n < 10)),
+///     SendNode::new(InlineAction::new(|n: &mut u32| {
+///         *n += 1;
+///         Status::Succeeded
+///     })),
+/// ]);
+///
+/// let count = thread::spawn(move || {
+///     let mut count = 0;
+///     while node.tick(&mut count) == Status::Succeeded {
+///         node.reset();
+///     }
+///     count
+/// });
+/// assert_eq!(count.join().unwrap(), 10);
+/// ```
+pub struct SendNode<'a, W>(Node<'a, W>);
+// Safety: the internals of the node, and of all of its descendants, are
+// `Send`, since every constructor either requires them to be or only adds
+// composites which hold nothing but their children and plain data. The rest of
+// a `Node` is plain data, shared names and atomics. No `&mut Node` is handed
+// out, so the internals can not be replaced.
+unsafe impl<'a, W> Send for SendNode<'a, W> {}
+impl<'a, W> SendNode<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a node with the given internals.
+    pub fn new<I>(internals: I) -> Self
+    where
+        I: Tickable<W> + Send + 'a,
+    {
+        SendNode(Node::new(internals))
+    }
+
+    /// Creates a `Sequence` of the given nodes.
+    pub fn sequence(children: Vec<SendNode<'a, W>>) -> Self {
+        let children = children.into_iter().map(SendNode::into_node).collect();
+        SendNode(crate::std_nodes::Sequence::new(children))
+    }
+
+    /// Creates a `Selector` of the given nodes.
+    pub fn selector(children: Vec<SendNode<'a, W>>) -> Self {
+        let children = children.into_iter().map(SendNode::into_node).collect();
+        SendNode(crate::std_nodes::Selector::new(children))
+    }
+
+    /// Sets the name of the node.
+    pub fn named<T: Into<Cow<'static, str>>>(self, name: Option<T>) -> Self {
+        SendNode(self.0.named(name))
+    }
+
+    /// Returns a reference to the node.
+    pub fn node(&self) -> &Node<'a, W> {
+        &self.0
+    }
+
+    /// Ticks the node a single time.
+    pub fn tick(&mut self, world: &mut W) -> Status {
+        self.0.tick(world)
+    }
+
+    /// Resets the node.
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    /// Returns the node, which is no longer `Send`.
+    pub fn into_node(self) -> Node<'a, W> {
+        self.0
+    }
+}
+impl<'a, W> From<SendNode<'a, W>> for Node<'a, W> {
+    fn from(node: SendNode<'a, W>) -> Self {
+        node.0
+    }
+}
+
+/// How far a node is through its work, such as "Docking: 65%".
+#[derive(Clone, PartialEq, Debug)]
+pub struct Progress {
+    /// The fraction of the work which is done, between zero and one.
+    fraction: f32,
+
+    /// A description of the current work.
+    message: Cow<'static, str>,
+}
+impl Progress {
+    /// Creates a new progress report.
+    ///
+    /// The fraction is clamped to be between zero and one.
+    pub fn new<M: Into<Cow<'static, str>>>(fraction: f32, message: M) -> Self {
+        Progress {
+            fraction: if fraction.is_nan() {
+                0.0
+            } else {
+                fraction.clamp(0.0, 1.0)
+            },
+            message: message.into(),
+        }
+    }
+
+    /// Returns the fraction of the work which is done, between zero and one.
+    pub fn fraction(&self) -> f32 {
+        self.fraction
+    }
+
+    /// Returns the description of the current work.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = (self.fraction * 100.0).round();
+        if self.message.is_empty() {
+            write!(f, "{}%", percent)
+        } else {
+            write!(f, "{}: {}%", self.message, percent)
+        }
+    }
+}
+
+/// A handle which enables or disables a `Node`.
+///
+/// Handles are cheap to clone, and all of the clones control the same node.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut branch = AlwaysSucceed::new();
+/// let switch = branch.enable_switch();
+/// let mut root = Selector::new(vec![branch, AlwaysRunning::new()]);
+/// assert_eq!(root.tick(&mut ()), Status::Succeeded);
+///
+/// switch.set(false);
+/// assert_eq!(root.tick(&mut ()), Status::Running);
+/// ```
+#[derive(Clone, Debug)]
+pub struct EnableSwitch(Arc<AtomicBool>);
+impl EnableSwitch {
+    /// Enables or disables the node.
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the node is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Function which supplies the stand-in status of a node during a dry run.
+type StandInFn<'r, W> = dyn Fn(&Node<'_, W>) -> Status + 'r;
+
+/// The state of a dry run through a tree.
+///
+/// A dry run evaluates conditions and the routing of composite nodes against
+/// the world, but never runs actions. Nodes whose outcome cannot be predicted
+/// are given a stand-in status instead. Nodes are identified by their position
+/// in a pre-order traversal of the tree, as in the `monitor` module.
+pub struct DryRun<'r, W> {
+    /// Node identifiers, keyed by the address of the node.
+    ids: HashMap<usize, usize>,
+
+    /// The identifiers of the nodes which would be ticked, in order.
+    path: Vec<usize>,
+
+    /// Whether the tree would be reset before being ticked.
+    after_reset: bool,
+
+    /// Supplies the status of nodes which cannot be predicted.
+    stand_in: &'r StandInFn<'r, W>,
+}
+impl<'r, W> DryRun<'r, W> {
+    /// Creates a dry run over the tree with the given root.
+    pub(crate) fn new(
+        root: &Node<'_, W>,
+        after_reset: bool,
+        stand_in: &'r StandInFn<'r, W>,
+    ) -> Self {
+        let ids = preorder(root)
+            .into_iter()
+            .enumerate()
+            .map(|(id, node)| (address(node), id))
+            .collect();
+        DryRun {
+            ids,
+            path: Vec::new(),
+            after_reset,
+            stand_in,
+        }
+    }
+
+    /// Predicts the status that ticking `node` would return, and records it in
+    /// the path.
+    pub fn tick(&mut self, node: &Node<'_, W>, world: &W) -> Status {
+        if let Some(&id) = self.ids.get(&address(node)) {
+            self.path.push(id);
+        }
+
+        if !node.is_enabled() {
+            return node.disabled_status;
+        }
+        match node.internals.dry_tick(world, self) {
+            Some(status) => status,
+            None => (self.stand_in)(node),
+        }
+    }
+
+    /// Returns `true` if the tree would be reset before this tick.
+    ///
+    /// When this is the case, nodes should be evaluated as if they had just
+    /// been created rather than from their current state.
+    pub fn after_reset(&self) -> bool {
+        self.after_reset
+    }
+
+    /// Consumes the dry run, returning the path of nodes that would be ticked.
+    pub(crate) fn into_path(self) -> Vec<usize> {
+        self.path
+    }
+}
+
+/// Returns the address of a node, which identifies it during a dry run.
+fn address<W>(node: &Node<'_, W>) -> usize {
+    node as *const Node<'_, W> as *const () as usize
+}
+
 impl<'a, W> fmt::Display for Node<'a, W> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}:( status = {:?}", self.name(), self.status())?;
         for child in self.children() {
             write!(f, ", {}", child)?;
@@ -156,10 +1053,87 @@ pub trait Tickable<W> {
     /// leaf node.
     ///
     /// This is likely the most unstable part of Aspen, use with caution.
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         Vec::with_capacity(0)
     }
 
+    /// Predicts the status that ticking the node would return, without
+    /// changing any state.
+    ///
+    /// Children should be evaluated with `DryRun::tick` so that they are
+    /// recorded in the path. Returning `None` means that the outcome cannot be
+    /// predicted without running the node, in which case the dry run uses a
+    /// stand-in status instead.
+    ///
+    /// The default behavior is to return `None`, which is suitable for actions
+    /// and any other leaf node with side effects.
+    fn dry_tick(&self, _world: &W, _run: &mut DryRun<'_, W>) -> Option<Status> {
+        None
+    }
+
+    /// Returns how far through its work this node is.
+    ///
+    /// The default behavior is to return `None`, meaning that the node does not
+    /// report progress.
+    fn progress(&self) -> Option<&Progress> {
+        None
+    }
+
+    /// Returns the indices of the children which can never affect the status
+    /// of this node, each with the reason why.
+    ///
+    /// This is used by `validate` to find authoring mistakes, so it should be
+    /// decided from the structure of the tree alone. The default behavior is
+    /// to report none.
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        Vec::new()
+    }
+
+    /// Called when the node is given a new name, or its name is removed.
+    ///
+    /// The default behavior is to do nothing. Nodes which hand work off, such
+    /// as to other threads, can use the name to label it.
+    fn on_rename(&mut self, _name: Option<&str>) {}
+
+    /// Returns the event last emitted by the work behind this node.
+    ///
+    /// The default behavior is to return `None`, meaning that the node does not
+    /// emit events.
+    fn latest_event(&self) -> Option<&(dyn Any + Send)> {
+        None
+    }
+
+    /// Returns when this node last heard from the work behind it.
+    ///
+    /// The default behavior is to return `None`, meaning that the node does not
+    /// track the liveness of any work.
+    fn last_heartbeat(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Returns the value produced by the last completed run of this node.
+    ///
+    /// The default behavior is to return `None`, meaning that the node does not
+    /// produce values.
+    fn result(&self) -> Option<&(dyn Any + Send)> {
+        None
+    }
+
+    /// Takes the value produced by the last completed run of this node.
+    ///
+    /// The default behavior is to return `None`.
+    fn take_result(&mut self) -> Option<Box<dyn Any + Send>> {
+        None
+    }
+
+    /// Ticks the cleanup which this node started when it was halted, if it
+    /// has not yet finished.
+    ///
+    /// A `BehaviorTree` calls this on all of its nodes after each tick while
+    /// any cleanup is pending, since a halted node is usually no longer ticked
+    /// by its parent. The default behavior is to do nothing.
+    fn tick_cleanup(&self, _world: &mut W) {}
+
     /// Returns the type of the node as a string literal.
     ///
     /// In general, this should be the name of the node type.
@@ -178,3 +1152,77 @@ pub trait Tickable<W> {
         Node::new(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::{LogPolicy, Node, TickContext, Tickable},
+        std_nodes::{AlwaysRunning, Coroutine, InlineAction, NoTick, Sequence, Step},
+        Status,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn log_policy_limits_logged_ticks() {
+        let mut node: Node<()> = AlwaysRunning::new();
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let policy = LogPolicy::rate_limited(second);
+        assert!(node.log_tick(policy, start));
+        assert!(!node.log_tick(policy, start + second / 2));
+        assert!(!node.log_tick(policy, start + second / 2));
+        assert_eq!(node.unlogged_ticks, 2);
+        assert!(node.log_tick(policy, start + second));
+        assert_eq!(node.unlogged_ticks, 0);
+
+        assert!(node.log_tick(LogPolicy::every_tick(), start + second));
+        assert!(!node.log_tick(LogPolicy::transitions_only(), start + second * 2));
+    }
+
+    #[test]
+    fn requested_tick_period_skips_the_branch() {
+        let mut ticks = 0;
+        let mut node = Sequence::new(vec![InlineAction::with_context(
+            |_: &mut (), ctx: &TickContext| {
+                ticks += 1;
+                ctx.request_tick_period(Duration::from_secs(60));
+                Status::Running
+            },
+        )]);
+
+        for _ in 0..3 {
+            assert_eq!(node.tick(&mut ()), Status::Running);
+        }
+        node.reset();
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        drop(node);
+        assert_eq!(ticks, 2);
+    }
+
+    #[test]
+    fn disabled_node_is_not_ticked() {
+        let mut node = NoTick::new();
+        node.set_enabled(false);
+        node.set_disabled_status(Status::Succeeded);
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        assert!(!node.is_enabled());
+    }
+
+    #[test]
+    fn disabling_resets_running_node() {
+        let mut steps = Vec::new();
+        let mut node = Coroutine::new(0u32, |n, s: &mut Vec<u32>| {
+            s.push(n);
+            Step::Yield(n + 1)
+        });
+        let switch = node.enable_switch();
+        node.tick(&mut steps);
+        node.tick(&mut steps);
+
+        switch.set(false);
+        assert_eq!(node.tick(&mut steps), Status::Failed);
+        switch.set(true);
+        node.tick(&mut steps);
+        assert_eq!(steps, vec![0, 1, 0]);
+    }
+}
@@ -0,0 +1,241 @@
+//! Loading node constructors from shared libraries.
+//!
+//! A plugin is a `cdylib` which depends on aspen and exports a
+//! `PluginDeclaration` with the `export_plugin!` macro. The declaration records
+//! the version of the registration ABI, the version of aspen and the type of
+//! world it was built against, and a function which adds its constructors to a
+//! `NodeRegistry`. `NodeRegistry::load_plugin` checks all three before calling
+//! it.
+//!
+//! Rust has no stable ABI, so a plugin must be built with the same compiler
+//! and the same version of aspen as the program which loads it. The version of
+//! aspen is checked, but the compiler can not be.
+//!
+//! Libraries are never unloaded, since the nodes built from them can outlive
+//! the registry.
+//!
+//! # Examples
+//!
+//! In the plugin:
+//!
+//! ```
+//! # use aspen::registry::NodeRegistry;
+//! # use aspen::std_nodes::Condition;
+//! # #[derive(Clone)] pub struct Rover { lidar_ok: bool }
+//! fn register(registry: &mut NodeRegistry<Rover>) {
+//!     registry.register("LidarOk", |_, _| Ok(Condition::new(|r: &Rover| r.lidar_ok)));
+//! }
+//!
+//! aspen::export_plugin!(Rover, register);
+//! ```
+//!
+//! In the executive:
+//!
+//! ```no_run
+//! # use aspen::registry::NodeRegistry;
+//! # #[derive(Clone)] pub struct Rover { lidar_ok: bool }
+//! let mut registry = NodeRegistry::<Rover>::with_std_nodes();
+//! // Safety: the library is a trusted plugin
+//! unsafe { registry.load_plugin("/opt/vendor/libperception.so") }?;
+//! assert!(registry.contains("LidarOk"));
+//! # Ok::<(), aspen::registry::BuildError>(())
+//! ```
+use crate::registry::{BuildError, NodeRegistry};
+use libloading::Library;
+use std::{any::type_name, mem, os::raw::c_void, path::Path};
+
+/// The version of the registration ABI.
+///
+/// This changes whenever `PluginDeclaration` does.
+pub const ABI_VERSION: u32 = 1;
+
+/// The version of aspen.
+pub const ASPEN_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The name of the symbol which `export_plugin!` exports.
+pub const DECLARATION_SYMBOL: &str = "ASPEN_PLUGIN_DECLARATION";
+
+/// Function which adds the constructors of a plugin to a registry.
+///
+/// The pointer is to a `NodeRegistry` for the world the plugin was built for.
+/// Type IDs are not stable between separately built libraries, so this is
+/// checked by comparing type names instead.
+pub type RegisterFn = unsafe fn(*mut c_void);
+
+/// What a plugin exports.
+///
+/// This is created by `export_plugin!`, rather than by hand. Registering a
+/// declaration made any other way is undefined behaviour if its `register`
+/// function does not match its world.
+#[derive(Clone, Copy, Debug)]
+pub struct PluginDeclaration {
+    /// The version of the registration ABI the plugin was built with.
+    pub abi_version: u32,
+
+    /// The version of aspen the plugin was built with.
+    pub aspen_version: &'static str,
+
+    /// Returns the name of the world type the plugin was built for.
+    pub world: fn() -> &'static str,
+
+    /// Adds the constructors of the plugin to a registry.
+    pub register: RegisterFn,
+}
+
+/// Exports a function which registers node constructors, so that the crate can
+/// be loaded as a plugin.
+///
+/// The function must take a `&mut NodeRegistry<W>`, where `W` is the given
+/// world type. This can only be used once per crate.
+#[macro_export]
+macro_rules! export_plugin {
+    ($world:ty, $register:path) => {
+        #[doc(hidden)]
+        fn __aspen_plugin_world() -> &'static str {
+            ::std::any::type_name::<$world>()
+        }
+
+        #[doc(hidden)]
+        unsafe fn __aspen_plugin_register(registry: *mut ::std::os::raw::c_void) {
+            $register(&mut *(registry as *mut $crate::registry::NodeRegistry<$world>))
+        }
+
+        #[doc(hidden)]
+        #[no_mangle]
+        pub static ASPEN_PLUGIN_DECLARATION: $crate::plugin::PluginDeclaration =
+            $crate::plugin::PluginDeclaration {
+                abi_version: $crate::plugin::ABI_VERSION,
+                aspen_version: $crate::plugin::ASPEN_VERSION,
+                world: __aspen_plugin_world,
+                register: __aspen_plugin_register,
+            };
+    };
+}
+
+impl<W: 'static> NodeRegistry<W> {
+    /// Loads the shared library at the given path and adds its constructors
+    /// to the registry.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initializers, and the declaration it exports
+    /// is trusted to be what `export_plugin!` creates. The library must have
+    /// been built with the same compiler as this program.
+    pub unsafe fn load_plugin<P: AsRef<Path>>(&mut self, path: P) -> Result<(), BuildError> {
+        let path = path.as_ref();
+        let fail = |reason: String| {
+            BuildError::new(format!(
+                "failed to load plugin {}: {}",
+                path.display(),
+                reason
+            ))
+        };
+
+        let library = Library::new(path).map_err(|e| fail(e.to_string()))?;
+        let declaration = *library
+            .get::<*const PluginDeclaration>(DECLARATION_SYMBOL.as_bytes())
+            .map_err(|e| fail(e.to_string()))?;
+        if declaration.is_null() {
+            return Err(fail(format!("{} is null", DECLARATION_SYMBOL)));
+        }
+
+        // The nodes built from the plugin can outlive the registry, so the
+        // library is never unloaded
+        mem::forget(library);
+
+        info!("Loaded plugin {}", path.display());
+        self.register_plugin(&*declaration)
+            .map_err(|e| fail(e.message().to_owned()))
+    }
+
+    /// Adds the constructors of a plugin to the registry, after checking that
+    /// it is compatible.
+    ///
+    /// This is what `load_plugin` does once the library is loaded, and can
+    /// also be used for plugins which are linked in statically.
+    ///
+    /// # Safety
+    ///
+    /// The declaration must have been created by `export_plugin!`. Its
+    /// `register` function is called with a pointer to this registry once the
+    /// name of the world type matches, but type names are not guaranteed to be
+    /// unique, so a declaration made by hand could treat the registry as one
+    /// for another world.
+    pub unsafe fn register_plugin(
+        &mut self,
+        declaration: &PluginDeclaration,
+    ) -> Result<(), BuildError> {
+        if declaration.abi_version != ABI_VERSION {
+            return Err(BuildError::new(format!(
+                "plugin uses ABI version {}, expected {}",
+                declaration.abi_version, ABI_VERSION
+            )));
+        }
+        if declaration.aspen_version != ASPEN_VERSION {
+            return Err(BuildError::new(format!(
+                "plugin was built with aspen {}, expected {}",
+                declaration.aspen_version, ASPEN_VERSION
+            )));
+        }
+        let world = (declaration.world)();
+        if world != type_name::<W>() {
+            return Err(BuildError::new(format!(
+                "plugin was built for world {}, expected {}",
+                world,
+                type_name::<W>()
+            )));
+        }
+
+        // Safety: the declaration was made by `export_plugin!` for this world,
+        // as the caller guarantees
+        (declaration.register)(self as *mut Self as *mut c_void);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        registry::{NodeRegistry, Params},
+        std_nodes::Condition,
+        Status,
+    };
+
+    fn register(registry: &mut NodeRegistry<u32>) {
+        registry.register("Positive", |_, _| Ok(Condition::new(|&x: &u32| x > 0)));
+    }
+
+    export_plugin!(u32, register);
+
+    #[test]
+    fn declarations_are_checked() {
+        let mut registry = NodeRegistry::<u32>::new();
+        // Safety: the declaration was made by `export_plugin!`
+        unsafe { registry.register_plugin(&ASPEN_PLUGIN_DECLARATION) }.unwrap();
+        let mut node = registry.build("Positive", &Params::new(), vec![]).unwrap();
+        assert_eq!(node.tick(&mut 1), Status::Succeeded);
+
+        let mut registry = NodeRegistry::<i32>::new();
+        // Safety: the world does not match, so the declaration is not called
+        let error = unsafe { registry.register_plugin(&ASPEN_PLUGIN_DECLARATION) }.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "plugin was built for world u32, expected i32"
+        );
+
+        // Safety: only the version differs, and it is checked first
+        let mut old = ASPEN_PLUGIN_DECLARATION;
+        old.abi_version = 0;
+        assert!(unsafe { NodeRegistry::<u32>::new().register_plugin(&old) }.is_err());
+    }
+
+    #[test]
+    fn missing_libraries_are_reported() {
+        let mut registry = NodeRegistry::<u32>::new();
+        let error = unsafe { registry.load_plugin("/nonexistent/libplugin.so") }.unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("failed to load plugin /nonexistent/libplugin.so: "));
+    }
+}
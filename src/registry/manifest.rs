@@ -0,0 +1,410 @@
+//! Descriptions of the registered node types, for editors and other tools.
+use crate::{json::push_string, loader::xml::push_escaped};
+use std::{any, fmt};
+
+/// The kind of a node type, as shown in the palette of an editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// A leaf which does something.
+    Action,
+
+    /// A leaf which checks something.
+    Condition,
+
+    /// A node with any number of children.
+    Control,
+
+    /// A node with exactly one child.
+    Decorator,
+}
+impl Category {
+    /// Returns the name of the category.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::Action => "Action",
+            Category::Condition => "Condition",
+            Category::Control => "Control",
+            Category::Decorator => "Decorator",
+        }
+    }
+}
+impl Default for Category {
+    fn default() -> Self {
+        Category::Action
+    }
+}
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A description of a parameter of a node type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParamInfo {
+    /// The key of the parameter.
+    name: String,
+
+    /// The type the value is parsed as.
+    value_type: String,
+
+    /// Whether the parameter must be given.
+    required: bool,
+
+    /// The value used when the parameter is not given.
+    default: Option<String>,
+
+    /// What the parameter is for.
+    description: String,
+}
+impl ParamInfo {
+    /// Describes a required parameter whose value is parsed as a `T`.
+    pub fn new<T>(name: &str, description: &str) -> Self {
+        ParamInfo {
+            name: name.to_owned(),
+            value_type: short_type_name(any::type_name::<T>()),
+            required: true,
+            default: None,
+            description: description.to_owned(),
+        }
+    }
+
+    /// Makes the parameter optional.
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    /// Makes the parameter optional, with the given default value.
+    pub fn with_default<D: fmt::Display>(mut self, default: D) -> Self {
+        self.required = false;
+        self.default = Some(default.to_string());
+        self
+    }
+
+    /// Returns the key of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the type the value is parsed as.
+    pub fn value_type(&self) -> &str {
+        &self.value_type
+    }
+
+    /// Returns `true` if the parameter must be given.
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Returns the value used when the parameter is not given, if there is
+    /// one.
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Returns what the parameter is for.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// A description of a registered node type.
+///
+/// `NodeRegistry::register` returns the description of the new type, so that
+/// it can be filled in where the type is registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeInfo {
+    /// The name the type is registered under.
+    type_name: String,
+
+    /// The kind of node.
+    category: Category,
+
+    /// What the node does.
+    description: String,
+
+    /// The parameters the node reads.
+    params: Vec<ParamInfo>,
+}
+impl NodeInfo {
+    /// Creates an empty description of the given type, as an action.
+    pub(crate) fn new(type_name: String) -> Self {
+        NodeInfo {
+            type_name,
+            category: Category::default(),
+            description: String::new(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Sets the kind of node.
+    pub fn set_category(&mut self, category: Category) -> &mut Self {
+        self.category = category;
+        self
+    }
+
+    /// Sets what the node does.
+    pub fn set_description<D: Into<String>>(&mut self, description: D) -> &mut Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Adds a parameter, replacing any existing one with the same key.
+    pub fn add_param(&mut self, param: ParamInfo) -> &mut Self {
+        match self.params.iter_mut().find(|p| p.name == param.name) {
+            Some(existing) => *existing = param,
+            None => self.params.push(param),
+        }
+        self
+    }
+
+    /// Returns the name the type is registered under.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the kind of node.
+    pub fn category(&self) -> Category {
+        self.category
+    }
+
+    /// Returns what the node does, which is empty if it was not described.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the parameters the node reads, in the order they were added.
+    pub fn params(&self) -> &[ParamInfo] {
+        &self.params
+    }
+}
+
+/// Descriptions of all of the node types in a registry, sorted by name.
+///
+/// This lets editors fill their palettes from the running program, so that
+/// they always match the nodes it can build.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::registry::{Category, NodeRegistry};
+/// let manifest = NodeRegistry::<()>::with_std_nodes().manifest();
+/// let timeout = manifest.get("Timeout").unwrap();
+/// assert_eq!(timeout.category(), Category::Decorator);
+/// assert_eq!(timeout.params()[0].name(), "ms");
+/// assert!(manifest.to_json().contains("\"type\": \"Timeout\""));
+/// assert!(manifest
+///     .to_groot_xml()
+///     .contains("<Decorator ID=\"Timeout\">"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// The descriptions, sorted by type name.
+    nodes: Vec<NodeInfo>,
+}
+impl Manifest {
+    /// Creates a manifest of the given descriptions.
+    pub(crate) fn new(mut nodes: Vec<NodeInfo>) -> Self {
+        nodes.sort_unstable_by(|a, b| a.type_name.cmp(&b.type_name));
+        Manifest { nodes }
+    }
+
+    /// Returns the descriptions, sorted by type name.
+    pub fn nodes(&self) -> &[NodeInfo] {
+        &self.nodes
+    }
+
+    /// Returns the description of the given type.
+    pub fn get(&self, type_name: &str) -> Option<&NodeInfo> {
+        self.nodes
+            .binary_search_by(|n| n.type_name.as_str().cmp(type_name))
+            .ok()
+            .map(|i| &self.nodes[i])
+    }
+
+    /// Writes the manifest as JSON.
+    ///
+    /// The result is an object with a `nodes` array, holding an object for
+    /// each type with its `type`, `category`, `description` and `params`. Each
+    /// parameter has a `name`, `type`, `required`, `default` and
+    /// `description`, where `default` is `null` if there is none.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n  \"nodes\": [");
+        for (i, node) in self.nodes.iter().enumerate() {
+            json.push_str(if i == 0 { "\n" } else { ",\n" });
+            json.push_str("    {\n      \"type\": ");
+            push_string(&mut json, &node.type_name);
+            json.push_str(",\n      \"category\": ");
+            push_string(&mut json, node.category.as_str());
+            json.push_str(",\n      \"description\": ");
+            push_string(&mut json, &node.description);
+            json.push_str(",\n      \"params\": [");
+            for (j, param) in node.params.iter().enumerate() {
+                json.push_str(if j == 0 { "\n" } else { ",\n" });
+                json.push_str("        {\"name\": ");
+                push_string(&mut json, &param.name);
+                json.push_str(", \"type\": ");
+                push_string(&mut json, &param.value_type);
+                json.push_str(", \"required\": ");
+                json.push_str(if param.required { "true" } else { "false" });
+                json.push_str(", \"default\": ");
+                match param.default {
+                    Some(ref default) => push_string(&mut json, default),
+                    None => json.push_str("null"),
+                }
+                json.push_str(", \"description\": ");
+                push_string(&mut json, &param.description);
+                json.push('}');
+            }
+            if !node.params.is_empty() {
+                json.push_str("\n      ");
+            }
+            json.push_str("]\n    }");
+        }
+        if !self.nodes.is_empty() {
+            json.push_str("\n  ");
+        }
+        json.push_str("]\n}\n");
+        json
+    }
+
+    /// Writes the manifest as a Groot palette.
+    ///
+    /// The result is a document with a `TreeNodesModel` element, which the
+    /// Groot editor reads to offer the nodes for drag and drop. Each type
+    /// becomes an element named after its category, with an `input_port` for
+    /// each parameter. The same `TreeNodesModel` can also be pasted into a
+    /// tree file, where the loader ignores it.
+    pub fn to_groot_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<root BTCPP_format=\"4\">\n  <TreeNodesModel>\n");
+        for node in &self.nodes {
+            xml.push_str("    <");
+            xml.push_str(node.category.as_str());
+            xml.push_str(" ID=\"");
+            push_escaped(&mut xml, &node.type_name);
+            if node.params.is_empty() {
+                xml.push_str("\"/>\n");
+                continue;
+            }
+
+            xml.push_str("\">\n");
+            for param in &node.params {
+                xml.push_str("      <input_port name=\"");
+                push_escaped(&mut xml, &param.name);
+                xml.push_str("\" type=\"");
+                push_escaped(&mut xml, &param.value_type);
+                if let Some(ref default) = param.default {
+                    xml.push_str("\" default=\"");
+                    push_escaped(&mut xml, default);
+                }
+                if param.description.is_empty() {
+                    xml.push_str("\"/>\n");
+                } else {
+                    xml.push_str("\">");
+                    push_escaped(&mut xml, &param.description);
+                    xml.push_str("</input_port>\n");
+                }
+            }
+            xml.push_str("    </");
+            xml.push_str(node.category.as_str());
+            xml.push_str(">\n");
+        }
+        xml.push_str("  </TreeNodesModel>\n</root>\n");
+        xml
+    }
+}
+
+/// Removes the module paths from a type name, so that
+/// `alloc::vec::Vec<alloc::string::String>` becomes `Vec<String>`.
+pub(crate) fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    for c in name.chars() {
+        short.push(c);
+        if short.ends_with("::") {
+            short.truncate(short.len() - 2);
+            while short.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                short.pop();
+            }
+        }
+    }
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{short_type_name, Category, Manifest, NodeInfo, ParamInfo};
+    use std::time::Duration;
+
+    #[test]
+    fn writes_json() {
+        assert_eq!(
+            short_type_name(std::any::type_name::<Duration>()),
+            "Duration"
+        );
+        assert_eq!(
+            short_type_name(std::any::type_name::<Vec<Option<String>>>()),
+            "Vec<Option<String>>"
+        );
+
+        let mut info = NodeInfo::new("MoveTo".to_owned());
+        info.set_description("Drives to \"goal\".")
+            .add_param(ParamInfo::new::<String>("goal", "where to go"))
+            .add_param(ParamInfo::new::<f64>("speed", "in m/s").with_default(0.5));
+        let mut condition = NodeInfo::new("Charged".to_owned());
+        condition.set_category(Category::Condition);
+        let manifest = Manifest::new(vec![info, condition]);
+
+        assert_eq!(manifest.nodes()[0].type_name(), "Charged");
+        assert_eq!(
+            manifest.to_json(),
+            r#"{
+  "nodes": [
+    {
+      "type": "Charged",
+      "category": "Condition",
+      "description": "",
+      "params": []
+    },
+    {
+      "type": "MoveTo",
+      "category": "Action",
+      "description": "Drives to \"goal\".",
+      "params": [
+        {"name": "goal", "type": "String", "required": true, "default": null, "description": "where to go"},
+        {"name": "speed", "type": "f64", "required": false, "default": "0.5", "description": "in m/s"}
+      ]
+    }
+  ]
+}
+"#
+        );
+        assert_eq!(Manifest::default().to_json(), "{\n  \"nodes\": []\n}\n");
+    }
+
+    #[test]
+    fn writes_groot_xml() {
+        let mut info = NodeInfo::new("MoveTo".to_owned());
+        info.add_param(ParamInfo::new::<String>("goal", "a <pose>"))
+            .add_param(ParamInfo::new::<f64>("speed", "").with_default(0.5));
+        let mut decorator = NodeInfo::new("Retry".to_owned());
+        decorator.set_category(Category::Decorator);
+        let manifest = Manifest::new(vec![info, decorator]);
+
+        assert_eq!(
+            manifest.to_groot_xml(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<root BTCPP_format="4">
+  <TreeNodesModel>
+    <Action ID="MoveTo">
+      <input_port name="goal" type="String">a &lt;pose&gt;</input_port>
+      <input_port name="speed" type="f64" default="0.5"/>
+    </Action>
+    <Decorator ID="Retry"/>
+  </TreeNodesModel>
+</root>
+"#
+        );
+    }
+}
@@ -0,0 +1,658 @@
+//! Building nodes from their type names and parameters.
+//!
+//! A `NodeRegistry` maps type names to constructors, so that trees can be
+//! assembled from a description which is only known at runtime, such as a tree
+//! file. Constructors are given the parameters of the node, which they read
+//! with the typed getters of `Params`, along with its children, which have
+//! already been built. When a tree is loaded from a file, errors from a
+//! constructor are reported with the path to the node and its position in the
+//! file.
+//!
+//! Each registered type has a `NodeInfo` describing its category, what it does
+//! and its parameters, and `NodeRegistry::manifest` collects these so that
+//! editors can show the nodes a program can build, either as JSON or as a
+//! palette for the Groot editor.
+//!
+//! With the `plugins` feature, constructors can also be loaded from shared
+//! libraries, as described in the `plugin` module.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::registry::{BuildError, Category, NodeRegistry, ParamInfo, Params};
+//! # use aspen::std_nodes::*;
+//! # use aspen::Status;
+//! # use aspen::node::Tickable;
+//! let mut registry = NodeRegistry::with_std_nodes();
+//! registry
+//!     .register("AtLeast", |params: &Params, _| {
+//!         let n: u32 = params.get("n")?;
+//!         Ok(Condition::new(move |&x: &u32| x >= n))
+//!     })
+//!     .set_category(Category::Condition)
+//!     .set_description("Succeeds if the world is at least `n`.")
+//!     .add_param(ParamInfo::new::<u32>(
+//!         "n",
+//!         "the smallest value which succeeds",
+//!     ));
+//!
+//! let mut node = registry.build(
+//!     "Invert",
+//!     &Params::new(),
+//!     vec![registry.build("AtLeast", &Params::new().with("n", "3"), vec![])?],
+//! )?;
+//! assert_eq!(node.tick(&mut 2), Status::Succeeded);
+//! # Ok::<(), BuildError>(())
+//! ```
+pub(crate) use self::manifest::short_type_name;
+pub use self::manifest::{Category, Manifest, NodeInfo, ParamInfo};
+
+use crate::{
+    blackboard::Blackboard,
+    node::{Node, Tickable},
+    std_nodes::{
+        ActiveSequence, AlwaysFail, AlwaysRunning, AlwaysSucceed, CheckTimer, Invert, Parallel,
+        Repeat, Selector, Sequence, StartTimer, Timeout, UntilFail, UntilSuccess,
+    },
+};
+use std::{
+    cell::Cell,
+    collections::{hash_map, HashMap},
+    error::Error,
+    fmt,
+    ops::RangeBounds,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+/// A single parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Param {
+    /// The key of the parameter.
+    key: String,
+
+    /// The value of the parameter.
+    value: String,
+
+    /// Whether the constructor has read the parameter.
+    used: Cell<bool>,
+
+    /// Whether the constructor may ignore the parameter.
+    optional: bool,
+}
+
+/// The parameters of a node, as strings, which constructors read with typed
+/// getters.
+///
+/// The registry keeps track of which parameters a constructor reads, and
+/// reports any others as unknown, so that misspelled parameters in a tree file
+/// are caught rather than silently ignored. When a tree is loaded from a file,
+/// the `name` of a node is given to its constructor too, which may read it
+/// like any other parameter but does not have to.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::registry::Params;
+/// let params = Params::new().with("speed", "0.8").with("retries", "x");
+/// assert_eq!(params.get::<f64>("speed"), Ok(0.8));
+/// assert_eq!(params.get_or("timeout", 30u64), Ok(30));
+/// assert_eq!(params.get_opt::<u32>("limit"), Ok(None));
+/// assert!(params.get_in("speed", 0.0..=0.5).is_err());
+/// assert!(params.get::<u32>("retries").is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Params {
+    /// The parameters, in the order they were given.
+    entries: Vec<Param>,
+}
+impl Params {
+    /// Creates an empty set of parameters.
+    pub fn new() -> Self {
+        Params::default()
+    }
+
+    /// Adds a parameter, replacing any existing one with the same key.
+    pub fn with<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Sets a parameter, replacing any existing one with the same key.
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        let key = key.into();
+        let value = value.into();
+        match self.entries.iter_mut().find(|p| p.key == key) {
+            Some(param) => param.value = value,
+            None => self.entries.push(Param {
+                key,
+                value,
+                used: Cell::new(false),
+                optional: false,
+            }),
+        }
+    }
+
+    /// Sets a parameter which is not reported as unknown if the constructor
+    /// does not read it, and which is left out of `iter`.
+    pub(crate) fn insert_optional<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        let key = key.into();
+        self.insert(key.clone(), value);
+        if let Some(param) = self.entries.iter_mut().find(|p| p.key == key) {
+            param.optional = true;
+        }
+    }
+
+    /// Returns the parameter with the given key as text.
+    pub fn raw(&self, key: &str) -> Option<&str> {
+        let param = self.entries.iter().find(|p| p.key == key)?;
+        param.used.set(true);
+        Some(&param.value)
+    }
+
+    /// Parses the parameter with the given key, which must be present.
+    pub fn get<T>(&self, key: &str) -> Result<T, BuildError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.get_opt(key)?
+            .ok_or_else(|| BuildError::new(format!("missing parameter {}", key)))
+    }
+
+    /// Parses the parameter with the given key, if it is present.
+    pub fn get_opt<T>(&self, key: &str) -> Result<Option<T>, BuildError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.raw(key)
+            .map(|v| {
+                v.parse().map_err(|e| {
+                    BuildError::new(format!("invalid parameter {}={:?}: {}", key, v, e))
+                })
+            })
+            .transpose()
+    }
+
+    /// Parses the parameter with the given key, or returns the default if it
+    /// is missing.
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T, BuildError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.get_opt(key).map(|v| v.unwrap_or(default))
+    }
+
+    /// Parses the parameter with the given key, which must be present and in
+    /// the given range.
+    pub fn get_in<T, R>(&self, key: &str, range: R) -> Result<T, BuildError>
+    where
+        T: FromStr + PartialOrd + fmt::Debug,
+        T::Err: fmt::Display,
+        R: RangeBounds<T> + fmt::Debug,
+    {
+        let value = self.get(key)?;
+        if range.contains(&value) {
+            Ok(value)
+        } else {
+            Err(BuildError::new(format!(
+                "parameter {} must be in {:?}, not {:?}",
+                key, range, value
+            )))
+        }
+    }
+
+    /// Returns the parameters, in the order they were given.
+    ///
+    /// This counts as reading all of them.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().filter(|p| !p.optional).map(|p| {
+            p.used.set(true);
+            (p.key.as_str(), p.value.as_str())
+        })
+    }
+
+    /// Returns the keys of the parameters which have not been read.
+    fn unused(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .filter(|p| !p.used.get() && !p.optional)
+            .map(|p| p.key.as_str())
+    }
+
+    /// Marks all of the parameters as unread.
+    fn clear_used(&self) {
+        for param in &self.entries {
+            param.used.set(false);
+        }
+    }
+}
+
+/// An error from building a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildError {
+    /// What went wrong.
+    message: String,
+}
+impl BuildError {
+    /// Creates an error with the given message.
+    pub fn new<M: fmt::Display>(message: M) -> Self {
+        BuildError {
+            message: message.to_string(),
+        }
+    }
+
+    /// Returns what went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+impl Error for BuildError {}
+
+/// Function which builds a node from its parameters and children.
+pub type ConstructorFn<W> =
+    dyn Fn(&Params, Vec<Node<'static, W>>) -> Result<Node<'static, W>, BuildError>;
+
+mod manifest;
+
+/// A registered node type.
+struct Entry<W> {
+    /// The constructor for the type.
+    constructor: Arc<ConstructorFn<W>>,
+
+    /// The description of the type.
+    info: NodeInfo,
+}
+
+/// A set of constructors for nodes, keyed by type name.
+pub struct NodeRegistry<W> {
+    /// The registered types.
+    entries: HashMap<String, Entry<W>>,
+}
+impl<W: 'static> NodeRegistry<W> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        NodeRegistry {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Creates a registry containing the standard nodes which do not need a
+    /// function from the world.
+    ///
+    /// These are the composites `Sequence`, `ActiveSequence`, `Selector` and
+    /// `Parallel` (with a `required` parameter), the decorators `Invert`,
+    /// `Repeat`, `UntilFail` and `UntilSuccess` (with an optional `limit`) and
+    /// `Timeout` (with a `ms` parameter), and the leaves `AlwaysSucceed`,
+    /// `AlwaysFail` and `AlwaysRunning`.
+    pub fn with_std_nodes() -> Self {
+        let mut registry = NodeRegistry::new();
+        let limit = || ParamInfo::new::<u32>("limit", "the most times to run the child").optional();
+
+        registry
+            .register("Sequence", |_, c| Ok(Sequence::new(c)))
+            .set_category(Category::Control)
+            .set_description("Runs its children in order until one does not succeed.");
+        registry
+            .register("ActiveSequence", |_, c| {
+                Ok(ActiveSequence::new().with_children(c).into_node())
+            })
+            .set_category(Category::Control)
+            .set_description("Runs its children in order, restarting from the first every tick.");
+        registry
+            .register("Selector", |_, c| Ok(Selector::new(c)))
+            .set_category(Category::Control)
+            .set_description("Runs its children in order until one does not fail.");
+        registry
+            .register("Parallel", |p, c| {
+                let required = p.get_or("required", c.len())?;
+                Ok(Parallel::new(required, c))
+            })
+            .set_category(Category::Control)
+            .set_description("Runs all of its children at once.")
+            .add_param(
+                ParamInfo::new::<usize>(
+                    "required",
+                    "how many children must succeed, or all of them if not given",
+                )
+                .optional(),
+            );
+        registry
+            .register("Invert", |_, c| Ok(Invert::new(only_child(c)?)))
+            .set_category(Category::Decorator)
+            .set_description("Swaps success and failure of its child.");
+        registry
+            .register("Repeat", |p, c| {
+                let child = only_child(c)?;
+                Ok(match p.get_opt("limit")? {
+                    Some(limit) => Repeat::with_limit(limit, child),
+                    None => Repeat::new(child),
+                })
+            })
+            .set_category(Category::Decorator)
+            .set_description("Runs its child again every time it finishes.")
+            .add_param(limit());
+        registry
+            .register("UntilFail", |p, c| {
+                let child = only_child(c)?;
+                Ok(match p.get_opt("limit")? {
+                    Some(limit) => UntilFail::with_limit(limit, child),
+                    None => UntilFail::new(child),
+                })
+            })
+            .set_category(Category::Decorator)
+            .set_description("Runs its child again until it fails.")
+            .add_param(limit());
+        registry
+            .register("UntilSuccess", |p, c| {
+                let child = only_child(c)?;
+                Ok(match p.get_opt("limit")? {
+                    Some(limit) => UntilSuccess::with_limit(limit, child),
+                    None => UntilSuccess::new(child),
+                })
+            })
+            .set_category(Category::Decorator)
+            .set_description("Runs its child again until it succeeds.")
+            .add_param(limit());
+        registry
+            .register("Timeout", |p, c| {
+                let ms = p.get("ms")?;
+                Ok(Timeout::new(Duration::from_millis(ms), only_child(c)?))
+            })
+            .set_category(Category::Decorator)
+            .set_description("Fails if its child runs for too long.")
+            .add_param(ParamInfo::new::<u64>(
+                "ms",
+                "how long the child may run, in milliseconds",
+            ));
+        registry
+            .register("AlwaysSucceed", |_, c| {
+                leaf(c).map(|_| AlwaysSucceed::new())
+            })
+            .set_description("Succeeds straight away.");
+        registry
+            .register("AlwaysFail", |_, c| leaf(c).map(|_| AlwaysFail::new()))
+            .set_description("Fails straight away.");
+        registry
+            .register("AlwaysRunning", |_, c| {
+                leaf(c).map(|_| AlwaysRunning::new())
+            })
+            .set_description("Never finishes.");
+        registry
+    }
+
+    /// Registers a constructor for the given type name, replacing any
+    /// existing one.
+    ///
+    /// This returns the description of the type, which starts out as an
+    /// action with no description or parameters.
+    pub fn register<N, F>(&mut self, type_name: N, constructor: F) -> &mut NodeInfo
+    where
+        N: Into<String>,
+        F: Fn(&Params, Vec<Node<'static, W>>) -> Result<Node<'static, W>, BuildError> + 'static,
+    {
+        let type_name = type_name.into();
+        let entry = Entry {
+            constructor: Arc::new(constructor),
+            info: NodeInfo::new(type_name.clone()),
+        };
+        match self.entries.entry(type_name) {
+            hash_map::Entry::Occupied(mut o) => {
+                o.insert(entry);
+                &mut o.into_mut().info
+            }
+            hash_map::Entry::Vacant(v) => &mut v.insert(entry).info,
+        }
+    }
+
+    /// Returns `true` if there is a constructor for the given type name.
+    pub fn contains(&self, type_name: &str) -> bool {
+        self.entries.contains_key(type_name)
+    }
+
+    /// Returns the registered type names, in alphabetical order.
+    pub fn type_names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.entries.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the description of the given type.
+    pub fn info(&self, type_name: &str) -> Option<&NodeInfo> {
+        self.entries.get(type_name).map(|e| &e.info)
+    }
+
+    /// Returns the description of the given type, so that it can be changed.
+    pub fn info_mut(&mut self, type_name: &str) -> Option<&mut NodeInfo> {
+        self.entries.get_mut(type_name).map(|e| &mut e.info)
+    }
+
+    /// Returns the descriptions of all of the registered types.
+    pub fn manifest(&self) -> Manifest {
+        Manifest::new(self.entries.values().map(|e| e.info.clone()).collect())
+    }
+
+    /// Builds a node of the given type.
+    pub fn build(
+        &self,
+        type_name: &str,
+        params: &Params,
+        children: Vec<Node<'static, W>>,
+    ) -> Result<Node<'static, W>, BuildError> {
+        self.construct(type_name, params, children).map_err(|e| {
+            if self.contains(type_name) {
+                BuildError::new(format!("{}: {}", type_name, e))
+            } else {
+                e
+            }
+        })
+    }
+
+    /// Returns the constructor for the given type name.
+    pub(crate) fn constructor(&self, type_name: &str) -> Option<Arc<ConstructorFn<W>>> {
+        self.entries
+            .get(type_name)
+            .map(|e| Arc::clone(&e.constructor))
+    }
+
+    /// Builds a node of the given type, with errors which do not say which
+    /// type it is.
+    fn construct(
+        &self,
+        type_name: &str,
+        params: &Params,
+        children: Vec<Node<'static, W>>,
+    ) -> Result<Node<'static, W>, BuildError> {
+        let entry = self
+            .entries
+            .get(type_name)
+            .ok_or_else(|| BuildError::new(format!("unknown node type {}", type_name)))?;
+        call(&*entry.constructor, params, children)
+    }
+}
+impl<W> NodeRegistry<W>
+where
+    W: AsRef<Blackboard> + AsMut<Blackboard> + 'static,
+{
+    /// Creates a registry containing the standard nodes, as from
+    /// `with_std_nodes`, along with those which keep their state on the
+    /// blackboard of the world.
+    ///
+    /// These are `StartTimer` (with a `name` parameter) and `CheckTimer` (with
+    /// `name` and `ms` parameters), which use the system clock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::registry::{BuildError, NodeRegistry, Params};
+    /// # use aspen::blackboard::Blackboard;
+    /// # use aspen::Status;
+    /// # use aspen::node::Tickable;
+    /// let registry = NodeRegistry::<Blackboard>::with_blackboard_nodes();
+    /// let name = Params::new().with("name", "search");
+    /// let mut start = registry.build("StartTimer", &name, vec![])?;
+    /// let mut check = registry.build("CheckTimer", &name.with("ms", "60000"), vec![])?;
+    ///
+    /// let mut blackboard = Blackboard::new();
+    /// assert_eq!(check.tick(&mut blackboard), Status::Failed);
+    /// start.tick(&mut blackboard);
+    /// assert_eq!(check.tick(&mut blackboard), Status::Succeeded);
+    /// # Ok::<(), BuildError>(())
+    /// ```
+    pub fn with_blackboard_nodes() -> Self {
+        let mut registry = NodeRegistry::with_std_nodes();
+        let name = || ParamInfo::new::<String>("name", "the name of the timer");
+
+        registry
+            .register("StartTimer", |p, c| {
+                let name: String = p.get("name")?;
+                leaf(c).map(|_| StartTimer::new(name))
+            })
+            .set_description("Starts, or restarts, a named timer.")
+            .add_param(name());
+        registry
+            .register("CheckTimer", |p, c| {
+                let name: String = p.get("name")?;
+                let ms = p.get("ms")?;
+                leaf(c).map(|_| CheckTimer::new(name, Duration::from_millis(ms)))
+            })
+            .set_category(Category::Condition)
+            .set_description("Succeeds while a named timer is within its limit.")
+            .add_param(name())
+            .add_param(ParamInfo::new::<u64>(
+                "ms",
+                "how long the timer may run, in milliseconds",
+            ));
+        registry
+    }
+}
+impl<W: 'static> Default for NodeRegistry<W> {
+    fn default() -> Self {
+        NodeRegistry::new()
+    }
+}
+impl<W> fmt::Debug for NodeRegistry<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<_> = self.entries.keys().collect();
+        names.sort_unstable();
+        f.debug_struct("NodeRegistry")
+            .field("types", &names)
+            .finish()
+    }
+}
+
+/// Calls a constructor, then checks that it read all of the parameters.
+pub(crate) fn call<W>(
+    constructor: &ConstructorFn<W>,
+    params: &Params,
+    children: Vec<Node<'static, W>>,
+) -> Result<Node<'static, W>, BuildError> {
+    params.clear_used();
+    let node = constructor(params, children)?;
+
+    let unused: Vec<_> = params.unused().collect();
+    if !unused.is_empty() {
+        return Err(BuildError::new(format!(
+            "unknown parameter{} {}",
+            if unused.len() == 1 { "" } else { "s" },
+            unused.join(", ")
+        )));
+    }
+    Ok(node)
+}
+
+/// Returns the only child of a decorator.
+fn only_child<W>(mut children: Vec<Node<'static, W>>) -> Result<Node<'static, W>, BuildError> {
+    match children.len() {
+        1 => Ok(children.remove(0)),
+        n => Err(BuildError::new(format!("expected one child, found {}", n))),
+    }
+}
+
+/// Checks that a leaf was given no children.
+fn leaf<W>(children: Vec<Node<'static, W>>) -> Result<(), BuildError> {
+    match children.len() {
+        0 => Ok(()),
+        n => Err(BuildError::new(format!(
+            "expected no children, found {}",
+            n
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodeRegistry, Params};
+    use crate::{node::Tickable, Status};
+
+    #[test]
+    fn builds_std_nodes() {
+        let registry = NodeRegistry::<()>::with_std_nodes();
+        let leaf = |name| registry.build(name, &Params::new(), vec![]).unwrap();
+        let mut node = registry
+            .build(
+                "Parallel",
+                &Params::new().with("required", "1"),
+                vec![leaf("AlwaysFail"), leaf("AlwaysSucceed")],
+            )
+            .unwrap();
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+    }
+
+    #[test]
+    fn reports_bad_nodes() {
+        let registry = NodeRegistry::<()>::with_std_nodes();
+        let error = |name, params: &Params| {
+            registry
+                .build(name, params, vec![])
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(error("Patrol", &Params::new()), "unknown node type Patrol");
+        assert_eq!(
+            error("Invert", &Params::new()),
+            "Invert: expected one child, found 0"
+        );
+
+        let child = registry
+            .build("AlwaysFail", &Params::new(), vec![])
+            .unwrap();
+        let e = registry
+            .build("Repeat", &Params::new().with("limit", "-1"), vec![child])
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "Repeat: invalid parameter limit=\"-1\": invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn unknown_parameters_are_reported() {
+        let registry = NodeRegistry::<()>::with_std_nodes();
+        let params = Params::new().with("limt", "3").with("colour", "red");
+        let child = registry
+            .build("AlwaysFail", &Params::new(), vec![])
+            .unwrap();
+        let e = registry
+            .build("Repeat", &params, vec![child])
+            .err()
+            .unwrap();
+        assert_eq!(e.to_string(), "Repeat: unknown parameters limt, colour");
+
+        let params = Params::new().with("ms", "20");
+        let child = registry
+            .build("AlwaysFail", &Params::new(), vec![])
+            .unwrap();
+        assert!(registry.build("Timeout", &params, vec![child]).is_ok());
+    }
+}
@@ -0,0 +1,39 @@
+//! A small, seedable pseudo-random number generator.
+
+/// A SplitMix64 pseudo-random number generator.
+///
+/// This is not suitable for cryptography, but it is fast, has no dependencies,
+/// and always produces the same sequence for the same seed.
+#[derive(Clone, Debug)]
+pub(crate) struct Rng(u64);
+impl Rng {
+    /// Creates a generator with the given seed.
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    /// Returns the next number in the sequence.
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number from zero up to, but not including, one.
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns `true` with the given probability.
+    #[allow(dead_code)]
+    pub(crate) fn chance(&mut self, probability: f64) -> bool {
+        self.unit() < probability
+    }
+
+    /// Returns a number which is less than `n`, which must not be zero.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
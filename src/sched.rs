@@ -0,0 +1,93 @@
+//! Scheduling controls for the current thread.
+//!
+//! These are only implemented on Linux. Elsewhere, they return an error of
+//! kind `Other`.
+use std::io;
+
+/// Sets the nice value of the current thread.
+///
+/// Lower values are scheduled more favorably. Lowering the value below zero
+/// usually needs elevated privileges.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_nice(nice: i32) -> io::Result<()> {
+    use std::os::raw::{c_int, c_uint};
+
+    extern "C" {
+        fn setpriority(which: c_int, who: c_uint, prio: c_int) -> c_int;
+    }
+
+    // On Linux, the nice value is per thread and `who = 0` refers to the
+    // calling thread
+    const PRIO_PROCESS: c_int = 0;
+    // Safety: setpriority only reads its integer arguments
+    if unsafe { setpriority(PRIO_PROCESS, 0, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Restricts the current thread to the CPU cores with the given indices.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_affinity(cores: &[usize]) -> io::Result<()> {
+    use std::os::raw::c_int;
+
+    extern "C" {
+        fn sched_setaffinity(pid: c_int, size: usize, mask: *const u64) -> c_int;
+    }
+
+    // The layout of glibc's and musl's cpu_set_t
+    let mut mask = [0u64; 16];
+    for &core in cores {
+        let word = mask
+            .get_mut(core / 64)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no such core"))?;
+        *word |= 1 << (core % 64);
+    }
+
+    // Safety: the mask is valid for reads of its whole size, which is passed
+    // along with it
+    let size = std::mem::size_of_val(&mask);
+    if unsafe { sched_setaffinity(0, size, mask.as_ptr()) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Sets the nice value of the current thread.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_nice(_nice: i32) -> io::Result<()> {
+    Err(unsupported())
+}
+
+/// Restricts the current thread to the CPU cores with the given indices.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_affinity(_cores: &[usize]) -> io::Result<()> {
+    Err(unsupported())
+}
+
+/// Returns the error for platforms without scheduling controls.
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "thread scheduling is only supported on Linux",
+    )
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::{set_affinity, set_nice};
+    use std::thread;
+
+    #[test]
+    fn controls_apply_to_the_current_thread() {
+        thread::spawn(|| {
+            set_nice(5).unwrap();
+            assert!(set_affinity(&[4096]).is_err());
+        })
+        .join()
+        .unwrap();
+    }
+}
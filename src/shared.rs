@@ -0,0 +1,196 @@
+//! Sharing the state of a tree with other threads.
+use crate::{bt::BehaviorTree, monitor, status::Status};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The state of a tree as of its last tick.
+#[derive(Default)]
+struct Snapshot {
+    /// The number of times the tree had been ticked.
+    tick_count: u64,
+
+    /// The status of every node, in pre-order.
+    statuses: Vec<Option<Status>>,
+}
+
+/// A behavior tree which publishes its state for other threads to read.
+///
+/// Nodes contain arbitrary, type-erased logic, so neither `Node` nor
+/// `BehaviorTree` is `Send`, and a tree cannot simply be put in an
+/// `Arc<Mutex<_>>` unless it is built as a `SendTree`. Instead, the tree stays
+/// on the thread that ticks it, which is the only one able to modify it. After
+/// every tick, the status of every node is copied to a snapshot which can be
+/// read through any number of `TreeHandle`s, on any thread.
+///
+/// A tree whose nodes must be created on another thread can be built inside
+/// the closure passed to `thread::spawn`, with the handle sent back over a
+/// channel.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::{BehaviorTree, SharedTree, Status};
+/// # use std::thread;
+/// let mut tree = SharedTree::new(BehaviorTree::new(Sequence::new(vec![
+///     AlwaysSucceed::new(),
+///     AlwaysRunning::new(),
+/// ])));
+/// let handle = tree.handle();
+///
+/// tree.tick(&mut ());
+/// thread::spawn(move || {
+///     assert_eq!(handle.status(), Some(Status::Running));
+///     assert_eq!(handle.node_status(1), Some(Status::Succeeded));
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+pub struct SharedTree<'a, W> {
+    /// The tree itself.
+    tree: BehaviorTree<'a, W>,
+
+    /// The snapshot read by the handles.
+    snapshot: Arc<Mutex<Snapshot>>,
+}
+impl<'a, W> SharedTree<'a, W> {
+    /// Wraps `tree`, publishing its current state.
+    pub fn new(tree: BehaviorTree<'a, W>) -> Self {
+        let shared = SharedTree {
+            tree,
+            snapshot: Arc::default(),
+        };
+        shared.publish();
+        shared
+    }
+
+    /// Returns a new handle to the published state of the tree.
+    pub fn handle(&self) -> TreeHandle {
+        TreeHandle {
+            snapshot: Arc::clone(&self.snapshot),
+        }
+    }
+
+    /// Returns a reference to the tree.
+    pub fn tree(&self) -> &BehaviorTree<'a, W> {
+        &self.tree
+    }
+
+    /// Ticks the tree once and publishes its new state.
+    pub fn tick(&mut self, world: &mut W) -> Status {
+        let status = self.tree.tick(world);
+        self.publish();
+        status
+    }
+
+    /// Resets the tree and publishes its new state.
+    pub fn reset(&mut self) {
+        self.tree.reset();
+        self.publish();
+    }
+
+    /// Returns the wrapped tree.
+    ///
+    /// Existing handles keep the last published state.
+    pub fn into_inner(self) -> BehaviorTree<'a, W> {
+        self.tree
+    }
+
+    /// Copies the state of the tree to the snapshot.
+    fn publish(&self) {
+        let mut snapshot = lock(&self.snapshot);
+        snapshot.tick_count = self.tree.tick_count();
+        monitor::collect_statuses(self.tree.root(), &mut snapshot.statuses);
+    }
+}
+
+/// A read-only view of the state of a `SharedTree`, which can be sent to and
+/// shared between threads.
+///
+/// Nodes are identified by their index in a pre-order traversal of the tree,
+/// so the root node has ID zero.
+#[derive(Clone)]
+pub struct TreeHandle {
+    /// The snapshot published by the tree.
+    snapshot: Arc<Mutex<Snapshot>>,
+}
+impl TreeHandle {
+    /// Returns the status of the root node.
+    pub fn status(&self) -> Option<Status> {
+        self.node_status(0)
+    }
+
+    /// Returns the status of the node with the given ID.
+    ///
+    /// Returns `None` if the node has not been ticked or there is no such
+    /// node.
+    pub fn node_status(&self, id: usize) -> Option<Status> {
+        lock(&self.snapshot).statuses.get(id).copied().flatten()
+    }
+
+    /// Returns the status of every node, in pre-order.
+    pub fn statuses(&self) -> Vec<Option<Status>> {
+        lock(&self.snapshot).statuses.clone()
+    }
+
+    /// Returns the number of times the tree had been ticked when its state
+    /// was published.
+    pub fn tick_count(&self) -> u64 {
+        lock(&self.snapshot).tick_count
+    }
+}
+
+/// Locks the snapshot.
+///
+/// The snapshot is always left consistent, so a panic while it was locked does
+/// not matter.
+fn lock(snapshot: &Mutex<Snapshot>) -> MutexGuard<'_, Snapshot> {
+    snapshot.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TreeHandle;
+    use crate::{
+        blackboard::Blackboard,
+        node::EnableSwitch,
+        std_nodes::{AlwaysRunning, AlwaysSucceed, Sequence},
+        BehaviorTree, RunSummary, SharedTree, Status,
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn thread_safe_types() {
+        assert_send_sync::<Status>();
+        assert_send_sync::<RunSummary>();
+        assert_send_sync::<EnableSwitch>();
+        assert_send_sync::<TreeHandle>();
+        fn assert_send<T: Send>() {}
+        assert_send::<Blackboard>();
+    }
+
+    #[test]
+    fn handle_sees_published_state() {
+        let mut tree = SharedTree::new(BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new(),
+            AlwaysRunning::new(),
+        ])));
+        let handle = tree.handle();
+        assert_eq!(handle.statuses(), vec![None, None, None]);
+
+        tree.tick(&mut ());
+        assert_eq!(handle.tick_count(), 1);
+        assert_eq!(
+            handle.statuses(),
+            vec![
+                Some(Status::Running),
+                Some(Status::Succeeded),
+                Some(Status::Running)
+            ]
+        );
+
+        tree.reset();
+        assert_eq!(handle.status(), None);
+        assert_eq!(handle.node_status(7), None);
+    }
+}
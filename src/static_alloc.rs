@@ -0,0 +1,370 @@
+//! Nodes and trees for microcontrollers, which never allocate.
+//!
+//! The standard nodes box their internals into a `Node`, keep their children
+//! in a `Vec` and may own their names, none of which suits a target without a
+//! heap. This module is the profile for such targets:
+//!
+//! - `StaticNode` gives any `Tickable` a `&'static str` name and tracks its
+//!   status, as `Node` does, but holds the internals inline.
+//! - `Sequence` and `Selector` keep their children in a `heapless::Vec` with a
+//!   fixed capacity, so the number of children can vary up to that capacity.
+//!   `SequenceN` and `SelectorN` in `std_nodes` can be used when the number is
+//!   known exactly.
+//! - `StaticTree` ticks a root node the way `BehaviorTree` does.
+//!
+//! Leaves are built from the cooperative nodes in `std_nodes::typed`, such as
+//! `InlineAction` and `Condition`, which are generic over their functions
+//! rather than boxing them. The threaded `Action` has no place here, so the
+//! profile is meant to be used with the default features disabled:
+//!
+//! ```toml
+//! aspen = { version = "0.3", default-features = false, features = ["static-alloc"] }
+//! ```
+//!
+//! The children of a composite all have the same type. Children of different
+//! types can be mixed by borrowing them as `&mut dyn Tickable<W>`, which is
+//! implemented for any mutable reference to a `Tickable`, from wherever they
+//! are stored, such as a `static` or the stack.
+//!
+//! As with the `typed` composites, children are not reported by `children`,
+//! so these trees do not take part in monitoring or dry runs.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::node::Tickable;
+//! # use aspen::static_alloc::{heapless, Selector, Sequence, StaticNode, StaticTree};
+//! # use aspen::std_nodes::typed::{Condition, InlineAction};
+//! # use aspen::Status;
+//! let mut low = StaticNode::new("LowBattery", Condition::new(|&level: &u8| level < 20));
+//! let mut charge = StaticNode::new(
+//!     "Charge",
+//!     InlineAction::new(|level: &mut u8| {
+//!         *level += 10;
+//!         Status::Succeeded
+//!     }),
+//! );
+//! let mut patrol = StaticNode::new("Patrol", InlineAction::new(|_: &mut u8| Status::Running));
+//!
+//! let mut recharge: heapless::Vec<&mut dyn Tickable<u8>, 4> = heapless::Vec::new();
+//! recharge.push(&mut low).ok();
+//! recharge.push(&mut charge).ok();
+//! let mut recharge = StaticNode::new("Recharge", Sequence::new(recharge));
+//!
+//! let mut root: heapless::Vec<&mut dyn Tickable<u8>, 2> = heapless::Vec::new();
+//! root.push(&mut recharge).ok();
+//! root.push(&mut patrol).ok();
+//! let mut tree = StaticTree::new(Selector::new(root));
+//!
+//! let mut level = 15;
+//! assert_eq!(tree.tick(&mut level), Status::Succeeded);
+//! assert_eq!(level, 25);
+//! assert_eq!(tree.tick(&mut level), Status::Running);
+//! ```
+use crate::{
+    node::{Progress, Tickable},
+    status::Status,
+    std_nodes::{selector::tick_active_selector, sequence::tick_sequence},
+};
+
+pub use heapless;
+
+/// A node with a `&'static str` name, which tracks its status.
+///
+/// This is the counterpart of `Node` for trees which do not allocate: the
+/// internals are held inline rather than boxed, and the name is never copied.
+/// Like a `Node`, it only resets its internals if it has been ticked since it
+/// was last reset.
+pub struct StaticNode<T> {
+    /// The name of the node.
+    name: &'static str,
+
+    /// The status of the node after its last tick, if it has been ticked since
+    /// it was last reset.
+    status: Option<Status>,
+
+    /// The logic of the node.
+    internals: T,
+}
+impl<T> StaticNode<T> {
+    /// Creates a new node with the given name and logic.
+    pub fn new(name: &'static str, internals: T) -> Self {
+        StaticNode {
+            name,
+            status: None,
+            internals,
+        }
+    }
+
+    /// Returns the name of the node.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the status of the node after its last tick, or `None` if it has
+    /// not been ticked since it was last reset.
+    pub fn status(&self) -> Option<Status> {
+        self.status
+    }
+
+    /// Returns a reference to the logic of the node.
+    pub fn internals(&self) -> &T {
+        &self.internals
+    }
+}
+impl<W, T> Tickable<W> for StaticNode<T>
+where
+    T: Tickable<W>,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        trace!("Ticking node {}", self.name);
+        let status = self.internals.tick(world);
+        self.status = Some(status);
+        status
+    }
+
+    fn reset(&mut self) {
+        if self.status.take().is_some() {
+            trace!("Resetting node {}", self.name);
+            self.internals.reset();
+        }
+    }
+
+    fn progress(&self) -> Option<&Progress> {
+        Tickable::<W>::progress(&self.internals)
+    }
+
+    /// Returns the type name of the logic of the node.
+    fn type_name(&self) -> &str {
+        Tickable::<W>::type_name(&self.internals)
+    }
+}
+
+impl<W, T> Tickable<W> for &mut T
+where
+    T: Tickable<W> + ?Sized,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        (**self).tick(world)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn progress(&self) -> Option<&Progress> {
+        Tickable::<W>::progress(&**self)
+    }
+
+    fn type_name(&self) -> &str {
+        Tickable::<W>::type_name(&**self)
+    }
+}
+
+/// A `Sequence` with up to `N` children.
+///
+/// See [`Sequence`](crate::std_nodes::Sequence) for the details of how the
+/// children are ticked.
+pub struct Sequence<C, const N: usize> {
+    /// The children of this node.
+    children: heapless::Vec<C, N>,
+
+    /// The next child to be ticked.
+    next_child: usize,
+}
+impl<C, const N: usize> Sequence<C, N> {
+    /// Creates a new `Sequence` node with the given children.
+    pub fn new(children: heapless::Vec<C, N>) -> Self {
+        Sequence {
+            children,
+            next_child: 0,
+        }
+    }
+
+    /// Adds a child after the existing ones, or gives it back if the node
+    /// already has `N` children.
+    pub fn push(&mut self, child: C) -> Result<(), C> {
+        self.children.push(child)
+    }
+}
+impl<W, C, const N: usize> Tickable<W> for Sequence<C, N>
+where
+    C: Tickable<W>,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_sequence(&mut self.children, &mut self.next_child, world)
+    }
+
+    fn reset(&mut self) {
+        // Reset all of our children
+        for child in &mut self.children {
+            child.reset();
+        }
+
+        self.next_child = 0;
+    }
+
+    /// Returns the string "Sequence".
+    fn type_name(&self) -> &'static str {
+        "Sequence"
+    }
+}
+
+/// A `Selector` with up to `N` children.
+///
+/// See [`Selector`](crate::std_nodes::Selector) for the details of how the
+/// children are ticked.
+pub struct Selector<C, const N: usize> {
+    /// The children of this node.
+    children: heapless::Vec<C, N>,
+}
+impl<C, const N: usize> Selector<C, N> {
+    /// Creates a new `Selector` node with the given children.
+    pub fn new(children: heapless::Vec<C, N>) -> Self {
+        Selector { children }
+    }
+
+    /// Adds a child after the existing ones, or gives it back if the node
+    /// already has `N` children.
+    pub fn push(&mut self, child: C) -> Result<(), C> {
+        self.children.push(child)
+    }
+}
+impl<W, C, const N: usize> Tickable<W> for Selector<C, N>
+where
+    C: Tickable<W>,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_active_selector(&mut self.children, world)
+    }
+
+    fn reset(&mut self) {
+        // Reset all of our children
+        for child in &mut self.children {
+            child.reset();
+        }
+    }
+
+    /// Returns the string "Selector".
+    fn type_name(&self) -> &'static str {
+        "Selector"
+    }
+}
+
+/// A behavior tree which never allocates.
+///
+/// This ticks its root the way `BehaviorTree` does, resetting it before the
+/// next tick once it has completed, but has none of the callbacks, logging
+/// policies or configs which need the heap.
+pub struct StaticTree<R> {
+    /// The root node of the tree.
+    root: StaticNode<R>,
+
+    /// The number of times the tree has been ticked.
+    tick_count: u64,
+}
+impl<R> StaticTree<R> {
+    /// Creates a new tree with the given root.
+    pub fn new(root: R) -> Self {
+        StaticTree {
+            root: StaticNode::new("Root", root),
+            tick_count: 0,
+        }
+    }
+
+    /// Returns the status of the root after the last tick, or `None` if the
+    /// tree has not been ticked since it was last reset.
+    pub fn status(&self) -> Option<Status> {
+        self.root.status()
+    }
+
+    /// Returns the number of times the tree has been ticked.
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// Returns a reference to the root node.
+    pub fn root(&self) -> &R {
+        self.root.internals()
+    }
+
+    /// Ticks the tree a single time.
+    ///
+    /// If the tree has already completed, it is reset first.
+    pub fn tick<W>(&mut self, world: &mut W) -> Status
+    where
+        R: Tickable<W>,
+    {
+        if self.root.status().map_or(false, |s| s.is_done()) {
+            debug!("Tree reset via ticking");
+            Tickable::<W>::reset(&mut self.root);
+        }
+        self.tick_count = self.tick_count.wrapping_add(1);
+        self.root.tick(world)
+    }
+
+    /// Resets the tree to a state identical to before it had ran.
+    pub fn reset<W>(&mut self)
+    where
+        R: Tickable<W>,
+    {
+        Tickable::<W>::reset(&mut self.root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{heapless, Selector, Sequence, StaticNode, StaticTree};
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::typed::{Condition, InlineAction},
+    };
+
+    #[test]
+    fn children_are_limited_to_the_capacity() {
+        let pass: fn(&()) -> bool = |_| true;
+        let mut node = Sequence::<_, 2>::new(heapless::Vec::new());
+        assert!(node.push(Condition::new(pass)).is_ok());
+        assert!(node.push(Condition::new(pass)).is_ok());
+        assert!(node.push(Condition::new(pass)).is_err());
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+    }
+
+    #[test]
+    fn trees_restart_after_completing() {
+        let mut step = StaticNode::new(
+            "Step",
+            InlineAction::new(|n: &mut u32| {
+                *n += 1;
+                if *n % 2 == 0 {
+                    Status::Succeeded
+                } else {
+                    Status::Running
+                }
+            }),
+        );
+        let mut never = StaticNode::new("Never", Condition::new(|_: &u32| false));
+        let mut children: heapless::Vec<&mut dyn Tickable<u32>, 2> = heapless::Vec::new();
+        children.push(&mut never).ok();
+        children.push(&mut step).ok();
+        let mut tree = StaticTree::new(Selector::new(children));
+
+        let mut n = 0;
+        assert_eq!(tree.tick(&mut n), Status::Running);
+        assert_eq!(tree.tick(&mut n), Status::Succeeded);
+        assert_eq!(tree.tick(&mut n), Status::Running);
+        assert_eq!(tree.tick_count(), 3);
+        assert_eq!(Tickable::<u32>::type_name(tree.root()), "Selector");
+
+        tree.reset::<u32>();
+        assert_eq!(tree.status(), None);
+        drop(tree);
+        assert_eq!(step.status(), None);
+        assert_eq!(never.name(), "Never");
+    }
+}
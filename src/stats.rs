@@ -0,0 +1,963 @@
+//! Statistics gathered from running behavior trees.
+use crate::{
+    bt::BehaviorTree,
+    clock::{Clock, SystemClock},
+    monitor::{preorder, structure_hash},
+    node::Tickable,
+    status::Status,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The number of bits of precision kept below the leading bit of a sample.
+///
+/// With three bits, the width of a bucket is never more than an eighth of its
+/// lower bound, which means reported values are within 12.5% of the truth.
+const SUB_BUCKET_BITS: u32 = 3;
+
+/// The number of linear sub-buckets for every power of two.
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+/// Samples below this value get a bucket each.
+const LINEAR_LIMIT: u64 = SUB_BUCKETS << 1;
+
+/// A histogram of durations with logarithmic buckets.
+///
+/// In the style of HDR histograms, the range of each bucket is proportional
+/// to its magnitude so that both nanosecond and multi-second samples can be
+/// stored with bounded relative error in a small amount of memory. Recording is
+/// constant time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// The number of samples in each bucket.
+    ///
+    /// This grows as larger samples are recorded.
+    buckets: Vec<u64>,
+
+    /// The total number of samples.
+    count: u64,
+
+    /// The smallest sample, in nanoseconds.
+    min: u64,
+
+    /// The largest sample, in nanoseconds.
+    max: u64,
+
+    /// The sum of all samples, in nanoseconds.
+    sum: u128,
+}
+impl LatencyHistogram {
+    /// Creates a new, empty histogram.
+    pub fn new() -> Self {
+        LatencyHistogram::default()
+    }
+
+    /// Adds a sample to the histogram.
+    ///
+    /// Durations longer than `u64::MAX` nanoseconds (roughly 584 years) are
+    /// saturated.
+    pub fn record(&mut self, duration: Duration) {
+        let nanos = duration_to_nanos(duration);
+        let index = bucket_index(nanos);
+        if index >= self.buckets.len() {
+            self.buckets.resize(index + 1, 0);
+        }
+        self.buckets[index] += 1;
+
+        if self.count == 0 || nanos < self.min {
+            self.min = nanos;
+        }
+        if nanos > self.max {
+            self.max = nanos;
+        }
+        self.count += 1;
+        self.sum += u128::from(nanos);
+    }
+
+    /// Adds all of the samples in `other` to this histogram.
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.count == 0 {
+            return;
+        }
+        if self.buckets.len() < other.buckets.len() {
+            self.buckets.resize(other.buckets.len(), 0);
+        }
+        for (mine, theirs) in self.buckets.iter_mut().zip(&other.buckets) {
+            *mine += theirs;
+        }
+
+        if self.count == 0 || other.min < self.min {
+            self.min = other.min;
+        }
+        if other.max > self.max {
+            self.max = other.max;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    /// Returns the number of samples.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the smallest sample, if there are any.
+    pub fn min(&self) -> Option<Duration> {
+        self.nonempty(self.min)
+    }
+
+    /// Returns the largest sample, if there are any.
+    pub fn max(&self) -> Option<Duration> {
+        self.nonempty(self.max)
+    }
+
+    /// Returns the mean of the samples, if there are any.
+    pub fn mean(&self) -> Option<Duration> {
+        self.nonempty((self.sum / u128::from(self.count.max(1))) as u64)
+    }
+
+    /// Returns the value below which the given fraction of samples fall.
+    ///
+    /// The quantile is clamped to be between zero and one, so `quantile(0.99)`
+    /// is the 99th percentile. The result is the upper bound of the bucket
+    /// containing the sample, but is never larger than the largest sample.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let quantile = if quantile.is_nan() {
+            0.0
+        } else {
+            quantile.clamp(0.0, 1.0)
+        };
+        let rank = ((quantile * self.count as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (index, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let value = bucket_upper_bound(index).min(self.max).max(self.min);
+                return Some(Duration::from_nanos(value));
+            }
+        }
+
+        // The ranks always add up to the count, so this shouldn't be reachable
+        self.max()
+    }
+
+    /// Clears all samples from the histogram.
+    pub fn clear(&mut self) {
+        *self = LatencyHistogram::default();
+    }
+
+    /// Converts a value to a duration if there are samples.
+    fn nonempty(&self, nanos: u64) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(Duration::from_nanos(nanos))
+        }
+    }
+}
+
+/// Converts a duration to nanoseconds, saturating on overflow.
+fn duration_to_nanos(duration: Duration) -> u64 {
+    let nanos = duration.as_nanos();
+    if nanos > u128::from(u64::MAX) {
+        u64::MAX
+    } else {
+        nanos as u64
+    }
+}
+
+/// Returns the bucket in which a sample belongs.
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < LINEAR_LIMIT {
+        return nanos as usize;
+    }
+
+    let exponent = 63 - nanos.leading_zeros();
+    let mantissa = (nanos >> (exponent - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+    let octave = u64::from(exponent - SUB_BUCKET_BITS - 1);
+    (LINEAR_LIMIT + octave * SUB_BUCKETS + mantissa) as usize
+}
+
+/// Returns the largest sample which would be placed in the given bucket.
+fn bucket_upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < LINEAR_LIMIT {
+        return index;
+    }
+
+    let octave = (index - LINEAR_LIMIT) / SUB_BUCKETS;
+    let mantissa = (index - LINEAR_LIMIT) % SUB_BUCKETS;
+    let shift = octave + 1;
+    let lower = (SUB_BUCKETS + mantissa) << shift;
+    lower.saturating_add((1 << shift) - 1)
+}
+
+/// Collects tick latencies for every node in a tree.
+///
+/// Latencies are gathered both per node, identified by their pre-order index,
+/// and per node type. A node's latency includes the time spent ticking its
+/// children.
+///
+/// The monitor reads the duration of the last tick of each node, so it should
+/// be observed after every tick of the tree - usually from the hook passed to
+/// `BehaviorTree::run`. Nodes which were not ticked since the last observation
+/// are not recorded.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::stats::LatencyMonitor;
+/// # use aspen::BehaviorTree;
+/// let mut tree = BehaviorTree::new(Repeat::with_limit(10, AlwaysSucceed::new()));
+/// let mut latencies = LatencyMonitor::new();
+/// tree.run(
+///     f64::INFINITY,
+///     &mut (),
+///     Some(|t: &BehaviorTree<()>| latencies.observe(t)),
+/// );
+///
+/// let histogram = latencies.by_type("AlwaysSucceed").unwrap();
+/// assert_eq!(histogram.count(), 10);
+/// assert!(histogram.quantile(0.99).is_some());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LatencyMonitor {
+    /// Histograms for each node type.
+    by_type: HashMap<String, LatencyHistogram>,
+
+    /// Histograms for each node, by pre-order index.
+    by_node: Vec<LatencyHistogram>,
+
+    /// The tick count of each node at the last observation.
+    tick_counts: Vec<u64>,
+
+    /// The hash of the structure of the tree seen during the last observation.
+    structure_hash: Option<u64>,
+}
+impl LatencyMonitor {
+    /// Creates a new monitor with no samples.
+    pub fn new() -> Self {
+        LatencyMonitor::default()
+    }
+
+    /// Records the latency of every node that was ticked since the last
+    /// observation.
+    ///
+    /// If the structure of the tree changed, the per-node histograms are
+    /// cleared. The per-type histograms are always kept.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        let nodes = preorder(tree.root());
+        let hash = structure_hash(tree.root());
+        if self.structure_hash != Some(hash) {
+            self.structure_hash = Some(hash);
+            self.by_node = vec![LatencyHistogram::new(); nodes.len()];
+            self.tick_counts = vec![0; nodes.len()];
+        }
+
+        for (id, node) in nodes.into_iter().enumerate() {
+            if node.tick_count() == self.tick_counts[id] {
+                continue;
+            }
+            self.tick_counts[id] = node.tick_count();
+
+            if let Some(duration) = node.last_tick_duration() {
+                self.by_node[id].record(duration);
+                // Only allocate a key the first time a type is seen
+                match self.by_type.get_mut(node.type_name()) {
+                    Some(histogram) => histogram.record(duration),
+                    None => self
+                        .by_type
+                        .entry(node.type_name().to_owned())
+                        .or_default()
+                        .record(duration),
+                }
+            }
+        }
+    }
+
+    /// Returns the histogram for all nodes of the given type.
+    pub fn by_type(&self, type_name: &str) -> Option<&LatencyHistogram> {
+        self.by_type.get(type_name)
+    }
+
+    /// Returns the histogram for the node with the given pre-order index.
+    pub fn by_node(&self, id: usize) -> Option<&LatencyHistogram> {
+        self.by_node.get(id)
+    }
+
+    /// Returns an iterator over the histograms of every node type.
+    pub fn types(&self) -> impl Iterator<Item = (&str, &LatencyHistogram)> {
+        self.by_type.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Renders the histograms in the OpenMetrics text format, as scraped by
+    /// Prometheus.
+    ///
+    /// Each histogram is exported as a summary with the median, 90th, 99th and
+    /// 100th percentiles, in seconds. The histograms of node types are
+    /// in the `aspen_node_type_latency_seconds` family, labelled with `type`,
+    /// and those of nodes which have been ticked are in
+    /// `aspen_node_latency_seconds`, labelled with the pre-order index as
+    /// `node`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::stats::LatencyMonitor;
+    /// # use aspen::BehaviorTree;
+    /// let mut tree = BehaviorTree::new(AlwaysSucceed::new());
+    /// let mut latencies = LatencyMonitor::new();
+    /// tree.tick(&mut ());
+    /// latencies.observe(&tree);
+    ///
+    /// let text = latencies.render_openmetrics();
+    /// assert!(text.contains("aspen_node_type_latency_seconds_count{type=\"AlwaysSucceed\"} 1"));
+    /// assert!(text.ends_with("# EOF\n"));
+    /// ```
+    pub fn render_openmetrics(&self) -> String {
+        let mut text = String::new();
+
+        let family = "aspen_node_type_latency_seconds";
+        push_summary_header(&mut text, family, "Tick latency of each node type.");
+        let mut types: Vec<_> = self.types().collect();
+        types.sort_unstable_by_key(|&(name, _)| name);
+        for (name, histogram) in types {
+            let label = format!("type=\"{}\"", escape_label(name));
+            push_summary(&mut text, family, &label, histogram);
+        }
+
+        let family = "aspen_node_latency_seconds";
+        push_summary_header(&mut text, family, "Tick latency of each node.");
+        for (id, histogram) in self.by_node.iter().enumerate() {
+            if histogram.count() > 0 {
+                push_summary(&mut text, family, &format!("node=\"{}\"", id), histogram);
+            }
+        }
+
+        text.push_str("# EOF\n");
+        text
+    }
+}
+
+/// The quantiles reported for each histogram by
+/// `LatencyMonitor::render_openmetrics`.
+const EXPORTED_QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 1.0];
+
+/// Writes the metadata of an OpenMetrics summary family.
+fn push_summary_header(text: &mut String, family: &str, help: &str) {
+    text.push_str(&format!("# TYPE {} summary\n", family));
+    text.push_str(&format!("# UNIT {} seconds\n", family));
+    text.push_str(&format!("# HELP {} {}\n", family, help));
+}
+
+/// Writes a histogram as the samples of an OpenMetrics summary.
+fn push_summary(text: &mut String, family: &str, label: &str, histogram: &LatencyHistogram) {
+    for &quantile in &EXPORTED_QUANTILES {
+        if let Some(value) = histogram.quantile(quantile) {
+            text.push_str(&format!(
+                "{}{{{},quantile=\"{}\"}} {}\n",
+                family,
+                label,
+                quantile,
+                value.as_secs_f64()
+            ));
+        }
+    }
+    let (sum, count) = (histogram.sum as f64 / 1e9, histogram.count());
+    text.push_str(&format!("{}_sum{{{}}} {}\n", family, label, sum));
+    text.push_str(&format!("{}_count{{{}}} {}\n", family, label, count));
+}
+
+/// Escapes a label value for the OpenMetrics text format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// A report that an activation of a node took longer than expected.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SlaViolation<'t> {
+    /// The pre-order index of the node.
+    pub node: usize,
+
+    /// The name of the node.
+    pub name: &'t str,
+
+    /// The declared maximum duration of an activation.
+    pub expected: Duration,
+
+    /// How long the activation had taken when the violation was found.
+    pub elapsed: Duration,
+
+    /// Whether the node was still running when the violation was found.
+    pub running: bool,
+}
+
+/// An activation of a node which is being timed.
+#[derive(Copy, Clone, Debug)]
+struct Activation {
+    /// When the activation started.
+    start: Instant,
+
+    /// Whether this activation has already been reported.
+    reported: bool,
+}
+
+/// Checks node activations against their expected durations.
+///
+/// Nodes declare how long they are expected to take with
+/// `Node::with_expected_duration`. Every activation of such a node that takes
+/// longer is reported to a callback and counted. An activation which is still
+/// running is reported as soon as it is found to be late, so that a stuck
+/// node is noticed before it completes. Each activation is reported at most
+/// once.
+///
+/// Activations are timed between observations, so the monitor should be
+/// driven from the hook passed to `BehaviorTree::run`.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::stats::SlaMonitor;
+/// # use aspen::BehaviorTree;
+/// # use std::time::Duration;
+/// let slow = InlineAction::new(|_| {
+///     std::thread::sleep(Duration::from_millis(2));
+///     aspen::Status::Succeeded
+/// })
+/// .with_expected_duration(Duration::from_millis(1));
+/// let mut tree = BehaviorTree::new(slow);
+///
+/// let mut late = Vec::new();
+/// let mut monitor = SlaMonitor::new(|v| late.push(v.node));
+/// tree.run(
+///     f64::INFINITY,
+///     &mut (),
+///     Some(|t: &BehaviorTree<()>| monitor.observe(t)),
+/// );
+///
+/// assert_eq!(monitor.violations(0), 1);
+/// # drop(monitor);
+/// assert_eq!(late, vec![0]);
+/// ```
+pub struct SlaMonitor<F> {
+    /// Called with every violation.
+    callback: F,
+
+    /// The activation in progress for each node, by pre-order index.
+    active: Vec<Option<Activation>>,
+
+    /// The tick count of each node at the last observation.
+    tick_counts: Vec<u64>,
+
+    /// The number of violations of each node, by pre-order index.
+    violations: Vec<u64>,
+
+    /// The durations of the completed activations of each node.
+    activations: Vec<LatencyHistogram>,
+
+    /// The hash of the structure of the tree seen during the last observation.
+    structure_hash: Option<u64>,
+}
+impl<F> SlaMonitor<F>
+where
+    F: FnMut(&SlaViolation<'_>),
+{
+    /// Creates a new monitor which reports violations to `callback`.
+    pub fn new(callback: F) -> Self {
+        SlaMonitor {
+            callback,
+            active: Vec::new(),
+            tick_counts: Vec::new(),
+            violations: Vec::new(),
+            activations: Vec::new(),
+            structure_hash: None,
+        }
+    }
+
+    /// Updates the activations of every node with an expected duration,
+    /// reporting any that have taken too long.
+    ///
+    /// If the structure of the tree changed, all of the counts are cleared.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        let now = Instant::now();
+        let nodes = preorder(tree.root());
+        let hash = structure_hash(tree.root());
+        if self.structure_hash != Some(hash) {
+            self.structure_hash = Some(hash);
+            self.active = vec![None; nodes.len()];
+            self.tick_counts = vec![0; nodes.len()];
+            self.violations = vec![0; nodes.len()];
+            self.activations = vec![LatencyHistogram::new(); nodes.len()];
+        }
+
+        for (id, node) in nodes.into_iter().enumerate() {
+            let ticked = node.tick_count() != self.tick_counts[id];
+            self.tick_counts[id] = node.tick_count();
+            let expected = match node.expected_duration() {
+                Some(expected) => expected,
+                None => continue,
+            };
+
+            let status = match node.status() {
+                Some(status) => status,
+                None => {
+                    // The node was reset before it completed
+                    self.active[id] = None;
+                    continue;
+                }
+            };
+
+            if ticked && self.active[id].is_none() {
+                // The activation started during the last tick
+                let tick = node.last_tick_duration().unwrap_or_default();
+                self.active[id] = Some(Activation {
+                    start: now.checked_sub(tick).unwrap_or(now),
+                    reported: false,
+                });
+            }
+
+            let activation = match self.active[id] {
+                Some(ref mut activation) => activation,
+                None => continue,
+            };
+            let elapsed = now.duration_since(activation.start);
+            if elapsed > expected && !activation.reported {
+                activation.reported = true;
+                self.violations[id] += 1;
+
+                let violation = SlaViolation {
+                    node: id,
+                    name: node.name(),
+                    expected,
+                    elapsed,
+                    running: !status.is_done(),
+                };
+                warn!(
+                    "Node {} ({}) took {:?}, expected at most {:?}",
+                    id, violation.name, elapsed, expected
+                );
+                (self.callback)(&violation);
+            }
+
+            if status.is_done() {
+                self.activations[id].record(elapsed);
+                self.active[id] = None;
+            }
+        }
+    }
+
+    /// Returns the number of violations of the node with the given pre-order
+    /// index.
+    pub fn violations(&self, id: usize) -> u64 {
+        self.violations.get(id).copied().unwrap_or(0)
+    }
+
+    /// Returns the total number of violations of every node.
+    pub fn total_violations(&self) -> u64 {
+        self.violations.iter().sum()
+    }
+
+    /// Returns the durations of the completed activations of the node with the
+    /// given pre-order index.
+    ///
+    /// Only nodes with an expected duration are timed.
+    pub fn activations(&self, id: usize) -> Option<&LatencyHistogram> {
+        self.activations.get(id)
+    }
+}
+
+/// How long a phase of a mission ran, and how its activations ended.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhaseStats {
+    /// The total time spent in the phase.
+    time: Duration,
+
+    /// The number of times a node of the phase was started.
+    activations: u64,
+
+    /// The number of activations which succeeded.
+    successes: u64,
+
+    /// The number of activations which failed.
+    failures: u64,
+}
+impl PhaseStats {
+    /// Returns the total time spent in the phase.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Returns the number of times a node tagged with the phase was started,
+    /// which is the number of ticks on which it was ticked without having
+    /// been running.
+    pub fn activations(&self) -> u64 {
+        self.activations
+    }
+
+    /// Returns the number of times a node tagged with the phase succeeded.
+    pub fn successes(&self) -> u64 {
+        self.successes
+    }
+
+    /// Returns the number of times a node tagged with the phase failed.
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+
+    /// Records a tick of a node of the phase, given the status it had before.
+    pub(crate) fn record(&mut self, before: Option<Status>, after: Option<Status>) {
+        if before != Some(Status::Running) {
+            self.activations += 1;
+        }
+        match after {
+            Some(Status::Succeeded) => self.successes += 1,
+            Some(Status::Failed) => self.failures += 1,
+            _ => {}
+        }
+    }
+
+    /// Adds time spent in the phase.
+    pub(crate) fn add_time(&mut self, time: Duration) {
+        self.time += time;
+    }
+}
+
+/// Rolls up how long a tree spends in each phase of its mission.
+///
+/// Subtrees are tagged with phases using `Node::in_phase`. Between two
+/// observations, a phase is counted as active if any node tagged with it was
+/// ticked, and the time between the observations is added to it. This
+/// includes the time between ticks, so the totals reflect how long the mission
+/// spent in each phase rather than how long the phase spent computing. A phase
+/// nested inside another counts towards both. Time is taken from a `Clock`,
+/// which is the system clock unless another is given.
+///
+/// The monitor should be observed after every tick of the tree - usually from
+/// the hook passed to `BehaviorTree::run`.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::stats::PhaseMonitor;
+/// # use aspen::clock::ManualClock;
+/// # use aspen::BehaviorTree;
+/// # use std::time::Duration;
+/// let mut tree = BehaviorTree::new(Sequence::new(vec![
+///     Condition::new(|&arrived: &bool| arrived).in_phase("Navigation"),
+///     AlwaysSucceed::new().in_phase("Manipulation"),
+/// ]));
+/// let clock = ManualClock::new();
+/// let mut phases = PhaseMonitor::with_clock(clock.clone());
+///
+/// phases.observe(&tree);
+/// for &arrived in &[false, true] {
+///     clock.advance(Duration::from_secs(1));
+///     tree.tick(&mut { arrived });
+///     phases.observe(&tree);
+/// }
+///
+/// let navigation = phases.phase("Navigation").unwrap();
+/// assert_eq!(navigation.time(), Duration::from_secs(2));
+/// assert_eq!(navigation.failures(), 1);
+/// assert_eq!(phases.phase("Manipulation").unwrap().successes(), 1);
+/// ```
+pub struct PhaseMonitor {
+    /// The source of the current time.
+    clock: Box<dyn Clock>,
+
+    /// The statistics of each phase.
+    phases: HashMap<String, PhaseStats>,
+
+    /// The tick count of each node at the last observation.
+    tick_counts: Vec<u64>,
+
+    /// The status of each node at the last observation.
+    statuses: Vec<Option<Status>>,
+
+    /// When the tree was last observed.
+    last: Option<Instant>,
+
+    /// The hash of the structure of the tree seen during the last observation.
+    structure_hash: Option<u64>,
+}
+impl PhaseMonitor {
+    /// Creates a new monitor using the system clock.
+    pub fn new() -> Self {
+        PhaseMonitor::with_clock(SystemClock)
+    }
+
+    /// Creates a new monitor using the given clock.
+    pub fn with_clock<C>(clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        PhaseMonitor {
+            clock: Box::new(clock),
+            phases: HashMap::new(),
+            tick_counts: Vec::new(),
+            statuses: Vec::new(),
+            last: None,
+            structure_hash: None,
+        }
+    }
+
+    /// Adds the time since the last observation to every phase which was
+    /// ticked since then.
+    ///
+    /// On the first observation, and when the structure of the tree changed,
+    /// phases which were ticked are given the duration of their last tick
+    /// instead.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        let now = self.clock.now();
+        let mut since = self.last.map(|last| now.saturating_duration_since(last));
+        self.last = Some(now);
+
+        let nodes = preorder(tree.root());
+        let hash = structure_hash(tree.root());
+        if self.structure_hash != Some(hash) {
+            self.structure_hash = Some(hash);
+            self.tick_counts = vec![0; nodes.len()];
+            self.statuses = vec![None; nodes.len()];
+            since = None;
+        }
+
+        let mut timed: Vec<&str> = Vec::new();
+        for (id, node) in nodes.into_iter().enumerate() {
+            let ticked = node.tick_count() != self.tick_counts[id];
+            self.tick_counts[id] = node.tick_count();
+            let before = std::mem::replace(&mut self.statuses[id], node.status());
+            let phase = match node.phase() {
+                Some(phase) if ticked => phase,
+                _ => continue,
+            };
+
+            // Only allocate a key the first time a phase is seen
+            let stats = match self.phases.get_mut(phase) {
+                Some(stats) => stats,
+                None => self.phases.entry(phase.to_owned()).or_default(),
+            };
+            stats.record(before, node.status());
+            if !timed.contains(&phase) {
+                timed.push(phase);
+                stats.add_time(
+                    since.unwrap_or_else(|| node.last_tick_duration().unwrap_or_default()),
+                );
+            }
+        }
+    }
+
+    /// Returns the statistics of the given phase, if it has been seen.
+    pub fn phase(&self, phase: &str) -> Option<&PhaseStats> {
+        self.phases.get(phase)
+    }
+
+    /// Returns an iterator over the statistics of every phase which has been
+    /// seen.
+    pub fn phases(&self) -> impl Iterator<Item = (&str, &PhaseStats)> {
+        self.phases.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+impl Default for PhaseMonitor {
+    fn default() -> Self {
+        PhaseMonitor::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bucket_index, bucket_upper_bound};
+    use crate::{
+        clock::ManualClock,
+        stats::{LatencyHistogram, LatencyMonitor, PhaseMonitor, SlaMonitor},
+        std_nodes::{AlwaysRunning, AlwaysSucceed, Selector, Sequence},
+        BehaviorTree,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn buckets_are_contiguous() {
+        let mut previous = None;
+        for nanos in (0..100_000).chain(vec![u64::MAX - 1, u64::MAX]) {
+            let index = bucket_index(nanos);
+            assert!(nanos <= bucket_upper_bound(index));
+            if let Some(p) = previous {
+                assert!(index == p || index == p + 1);
+            }
+            previous = Some(index);
+            if nanos == 99_999 {
+                previous = None;
+            }
+        }
+    }
+
+    #[test]
+    fn quantiles() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.quantile(0.5), None);
+
+        for micros in 1..=100 {
+            histogram.record(Duration::from_micros(micros));
+        }
+
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.min(), Some(Duration::from_micros(1)));
+        assert_eq!(histogram.max(), Some(Duration::from_micros(100)));
+        assert_eq!(histogram.quantile(1.0), Some(Duration::from_micros(100)));
+
+        let p50 = histogram.quantile(0.5).unwrap();
+        assert!(p50 >= Duration::from_micros(50));
+        assert!(p50 <= Duration::from_micros(57));
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = LatencyHistogram::new();
+        a.record(Duration::from_millis(1));
+        let mut b = LatencyHistogram::new();
+        b.record(Duration::from_millis(3));
+
+        a.merge(&b);
+        assert_eq!(a.count(), 2);
+        assert_eq!(a.mean(), Some(Duration::from_millis(2)));
+    }
+
+    #[test]
+    fn only_ticked_nodes_are_recorded() {
+        let mut tree = BehaviorTree::new(Selector::new(vec![
+            AlwaysRunning::new(),
+            AlwaysSucceed::new(),
+        ]));
+        let mut monitor = LatencyMonitor::new();
+
+        for _ in 0..3 {
+            tree.tick(&mut ());
+            monitor.observe(&tree);
+        }
+        monitor.observe(&tree);
+
+        assert_eq!(monitor.by_node(0).unwrap().count(), 3);
+        assert_eq!(monitor.by_node(1).unwrap().count(), 3);
+        assert_eq!(monitor.by_node(2).unwrap().count(), 0);
+        assert_eq!(monitor.by_type("Selector").unwrap().count(), 3);
+        assert!(monitor.by_type("AlwaysSucceed").is_none());
+    }
+
+    #[test]
+    fn structure_changes_of_the_same_size_clear_nodes() {
+        let mut monitor = LatencyMonitor::new();
+        let mut tree = BehaviorTree::new(Selector::new(vec![AlwaysSucceed::new()]));
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+
+        let mut tree = BehaviorTree::new(Sequence::new(vec![AlwaysRunning::new()]));
+        monitor.observe(&tree);
+        assert_eq!(monitor.by_node(1).unwrap().count(), 0);
+
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+        assert_eq!(monitor.by_node(1).unwrap().count(), 1);
+        assert_eq!(monitor.by_type("AlwaysSucceed").unwrap().count(), 1);
+    }
+
+    #[test]
+    fn openmetrics_export() {
+        let mut tree = BehaviorTree::new(Selector::new(vec![
+            AlwaysRunning::new(),
+            AlwaysSucceed::new(),
+        ]));
+        let mut monitor = LatencyMonitor::new();
+        tree.tick(&mut ());
+        monitor.observe(&tree);
+
+        let text = monitor.render_openmetrics();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "# TYPE aspen_node_type_latency_seconds summary");
+        assert!(lines.contains(&"aspen_node_type_latency_seconds_count{type=\"Selector\"} 1"));
+        assert!(lines.contains(&"aspen_node_latency_seconds_count{node=\"1\"} 1"));
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("aspen_node_latency_seconds{node=\"0\",quantile=\"0.99\"} ")));
+        assert!(!text.contains("node=\"2\""));
+        assert_eq!(lines.last(), Some(&"# EOF"));
+
+        assert_eq!(super::escape_label("a\"b\\c\n"), "a\\\"b\\\\c\\n");
+    }
+
+    #[test]
+    fn running_activation_reported_once() {
+        let root = AlwaysRunning::new().with_expected_duration(Duration::from_secs(0));
+        let mut tree = BehaviorTree::new(root);
+        let mut reports = Vec::new();
+        let mut monitor = SlaMonitor::new(|v| reports.push(v.running));
+
+        for _ in 0..3 {
+            tree.tick(&mut ());
+            std::thread::sleep(Duration::from_millis(1));
+            monitor.observe(&tree);
+        }
+
+        assert_eq!(monitor.total_violations(), 1);
+        drop(monitor);
+        assert_eq!(reports, vec![true]);
+    }
+
+    #[test]
+    fn fast_activations_are_not_reported() {
+        let mut tree = BehaviorTree::new(Selector::new(vec![
+            AlwaysSucceed::new().with_expected_duration(Duration::from_secs(3600)),
+            AlwaysRunning::new(),
+        ]));
+        let mut monitor = SlaMonitor::new(|_| panic!("no violation expected"));
+
+        for _ in 0..3 {
+            tree.tick(&mut ());
+            monitor.observe(&tree);
+        }
+
+        assert_eq!(monitor.total_violations(), 0);
+        assert_eq!(monitor.activations(1).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn nested_phases_count_towards_both() {
+        let mut tree = BehaviorTree::new(
+            Sequence::new(vec![
+                AlwaysSucceed::new(),
+                AlwaysRunning::new().in_phase("Recovery"),
+            ])
+            .in_phase("Mission"),
+        );
+        let clock = ManualClock::new();
+        let mut monitor = PhaseMonitor::with_clock(clock.clone());
+        monitor.observe(&tree);
+
+        for _ in 0..3 {
+            clock.advance(Duration::from_secs(2));
+            tree.tick(&mut ());
+            monitor.observe(&tree);
+        }
+
+        let mission = monitor.phase("Mission").unwrap();
+        let recovery = monitor.phase("Recovery").unwrap();
+        assert_eq!(mission.time(), Duration::from_secs(6));
+        assert_eq!(recovery.time(), Duration::from_secs(6));
+        assert_eq!(mission.activations(), 1);
+        assert_eq!(recovery.activations(), 1);
+        assert_eq!(mission.successes() + mission.failures(), 0);
+        assert_eq!(monitor.phases().count(), 2);
+    }
+}
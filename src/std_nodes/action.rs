@@ -1,11 +1,24 @@
 //! Nodes that cause the execution of tasks.
+#[cfg(feature = "action")]
+use crate::{blackboard::Blackboard, node::Progress, sched};
 use crate::{
-    node::{Node, Tickable},
+    node::{Node, TickContext, Tickable},
     status::Status,
 };
+#[cfg(feature = "action")]
 use std::{
-    sync::{mpsc, mpsc::TryRecvError, Arc},
+    any::Any,
+    borrow::Cow,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+        mpsc::{RecvTimeoutError, TryRecvError},
+        Arc, Mutex,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
 /// A node that manages the execution of tasks in a separate thread.
@@ -18,8 +31,9 @@ use std::{
 /// despite the function being run in a separate thread, there will usually
 /// only be one thread modifying the world.
 ///
-/// Note that the supplied function will be called again the next tick if the
-/// function returns either `Initialized` or `Running`.
+/// Note that by default the supplied function will be called again the next
+/// tick if the function returns either `Initialized` or `Running`. This can be
+/// changed with a `RestartPolicy`.
 ///
 /// # State
 ///
@@ -31,7 +45,8 @@ use std::{
 ///
 /// **Succeeded:** When the function returns `Succeeded`.
 ///
-/// **Failed:** When the function returns `Failed`.
+/// **Failed:** When the function returns `Failed`, or when it crashes and the
+/// `CrashPolicy` is `Fail`.
 ///
 /// # Children
 ///
@@ -65,28 +80,236 @@ use std::{
 /// assert_eq!(action.status().unwrap(), Status::Succeeded);
 /// assert_eq!(result.load(Ordering::SeqCst), 90);
 /// ```
+#[cfg(feature = "action")]
 pub struct Action<W>
 where
-    W: Clone + Send + Sync + 'static,
+    W: Clone + Send + 'static,
 {
     /// The task which is to be run.
-    func: Arc<dyn Fn(W) -> Status + Send + Sync>,
+    func: Arc<TaskFn<W>>,
+
+    /// The configuration of this node.
+    config: ActionConfig,
 
     /// Channel on which the task will communicate.
-    rx: Option<mpsc::Receiver<Status>>,
+    ///
+    /// This is only present while a task is in flight.
+    rx: Option<mpsc::Receiver<TaskMessage>>,
+
+    /// The worker thread of the last task, if it could be started.
+    ///
+    /// Threads which finish on their own are never joined, so this may be a
+    /// thread which has already exited.
+    worker: Option<thread::JoinHandle<()>>,
+
+    /// The halt signal for the task in flight.
+    halt: HaltSignal,
+
+    /// The progress last reported by the task.
+    progress: Option<Progress>,
+
+    /// The event last emitted by the task.
+    event: Option<Box<dyn Any + Send>>,
+
+    /// When the node last picked up a message from the task.
+    last_heartbeat: Option<Instant>,
+
+    /// The value returned by the last task which finished, unless it was
+    /// delivered to the world.
+    result: Option<Box<dyn Any + Send>>,
+
+    /// Delivers the values returned by the task to the world.
+    deliver: Option<Box<DeliverFn<W>>>,
+
+    /// The number of times in a row the task finished without completing.
+    restarts: u32,
+
+    /// When the task may next be restarted, if it is being held back.
+    restart_at: Option<Instant>,
+
+    /// The name given to the worker threads.
+    thread_name: String,
+
+    /// The deadline of the latest tick, shared with the task in flight.
+    deadline: Arc<Mutex<Option<Instant>>>,
+
+    /// The tick period requested by the task in flight.
+    tick_period: Option<Duration>,
 }
+#[cfg(feature = "action")]
 impl<W> Action<W>
 where
-    W: Clone + Send + Sync + 'static,
+    W: Clone + Send + 'static,
 {
     /// Creates a new Action node that will execute the given task.
     pub fn new<F>(task: F) -> Node<'static, W>
     where
         F: Fn(W) -> Status + Send + Sync + 'static,
+    {
+        Action::with_config(ActionConfig::default(), move |world, _: &HaltSignal| {
+            task(world)
+        })
+    }
+
+    /// Creates a new Action node with the given configuration.
+    ///
+    /// The task is given a `HaltSignal` which is raised when the node is reset
+    /// or dropped while the task is still running. Long-running tasks should
+    /// check it periodically and return early when it is raised.
+    pub fn with_config<F>(config: ActionConfig, task: F) -> Node<'static, W>
+    where
+        F: Fn(W, &HaltSignal) -> Status + Send + Sync + 'static,
+    {
+        Action::with_context(config, move |world, ctx: &TaskContext| {
+            task(world, ctx.halt_signal())
+        })
+    }
+
+    /// Creates a new Action node whose task is given a `TaskContext`.
+    ///
+    /// As well as the halt signal, the context lets the task report its
+    /// progress back to the node while it runs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::Status;
+    /// # use aspen::node::Tickable;
+    /// let mut action = Action::with_context(ActionConfig::new(), |_: (), ctx: &TaskContext| {
+    ///     for step in 0..4 {
+    ///         ctx.report_progress(step as f32 / 4.0, "Docking");
+    ///     }
+    ///     Status::Succeeded
+    /// });
+    ///
+    /// while action.tick(&mut ()) == Status::Running {}
+    /// assert_eq!(action.progress().unwrap().to_string(), "Docking: 75%");
+    /// ```
+    pub fn with_context<F>(config: ActionConfig, task: F) -> Node<'static, W>
+    where
+        F: Fn(W, &TaskContext) -> Status + Send + Sync + 'static,
+    {
+        Action::build(config, move |world, ctx| (task(world, ctx), None), None)
+    }
+
+    /// Creates a new Action node whose task returns a value as well as a
+    /// status.
+    ///
+    /// The value from the last task which finished is kept by the node, even
+    /// when it is reset, until it is taken with `Node::take_result` or the
+    /// task is started again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::Status;
+    /// # use aspen::node::Tickable;
+    /// let mut plan = Action::with_result(ActionConfig::new(), |goal: u32, _: &TaskContext| {
+    ///     (Status::Succeeded, (0..=goal).collect::<Vec<_>>())
+    /// });
+    ///
+    /// while plan.tick(&mut 3) == Status::Running {}
+    /// assert_eq!(plan.take_result::<Vec<u32>>(), Some(vec![0, 1, 2, 3]));
+    /// assert_eq!(plan.take_result::<Vec<u32>>(), None);
+    /// ```
+    pub fn with_result<F, T>(config: ActionConfig, task: F) -> Node<'static, W>
+    where
+        F: Fn(W, &TaskContext) -> (Status, T) + Send + Sync + 'static,
+        T: Any + Send,
+    {
+        Action::build(config, boxed_result(task), None)
+    }
+
+    /// Creates a new Action node whose task returns a value which is written
+    /// to the blackboard.
+    ///
+    /// The value is written to the entry with the given key on the tick which
+    /// sees the task finish, so the nodes after this one can use it straight
+    /// away.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::Status;
+    /// # use aspen::node::Tickable;
+    /// # use aspen::blackboard::Blackboard;
+    /// struct World {
+    ///     goal: u32,
+    ///     blackboard: Blackboard,
+    /// }
+    ///
+    /// // The task only needs the goal, so the copy it is given has an empty
+    /// // blackboard
+    /// impl Clone for World {
+    ///     fn clone(&self) -> Self {
+    ///         World {
+    ///             goal: self.goal,
+    ///             blackboard: Blackboard::new(),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// impl AsMut<Blackboard> for World {
+    ///     fn as_mut(&mut self) -> &mut Blackboard {
+    ///         &mut self.blackboard
+    ///     }
+    /// }
+    ///
+    /// let mut plan =
+    ///     Action::with_result_to(ActionConfig::new(), "path", |w: World, _: &TaskContext| {
+    ///         (Status::Succeeded, (0..=w.goal).collect::<Vec<_>>())
+    ///     });
+    ///
+    /// let mut world = World {
+    ///     goal: 2,
+    ///     blackboard: Blackboard::new(),
+    /// };
+    /// while plan.tick(&mut world) == Status::Running {}
+    /// assert_eq!(
+    ///     world.blackboard.get::<Vec<u32>>("path"),
+    ///     Some(&vec![0, 1, 2])
+    /// );
+    /// ```
+    pub fn with_result_to<F, T, K>(config: ActionConfig, key: K, task: F) -> Node<'static, W>
+    where
+        W: AsMut<Blackboard>,
+        F: Fn(W, &TaskContext) -> (Status, T) + Send + Sync + 'static,
+        T: Any + Send,
+        K: Into<String>,
+    {
+        let key = key.into();
+        let deliver = move |world: &mut W, value| world.as_mut().set_boxed(key.clone(), value);
+        Action::build(config, boxed_result(task), Some(Box::new(deliver)))
+    }
+
+    /// Creates a new Action node from its parts.
+    fn build<F>(
+        config: ActionConfig,
+        task: F,
+        deliver: Option<Box<DeliverFn<W>>>,
+    ) -> Node<'static, W>
+    where
+        F: Fn(W, &TaskContext) -> (Status, TaskValue) + Send + Sync + 'static,
     {
         let internals = Action {
             func: Arc::new(task),
+            config,
             rx: None,
+            worker: None,
+            halt: HaltSignal::new(),
+            progress: None,
+            event: None,
+            last_heartbeat: None,
+            result: None,
+            deliver,
+            restarts: 0,
+            restart_at: None,
+            thread_name: thread_name("Action"),
+            deadline: Arc::new(Mutex::new(None)),
+            tick_period: None,
         };
 
         Node::new(internals)
@@ -95,22 +318,170 @@ where
     /// Launches a new worker thread to run the task.
     fn start_thread(&mut self, world: &W) {
         // Create our new channels
-        let (tx, rx) = mpsc::sync_channel(0);
+        let (tx, rx) = mpsc::channel();
 
         // Then clone the function so we can move it
         let func_clone = self.func.clone();
 
-        // Finally, boot up the thread
+        // Every run gets its own signal, so that halting an abandoned run does
+        // not affect the next one
+        self.halt = HaltSignal::new();
+        let ctx = TaskContext {
+            halt: self.halt.clone(),
+            deadline: self.deadline.clone(),
+            tx,
+        };
+
+        // Finally, boot up the thread. If the node has been dropped, there is
+        // nobody to send the result to. If the thread cannot be started, the
+        // context is dropped and the next tick sees the task crash
         let world_clone = world.clone();
-        thread::spawn(move || tx.send((func_clone)(world_clone)).unwrap());
+        let nice = self.config.nice;
+        let affinity = self.config.affinity.clone();
+        let spawned = thread::Builder::new()
+            .name(self.thread_name.clone())
+            .spawn(move || {
+                if let Some(nice) = nice {
+                    if let Err(e) = sched::set_nice(nice) {
+                        warn!("Failed to set the priority of an action thread: {}", e);
+                    }
+                }
+                if let Some(cores) = affinity {
+                    if let Err(e) = sched::set_affinity(&cores) {
+                        warn!("Failed to set the affinity of an action thread: {}", e);
+                    }
+                }
+
+                let message =
+                    match panic::catch_unwind(AssertUnwindSafe(|| (func_clone)(world_clone, &ctx)))
+                    {
+                        Ok((status, value)) => TaskMessage::Done(status, value),
+                        Err(payload) => TaskMessage::Crashed(panic_message(&*payload)),
+                    };
+                let _ = ctx.tx.send(message);
+            });
+        self.worker = match spawned {
+            Ok(worker) => Some(worker),
+            Err(e) => {
+                error!("Failed to start action thread: {}", e);
+                None
+            }
+        };
 
         // Store the rx for later use
         self.rx = Some(rx);
+        self.progress = None;
+        self.event = None;
+        self.last_heartbeat = None;
+        self.result = None;
+        self.tick_period = None;
+    }
+
+    /// Starts the task or catches up on what it has sent.
+    fn poll(&mut self, world: &mut W) -> Status {
+        let rx = match self.rx {
+            Some(ref rx) => rx,
+            None => {
+                if let Some(at) = self.restart_at {
+                    if Instant::now() < at {
+                        return Status::Running;
+                    }
+                    self.restart_at = None;
+                }
+                self.start_thread(world);
+                return Status::Running;
+            }
+        };
+
+        // Catch up on everything the task has sent since the last tick
+        loop {
+            let message = rx.try_recv();
+            if message.is_ok() {
+                self.last_heartbeat = Some(Instant::now());
+            }
+            match message {
+                Ok(TaskMessage::Heartbeat) => {}
+                Ok(TaskMessage::Progress(p)) => self.progress = Some(p),
+                Ok(TaskMessage::Event(e)) => self.event = Some(e),
+                Ok(TaskMessage::TickPeriod(p)) => self.tick_period = Some(p),
+                Ok(TaskMessage::Done(s, value)) => {
+                    self.rx = None;
+                    match (value, &mut self.deliver) {
+                        (Some(value), Some(deliver)) => deliver(world, value),
+                        (value, _) => self.result = value,
+                    }
+                    return self.finished(s);
+                }
+                Ok(TaskMessage::Crashed(message)) => {
+                    self.rx = None;
+                    return self.crashed(&message);
+                }
+                Err(TryRecvError::Empty) => return Status::Running,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    return self.crashed("the worker thread went away");
+                }
+            }
+        }
+    }
+
+    /// Waits for the worker thread to exit, once its task has returned.
+    fn join_worker(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                warn!("Action thread panicked after its task returned");
+            }
+        }
+    }
+
+    /// Applies the restart policy to the status the task finished with.
+    fn finished(&mut self, status: Status) -> Status {
+        if status.is_done() {
+            self.restarts = 0;
+            return status;
+        }
+
+        self.restarts += 1;
+        match self.config.restart_policy {
+            RestartPolicy::EachTick => {}
+            RestartPolicy::AfterDelay(delay) => self.restart_at = Some(Instant::now() + delay),
+            RestartPolicy::Never => {
+                warn!(
+                    "Action task returned {:?} and will not be restarted",
+                    status
+                );
+                self.restarts = 0;
+                return Status::Failed;
+            }
+        }
+
+        debug!(
+            "Action task returned {:?}, restarting it ({} in a row)",
+            status, self.restarts
+        );
+        if let Some(ref callback) = self.config.on_restart {
+            callback(self.restarts);
+        }
+        status
+    }
+
+    /// Reports that the task crashed and applies the crash policy.
+    fn crashed(&self, reason: &str) -> Status {
+        error!("Action task crashed: {}", reason);
+        if let Some(ref callback) = self.config.on_crash {
+            callback(reason);
+        }
+
+        match self.config.crash_policy {
+            CrashPolicy::Fail => Status::Failed,
+            CrashPolicy::Panic => panic!("Action task crashed: {}", reason),
+        }
     }
 }
+#[cfg(feature = "action")]
 impl<W> Tickable<W> for Action<W>
 where
-    W: Clone + Send + Sync + 'static,
+    W: Clone + Send + 'static,
 {
     /// Ticks the Action node a single time.
     ///
@@ -119,38 +490,61 @@ where
     /// which will be run in a separate thread. Usually, this should be an
     /// `Arc`.
     fn tick(&mut self, world: &mut W) -> Status {
-        let (status, reset) = if let Some(ref mut rx) = self.rx {
-            match rx.try_recv() {
-                Ok(Status::Running) => (Status::Running, true),
-                Ok(s) => (s, false),
-                Err(TryRecvError::Empty) => (Status::Running, false),
-                Err(e) => panic!("Thread died before finishing {}", e),
-            }
-        } else {
-            self.start_thread(world);
-            (Status::Running, false)
-        };
-
-        if reset {
-            self.rx.take();
+        let ctx = TickContext::current();
+        *self.deadline.lock().unwrap() = ctx.deadline();
+        let status = self.poll(world);
+        if let (Status::Running, Some(period)) = (status, self.tick_period) {
+            ctx.request_tick_period(period);
         }
-
         status
     }
 
     /// Resets the internal state of this node.
     ///
-    /// If there is a task currently running, this will block until the task is
-    /// completed.
+    /// If there is a task currently running, this will raise its halt signal
+    /// and block until the task is completed.
     fn reset(&mut self) {
         // I debated what to do here for a while. I could see someone wanting to detach
         // the thread due to time constraints, but it seems to me that it would be
         // better to avoid potential bugs that come from a node only looking
         // like its been fully reset.
-        if let Some(ref mut rx) = self.rx {
-            rx.recv().unwrap();
+        if let Some(rx) = self.rx.take() {
+            self.halt.raise();
+            if let Wait::Crashed(message) = wait_for_task(&rx, None) {
+                self.crashed(&message);
+            }
+            self.join_worker();
         }
-        self.rx = None;
+        self.progress = None;
+        self.event = None;
+        self.last_heartbeat = None;
+        self.restarts = 0;
+        self.restart_at = None;
+    }
+
+    /// Names the worker threads after the node.
+    fn on_rename(&mut self, name: Option<&str>) {
+        self.thread_name = thread_name(name.unwrap_or("Action"));
+    }
+
+    fn progress(&self) -> Option<&Progress> {
+        self.progress.as_ref()
+    }
+
+    fn latest_event(&self) -> Option<&(dyn Any + Send)> {
+        self.event.as_deref()
+    }
+
+    fn last_heartbeat(&self) -> Option<Instant> {
+        self.last_heartbeat
+    }
+
+    fn result(&self) -> Option<&(dyn Any + Send)> {
+        self.result.as_deref()
+    }
+
+    fn take_result(&mut self) -> Option<Box<dyn Any + Send>> {
+        self.result.take()
     }
 
     /// Returns the constant string "Action"
@@ -158,9 +552,479 @@ where
         "Action"
     }
 }
+#[cfg(feature = "action")]
+impl<W> Drop for Action<W>
+where
+    W: Clone + Send + 'static,
+{
+    /// Raises the halt signal of a running task, then applies the configured
+    /// `DropPolicy`.
+    fn drop(&mut self) {
+        let rx = match self.rx.take() {
+            Some(rx) => rx,
+            None => return,
+        };
+
+        self.halt.raise();
+        match self.config.drop_policy {
+            DropPolicy::Abandon => {
+                warn!("Abandoning running action thread");
+            }
+            DropPolicy::Join => {
+                debug!("Waiting for running action thread");
+                if let Wait::Crashed(message) = wait_for_task(&rx, None) {
+                    warn!("Action task crashed while being dropped: {}", message);
+                }
+                self.join_worker();
+            }
+            DropPolicy::JoinTimeout(timeout) => {
+                debug!("Waiting up to {:?} for running action thread", timeout);
+                match wait_for_task(&rx, Some(Instant::now() + timeout)) {
+                    Wait::Done => self.join_worker(),
+                    Wait::Crashed(message) => {
+                        warn!("Action task crashed while being dropped: {}", message);
+                        self.join_worker();
+                    }
+                    Wait::TimedOut => warn!(
+                        "Action thread did not finish within {:?}, abandoning it",
+                        timeout
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// How waiting for a task ended.
+#[cfg(feature = "action")]
+enum Wait {
+    /// The task finished.
+    Done,
+
+    /// The task crashed, for the given reason.
+    Crashed(String),
+
+    /// The deadline passed first.
+    TimedOut,
+}
+
+/// Waits until a task has finished, discarding anything else it sends.
+#[cfg(feature = "action")]
+fn wait_for_task(rx: &mpsc::Receiver<TaskMessage>, deadline: Option<Instant>) -> Wait {
+    loop {
+        let message = match deadline {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(timeout) {
+                    Ok(m) => m,
+                    Err(RecvTimeoutError::Timeout) => return Wait::TimedOut,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            None => match rx.recv() {
+                Ok(m) => m,
+                Err(_) => break,
+            },
+        };
+        match message {
+            TaskMessage::Done(..) => return Wait::Done,
+            TaskMessage::Crashed(message) => return Wait::Crashed(message),
+            _ => {}
+        }
+    }
+
+    Wait::Crashed("the worker thread went away".to_owned())
+}
+
+/// Returns the name of the worker threads of a node with the given name.
+#[cfg(feature = "action")]
+fn thread_name(node: &str) -> String {
+    format!("bt-action:{}", node)
+}
+
+/// Returns the message carried by a panic, if it has one.
+#[cfg(feature = "action")]
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "the task panicked".to_owned()
+    }
+}
+
+/// Function run by an `Action` in its worker thread.
+#[cfg(feature = "action")]
+type TaskFn<W> = dyn Fn(W, &TaskContext) -> (Status, TaskValue) + Send + Sync;
+
+/// The value returned by a task, if it returns one.
+#[cfg(feature = "action")]
+type TaskValue = Option<Box<dyn Any + Send>>;
+
+/// Function which delivers the value returned by a task to the world.
+#[cfg(feature = "action")]
+type DeliverFn<W> = dyn FnMut(&mut W, Box<dyn Any + Send>);
+
+/// Wraps a task returning a value into one returning a boxed value.
+#[cfg(feature = "action")]
+fn boxed_result<W, F, T>(task: F) -> impl Fn(W, &TaskContext) -> (Status, TaskValue)
+where
+    F: Fn(W, &TaskContext) -> (Status, T),
+    T: Any + Send,
+{
+    move |world, ctx| {
+        let (status, value) = task(world, ctx);
+        (status, Some(Box::new(value) as Box<dyn Any + Send>))
+    }
+}
+
+/// A message sent by a running task to its `Action`.
+#[cfg(feature = "action")]
+enum TaskMessage {
+    /// The task is still alive.
+    Heartbeat,
+
+    /// The task made progress.
+    Progress(Progress),
+
+    /// The task emitted an event.
+    Event(Box<dyn Any + Send>),
+
+    /// The task asked to be ticked at most once per period.
+    TickPeriod(Duration),
+
+    /// The task finished with the given status and value.
+    Done(Status, TaskValue),
+
+    /// The task panicked with the given message.
+    Crashed(String),
+}
+
+/// The connection between a running `Action` task and its node.
+#[cfg(feature = "action")]
+pub struct TaskContext {
+    /// The signal raised when the node wants the task to stop.
+    halt: HaltSignal,
+
+    /// The deadline of the latest tick of the node.
+    deadline: Arc<Mutex<Option<Instant>>>,
+
+    /// Channel to the node.
+    tx: mpsc::Sender<TaskMessage>,
+}
+#[cfg(feature = "action")]
+impl TaskContext {
+    /// Returns `true` if the task has been asked to stop.
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_raised()
+    }
+
+    /// Returns the signal which is raised when the task is asked to stop.
+    pub fn halt_signal(&self) -> &HaltSignal {
+        &self.halt
+    }
+
+    /// Reports how far through its work the task is.
+    ///
+    /// The node picks up the latest report on its next tick, and makes it
+    /// available through `Node::progress`.
+    pub fn report_progress<M: Into<Cow<'static, str>>>(&self, fraction: f32, message: M) {
+        // If the node has gone away, nobody is interested any more
+        let _ = self
+            .tx
+            .send(TaskMessage::Progress(Progress::new(fraction, message)));
+    }
+
+    /// Returns the deadline of the latest tick of the node, if the tree was
+    /// given one.
+    ///
+    /// Tasks which work in steps can use this to hand back control at the
+    /// pace of the tree.
+    pub fn tick_deadline(&self) -> Option<Instant> {
+        *self.deadline.lock().unwrap()
+    }
+
+    /// Asks for the node to be ticked at most once per `period` while the task
+    /// runs.
+    ///
+    /// This lowers the tick rate of the node's branch, as described for
+    /// `TickContext::request_tick_period`, which is useful for slow tasks
+    /// that would otherwise be polled needlessly. The request lasts until the
+    /// task finishes, and its completion is noticed up to `period` late.
+    pub fn request_tick_period(&self, period: Duration) {
+        let _ = self.tx.send(TaskMessage::TickPeriod(period));
+    }
+
+    /// Tells the node that the task is still alive.
+    ///
+    /// Every message from the task counts as a heartbeat, so this is only
+    /// needed by tasks which go a long time without reporting anything else.
+    /// The node records when it last heard from the task, which is available
+    /// through `Node::last_heartbeat`.
+    pub fn heartbeat(&self) {
+        let _ = self.tx.send(TaskMessage::Heartbeat);
+    }
+
+    /// Emits an event from the task, such as a waypoint being reached.
+    ///
+    /// The node picks up the latest event on its next tick, and makes it
+    /// available through `Node::latest_event`. Events which are superseded
+    /// before the node is ticked are not seen by the tree.
+    pub fn emit<T: Any + Send>(&self, event: T) {
+        let _ = self.tx.send(TaskMessage::Event(Box::new(event)));
+    }
+}
+#[cfg(feature = "action")]
+impl fmt::Debug for TaskContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskContext")
+            .field("halt", &self.halt)
+            .finish()
+    }
+}
+
+/// A flag used to ask a running `Action` task to stop early.
+#[cfg(feature = "action")]
+#[derive(Clone, Debug, Default)]
+pub struct HaltSignal {
+    /// Whether or not the signal has been raised.
+    raised: Arc<AtomicBool>,
+}
+#[cfg(feature = "action")]
+impl HaltSignal {
+    /// Creates a new signal which has not been raised.
+    pub fn new() -> Self {
+        HaltSignal::default()
+    }
+
+    /// Returns `true` if the task has been asked to stop.
+    pub fn is_raised(&self) -> bool {
+        self.raised.load(Ordering::SeqCst)
+    }
+
+    /// Asks the task to stop.
+    pub fn raise(&self) {
+        self.raised.store(true, Ordering::SeqCst);
+    }
+}
+
+/// What an `Action` does when it is dropped while its task is still running.
+///
+/// In every case, the task's `HaltSignal` is raised first.
+#[cfg(feature = "action")]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DropPolicy {
+    /// Leave the task running in the background and log a warning.
+    Abandon,
+
+    /// Block until the task has returned and its thread has exited.
+    Join,
+
+    /// Block until the task has returned and its thread has exited, or the
+    /// timeout has elapsed. If the timeout elapses, the task is abandoned and a
+    /// warning is logged.
+    JoinTimeout(Duration),
+}
+#[cfg(feature = "action")]
+impl Default for DropPolicy {
+    fn default() -> Self {
+        DropPolicy::Abandon
+    }
+}
+
+/// Configuration for an `Action` node.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use std::time::Duration;
+/// let config = ActionConfig::new().drop_policy(DropPolicy::JoinTimeout(Duration::from_secs(1)));
+/// let action = Action::with_config(config, |_: (), halt: &HaltSignal| {
+///     while !halt.is_raised() {
+///         // Do some work
+/// #       break;
+///     }
+///     Status::Succeeded
+/// });
+/// ```
+#[cfg(feature = "action")]
+#[derive(Clone, Default)]
+pub struct ActionConfig {
+    /// What to do with a running task when the node is dropped.
+    drop_policy: DropPolicy,
+
+    /// What to do when a task crashes.
+    crash_policy: CrashPolicy,
+
+    /// Called with the reason whenever a task crashes.
+    on_crash: Option<Arc<CrashFn>>,
+
+    /// What to do when a task finishes without completing.
+    restart_policy: RestartPolicy,
+
+    /// Called with the number of restarts in a row whenever a task is to be
+    /// restarted.
+    on_restart: Option<Arc<RestartFn>>,
+
+    /// The nice value of the worker threads.
+    nice: Option<i32>,
+
+    /// The CPU cores the worker threads may run on.
+    affinity: Option<Vec<usize>>,
+}
+#[cfg(feature = "action")]
+impl ActionConfig {
+    /// Creates the default configuration.
+    pub fn new() -> Self {
+        ActionConfig::default()
+    }
+
+    /// Sets what to do with a running task when the node is dropped.
+    ///
+    /// The default is `DropPolicy::Abandon`.
+    pub fn drop_policy(mut self, policy: DropPolicy) -> Self {
+        self.drop_policy = policy;
+        self
+    }
+
+    /// Sets what to do when a task crashes.
+    ///
+    /// The default is `CrashPolicy::Fail`.
+    pub fn crash_policy(mut self, policy: CrashPolicy) -> Self {
+        self.crash_policy = policy;
+        self
+    }
+
+    /// Sets a function to be called with the reason whenever a task crashes.
+    ///
+    /// The function is called on the thread ticking the node, before the
+    /// crash policy is applied.
+    pub fn on_crash<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_crash = Some(Arc::new(callback));
+        self
+    }
+
+    /// Sets what to do when a task finishes with `Running` or `Initialized`.
+    ///
+    /// The default is `RestartPolicy::EachTick`.
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Sets a function to be called whenever a task is to be restarted.
+    ///
+    /// The function is given the number of times in a row the task has
+    /// finished without completing, which makes restart loops easy to spot.
+    pub fn on_restart<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(u32) + Send + Sync + 'static,
+    {
+        self.on_restart = Some(Arc::new(callback));
+        self
+    }
+
+    /// Sets the nice value of the worker threads.
+    ///
+    /// Lower values are scheduled more favorably, and lowering the value below
+    /// zero usually needs elevated privileges. If the value cannot be set, a
+    /// warning is logged and the task runs anyway. This is only supported on
+    /// Linux.
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    /// Restricts the worker threads to the CPU cores with the given indices.
+    ///
+    /// This keeps tasks off the cores reserved for control loops. If the
+    /// affinity cannot be set, a warning is logged and the task runs anyway.
+    /// This is only supported on Linux.
+    pub fn affinity<I: IntoIterator<Item = usize>>(mut self, cores: I) -> Self {
+        self.affinity = Some(cores.into_iter().collect());
+        self
+    }
+}
+#[cfg(feature = "action")]
+impl fmt::Debug for ActionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActionConfig")
+            .field("drop_policy", &self.drop_policy)
+            .field("crash_policy", &self.crash_policy)
+            .field("on_crash", &self.on_crash.is_some())
+            .field("restart_policy", &self.restart_policy)
+            .field("on_restart", &self.on_restart.is_some())
+            .field("nice", &self.nice)
+            .field("affinity", &self.affinity)
+            .finish()
+    }
+}
+
+/// Function called when an `Action` task crashes.
+#[cfg(feature = "action")]
+type CrashFn = dyn Fn(&str) + Send + Sync;
+
+/// Function called when an `Action` task is to be restarted.
+#[cfg(feature = "action")]
+type RestartFn = dyn Fn(u32) + Send + Sync;
+
+/// What an `Action` does when its task finishes with `Running` or
+/// `Initialized`.
+///
+/// Apart from with `Never`, the node returns the status of the task. Every
+/// restart is logged at the debug level and reported to the `on_restart`
+/// function of the `ActionConfig`, if any.
+#[cfg(feature = "action")]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RestartPolicy {
+    /// Start the task again on the next tick.
+    EachTick,
+
+    /// Start the task again on the first tick after the delay has elapsed.
+    AfterDelay(Duration),
+
+    /// Fail the node instead, with a warning.
+    Never,
+}
+#[cfg(feature = "action")]
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy::EachTick
+    }
+}
+
+/// What an `Action` does when its task crashes.
+///
+/// A task crashes when it panics, or its thread otherwise goes away without
+/// finishing. Either way, an error is logged and the `on_crash` function of
+/// the `ActionConfig`, if any, is called first.
+#[cfg(feature = "action")]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CrashPolicy {
+    /// Fail the node. The task will be started again on the next tick.
+    Fail,
+
+    /// Panic on the thread ticking the node.
+    Panic,
+}
+#[cfg(feature = "action")]
+impl Default for CrashPolicy {
+    fn default() -> Self {
+        CrashPolicy::Fail
+    }
+}
 
 /// Convenience macro for creating Action nodes.
 ///
+/// An optional string literal before the function is used as the name of
+/// the node.
+///
 /// # Examples
 ///
 /// ```
@@ -168,10 +1032,15 @@ where
 /// # fn foo(_: ()) -> aspen::Status { aspen::Status::Succeeded }
 /// # fn main() {
 /// let mut action = Action! { |s| foo(s) };
+/// let mut named = Action! { "Foo", |s| foo(s) };
 /// # }
 /// ```
+#[cfg(feature = "action")]
 #[macro_export]
 macro_rules! Action {
+    ( $name:literal, $e:expr ) => {
+        $crate::std_nodes::Action::new($e).named(Some($name))
+    };
     ( $e:expr ) => {
         $crate::std_nodes::Action::new($e)
     };
@@ -243,6 +1112,42 @@ where
 
         Node::new(internals)
     }
+
+    /// Creates a new `InlineAction` node whose task is given the context of
+    /// the tick.
+    ///
+    /// This lets a task bound its work by the deadline of the tick, or ask to
+    /// be ticked less often.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::Status;
+    /// # use aspen::node::{TickContext, Tickable};
+    /// # use std::time::{Duration, Instant};
+    /// let mut scan = InlineAction::with_context(|cells: &mut Vec<u32>, ctx: &TickContext| {
+    ///     let stop = ctx
+    ///         .deadline()
+    ///         .unwrap_or_else(|| Instant::now() + Duration::from_millis(1));
+    ///     while let Some(cell) = cells.pop() {
+    ///         // Check the cell
+    ///         if Instant::now() >= stop {
+    ///             return Status::Running;
+    ///         }
+    /// #       let _ = cell;
+    ///     }
+    ///     Status::Succeeded
+    /// });
+    ///
+    /// assert_eq!(scan.tick(&mut vec![1, 2, 3]), Status::Succeeded);
+    /// ```
+    pub fn with_context<F>(mut task: F) -> Node<'a, W>
+    where
+        F: FnMut(&mut W, &TickContext) -> Status + 'a,
+    {
+        InlineAction::new(move |world: &mut W| task(world, &TickContext::current()))
+    }
 }
 impl<'a, W> Tickable<W> for InlineAction<'a, W> {
     fn tick(&mut self, world: &mut W) -> Status {
@@ -261,6 +1166,9 @@ impl<'a, W> Tickable<W> for InlineAction<'a, W> {
 
 /// Convenience macro for creating [`InlineAction`] nodes.
 ///
+/// An optional string literal before the function is used as the name of
+/// the node.
+///
 /// # Examples
 ///
 /// ```
@@ -269,10 +1177,14 @@ impl<'a, W> Tickable<W> for InlineAction<'a, W> {
 /// # fn foo(_: &mut ()) -> Status { Status::Running }
 /// # fn main() {
 /// let mut action = InlineAction! { |s| foo(s) };
+/// let mut named = InlineAction! { "Foo", |s| foo(s) };
 /// # }
 /// ```
 #[macro_export]
 macro_rules! InlineAction {
+    ( $name:literal, $e:expr ) => {
+        $crate::std_nodes::InlineAction::new($e).named(Some($name))
+    };
     ( $e:expr ) => {
         $crate::std_nodes::InlineAction::new($e)
     };
@@ -280,16 +1192,21 @@ macro_rules! InlineAction {
 
 #[cfg(test)]
 mod test {
-    use crate::{
-        node::Tickable,
-        status::Status,
-        std_nodes::{Action, InlineAction},
+    #[cfg(feature = "action")]
+    use crate::std_nodes::{
+        Action, ActionConfig, CrashPolicy, DropPolicy, HaltSignal, RestartPolicy, TaskContext,
     };
+    use crate::{node::Tickable, status::Status, std_nodes::InlineAction};
+    #[cfg(feature = "action")]
     use std::{
-        sync::{mpsc, Mutex},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc, Mutex,
+        },
         thread, time,
     };
 
+    #[cfg(feature = "action")]
     #[test]
     fn failure() {
         let (tx, rx) = mpsc::sync_channel(0);
@@ -315,6 +1232,7 @@ mod test {
         assert_eq!(status, Status::Failed);
     }
 
+    #[cfg(feature = "action")]
     #[test]
     fn success() {
         let (tx, rx) = mpsc::sync_channel(0);
@@ -340,6 +1258,214 @@ mod test {
         assert_eq!(status, Status::Succeeded);
     }
 
+    #[cfg(feature = "action")]
+    #[test]
+    fn reset_after_completion() {
+        let mut action = Action::new(|_| Status::Succeeded);
+        while action.tick(&mut ()) == Status::Running {}
+        action.reset();
+        while action.tick(&mut ()) == Status::Running {}
+        assert_eq!(action.status(), Some(Status::Succeeded));
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn reset_raises_halt() {
+        let mut action = Action::with_config(ActionConfig::new(), |_, halt: &HaltSignal| {
+            while !halt.is_raised() {
+                thread::sleep(time::Duration::from_millis(1));
+            }
+            Status::Failed
+        });
+        assert_eq!(action.tick(&mut ()), Status::Running);
+        action.reset();
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn drop_joins() {
+        let finished = Arc::new(AtomicBool::new(false));
+        let task_finished = finished.clone();
+        let config = ActionConfig::new().drop_policy(DropPolicy::Join);
+        let mut action = Action::with_config(config, move |_, halt: &HaltSignal| {
+            while !halt.is_raised() {
+                thread::sleep(time::Duration::from_millis(1));
+            }
+            task_finished.store(true, Ordering::SeqCst);
+            Status::Failed
+        });
+
+        assert_eq!(action.tick(&mut ()), Status::Running);
+        drop(action);
+        assert!(finished.load(Ordering::SeqCst));
+        // The worker thread held the last other reference, through the task,
+        // until it exited
+        assert_eq!(Arc::strong_count(&finished), 1);
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn drop_times_out() {
+        let (tx, rx) = mpsc::channel::<()>();
+        let mrx = Mutex::new(rx);
+        let finished = Arc::new(AtomicBool::new(false));
+        let task_finished = finished.clone();
+        let config = ActionConfig::new()
+            .drop_policy(DropPolicy::JoinTimeout(time::Duration::from_millis(10)));
+        let mut action = Action::with_config(config, move |_, _: &HaltSignal| {
+            // Ignore the halt signal entirely
+            let _ = mrx.lock().unwrap().recv();
+            task_finished.store(true, Ordering::SeqCst);
+            Status::Succeeded
+        });
+
+        assert_eq!(action.tick(&mut ()), Status::Running);
+        let start = time::Instant::now();
+        drop(action);
+        assert!(start.elapsed() < time::Duration::from_secs(1));
+        assert!(!finished.load(Ordering::SeqCst));
+        drop(tx);
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn result_survives_reset_until_restart() {
+        let mut action = Action::with_result(ActionConfig::new(), |n: u32, _: &TaskContext| {
+            (Status::Succeeded, n * 2)
+        });
+
+        while action.tick(&mut 4) == Status::Running {}
+        action.reset();
+        assert_eq!(action.take_result::<String>(), None);
+        assert_eq!(action.take_result::<u32>(), Some(8));
+
+        while action.tick(&mut 5) == Status::Running {}
+        action.reset();
+        assert_eq!(action.tick(&mut 6), Status::Running);
+        assert_eq!(action.take_result::<u32>(), None);
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn latest_event_is_exposed() {
+        let (tx, rx) = mpsc::sync_channel::<()>(0);
+        let mrx = Mutex::new(rx);
+
+        let mut action =
+            Action::with_context(ActionConfig::new(), move |_: (), ctx: &TaskContext| {
+                for waypoint in 1..=3u32 {
+                    ctx.emit(waypoint);
+                    mrx.lock().unwrap().recv().unwrap();
+                }
+                Status::Succeeded
+            });
+
+        assert_eq!(action.tick(&mut ()), Status::Running);
+        assert_eq!(action.last_heartbeat(), None);
+        for waypoint in 1..=3u32 {
+            while action.latest_event::<u32>() != Some(&waypoint) {
+                assert_eq!(action.tick(&mut ()), Status::Running);
+            }
+            assert!(action.last_heartbeat().is_some());
+            assert_eq!(action.latest_event::<String>(), None);
+            tx.send(()).unwrap();
+        }
+
+        while action.tick(&mut ()) == Status::Running {}
+        action.reset();
+        assert_eq!(action.latest_event::<u32>(), None);
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn crashed_task_fails_the_node() {
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let seen = reasons.clone();
+        let config = ActionConfig::new()
+            .on_crash(move |reason| seen.lock().unwrap().push(reason.to_owned()));
+        let mut action = Action::with_config(config, |_: (), _: &HaltSignal| -> Status {
+            panic!("lost the map")
+        });
+
+        let mut status = Status::Running;
+        while status == Status::Running {
+            status = action.tick(&mut ());
+        }
+
+        assert_eq!(status, Status::Failed);
+        assert_eq!(*reasons.lock().unwrap(), vec!["lost the map".to_owned()]);
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    #[should_panic(expected = "Action task crashed: lost the map")]
+    fn crash_policy_can_panic() {
+        let config = ActionConfig::new().crash_policy(CrashPolicy::Panic);
+        let mut action = Action::with_config(config, |_: (), _: &HaltSignal| -> Status {
+            panic!("lost the map")
+        });
+
+        while action.tick(&mut ()) == Status::Running {}
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn restarts_are_reported() {
+        let runs = Arc::new(Mutex::new(0));
+        let counter = runs.clone();
+        let restarts = Arc::new(Mutex::new(Vec::new()));
+        let seen = restarts.clone();
+        let config = ActionConfig::new()
+            .restart_policy(RestartPolicy::AfterDelay(time::Duration::from_secs(60)))
+            .on_restart(move |n| seen.lock().unwrap().push(n));
+        let mut action = Action::with_config(config, move |_: (), _: &HaltSignal| {
+            *counter.lock().unwrap() += 1;
+            Status::Running
+        });
+
+        while restarts.lock().unwrap().is_empty() {
+            assert_eq!(action.tick(&mut ()), Status::Running);
+        }
+        for _ in 0..5 {
+            assert_eq!(action.tick(&mut ()), Status::Running);
+        }
+        assert_eq!(*runs.lock().unwrap(), 1);
+        assert_eq!(*restarts.lock().unwrap(), vec![1]);
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn restarts_can_be_disabled() {
+        let config = ActionConfig::new().restart_policy(RestartPolicy::Never);
+        let mut action = Action::with_config(config, |_: (), _: &HaltSignal| Status::Running);
+
+        let mut status = Status::Running;
+        while status == Status::Running {
+            status = action.tick(&mut ());
+        }
+        assert_eq!(status, Status::Failed);
+    }
+
+    #[cfg(feature = "action")]
+    #[test]
+    fn threads_are_named_after_the_node() {
+        let mut action = Action::new(|_| {
+            let name = thread::current().name().map(str::to_owned);
+            if name.as_deref() == Some("bt-action:Dock") {
+                Status::Succeeded
+            } else {
+                Status::Failed
+            }
+        })
+        .named(Some("Dock"));
+
+        let mut status = Status::Running;
+        while status == Status::Running {
+            status = action.tick(&mut ());
+        }
+        assert_eq!(status, Status::Succeeded);
+    }
+
     #[test]
     fn inline_failure() {
         assert_eq!(
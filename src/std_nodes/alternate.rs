@@ -0,0 +1,176 @@
+//! A node that takes turns between its children.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+
+/// What an `Alternate` node does with its turn when it is reset.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AlternatePolicy {
+    /// The turn is kept, so the child after the one which last completed is
+    /// run next even if the parent of the node is restarted. A child which was
+    /// interrupted by the reset is run again from the start.
+    Remember,
+
+    /// The turn goes back to the first child.
+    Restart,
+}
+impl Default for AlternatePolicy {
+    fn default() -> Self {
+        AlternatePolicy::Remember
+    }
+}
+
+/// A node that runs its children in turn, one per activation.
+///
+/// Each time this node is run, it runs one of its children to completion and
+/// finishes with its status. First the first child is run, then the second and
+/// so on, going back to the first child after the last one. With two children
+/// this alternates between them, such as patrolling two waypoints in turn or
+/// trying two strategies one after the other.
+///
+/// The turn only moves on when a child completes. Whether the turn is kept
+/// when this node is reset depends on its `AlternatePolicy`, which is
+/// `Remember` for `Alternate::new`.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after being created or reset.
+///
+/// **Running:** While the child whose turn it is is running.
+///
+/// **Succeeded:** When the child whose turn it was succeeded.
+///
+/// **Failed:** When the child whose turn it was failed, or if there are no
+/// children.
+///
+/// # Children
+///
+/// Any number. Only the child whose turn it is is ticked. All children are
+/// reset when this node is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = Alternate::new(vec![AlwaysSucceed::new(), AlwaysFail::new()]);
+///
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// node.reset();
+/// assert_eq!(node.tick(&mut ()), Status::Failed);
+/// node.reset();
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// ```
+pub struct Alternate<'a, W> {
+    /// Child nodes.
+    children: Vec<Node<'a, W>>,
+
+    /// The index of the child whose turn it is.
+    turn: usize,
+
+    /// What happens to the turn on a reset.
+    policy: AlternatePolicy,
+}
+impl<'a, W> Alternate<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Alternate` node which remembers its turn when reset.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Alternate::with_policy(AlternatePolicy::default(), children)
+    }
+
+    /// Creates a new `Alternate` node with the given policy.
+    pub fn with_policy(policy: AlternatePolicy, children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        let internals = Alternate {
+            children,
+            turn: 0,
+            policy,
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Tickable<W> for Alternate<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let count = self.children.len();
+        let child = match self.children.get_mut(self.turn) {
+            Some(child) => child,
+            None => return Status::Failed,
+        };
+
+        let status = child.tick(world);
+        if status.is_done() {
+            self.turn = (self.turn + 1) % count;
+        }
+        status
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let turn = if run.after_reset() && self.policy == AlternatePolicy::Restart {
+            0
+        } else {
+            self.turn
+        };
+        match self.children.get(turn) {
+            Some(child) => Some(run.tick(child, world)),
+            None => Some(Status::Failed),
+        }
+    }
+
+    fn reset(&mut self) {
+        if self.policy == AlternatePolicy::Restart {
+            self.turn = 0;
+        }
+        for child in &mut self.children {
+            child.reset();
+        }
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "Alternate".
+    fn type_name(&self) -> &'static str {
+        "Alternate"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AlternatePolicy;
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{Alternate, CountedTick, NoTick},
+    };
+
+    #[test]
+    fn turns_follow_policy() {
+        let mut node = Alternate::new(vec![
+            CountedTick::new(Status::Succeeded, 1, true),
+            CountedTick::new(Status::Running, 2, true),
+            CountedTick::new(Status::Failed, 0, true),
+        ]);
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        node.reset();
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        drop(node);
+
+        let mut node = Alternate::with_policy(
+            AlternatePolicy::Restart,
+            vec![CountedTick::new(Status::Succeeded, 2, true), NoTick::new()],
+        );
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        node.reset();
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+
+        assert_eq!(
+            Alternate::<()>::new(Vec::new()).tick(&mut ()),
+            Status::Failed
+        );
+    }
+}
@@ -0,0 +1,536 @@
+//! Nodes which react to the contents of the blackboard.
+use crate::{
+    blackboard::{Blackboard, BlackboardBridge, BlackboardFile},
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::{borrow::Cow, mem};
+
+/// A node that succeeds only on the ticks when a blackboard entry has changed.
+///
+/// When ticked, this node compares the revision of the entry with the one it
+/// saw on its previous tick. It succeeds if the entry was written, created, or
+/// removed since then, and fails otherwise. This allows event-style patterns,
+/// such as reacting to a new goal, inside an otherwise polled tree.
+///
+/// Before its first tick, the node has seen the entry as absent, so an entry
+/// which already exists counts as a change. The last seen revision is kept
+/// when the node is reset, so that resetting the tree does not replay old
+/// changes.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** When the entry changed since the previous tick.
+///
+/// **Failed:** When the entry did not change since the previous tick.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::blackboard::Blackboard;
+/// let mut node = OnBlackboardChange::new("goal");
+/// let mut blackboard = Blackboard::new();
+/// assert_eq!(node.tick(&mut blackboard), Status::Failed);
+///
+/// blackboard.set("goal", (3, 4));
+/// assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+/// assert_eq!(node.tick(&mut blackboard), Status::Failed);
+/// ```
+pub struct OnBlackboardChange {
+    /// The key of the entry to watch.
+    key: Cow<'static, str>,
+
+    /// The revision of the entry seen on the previous tick.
+    seen: Option<u64>,
+}
+impl OnBlackboardChange {
+    /// Creates a new `OnBlackboardChange` node watching the entry with the
+    /// given key.
+    pub fn new<'a, W, K>(key: K) -> Node<'a, W>
+    where
+        W: AsRef<Blackboard>,
+        K: Into<Cow<'static, str>>,
+    {
+        Node::new(OnBlackboardChange {
+            key: key.into(),
+            seen: None,
+        })
+    }
+}
+impl<W> Tickable<W> for OnBlackboardChange
+where
+    W: AsRef<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        let revision = world.as_ref().revision(&self.key);
+        if revision == self.seen {
+            return Status::Failed;
+        }
+
+        trace!("Blackboard entry {} changed", self.key);
+        self.seen = revision;
+        Status::Succeeded
+    }
+
+    fn dry_tick(&self, world: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        if world.as_ref().revision(&self.key) == self.seen {
+            Some(Status::Failed)
+        } else {
+            Some(Status::Succeeded)
+        }
+    }
+
+    fn reset(&mut self) {
+        // The last seen revision is deliberately kept
+    }
+
+    /// Returns the string "OnBlackboardChange".
+    fn type_name(&self) -> &'static str {
+        "OnBlackboardChange"
+    }
+}
+
+/// Convenience macro for creating OnBlackboardChange nodes.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate aspen;
+/// # use aspen::blackboard::Blackboard;
+/// # use aspen::node::Node;
+/// # fn main() {
+/// let on_goal: Node<Blackboard> = OnBlackboardChange! { "goal" };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! OnBlackboardChange {
+    ( $k:expr ) => {
+        $crate::std_nodes::OnBlackboardChange::new($k)
+    };
+}
+
+/// A node that saves blackboard entries to a file.
+///
+/// This node acts as a checkpoint: placing it in the tree determines when the
+/// entries registered with the `BlackboardFile` are saved. The file is only
+/// written if one of the entries changed since it was last saved or loaded.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** When the file is up to date.
+///
+/// **Failed:** When the file could not be written.
+///
+/// # Children
+///
+/// None.
+pub struct SaveBlackboard {
+    /// The file the entries are saved to.
+    file: BlackboardFile,
+}
+impl SaveBlackboard {
+    /// Creates a new `SaveBlackboard` node which saves to `file`.
+    pub fn new<'a, W>(file: BlackboardFile) -> Node<'a, W>
+    where
+        W: AsRef<Blackboard>,
+    {
+        Node::new(SaveBlackboard { file })
+    }
+}
+impl<W> Tickable<W> for SaveBlackboard
+where
+    W: AsRef<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        match self.file.save_if_dirty(world.as_ref()) {
+            Ok(_) => Status::Succeeded,
+            Err(e) => {
+                warn!("Failed to save blackboard to {:?}: {}", self.file.path(), e);
+                Status::Failed
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "SaveBlackboard".
+    fn type_name(&self) -> &'static str {
+        "SaveBlackboard"
+    }
+}
+
+/// A node that gives its child its own scope on the blackboard.
+///
+/// While the child is ticked, the blackboard of the world has a scope entered
+/// which holds the child's own entries. The child can read every entry of the
+/// enclosing scopes, but the values it writes stay in its scope and shadow
+/// any with the same key outside, so that subtrees written separately cannot
+/// clobber each other's keys. Keys which are exported are promoted to the
+/// enclosing scope after every tick of the child, which is how the subtree
+/// hands back its results.
+///
+/// The entries of the scope are kept between ticks and when this node is
+/// reset, so the child can keep its own state for as long as this node
+/// exists.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running.
+///
+/// **Succeeded:** When the child succeeds.
+///
+/// **Failed:** When the child fails.
+///
+/// # Children
+///
+/// One, which is ticked every time this node is and reset whenever this node
+/// is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::blackboard::Blackboard;
+/// let plan = InlineAction::new(|b: &mut Blackboard| {
+///     let goal = *b.get::<u32>("goal").unwrap();
+///     b.set("target", goal + 1);
+///     b.set("path", vec![goal, goal + 1]);
+///     Status::Succeeded
+/// });
+/// let mut node = ScopedBlackboard::with_exports(vec!["path"], plan);
+///
+/// let mut blackboard = Blackboard::new();
+/// blackboard.set("goal", 3u32);
+/// assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+/// assert_eq!(blackboard.get::<Vec<u32>>("path"), Some(&vec![3, 4]));
+/// assert!(!blackboard.contains("target"));
+/// ```
+pub struct ScopedBlackboard<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The entries of the child's scope.
+    scope: Blackboard,
+
+    /// The keys which are promoted to the enclosing scope.
+    exports: Vec<Cow<'static, str>>,
+}
+impl<'a, W> ScopedBlackboard<'a, W>
+where
+    W: AsMut<Blackboard> + 'a,
+{
+    /// Creates a new `ScopedBlackboard` node which keeps every write of its
+    /// child in its scope.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        ScopedBlackboard::with_exports(Vec::<&'static str>::new(), child)
+    }
+
+    /// Creates a new `ScopedBlackboard` node which promotes the values of the
+    /// given keys to the enclosing scope.
+    pub fn with_exports<I, K>(exports: I, child: Node<'a, W>) -> Node<'a, W>
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Cow<'static, str>>,
+    {
+        let internals = ScopedBlackboard {
+            child,
+            scope: Blackboard::new(),
+            exports: exports.into_iter().map(Into::into).collect(),
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Tickable<W> for ScopedBlackboard<'a, W>
+where
+    W: AsMut<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        world.as_mut().enter_scope(mem::take(&mut self.scope));
+        let status = self.child.tick(world);
+
+        let blackboard = world.as_mut();
+        for key in &self.exports {
+            if blackboard.promote(key) {
+                trace!("Promoted blackboard entry {}", key);
+            }
+        }
+        self.scope = blackboard.exit_scope().unwrap_or_default();
+        status
+    }
+
+    fn reset(&mut self) {
+        // The entries of the scope are kept
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "ScopedBlackboard".
+    fn type_name(&self) -> &'static str {
+        "ScopedBlackboard"
+    }
+}
+
+/// A node that keeps the bridged fields of the world in step with the
+/// blackboard while its child is ticked.
+///
+/// Before each tick of the child, the fields of the world which are bridged
+/// to the blackboard by its `BlackboardBridge` implementation are written to
+/// the blackboard if they changed. After the tick, the entries which were
+/// written during it are read back into their fields. Nodes which use the
+/// world's fields and nodes which use the blackboard can therefore be mixed
+/// in the child, which lets a tree move to the blackboard one subtree at a
+/// time. Placed at the root, the two are kept in step on every tick.
+///
+/// When a field and its entry are both changed during a tick, the entry wins.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running.
+///
+/// **Succeeded:** When the child succeeds.
+///
+/// **Failed:** When the child fails.
+///
+/// # Children
+///
+/// One, which is ticked every time this node is and reset whenever this node
+/// is reset.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate aspen;
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::blackboard::Blackboard;
+/// # fn main() {
+/// #[derive(Default)]
+/// struct World {
+///     charge: u32,
+///     blackboard: Blackboard,
+/// }
+/// impl AsMut<Blackboard> for World {
+///     fn as_mut(&mut self) -> &mut Blackboard {
+///         &mut self.blackboard
+///     }
+/// }
+/// blackboard_bridge!(World { charge: "charge" });
+///
+/// // A node which has moved to the blackboard
+/// let mut node = SyncBlackboard::new(InlineAction::new(|w: &mut World| {
+///     let charge = *w.blackboard.get::<u32>("charge").unwrap();
+///     w.blackboard.set("charge", charge * 2);
+///     Status::Succeeded
+/// }));
+///
+/// let mut world = World::default();
+/// world.charge = 5;
+/// assert_eq!(node.tick(&mut world), Status::Succeeded);
+/// assert_eq!(world.charge, 10);
+/// # }
+/// ```
+pub struct SyncBlackboard<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The revisions of the bridged entries after they were last written.
+    revisions: Vec<Option<u64>>,
+}
+impl<'a, W> SyncBlackboard<'a, W>
+where
+    W: BlackboardBridge + AsMut<Blackboard> + 'a,
+{
+    /// Creates a new `SyncBlackboard` node.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(SyncBlackboard {
+            child,
+            revisions: Vec::new(),
+        })
+    }
+}
+impl<'a, W> Tickable<W> for SyncBlackboard<'a, W>
+where
+    W: BlackboardBridge + AsMut<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        // The blackboard is taken out of the world so both can be borrowed
+        let mut blackboard = mem::take(world.as_mut());
+        world.export(&mut blackboard);
+        let keys = world.bridged_keys();
+        self.revisions.clear();
+        self.revisions.extend(keys.iter().map(|k| blackboard.revision(k)));
+        *world.as_mut() = blackboard;
+
+        let status = self.child.tick(world);
+
+        let blackboard = mem::take(world.as_mut());
+        for (key, &revision) in keys.iter().zip(&self.revisions) {
+            if blackboard.revision(key) != revision {
+                trace!("Reading blackboard entry {} back into the world", key);
+                world.import(key, &blackboard);
+            }
+        }
+        *world.as_mut() = blackboard;
+        status
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "SyncBlackboard".
+    fn type_name(&self) -> &'static str {
+        "SyncBlackboard"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        blackboard::{Blackboard, BlackboardBridge, BlackboardFile},
+        node::Tickable,
+        status::Status,
+        std_nodes::{
+            InlineAction, OnBlackboardChange, SaveBlackboard, ScopedBlackboard, Sequence,
+            SyncBlackboard,
+        },
+    };
+    use std::env;
+
+    #[derive(Default)]
+    struct Bridged {
+        count: u32,
+        blackboard: Blackboard,
+    }
+    impl AsMut<Blackboard> for Bridged {
+        fn as_mut(&mut self) -> &mut Blackboard {
+            &mut self.blackboard
+        }
+    }
+    crate::blackboard_bridge!(Bridged { count: "count" });
+
+    #[test]
+    fn existing_entry_counts_as_changed() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set("a", 1);
+        let mut node = OnBlackboardChange::new("a");
+        assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+        assert_eq!(node.tick(&mut blackboard), Status::Failed);
+    }
+
+    #[test]
+    fn removal_counts_as_changed() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set("a", 1);
+        let mut node = OnBlackboardChange::new("a");
+        node.tick(&mut blackboard);
+
+        blackboard.remove("a");
+        assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+    }
+
+    #[test]
+    fn reset_does_not_replay_changes() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set("a", 1);
+        let mut node = Sequence::new(vec![OnBlackboardChange::new("a")]);
+        assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+
+        node.reset();
+        assert_eq!(node.tick(&mut blackboard), Status::Failed);
+    }
+
+    #[test]
+    fn save_fails_when_file_cannot_be_written() {
+        let path = env::temp_dir().join("aspen-missing-dir").join("bb.json");
+        let mut node = SaveBlackboard::new(BlackboardFile::new(path).persist::<u8>("a"));
+
+        let mut blackboard = Blackboard::new();
+        assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+
+        blackboard.set("a", 1u8);
+        assert_eq!(node.tick(&mut blackboard), Status::Failed);
+    }
+
+    #[test]
+    fn sibling_scopes_do_not_collide() {
+        let count = || {
+            InlineAction::new(|b: &mut Blackboard| {
+                let next = b.get::<u32>("count").map_or(0, |c| c + 1);
+                b.set("count", next);
+                Status::Succeeded
+            })
+        };
+        let mut node = Sequence::new(vec![
+            ScopedBlackboard::new(count()),
+            ScopedBlackboard::new(count()),
+        ]);
+
+        let mut blackboard = Blackboard::new();
+        blackboard.set("count", 10u32);
+        for _ in 0..2 {
+            assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+            node.reset();
+        }
+
+        // Neither scope wrote to the shared value
+        assert_eq!(blackboard.get::<u32>("count"), Some(&10));
+        assert!(blackboard.exit_scope().is_none());
+    }
+
+    #[test]
+    fn unchanged_fields_are_not_rewritten() {
+        let mut node = SyncBlackboard::new(InlineAction::new(|w: &mut Bridged| {
+            w.count += 1;
+            Status::Succeeded
+        }));
+        let mut world = Bridged::default();
+
+        node.tick(&mut world);
+        let revision = world.blackboard.revision("count");
+        assert_eq!(world.count, 1);
+        assert_eq!(world.bridged_keys(), &["count"]);
+
+        // The change made to the field is only written on the next tick
+        node.tick(&mut world);
+        assert_eq!(world.blackboard.get::<u32>("count"), Some(&1));
+        assert!(world.blackboard.revision("count") > revision);
+
+        let mut blackboard = Blackboard::new();
+        world.export(&mut blackboard);
+        let revision = blackboard.revision("count");
+        world.export(&mut blackboard);
+        assert_eq!(blackboard.revision("count"), revision);
+    }
+}
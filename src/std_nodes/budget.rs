@@ -0,0 +1,182 @@
+//! A selector which only tries the children it can afford.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+
+/// Function which reads the budget which is available from the world.
+type BudgetFn<'a, W> = dyn Fn(&W) -> f64 + 'a;
+
+/// Function which works out what a child would cost in the current world.
+type CostFn<'a, W> = dyn Fn(&W) -> f64 + 'a;
+
+/// A child of a `BudgetedSelector`, with its cost.
+struct Choice<'a, W> {
+    /// The cost of running the child.
+    cost: Box<CostFn<'a, W>>,
+
+    /// The child itself.
+    node: Node<'a, W>,
+}
+
+/// A selector that skips the children which cost more than the budget.
+///
+/// Each child is given with a function which works out its cost, and the node
+/// is given a function which reads the available budget from the world, such
+/// as the charge left in a battery or the action points of a game character.
+/// Children are listed best first. Every tick, this node ticks its affordable
+/// children in order until one of them does not fail, like a `Selector`, so
+/// the best choice which can be afforded is chosen. Children which cost more
+/// than the budget are skipped as if they had failed.
+///
+/// A child which is running is not skipped, even if the budget has dropped
+/// below its cost while it was running, so that it is not abandoned for using
+/// up the budget it was started with.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** The latest ticked child is running.
+///
+/// **Succeeded:** The latest ticked child succeeded.
+///
+/// **Failed:** Every child failed or cost too much.
+///
+/// # Children
+///
+/// Any number. Children after the one which did not fail are reset, as are
+/// children which are skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// // The world is the charge left in the battery, as a percentage
+/// let mut node = BudgetedSelector::new(|&charge: &f64| charge)
+///     .with_option(|_| 40.0, Condition::new(|_| true).named(Some("patrol")))
+///     .with_option(|_| 5.0, AlwaysRunning::new().named(Some("dock")))
+///     .into_node();
+///
+/// assert_eq!(node.tick(&mut 80.0), Status::Succeeded);
+/// node.reset();
+/// assert_eq!(node.tick(&mut 20.0), Status::Running);
+/// assert_eq!(node.children()[1].status(), Some(Status::Running));
+/// ```
+pub struct BudgetedSelector<'a, W> {
+    /// Reads the available budget.
+    budget: Box<BudgetFn<'a, W>>,
+
+    /// The children, best first.
+    choices: Vec<Choice<'a, W>>,
+}
+impl<'a, W> BudgetedSelector<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `BudgetedSelector` with no children, which reads the
+    /// available budget with the given function.
+    pub fn new<F>(budget: F) -> Self
+    where
+        F: Fn(&W) -> f64 + 'a,
+    {
+        BudgetedSelector {
+            budget: Box::new(budget),
+            choices: Vec::new(),
+        }
+    }
+
+    /// Adds a child after the existing ones, with a function which works out
+    /// its cost.
+    pub fn with_option<F, T>(mut self, cost: F, child: T) -> Self
+    where
+        F: Fn(&W) -> f64 + 'a,
+        T: Tickable<W> + 'a,
+    {
+        self.choices.push(Choice {
+            cost: Box::new(cost),
+            node: child.into_node(),
+        });
+        self
+    }
+
+    /// Returns `true` if the child can be ticked with the given budget.
+    fn affordable(choice: &Choice<'a, W>, world: &W, budget: f64) -> bool {
+        choice.node.status() == Some(Status::Running) || (choice.cost)(world) <= budget
+    }
+}
+impl<'a, W> Tickable<W> for BudgetedSelector<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let budget = (self.budget)(world);
+        let mut ret_status = Status::Failed;
+        for choice in &mut self.choices {
+            if ret_status == Status::Failed && BudgetedSelector::affordable(choice, world, budget) {
+                ret_status = choice.node.tick(world);
+            } else {
+                choice.node.reset();
+            }
+        }
+
+        ret_status
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let budget = (self.budget)(world);
+        let mut ret_status = Status::Failed;
+        for choice in &self.choices {
+            if ret_status != Status::Failed {
+                break;
+            }
+            let running = !run.after_reset() && choice.node.status() == Some(Status::Running);
+            if running || (choice.cost)(world) <= budget {
+                ret_status = run.tick(&choice.node, world);
+            }
+        }
+
+        Some(ret_status)
+    }
+
+    fn reset(&mut self) {
+        for choice in &mut self.choices {
+            choice.node.reset();
+        }
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.choices.iter().map(|o| &o.node).collect()
+    }
+
+    /// Returns the string "BudgetedSelector".
+    fn type_name(&self) -> &'static str {
+        "BudgetedSelector"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{BudgetedSelector, CountedTick, NoTick, YesTick},
+    };
+
+    #[test]
+    fn expensive_children_are_skipped() {
+        let mut node = BudgetedSelector::new(|&budget: &f64| budget)
+            .with_option(|_| 10.0, NoTick::new())
+            .with_option(|_| 3.0, YesTick::new(Status::Failed))
+            .with_option(|&budget| budget, YesTick::new(Status::Succeeded))
+            .with_option(|_| 0.0, NoTick::new());
+        assert_eq!(node.tick(&mut 5.0), Status::Succeeded);
+        drop(node);
+
+        // A running child keeps going when the budget drops below its cost
+        let mut node = BudgetedSelector::new(|&budget: &f64| budget)
+            .with_option(|_| 4.0, CountedTick::new(Status::Running, 2, true))
+            .with_option(|_| 0.0, NoTick::new());
+        assert_eq!(node.tick(&mut 5.0), Status::Running);
+        assert_eq!(node.tick(&mut 1.0), Status::Running);
+    }
+}
@@ -0,0 +1,267 @@
+//! Nodes that let a long mission carry on from where it was when its state
+//! was saved.
+use crate::{
+    blackboard::Blackboard,
+    node::{DryRun, Node, Tickable},
+    status::Status,
+    std_nodes::sequence::{dry_tick_sequence, tick_sequence},
+};
+use std::borrow::Cow;
+
+/// The blackboard key which holds the name of the last checkpoint reached.
+///
+/// Persisting this key as a `String` with a `BlackboardFile` saves the
+/// progress of a mission along with the rest of its state.
+pub const CHECKPOINT_KEY: &str = "checkpoint";
+
+/// A node that records that a mission has reached a checkpoint.
+///
+/// When ticked, this node writes its name to the `CHECKPOINT_KEY` entry of the
+/// blackboard and succeeds. The node is named after its checkpoint, which is
+/// how `ResumeFrom` finds it, so it should not be renamed.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** Always.
+///
+/// **Failed:** Never.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::blackboard::Blackboard;
+/// let mut node = Checkpoint::new("docked");
+/// let mut blackboard = Blackboard::new();
+///
+/// assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+/// assert_eq!(blackboard.get::<String>(CHECKPOINT_KEY).unwrap(), "docked");
+/// ```
+pub struct Checkpoint {
+    /// The name of the checkpoint.
+    name: Cow<'static, str>,
+}
+impl Checkpoint {
+    /// Creates a new `Checkpoint` node with the given name.
+    pub fn new<'a, W, N>(name: N) -> Node<'a, W>
+    where
+        W: AsMut<Blackboard>,
+        N: Into<Cow<'static, str>>,
+    {
+        let name = name.into();
+        Node::new(Checkpoint { name: name.clone() }).named(Some(name))
+    }
+}
+impl<W> Tickable<W> for Checkpoint
+where
+    W: AsMut<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        let blackboard = world.as_mut();
+        if blackboard.get::<String>(CHECKPOINT_KEY).map(String::as_str) != Some(self.name.as_ref())
+        {
+            info!("Reached checkpoint {}", self.name);
+            blackboard.set(CHECKPOINT_KEY, self.name.to_string());
+        }
+        Status::Succeeded
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "Checkpoint".
+    fn type_name(&self) -> &'static str {
+        "Checkpoint"
+    }
+}
+
+/// A sequence that skips the children which were completed before the state of
+/// the mission was saved.
+///
+/// On the first tick after it is created, this node reads the last checkpoint
+/// reached from the `CHECKPOINT_KEY` entry of the blackboard. If a `Checkpoint`
+/// node with that name is among its descendants, the children before the one
+/// which holds it are skipped as if they had succeeded, so that physical
+/// actions which were completed before a restart are not done again. After
+/// that, and whenever the checkpoint is not found, it behaves like a
+/// `Sequence`.
+///
+/// Only `ResumeFrom` nodes skip children, so a checkpoint inside nested
+/// sequences needs a `ResumeFrom` at each level to be resumed from precisely.
+/// Resetting this node is taken as a deliberate restart, so it does not skip
+/// children again afterwards.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** The latest ticked child is running.
+///
+/// **Succeeded:** All of the children which were not skipped succeeded.
+///
+/// **Failed:** One of the children failed.
+///
+/// # Children
+///
+/// Any number. Children before the one holding the checkpoint are never ticked
+/// when resuming. All children are reset when this node is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::blackboard::Blackboard;
+/// let mut blackboard = Blackboard::new();
+/// blackboard.set(CHECKPOINT_KEY, "surveyed".to_owned());
+///
+/// let mut mission = ResumeFrom::new(vec![
+///     AlwaysRunning::new().named(Some("survey")),
+///     Checkpoint::new("surveyed"),
+///     AlwaysSucceed::new().named(Some("return")),
+/// ]);
+/// assert_eq!(mission.tick(&mut blackboard), Status::Succeeded);
+/// assert_eq!(mission.children()[0].status(), None);
+/// ```
+pub struct ResumeFrom<'a, W> {
+    /// Child nodes.
+    children: Vec<Node<'a, W>>,
+
+    /// The index of the next child to tick.
+    next_child: usize,
+
+    /// Whether the node has not yet been ticked or reset.
+    fresh: bool,
+}
+impl<'a, W> ResumeFrom<'a, W>
+where
+    W: AsRef<Blackboard> + 'a,
+{
+    /// Creates a new `ResumeFrom` node from a vector of nodes.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        let internals = ResumeFrom {
+            children,
+            next_child: 0,
+            fresh: true,
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> ResumeFrom<'a, W>
+where
+    W: AsRef<Blackboard>,
+{
+    /// Returns the index of the child which holds the saved checkpoint, or
+    /// zero if there is none.
+    fn resume_point(&self, world: &W) -> usize {
+        let name = match world.as_ref().get::<String>(CHECKPOINT_KEY) {
+            Some(name) => name,
+            None => return 0,
+        };
+        self.children
+            .iter()
+            .position(|c| holds_checkpoint(c, name))
+            .unwrap_or(0)
+    }
+}
+impl<'a, W> Tickable<W> for ResumeFrom<'a, W>
+where
+    W: AsRef<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.fresh {
+            self.fresh = false;
+            self.next_child = self.resume_point(world);
+            if self.next_child > 0 {
+                info!(
+                    "Resuming from {}, skipping {} children",
+                    self.children[self.next_child].name(),
+                    self.next_child
+                );
+            }
+        }
+        tick_sequence(&mut self.children, &mut self.next_child, world)
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let next_child = if self.fresh && !run.after_reset() {
+            self.resume_point(world)
+        } else {
+            self.next_child
+        };
+        Some(dry_tick_sequence(&self.children, next_child, world, run))
+    }
+
+    fn reset(&mut self) {
+        for child in &mut self.children {
+            child.reset();
+        }
+
+        self.next_child = 0;
+        self.fresh = false;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "ResumeFrom".
+    fn type_name(&self) -> &'static str {
+        "ResumeFrom"
+    }
+}
+
+/// Returns `true` if `node` or one of its descendants is the checkpoint with
+/// the given name.
+fn holds_checkpoint<W>(node: &Node<'_, W>, name: &str) -> bool {
+    (node.type_name() == "Checkpoint" && node.name() == name)
+        || node.children().iter().any(|c| holds_checkpoint(c, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CHECKPOINT_KEY;
+    use crate::{
+        blackboard::Blackboard,
+        node::Tickable,
+        status::Status,
+        std_nodes::{Checkpoint, CountedTick, NoTick, ResumeFrom, Sequence, YesTick},
+    };
+
+    #[test]
+    fn resumes_from_nested_checkpoint() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set(CHECKPOINT_KEY, "b".to_owned());
+        let mut mission = ResumeFrom::new(vec![
+            NoTick::new(),
+            Checkpoint::new("a"),
+            ResumeFrom::new(vec![NoTick::new(), Checkpoint::new("b")]),
+            YesTick::new(Status::Succeeded),
+        ]);
+        assert_eq!(mission.tick(&mut blackboard), Status::Succeeded);
+        drop(mission);
+
+        // Without a saved checkpoint, or after a reset, nothing is skipped
+        let mut blackboard = Blackboard::new();
+        let mut mission = ResumeFrom::new(vec![
+            CountedTick::new(Status::Succeeded, 2, true),
+            Sequence::new(vec![Checkpoint::new("a")]),
+        ]);
+        assert_eq!(mission.tick(&mut blackboard), Status::Succeeded);
+        assert_eq!(blackboard.get::<String>(CHECKPOINT_KEY).unwrap(), "a");
+        mission.reset();
+        assert_eq!(mission.tick(&mut blackboard), Status::Succeeded);
+    }
+}
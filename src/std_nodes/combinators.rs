@@ -0,0 +1,145 @@
+//! Free functions for building trees from ordinary expressions.
+//!
+//! These are an alternative to the node macros for trees which are built
+//! programmatically, for example from a configuration file. Composites accept
+//! anything that can be iterated over to produce nodes, so children can come
+//! straight from an iterator chain.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::std_nodes::combinators::*;
+//! # use aspen::node::Tickable;
+//! # use aspen::Status;
+//! # use std::time::Duration;
+//! let waypoints = vec![3, 5, 8];
+//! let mut patrol = seq(waypoints.into_iter().map(|w| {
+//!     timeout(
+//!         Duration::from_secs(30),
+//!         inline(move |p: &mut u32| {
+//!             *p = w;
+//!             Status::Succeeded
+//!         }),
+//!     )
+//! }));
+//!
+//! let mut position = 0;
+//! assert_eq!(patrol.tick(&mut position), Status::Succeeded);
+//! assert_eq!(position, 8);
+//! ```
+use crate::{
+    node::{Node, Tickable},
+    status::Status,
+    std_nodes::{
+        ActiveSequence, Condition, InlineAction, Invert, Parallel, Repeat, Selector, Sequence,
+        StatefulSelector, Timeout,
+    },
+};
+use std::time::Duration;
+
+/// Creates a `Sequence` from the given children.
+pub fn seq<'a, W, I>(children: I) -> Node<'a, W>
+where
+    W: 'a,
+    I: IntoIterator<Item = Node<'a, W>>,
+{
+    Sequence::new(children.into_iter().collect())
+}
+
+/// Creates an `ActiveSequence` from the given children.
+pub fn active_seq<'a, W, I>(children: I) -> Node<'a, W>
+where
+    W: 'a,
+    I: IntoIterator<Item = Node<'a, W>>,
+{
+    ActiveSequence::new()
+        .with_children(children.into_iter().collect())
+        .into_node()
+}
+
+/// Creates a `Selector` from the given children.
+pub fn sel<'a, W, I>(children: I) -> Node<'a, W>
+where
+    W: 'a,
+    I: IntoIterator<Item = Node<'a, W>>,
+{
+    Selector::new(children.into_iter().collect())
+}
+
+/// Creates a `StatefulSelector` from the given children.
+pub fn stateful_sel<'a, W, I>(children: I) -> Node<'a, W>
+where
+    W: Clone + 'a,
+    I: IntoIterator<Item = Node<'a, W>>,
+{
+    StatefulSelector::new(children.into_iter().collect())
+}
+
+/// Creates a `Parallel` node which needs `required_successes` of the given
+/// children to succeed.
+pub fn par<'a, W, I>(required_successes: usize, children: I) -> Node<'a, W>
+where
+    W: 'a,
+    I: IntoIterator<Item = Node<'a, W>>,
+{
+    Parallel::new(required_successes, children.into_iter().collect())
+}
+
+/// Creates an `Invert` node.
+pub fn invert<'a, W: 'a>(child: Node<'a, W>) -> Node<'a, W> {
+    Invert::new(child)
+}
+
+/// Creates a `Repeat` node which runs its child `limit` times.
+pub fn repeat<'a, W: 'a>(limit: u32, child: Node<'a, W>) -> Node<'a, W> {
+    Repeat::with_limit(limit, child)
+}
+
+/// Creates a `Timeout` node which gives its child `limit` to complete.
+pub fn timeout<'a, W: 'a>(limit: Duration, child: Node<'a, W>) -> Node<'a, W> {
+    Timeout::new(limit, child)
+}
+
+/// Creates a `Condition` node.
+pub fn condition<'a, W, F>(func: F) -> Node<'a, W>
+where
+    W: 'a,
+    F: Fn(&W) -> bool + 'a,
+{
+    Condition::new(func)
+}
+
+/// Creates an `InlineAction` node.
+pub fn inline<'a, W, F>(task: F) -> Node<'a, W>
+where
+    W: 'a,
+    F: FnMut(&mut W) -> Status + 'a,
+{
+    InlineAction::new(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{active_seq, condition, invert, par, repeat, sel, seq};
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysRunning, AlwaysSucceed},
+    };
+
+    #[test]
+    fn builds_nested_trees() {
+        let mut root = sel(vec![
+            seq((0..3).map(|i| condition(move |&n: &u32| n > i))),
+            active_seq(vec![invert(AlwaysSucceed::new())]),
+            par(
+                1,
+                vec![repeat(2, AlwaysSucceed::new()), AlwaysRunning::new()],
+            ),
+        ]);
+
+        assert_eq!(root.tick(&mut 5), Status::Succeeded);
+        assert_eq!(root.children().len(), 3);
+        assert_eq!(root.children()[0].children().len(), 3);
+    }
+}
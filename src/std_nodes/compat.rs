@@ -0,0 +1,811 @@
+//! Nodes with the names and semantics of BehaviorTree.CPP 4.x.
+//!
+//! The standard nodes in this crate follow their own conventions, which differ
+//! from BehaviorTree.CPP in a few places that matter when porting a tree. For
+//! example, a BT.CPP `Sequence` remembers its running child like this crate's
+//! `Sequence`, but starts again from the first child after one fails, and its
+//! `RetryUntilSuccessful` retries within a single tick. The nodes in this
+//! module follow the BT.CPP behavior as it is documented, so that a ported
+//! tree should tick the same leaves in the same order as the original.
+//!
+//! The tests compare the leaves these nodes tick and halt with the traces in
+//! `tests/compat/traces.txt`. The traces checked in are written by hand from
+//! the BT.CPP documentation, so they show that the nodes match the documented
+//! behavior rather than BT.CPP itself. `tests/compat/record.cpp` builds the
+//! same trees with BT.CPP 4.x and writes the traces it produces, so that they
+//! can be recorded from BT.CPP and any differences caught by the tests.
+//!
+//! Halting a node in BT.CPP corresponds to resetting it here. As in BT.CPP,
+//! every node resets its children when it completes, so a completed composite
+//! starts again from the beginning the next time it is ticked.
+//!
+//! | BT.CPP                    | Here                        | Nearest standard node |
+//! |---------------------------|-----------------------------|-----------------------|
+//! | `Sequence`                | [`Sequence`]                | `Sequence`            |
+//! | `SequenceWithMemory`      | [`SequenceWithMemory`]      | `Sequence`            |
+//! | `ReactiveSequence`        | [`ReactiveSequence`]        | `ActiveSequence`      |
+//! | `Fallback`                | [`Fallback`]                | `StatefulSelector`    |
+//! | `ReactiveFallback`        | [`ReactiveFallback`]        | `Selector`            |
+//! | `Inverter`                | [`Inverter`]                | `Invert`              |
+//! | `ForceSuccess`            | [`ForceSuccess`]            | `AlwaysSucceed`       |
+//! | `ForceFailure`            | [`ForceFailure`]            | `AlwaysFail`          |
+//! | `RetryUntilSuccessful`    | [`RetryUntilSuccessful`]    | `UntilSuccess`        |
+//! | `Repeat`                  | [`Repeat`]                  | `Repeat`              |
+//! | `KeepRunningUntilFailure` | [`KeepRunningUntilFailure`] | `UntilFail`           |
+//!
+//! `SequenceStar`, the name used before BT.CPP 4.0, is kept as an alias of
+//! `SequenceWithMemory`.
+use crate::{
+    node::{Node, Tickable},
+    status::Status,
+};
+
+/// Returns the opposite of a completed status.
+fn opposite(status: Status) -> Status {
+    match status {
+        Status::Succeeded => Status::Failed,
+        Status::Failed => Status::Succeeded,
+        Status::Running => Status::Running,
+    }
+}
+
+/// Resets every child.
+fn reset_all<W>(children: &mut [Node<'_, W>]) {
+    for child in children {
+        child.reset();
+    }
+}
+
+/// Ticks `children` in order, starting at `current`, until one of them
+/// returns `stop` or is running.
+///
+/// When a child returns `stop`, either every child is reset and the next tick
+/// starts from the first child, or, if `remember` is set, only that child and
+/// the ones after it are reset and the next tick starts from it.
+fn tick_ordered<W>(
+    children: &mut [Node<'_, W>],
+    current: &mut usize,
+    world: &mut W,
+    stop: Status,
+    remember: bool,
+) -> Status {
+    while *current < children.len() {
+        match children[*current].tick(world) {
+            Status::Running => return Status::Running,
+            s if s == stop => {
+                if remember {
+                    reset_all(&mut children[*current..]);
+                } else {
+                    reset_all(children);
+                    *current = 0;
+                }
+                return s;
+            }
+            _ => *current += 1,
+        }
+    }
+
+    reset_all(children);
+    *current = 0;
+    opposite(stop)
+}
+
+/// Ticks `children` from the first one until one of them returns `stop` or is
+/// running.
+///
+/// Every child other than the running one is reset, so that only a single
+/// child is ever running.
+fn tick_reactive<W>(children: &mut [Node<'_, W>], world: &mut W, stop: Status) -> Status {
+    for i in 0..children.len() {
+        match children[i].tick(world) {
+            Status::Running => {
+                reset_all(&mut children[..i]);
+                reset_all(&mut children[i + 1..]);
+                return Status::Running;
+            }
+            s if s == stop => {
+                reset_all(children);
+                return s;
+            }
+            _ => {}
+        }
+    }
+
+    reset_all(children);
+    opposite(stop)
+}
+
+/// The BT.CPP `Sequence` node.
+///
+/// Ticks its children in order while they succeed, resuming at the running
+/// child on the next tick. If a child fails, every child is reset and the
+/// sequence fails; the next tick starts again from the first child.
+pub struct Sequence<'a, W> {
+    /// The children of this node.
+    children: Vec<Node<'a, W>>,
+
+    /// The index of the child to be ticked next.
+    current: usize,
+}
+impl<'a, W> Sequence<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Sequence` node.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Node::new(Sequence {
+            children,
+            current: 0,
+        })
+    }
+}
+impl<'a, W> Tickable<W> for Sequence<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_ordered(
+            &mut self.children,
+            &mut self.current,
+            world,
+            Status::Failed,
+            false,
+        )
+    }
+
+    fn reset(&mut self) {
+        reset_all(&mut self.children);
+        self.current = 0;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "Sequence".
+    fn type_name(&self) -> &'static str {
+        "Sequence"
+    }
+}
+
+/// The BT.CPP `SequenceWithMemory` node.
+///
+/// Like `Sequence`, except that after a child fails, the next tick starts
+/// again from the failed child instead of the first one. Children which
+/// already succeeded are not ticked again until the sequence completes or is
+/// reset.
+pub struct SequenceWithMemory<'a, W> {
+    /// The children of this node.
+    children: Vec<Node<'a, W>>,
+
+    /// The index of the child to be ticked next.
+    current: usize,
+}
+impl<'a, W> SequenceWithMemory<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `SequenceWithMemory` node.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Node::new(SequenceWithMemory {
+            children,
+            current: 0,
+        })
+    }
+}
+impl<'a, W> Tickable<W> for SequenceWithMemory<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_ordered(
+            &mut self.children,
+            &mut self.current,
+            world,
+            Status::Failed,
+            true,
+        )
+    }
+
+    fn reset(&mut self) {
+        reset_all(&mut self.children);
+        self.current = 0;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "SequenceWithMemory".
+    fn type_name(&self) -> &'static str {
+        "SequenceWithMemory"
+    }
+}
+
+/// The name of `SequenceWithMemory` before BT.CPP 4.0.
+pub type SequenceStar<'a, W> = SequenceWithMemory<'a, W>;
+
+/// The BT.CPP `ReactiveSequence` node.
+///
+/// Ticks its children from the first one on every tick, while they succeed.
+/// When a child is running, every other child is reset. When a child fails,
+/// every child is reset and the sequence fails.
+pub struct ReactiveSequence<'a, W> {
+    /// The children of this node.
+    children: Vec<Node<'a, W>>,
+}
+impl<'a, W> ReactiveSequence<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `ReactiveSequence` node.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Node::new(ReactiveSequence { children })
+    }
+}
+impl<'a, W> Tickable<W> for ReactiveSequence<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_reactive(&mut self.children, world, Status::Failed)
+    }
+
+    fn reset(&mut self) {
+        reset_all(&mut self.children);
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "ReactiveSequence".
+    fn type_name(&self) -> &'static str {
+        "ReactiveSequence"
+    }
+}
+
+/// The BT.CPP `Fallback` node.
+///
+/// Ticks its children in order while they fail, resuming at the running child
+/// on the next tick. If a child succeeds, every child is reset and the
+/// fallback succeeds.
+pub struct Fallback<'a, W> {
+    /// The children of this node.
+    children: Vec<Node<'a, W>>,
+
+    /// The index of the child to be ticked next.
+    current: usize,
+}
+impl<'a, W> Fallback<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Fallback` node.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Node::new(Fallback {
+            children,
+            current: 0,
+        })
+    }
+}
+impl<'a, W> Tickable<W> for Fallback<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_ordered(
+            &mut self.children,
+            &mut self.current,
+            world,
+            Status::Succeeded,
+            false,
+        )
+    }
+
+    fn reset(&mut self) {
+        reset_all(&mut self.children);
+        self.current = 0;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "Fallback".
+    fn type_name(&self) -> &'static str {
+        "Fallback"
+    }
+}
+
+/// The BT.CPP `ReactiveFallback` node.
+///
+/// Ticks its children from the first one on every tick, while they fail.
+/// When a child is running, every other child is reset. When a child
+/// succeeds, every child is reset and the fallback succeeds.
+pub struct ReactiveFallback<'a, W> {
+    /// The children of this node.
+    children: Vec<Node<'a, W>>,
+}
+impl<'a, W> ReactiveFallback<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `ReactiveFallback` node.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Node::new(ReactiveFallback { children })
+    }
+}
+impl<'a, W> Tickable<W> for ReactiveFallback<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_reactive(&mut self.children, world, Status::Succeeded)
+    }
+
+    fn reset(&mut self) {
+        reset_all(&mut self.children);
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "ReactiveFallback".
+    fn type_name(&self) -> &'static str {
+        "ReactiveFallback"
+    }
+}
+
+/// The BT.CPP `Inverter` node.
+///
+/// Swaps the success and failure of its child. The child is reset whenever it
+/// completes.
+pub struct Inverter<'a, W> {
+    /// The child of this node.
+    child: Node<'a, W>,
+}
+impl<'a, W> Inverter<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Inverter` node.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(Inverter { child })
+    }
+}
+impl<'a, W> Tickable<W> for Inverter<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let status = self.child.tick(world);
+        if status.is_done() {
+            self.child.reset();
+        }
+        opposite(status)
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "Inverter".
+    fn type_name(&self) -> &'static str {
+        "Inverter"
+    }
+}
+
+/// The BT.CPP `ForceSuccess` node.
+///
+/// Succeeds whenever its child completes. The child is reset whenever it
+/// completes.
+pub struct ForceSuccess<'a, W> {
+    /// The child of this node.
+    child: Node<'a, W>,
+}
+impl<'a, W> ForceSuccess<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `ForceSuccess` node.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(ForceSuccess { child })
+    }
+}
+impl<'a, W> Tickable<W> for ForceSuccess<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.child.tick(world) == Status::Running {
+            return Status::Running;
+        }
+
+        self.child.reset();
+        Status::Succeeded
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "ForceSuccess".
+    fn type_name(&self) -> &'static str {
+        "ForceSuccess"
+    }
+}
+
+/// The BT.CPP `ForceFailure` node.
+///
+/// Fails whenever its child completes. The child is reset whenever it
+/// completes.
+pub struct ForceFailure<'a, W> {
+    /// The child of this node.
+    child: Node<'a, W>,
+}
+impl<'a, W> ForceFailure<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `ForceFailure` node.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(ForceFailure { child })
+    }
+}
+impl<'a, W> Tickable<W> for ForceFailure<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.child.tick(world) == Status::Running {
+            return Status::Running;
+        }
+
+        self.child.reset();
+        Status::Failed
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "ForceFailure".
+    fn type_name(&self) -> &'static str {
+        "ForceFailure"
+    }
+}
+
+/// The BT.CPP `RetryUntilSuccessful` node.
+///
+/// Ticks its child until it succeeds, up to `num_attempts` times. After a
+/// failure, the child is reset and retried straight away, within the same
+/// tick. A negative number of attempts retries forever, as `-1` does in
+/// BT.CPP.
+pub struct RetryUntilSuccessful<'a, W> {
+    /// The child of this node.
+    child: Node<'a, W>,
+
+    /// The maximum number of attempts, or a negative number for no limit.
+    num_attempts: i32,
+
+    /// The number of attempts which have failed.
+    failures: i32,
+}
+impl<'a, W> RetryUntilSuccessful<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `RetryUntilSuccessful` node.
+    pub fn new(num_attempts: i32, child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(RetryUntilSuccessful {
+            child,
+            num_attempts,
+            failures: 0,
+        })
+    }
+}
+impl<'a, W> Tickable<W> for RetryUntilSuccessful<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        while self.num_attempts < 0 || self.failures < self.num_attempts {
+            match self.child.tick(world) {
+                Status::Running => return Status::Running,
+                Status::Succeeded => {
+                    self.failures = 0;
+                    self.child.reset();
+                    return Status::Succeeded;
+                }
+                Status::Failed => {
+                    self.failures += 1;
+                    self.child.reset();
+                }
+            }
+        }
+
+        self.failures = 0;
+        Status::Failed
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+        self.failures = 0;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "RetryUntilSuccessful".
+    fn type_name(&self) -> &'static str {
+        "RetryUntilSuccessful"
+    }
+}
+
+/// The BT.CPP `Repeat` node.
+///
+/// Ticks its child until it has succeeded `num_cycles` times, failing as soon
+/// as the child fails. After a success, the child is reset and run again
+/// straight away, within the same tick. A negative number of cycles repeats
+/// forever, as `-1` does in BT.CPP.
+pub struct Repeat<'a, W> {
+    /// The child of this node.
+    child: Node<'a, W>,
+
+    /// The number of cycles, or a negative number for no limit.
+    num_cycles: i32,
+
+    /// The number of cycles which have succeeded.
+    successes: i32,
+}
+impl<'a, W> Repeat<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Repeat` node.
+    pub fn new(num_cycles: i32, child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(Repeat {
+            child,
+            num_cycles,
+            successes: 0,
+        })
+    }
+}
+impl<'a, W> Tickable<W> for Repeat<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        while self.num_cycles < 0 || self.successes < self.num_cycles {
+            match self.child.tick(world) {
+                Status::Running => return Status::Running,
+                Status::Succeeded => {
+                    self.successes += 1;
+                    self.child.reset();
+                }
+                Status::Failed => {
+                    self.successes = 0;
+                    self.child.reset();
+                    return Status::Failed;
+                }
+            }
+        }
+
+        self.successes = 0;
+        Status::Succeeded
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+        self.successes = 0;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "Repeat".
+    fn type_name(&self) -> &'static str {
+        "Repeat"
+    }
+}
+
+/// The BT.CPP `KeepRunningUntilFailure` node.
+///
+/// Runs while its child keeps succeeding, resetting the child after each
+/// success, and fails once the child fails.
+pub struct KeepRunningUntilFailure<'a, W> {
+    /// The child of this node.
+    child: Node<'a, W>,
+}
+impl<'a, W> KeepRunningUntilFailure<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `KeepRunningUntilFailure` node.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(KeepRunningUntilFailure { child })
+    }
+}
+impl<'a, W> Tickable<W> for KeepRunningUntilFailure<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        match self.child.tick(world) {
+            Status::Running => Status::Running,
+            Status::Succeeded => {
+                self.child.reset();
+                Status::Running
+            }
+            Status::Failed => {
+                self.child.reset();
+                Status::Failed
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "KeepRunningUntilFailure".
+    fn type_name(&self) -> &'static str {
+        "KeepRunningUntilFailure"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Each test ticks a tree of scripted leaves and compares the log of leaf
+    //! ticks and halts with the trace of the same tree in
+    //! `tests/compat/traces.txt`. The trees must be kept the same as those in
+    //! `tests/compat/record.cpp`, which records the traces from BT.CPP.
+    use super::{
+        Fallback, ReactiveFallback, ReactiveSequence, Repeat, RetryUntilSuccessful, Sequence,
+        SequenceStar, SequenceWithMemory,
+    };
+    use crate::{
+        node::{Node, Tickable},
+        status::Status,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// The expected traces.
+    const TRACES: &str = include_str!("../../tests/compat/traces.txt");
+
+    /// Returns the expected log and status of every tick of the named case.
+    fn trace(case: &str) -> Vec<(&'static str, Status)> {
+        let header = format!("[{}]", case);
+        let trace: Vec<_> = TRACES
+            .lines()
+            .skip_while(|line| *line != header)
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .map(|line| {
+                let mut parts = line.splitn(2, " -> ");
+                let log = parts.next().unwrap();
+                let status = match parts.next() {
+                    Some("SUCCESS") => Status::Succeeded,
+                    Some("FAILURE") => Status::Failed,
+                    Some("RUNNING") => Status::Running,
+                    _ => panic!("bad line in trace {}: {}", case, line),
+                };
+                (log, status)
+            })
+            .collect();
+        assert!(!trace.is_empty(), "no trace for {}", case);
+        trace
+    }
+
+    /// A leaf which returns the statuses in its script in a loop, logging
+    /// every tick and every halt.
+    struct Scripted {
+        name: &'static str,
+        script: &'static str,
+        next: usize,
+        running: bool,
+        log: Log,
+    }
+    impl Tickable<()> for Scripted {
+        fn tick(&mut self, _: &mut ()) -> Status {
+            let c = self.script.as_bytes()[self.next % self.script.len()];
+            self.next += 1;
+            self.log
+                .borrow_mut()
+                .push(format!("{}{}", self.name, c as char));
+            let status = match c {
+                b'S' => Status::Succeeded,
+                b'F' => Status::Failed,
+                _ => Status::Running,
+            };
+            self.running = status == Status::Running;
+            status
+        }
+
+        fn reset(&mut self) {
+            if self.running {
+                self.log.borrow_mut().push(format!("halt {}", self.name));
+                self.running = false;
+            }
+        }
+
+        fn type_name(&self) -> &'static str {
+            self.name
+        }
+    }
+
+    fn leaf(log: &Log, name: &'static str, script: &'static str) -> Node<'static, ()> {
+        Node::new(Scripted {
+            name,
+            script,
+            next: 0,
+            running: false,
+            log: Rc::clone(log),
+        })
+    }
+
+    /// Ticks `node` once per expected line, checking the status and the log.
+    fn check(log: &Log, mut node: Node<'_, ()>, trace: &[(&str, Status)]) {
+        for (i, &(expected, status)) in trace.iter().enumerate() {
+            assert_eq!(node.tick(&mut ()), status, "status of tick {}", i);
+            assert_eq!(
+                log.borrow_mut().split_off(0).join(" "),
+                expected,
+                "tick {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn sequence_restarts_after_failure() {
+        let log = Log::default();
+        let node = Sequence::new(vec![leaf(&log, "A", "S"), leaf(&log, "B", "RF")]);
+        check(&log, node, &trace("sequence_restarts_after_failure"));
+    }
+
+    #[test]
+    fn sequence_with_memory_resumes_at_failed_child() {
+        let log = Log::default();
+        let node = SequenceWithMemory::new(vec![leaf(&log, "A", "S"), leaf(&log, "B", "FS")]);
+        check(
+            &log,
+            node,
+            &trace("sequence_with_memory_resumes_at_failed_child"),
+        );
+
+        let node: Node<()> = SequenceStar::new(vec![]);
+        assert_eq!(node.name(), "SequenceWithMemory");
+    }
+
+    #[test]
+    fn reactive_sequence_halts_running_child_on_failure() {
+        let log = Log::default();
+        let node = ReactiveSequence::new(vec![leaf(&log, "A", "SSF"), leaf(&log, "B", "R")]);
+        check(
+            &log,
+            node,
+            &trace("reactive_sequence_halts_running_child_on_failure"),
+        );
+    }
+
+    #[test]
+    fn fallback_resumes_running_child() {
+        let log = Log::default();
+        let node = Fallback::new(vec![leaf(&log, "A", "F"), leaf(&log, "B", "RS")]);
+        check(&log, node, &trace("fallback_resumes_running_child"));
+    }
+
+    #[test]
+    fn reactive_fallback_rechecks_earlier_children() {
+        let log = Log::default();
+        let node = ReactiveFallback::new(vec![leaf(&log, "A", "FS"), leaf(&log, "B", "R")]);
+        check(
+            &log,
+            node,
+            &trace("reactive_fallback_rechecks_earlier_children"),
+        );
+    }
+
+    #[test]
+    fn retry_and_repeat_loop_within_a_tick() {
+        let log = Log::default();
+        let node = RetryUntilSuccessful::new(3, leaf(&log, "A", "FFR"));
+        check(&log, node, &trace("retry_stops_while_running"));
+
+        let node = RetryUntilSuccessful::new(2, leaf(&log, "B", "F"));
+        check(&log, node, &trace("retry_gives_up"));
+
+        let node = Repeat::new(3, leaf(&log, "C", "SSRS"));
+        check(&log, node, &trace("repeat_loops_within_a_tick"));
+    }
+}
@@ -1,8 +1,48 @@
 //! Nodes which query the state of the world.
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
     status::Status,
 };
+use std::{
+    cell::Cell,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// A boolean which can be read through a shared reference, such as a flag set
+/// by an interrupt handler or another thread.
+pub trait Flag {
+    /// Returns the current value of the flag.
+    fn get(&self) -> bool;
+}
+impl Flag for AtomicBool {
+    fn get(&self) -> bool {
+        self.load(Ordering::SeqCst)
+    }
+}
+impl Flag for Cell<bool> {
+    fn get(&self) -> bool {
+        Cell::get(self)
+    }
+}
+impl<T: Flag + ?Sized> Flag for &T {
+    fn get(&self) -> bool {
+        (**self).get()
+    }
+}
+impl<T: Flag + ?Sized> Flag for Rc<T> {
+    fn get(&self) -> bool {
+        (**self).get()
+    }
+}
+impl<T: Flag + ?Sized> Flag for Arc<T> {
+    fn get(&self) -> bool {
+        (**self).get()
+    }
+}
 
 /// A node whose status is determined by a function.
 ///
@@ -62,6 +102,33 @@ where
         };
         Node::new(internals)
     }
+
+    /// Constructs a new Condition node that succeeds while the given flag is
+    /// set.
+    ///
+    /// The flag is read directly rather than from the world, so a simple
+    /// external signal does not need to be part of the world type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::Status;
+    /// # use aspen::node::Tickable;
+    /// # use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    /// let estop = Arc::new(AtomicBool::new(false));
+    /// let mut node = Condition::from_flag(Arc::clone(&estop));
+    ///
+    /// assert_eq!(node.tick(&mut ()), Status::Failed);
+    /// estop.store(true, Ordering::SeqCst);
+    /// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+    /// ```
+    pub fn from_flag<F>(flag: F) -> Node<'a, W>
+    where
+        F: Flag + 'a,
+    {
+        Condition::new(move |_| flag.get())
+    }
 }
 impl<'a, W> Tickable<W> for Condition<'a, W> {
     fn tick(&mut self, world: &mut W) -> Status {
@@ -73,6 +140,14 @@ impl<'a, W> Tickable<W> for Condition<'a, W> {
         }
     }
 
+    fn dry_tick(&self, world: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        if (*self.func)(world) {
+            Some(Status::Succeeded)
+        } else {
+            Some(Status::Failed)
+        }
+    }
+
     fn reset(&mut self) {
         // No-op
     }
@@ -85,6 +160,9 @@ impl<'a, W> Tickable<W> for Condition<'a, W> {
 
 /// Convenience macro for creating Condition nodes.
 ///
+/// An optional string literal before the function is used as the name of
+/// the node.
+///
 /// # Examples
 ///
 /// ```
@@ -92,10 +170,14 @@ impl<'a, W> Tickable<W> for Condition<'a, W> {
 /// # fn test(_: &()) -> bool { false }
 /// # fn main() {
 /// let condition = Condition! { |s| test(s) };
+/// let named = Condition! { "Test", |s| test(s) };
 /// # }
 /// ```
 #[macro_export]
 macro_rules! Condition {
+    ( $name:literal, $e:expr ) => {
+        $crate::std_nodes::Condition::new($e).named(Some($name))
+    };
     ( $e:expr ) => {
         $crate::std_nodes::Condition::new($e)
     };
@@ -104,6 +186,7 @@ macro_rules! Condition {
 #[cfg(test)]
 mod tests {
     use crate::{node::Tickable, status::Status, std_nodes::Condition};
+    use std::cell::Cell;
 
     #[test]
     fn failure() {
@@ -116,4 +199,19 @@ mod tests {
         let mut cond = Condition::new(|_| true);
         assert_eq!(cond.tick(&mut ()), Status::Succeeded);
     }
+
+    #[test]
+    fn flags_are_read_directly() {
+        let flag = Cell::new(true);
+        let mut cond = Condition::from_flag(&flag);
+        assert_eq!(cond.tick(&mut ()), Status::Succeeded);
+        flag.set(false);
+        assert_eq!(cond.tick(&mut ()), Status::Failed);
+    }
+
+    #[test]
+    fn macro_sets_name() {
+        let cond = crate::Condition! { "BatteryOk", |_: &()| true };
+        assert_eq!(cond.name(), "BatteryOk");
+    }
 }
@@ -1,6 +1,6 @@
 //! Nodes that have a constant behavior.
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
     status::Status,
 };
 
@@ -84,13 +84,23 @@ impl<'a, W> Tickable<W> for AlwaysFail<'a, W> {
         Status::Failed
     }
 
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if let Some(ref child) = self.child {
+            if !run.tick(child, world).is_done() {
+                return Some(Status::Running);
+            }
+        }
+
+        Some(Status::Failed)
+    }
+
     fn reset(&mut self) {
         if let Some(ref mut child) = self.child {
             child.reset();
         }
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         if let Some(ref child) = self.child {
             vec![child]
         } else {
@@ -219,7 +229,17 @@ impl<'a, W> Tickable<W> for AlwaysSucceed<'a, W> {
         Status::Succeeded
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if let Some(ref child) = self.child {
+            if !run.tick(child, world).is_done() {
+                return Some(Status::Running);
+            }
+        }
+
+        Some(Status::Succeeded)
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
         if let Some(ref child) = self.child {
             vec![child]
         } else {
@@ -312,6 +332,10 @@ impl<W> Tickable<W> for AlwaysRunning {
         Status::Running
     }
 
+    fn dry_tick(&self, _: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(Status::Running)
+    }
+
     fn reset(&mut self) {
         // No-op
     }
@@ -0,0 +1,219 @@
+//! A selector which rests the children which have recently failed.
+use crate::{
+    clock::{Clock, SystemClock},
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::time::{Duration, Instant};
+
+/// A child of a `CooldownSelector`, with its cooldown.
+struct Cooled<'a, W> {
+    /// How long the child is skipped for after it fails.
+    cooldown: Duration,
+
+    /// When the child may next be ticked, if it is cooling down.
+    until: Option<Instant>,
+
+    /// The child itself.
+    node: Node<'a, W>,
+}
+impl<'a, W> Cooled<'a, W> {
+    /// Returns `true` if the child is being skipped at the given time.
+    fn cooling(&self, now: Instant) -> bool {
+        match self.until {
+            Some(until) => now < until,
+            None => false,
+        }
+    }
+}
+
+/// A selector that skips the children which failed recently.
+///
+/// Each child is given with a cooldown. Every tick, this node ticks its
+/// children in order until one of them does not fail, like a `Selector`. When
+/// a child fails, it is skipped as if it had failed again until its cooldown
+/// has passed, so a high priority child which keeps failing straight away
+/// cannot stop the children after it from getting anything done.
+///
+/// Cooldowns last through resets of this node, since they are about how
+/// recently a child failed rather than about the current attempt.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** The latest ticked child is running.
+///
+/// **Succeeded:** The latest ticked child succeeded.
+///
+/// **Failed:** Every child failed or is cooling down.
+///
+/// # Children
+///
+/// Any number. Children after the one which did not fail are reset, as are
+/// children which are skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::clock::ManualClock;
+/// # use std::time::Duration;
+/// let clock = ManualClock::new();
+/// let mut node = CooldownSelector::new()
+///     .with_clock(clock.clone())
+///     .with_child(
+///         Duration::from_secs(5),
+///         AlwaysFail::new().named(Some("grasp")),
+///     )
+///     .with_child(
+///         Duration::from_secs(0),
+///         AlwaysRunning::new().named(Some("search")),
+///     )
+///     .into_node();
+///
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// assert_eq!(node.children()[0].status(), Some(Status::Failed));
+///
+/// // The first child is skipped until its cooldown has passed
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// assert_eq!(node.children()[0].status(), None);
+///
+/// clock.advance(Duration::from_secs(5));
+/// node.tick(&mut ());
+/// assert_eq!(node.children()[0].status(), Some(Status::Failed));
+/// ```
+pub struct CooldownSelector<'a, W> {
+    /// The children, in order.
+    children: Vec<Cooled<'a, W>>,
+
+    /// The source of the current time.
+    clock: Box<dyn Clock + 'a>,
+}
+impl<'a, W> CooldownSelector<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `CooldownSelector` with no children, using the system
+    /// clock.
+    pub fn new() -> Self {
+        CooldownSelector {
+            children: Vec::new(),
+            clock: Box::new(SystemClock),
+        }
+    }
+
+    /// Sets the clock the cooldowns are measured with.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'a,
+    {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Adds a child after the existing ones, which is skipped for `cooldown`
+    /// after each time it fails.
+    pub fn with_child<T>(mut self, cooldown: Duration, child: T) -> Self
+    where
+        T: Tickable<W> + 'a,
+    {
+        self.children.push(Cooled {
+            cooldown,
+            until: None,
+            node: child.into_node(),
+        });
+        self
+    }
+}
+impl<'a, W> Default for CooldownSelector<'a, W>
+where
+    W: 'a,
+{
+    fn default() -> Self {
+        CooldownSelector::new()
+    }
+}
+impl<'a, W> Tickable<W> for CooldownSelector<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let now = self.clock.now();
+        let mut ret_status = Status::Failed;
+        for child in &mut self.children {
+            if ret_status == Status::Failed && !child.cooling(now) {
+                ret_status = child.node.tick(world);
+                if ret_status == Status::Failed {
+                    child.until = Some(now + child.cooldown);
+                }
+            } else {
+                child.node.reset();
+            }
+        }
+
+        ret_status
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let now = self.clock.now();
+        let mut ret_status = Status::Failed;
+        for child in &self.children {
+            if ret_status != Status::Failed {
+                break;
+            }
+            if !child.cooling(now) {
+                ret_status = run.tick(&child.node, world);
+            }
+        }
+
+        Some(ret_status)
+    }
+
+    fn reset(&mut self) {
+        for child in &mut self.children {
+            child.node.reset();
+        }
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().map(|c| &c.node).collect()
+    }
+
+    /// Returns the string "CooldownSelector".
+    fn type_name(&self) -> &'static str {
+        "CooldownSelector"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::ManualClock,
+        node::Tickable,
+        status::Status,
+        std_nodes::{CooldownSelector, CountedTick, YesTick},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn failed_children_are_skipped_until_cooled_down() {
+        let clock = ManualClock::new();
+        let mut node = CooldownSelector::new()
+            .with_clock(clock.clone())
+            .with_child(
+                Duration::from_secs(2),
+                CountedTick::new(Status::Failed, 2, true),
+            )
+            .with_child(Duration::from_secs(0), YesTick::new(Status::Succeeded));
+
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+
+        // Resets do not end the cooldown
+        node.reset();
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+    }
+}
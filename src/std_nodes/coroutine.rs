@@ -0,0 +1,198 @@
+//! Nodes that run resumable tasks within the ticking thread.
+use crate::{
+    node::{Node, Tickable},
+    status::Status,
+};
+
+/// The result of running one step of a `Coroutine`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Step<S> {
+    /// The task is not done yet. The next tick will resume from the given
+    /// state.
+    Yield(S),
+
+    /// The task completed successfully.
+    Succeed,
+
+    /// The task failed.
+    Fail,
+}
+
+/// Function which runs a single step of a `Coroutine`.
+type StepFn<'a, W, S> = dyn FnMut(S, &mut W) -> Step<S> + 'a;
+
+/// A node that runs a multi-tick task written as a state machine.
+///
+/// Each tick runs a single step of the task: the supplied function is given the
+/// current state and returns either the state to resume from on the next tick
+/// or the outcome of the task. This makes it easy to write actions that take
+/// several ticks to complete without needing a separate thread, and without
+/// hand-writing a `Tickable`.
+///
+/// The state starts as a clone of the initial state every time the node is
+/// reset.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the function yields a new state.
+///
+/// **Succeeded:** When the function returns `Step::Succeed`.
+///
+/// **Failed:** When the function returns `Step::Fail`.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// A task that opens a gripper, waits for it to open, then closes it again:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// #[derive(Clone)]
+/// enum Grip {
+///     Open,
+///     Wait(u32),
+///     Close,
+/// }
+///
+/// let mut node = Coroutine::new(Grip::Open, |state, open: &mut bool| match state {
+///     Grip::Open => {
+///         *open = true;
+///         Step::Yield(Grip::Wait(2))
+///     }
+///     Grip::Wait(0) => Step::Yield(Grip::Close),
+///     Grip::Wait(n) => Step::Yield(Grip::Wait(n - 1)),
+///     Grip::Close => {
+///         *open = false;
+///         Step::Succeed
+///     }
+/// });
+///
+/// let mut open = false;
+/// for _ in 0..4 {
+///     assert_eq!(node.tick(&mut open), Status::Running);
+/// }
+/// assert_eq!(node.tick(&mut open), Status::Succeeded);
+/// assert!(!open);
+/// ```
+pub struct Coroutine<'a, W, S> {
+    /// The state to start from after a reset.
+    initial: S,
+
+    /// The state to resume from, if the task is in progress.
+    state: Option<S>,
+
+    /// The function which runs a single step of the task.
+    func: Box<StepFn<'a, W, S>>,
+}
+impl<'a, W, S> Coroutine<'a, W, S>
+where
+    W: 'a,
+    S: Clone + 'a,
+{
+    /// Creates a new `Coroutine` node which starts from `initial` and is
+    /// advanced by `step`.
+    pub fn new<F>(initial: S, step: F) -> Node<'a, W>
+    where
+        F: FnMut(S, &mut W) -> Step<S> + 'a,
+    {
+        let internals = Coroutine {
+            initial,
+            state: None,
+            func: Box::new(step),
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W, S> Tickable<W> for Coroutine<'a, W, S>
+where
+    S: Clone,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        let state = match self.state.take() {
+            Some(s) => s,
+            None => self.initial.clone(),
+        };
+
+        match (*self.func)(state, world) {
+            Step::Yield(next) => {
+                self.state = Some(next);
+                Status::Running
+            }
+            Step::Succeed => Status::Succeeded,
+            Step::Fail => Status::Failed,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+
+    /// Returns the string "Coroutine".
+    fn type_name(&self) -> &'static str {
+        "Coroutine"
+    }
+}
+
+/// Convenience macro for creating Coroutine nodes.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate aspen;
+/// # use aspen::std_nodes::Step;
+/// # fn main() {
+/// let countdown = Coroutine! { 3u32, |n, _: &mut ()| {
+///     if n == 0 { Step::Succeed } else { Step::Yield(n - 1) }
+/// }};
+/// # }
+/// ```
+#[macro_export]
+macro_rules! Coroutine {
+    ( $s:expr, $e:expr ) => {
+        $crate::std_nodes::Coroutine::new($s, $e)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{Coroutine, Step},
+    };
+
+    #[test]
+    fn yields_until_done() {
+        let mut node = Coroutine::new(0u32, |n, _: &mut ()| {
+            if n == 2 {
+                Step::Fail
+            } else {
+                Step::Yield(n + 1)
+            }
+        });
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+    }
+
+    #[test]
+    fn reset_restarts() {
+        let mut steps = Vec::new();
+        let mut node = Coroutine::new(0u32, |n, s: &mut Vec<u32>| {
+            s.push(n);
+            Step::Yield(n + 1)
+        });
+        node.tick(&mut steps);
+        node.tick(&mut steps);
+        node.reset();
+        node.tick(&mut steps);
+        assert_eq!(steps, vec![0, 1, 0]);
+    }
+}
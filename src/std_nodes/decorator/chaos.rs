@@ -0,0 +1,232 @@
+use crate::{
+    node::{Node, Tickable},
+    rng::Rng,
+    status::Status,
+};
+use std::{thread, time::Duration};
+
+/// Configuration for a `Chaos` node.
+///
+/// The probabilities are checked independently on every tick. By default none
+/// of the faults are injected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChaosConfig {
+    /// The seed for the random number generator.
+    seed: u64,
+
+    /// The probability of failing instead of ticking the child.
+    failure: f64,
+
+    /// The probability of returning `Running` instead of ticking the child.
+    running: f64,
+
+    /// The probability of sleeping before ticking the child.
+    delay: f64,
+
+    /// How long to sleep when a delay is injected.
+    delay_for: Duration,
+}
+impl ChaosConfig {
+    /// Creates a configuration which injects no faults, with the given seed.
+    ///
+    /// The same seed and probabilities always inject the same faults on the
+    /// same ticks, so a failing run can be reproduced.
+    pub fn new(seed: u64) -> Self {
+        ChaosConfig {
+            seed,
+            failure: 0.0,
+            running: 0.0,
+            delay: 0.0,
+            delay_for: Duration::from_secs(0),
+        }
+    }
+
+    /// Sets the probability of failing instead of ticking the child.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between zero and one.
+    pub fn failure(mut self, probability: f64) -> Self {
+        self.failure = check(probability);
+        self
+    }
+
+    /// Sets the probability of returning a spurious `Running` instead of
+    /// ticking the child.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between zero and one.
+    pub fn running(mut self, probability: f64) -> Self {
+        self.running = check(probability);
+        self
+    }
+
+    /// Sets the probability of sleeping for `duration` before ticking the
+    /// child, to simulate a slow subsystem.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between zero and one.
+    pub fn delay(mut self, probability: f64, duration: Duration) -> Self {
+        self.delay = check(probability);
+        self.delay_for = duration;
+        self
+    }
+}
+
+/// Checks that a probability is valid.
+fn check(probability: f64) -> f64 {
+    assert!(
+        (0.0..=1.0).contains(&probability),
+        "probability {} is not between zero and one",
+        probability
+    );
+    probability
+}
+
+/// A node that randomly injects faults into its child, for robustness testing.
+///
+/// On each tick, this node may fail or return `Running` without ticking its
+/// child, or sleep before ticking it, according to the probabilities in its
+/// `ChaosConfig`. Otherwise it has the same status as its child. Wrapping flaky
+/// subsystems in this node while testing shows whether the rest of the tree
+/// copes with them before it meets them in the field.
+///
+/// The faults are chosen by a generator seeded from the configuration, which
+/// is reseeded when the node is reset, so runs are reproducible.
+///
+/// This node is only available with the `chaos` feature, which is intended
+/// for test builds.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running, or when a spurious `Running` is
+/// injected.
+///
+/// **Succeeded:** When the child succeeds.
+///
+/// **Failed:** When the child fails, or when a failure is injected.
+///
+/// # Children
+///
+/// One. The child is reset when a failure is injected while it is running, and
+/// whenever this node is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let config = ChaosConfig::new(7).failure(0.2).running(0.1);
+/// let mut node = Chaos::new(config, AlwaysSucceed::new());
+///
+/// let failures = (0..1000)
+///     .filter(|_| node.tick(&mut ()) == Status::Failed)
+///     .count();
+/// assert!(failures > 100 && failures < 300);
+/// ```
+pub struct Chaos<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The faults to inject.
+    config: ChaosConfig,
+
+    /// The generator choosing the faults.
+    rng: Rng,
+}
+impl<'a, W> Chaos<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Chaos` node which injects faults into `child`.
+    pub fn new(config: ChaosConfig, child: Node<'a, W>) -> Node<'a, W> {
+        let internals = Chaos {
+            child,
+            rng: Rng::new(config.seed),
+            config,
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Tickable<W> for Chaos<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.rng.chance(self.config.failure) {
+            debug!("Injecting a failure into {}", self.child.name());
+            self.child.reset();
+            return Status::Failed;
+        }
+
+        if self.rng.chance(self.config.running) {
+            debug!("Injecting a spurious Running into {}", self.child.name());
+            return Status::Running;
+        }
+
+        if self.rng.chance(self.config.delay) {
+            debug!("Injecting a delay into {}", self.child.name());
+            thread::sleep(self.config.delay_for);
+        }
+
+        self.child.tick(world)
+    }
+
+    fn reset(&mut self) {
+        self.rng = Rng::new(self.config.seed);
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "Chaos".
+    fn type_name(&self) -> &'static str {
+        "Chaos"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysSucceed, Chaos, ChaosConfig, CountedTick, NoTick},
+    };
+
+    #[test]
+    fn no_faults_by_default() {
+        let mut node = Chaos::new(
+            ChaosConfig::new(1),
+            CountedTick::new(Status::Succeeded, 10, true),
+        );
+        for _ in 0..10 {
+            assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        }
+        drop(node);
+    }
+
+    #[test]
+    fn certain_failure_never_ticks_child() {
+        let mut node = Chaos::new(ChaosConfig::new(1).failure(1.0), NoTick::new());
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+    }
+
+    #[test]
+    fn same_seed_same_faults() {
+        let config = ChaosConfig::new(42).failure(0.3).running(0.3);
+        let mut a = Chaos::new(config.clone(), AlwaysSucceed::new());
+        let mut b = Chaos::new(config, AlwaysSucceed::new());
+
+        let first: Vec<_> = (0..50).map(|_| a.tick(&mut ())).collect();
+        let second: Vec<_> = (0..50).map(|_| b.tick(&mut ())).collect();
+        assert_eq!(first, second);
+
+        a.reset();
+        let replay: Vec<_> = (0..50).map(|_| a.tick(&mut ())).collect();
+        assert_eq!(first, replay);
+    }
+}
@@ -1,13 +1,18 @@
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
     status::Status,
 };
 
+/// Function run on the status of a `Decorator`'s child.
+type DecoratorFn<'a, W> = dyn FnMut(Status, &mut W) -> Status + 'a;
+
 /// A node whose status is determined by running a function on its child's
 /// status.
 ///
 /// This node will tick its child and then run the supplied function on the
-/// child's return status.
+/// child's return status. Functions created with `Decorator::new_mut` are also
+/// given mutable access to the world, so they can record outcomes (such as
+/// counting failures) without needing a custom node.
 ///
 /// # State
 ///
@@ -47,9 +52,28 @@ use crate::{
 /// let mut node = Decorator::new(child, invert);
 /// assert_eq!(node.tick(&mut ()), Status::Failed);
 /// ```
+///
+/// A decorator that counts the failures of its child, but always succeeds:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = Decorator::new_mut(AlwaysFail::new(), |s, failures: &mut u32| {
+///     if s == Status::Failed {
+///         *failures += 1;
+///     }
+///     Status::Succeeded
+/// });
+///
+/// let mut failures = 0;
+/// assert_eq!(node.tick(&mut failures), Status::Succeeded);
+/// assert_eq!(node.tick(&mut failures), Status::Succeeded);
+/// assert_eq!(failures, 2);
+/// ```
 pub struct Decorator<'a, W> {
     /// Function that is performed on the child's status.
-    func: Box<dyn Fn(Status, &W) -> Status + 'a>,
+    func: Box<DecoratorFn<'a, W>>,
 
     /// Child node.
     child: Node<'a, W>,
@@ -63,6 +87,15 @@ where
     pub fn new<F>(child: Node<'a, W>, func: F) -> Node<'a, W>
     where
         F: Fn(Status, &W) -> Status + 'a,
+    {
+        Self::new_mut(child, move |s, w: &mut W| func(s, w))
+    }
+
+    /// Creates a new Decorator node with the supplied child node and function
+    /// to be run on the child's status, which may also modify the world.
+    pub fn new_mut<F>(child: Node<'a, W>, func: F) -> Node<'a, W>
+    where
+        F: FnMut(Status, &mut W) -> Status + 'a,
     {
         let internals = Decorator {
             func: Box::new(func),
@@ -79,11 +112,18 @@ impl<'a, W> Tickable<W> for Decorator<'a, W> {
         (*self.func)(child_status, world)
     }
 
+    /// The function may modify the world, so it is not run during a dry run
+    /// and this node is given a stand-in status.
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        run.tick(&self.child, world);
+        None
+    }
+
     fn reset(&mut self) {
         self.child.reset();
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         vec![&self.child]
     }
 
@@ -93,12 +133,47 @@ impl<'a, W> Tickable<W> for Decorator<'a, W> {
     }
 }
 
+/// Which of a child's completed statuses an `Invert` node inverts.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InvertPolicy {
+    /// Successes become failures and failures become successes.
+    Both,
+
+    /// Failures become successes. Successes are left untouched.
+    Failures,
+
+    /// Successes become failures. Failures are left untouched.
+    Successes,
+}
+impl InvertPolicy {
+    /// Returns the status after applying this policy to `status`.
+    fn apply(self, status: Status) -> Status {
+        match (status, self) {
+            (Status::Succeeded, InvertPolicy::Both)
+            | (Status::Succeeded, InvertPolicy::Successes) => Status::Failed,
+            (Status::Failed, InvertPolicy::Both) | (Status::Failed, InvertPolicy::Failures) => {
+                Status::Succeeded
+            }
+            (s, _) => s,
+        }
+    }
+}
+impl Default for InvertPolicy {
+    fn default() -> Self {
+        InvertPolicy::Both
+    }
+}
+
 /// A node that returns the opposite completed status from its child.
 ///
 /// This node inverts the compeleted status of its child node. If the child
 /// succeeds, this node fails and vice versa. This node is considered running if
 /// the child is running.
 ///
+/// Using `Invert::with_policy`, the node can instead invert only one of the
+/// completed statuses. This is useful when adapting a condition whose polarity
+/// only partially matches the rest of the tree.
+///
 /// # State
 ///
 /// **Initialized:** Before being ticked after either being created or reset.
@@ -125,26 +200,45 @@ impl<'a, W> Tickable<W> for Decorator<'a, W> {
 /// let mut node = Invert::new(AlwaysFail::new());
 /// assert_eq!(node.tick(&mut ()), Status::Succeeded);
 /// ```
+///
+/// Turns failures into successes, but leaves successes alone:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = Invert::with_policy(InvertPolicy::Failures, AlwaysSucceed::new());
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// ```
 pub struct Invert<'a, W> {
     /// Child node.
     child: Node<'a, W>,
+
+    /// Which of the child's statuses are inverted.
+    policy: InvertPolicy,
 }
 impl<'a, W> Invert<'a, W>
 where
     W: 'a,
 {
-    /// Creates a new `Invert` node.
+    /// Creates a new `Invert` node which inverts both successes and failures.
     pub fn new(child: Node<'a, W>) -> Node<'a, W> {
-        Node::new(Invert { child })
+        Self::with_policy(InvertPolicy::Both, child)
+    }
+
+    /// Creates a new `Invert` node which only inverts the statuses selected by
+    /// the policy.
+    pub fn with_policy(policy: InvertPolicy, child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(Invert { child, policy })
     }
 }
 impl<'a, W> Tickable<W> for Invert<'a, W> {
     fn tick(&mut self, world: &mut W) -> Status {
-        match self.child.tick(world) {
-            Status::Succeeded => Status::Failed,
-            Status::Failed => Status::Succeeded,
-            s @ Status::Running => s,
-        }
+        self.policy.apply(self.child.tick(world))
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(self.policy.apply(run.tick(&self.child, world)))
     }
 
     fn reset(&mut self) {
@@ -152,7 +246,7 @@ impl<'a, W> Tickable<W> for Invert<'a, W> {
         self.child.reset();
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         vec![&self.child]
     }
 
@@ -168,10 +262,14 @@ impl<'a, W> Tickable<W> for Invert<'a, W> {
 ///
 /// ```
 /// # #[macro_use] extern crate aspen;
+/// # use aspen::std_nodes::InvertPolicy;
 /// # fn main() {
 /// let invert = Invert! {
 ///     Condition!{ |&a: &u32| a < 9 }
 /// };
+/// let only_failures = Invert! { InvertPolicy::Failures,
+///     Condition!{ |&a: &u32| a < 9 }
+/// };
 /// # }
 /// ```
 #[macro_export]
@@ -179,6 +277,9 @@ macro_rules! Invert {
     ( $e:expr ) => {
         $crate::std_nodes::Invert::new($e)
     };
+    ( $p:expr, $e:expr ) => {
+        $crate::std_nodes::Invert::with_policy($p, $e)
+    };
 }
 
 #[cfg(test)]
@@ -186,7 +287,7 @@ mod tests {
     use crate::{
         node::Tickable,
         status::Status,
-        std_nodes::{Decorator, Invert, YesTick},
+        std_nodes::{Decorator, Invert, InvertPolicy, YesTick},
     };
 
     fn rotate(s: Status, _: &()) -> Status {
@@ -221,6 +322,19 @@ mod tests {
         assert_eq!(fail_status, rotate(Status::Failed, &()));
     }
 
+    #[test]
+    fn decorator_mut_updates_world() {
+        let mut dec = Decorator::new_mut(YesTick::new(Status::Failed), |s, n: &mut u32| {
+            *n += 1;
+            s
+        });
+        let mut count = 0;
+        let status = dec.tick(&mut count);
+        drop(dec);
+        assert_eq!(status, Status::Failed);
+        assert_eq!(count, 1);
+    }
+
     #[test]
     fn invert_success_to_failure() {
         let mut s2f = Invert::new(YesTick::new(Status::Failed));
@@ -244,4 +358,30 @@ mod tests {
         drop(r);
         assert_eq!(rs, Status::Running);
     }
+
+    #[test]
+    fn invert_only_failures() {
+        let mut f = Invert::with_policy(InvertPolicy::Failures, YesTick::new(Status::Failed));
+        let fs = f.tick(&mut ());
+        drop(f);
+        assert_eq!(fs, Status::Succeeded);
+
+        let mut s = Invert::with_policy(InvertPolicy::Failures, YesTick::new(Status::Succeeded));
+        let ss = s.tick(&mut ());
+        drop(s);
+        assert_eq!(ss, Status::Succeeded);
+    }
+
+    #[test]
+    fn invert_only_successes() {
+        let mut s = Invert::with_policy(InvertPolicy::Successes, YesTick::new(Status::Succeeded));
+        let ss = s.tick(&mut ());
+        drop(s);
+        assert_eq!(ss, Status::Failed);
+
+        let mut f = Invert::with_policy(InvertPolicy::Successes, YesTick::new(Status::Failed));
+        let fs = f.tick(&mut ());
+        drop(f);
+        assert_eq!(fs, Status::Failed);
+    }
 }
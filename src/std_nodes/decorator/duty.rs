@@ -0,0 +1,201 @@
+use crate::{
+    clock::{Clock, SystemClock},
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::time::{Duration, Instant};
+
+/// A node that only ticks its child during the "on" part of a repeating
+/// period.
+///
+/// The first period starts on the first tick after the node is created or
+/// reset. For the first `on` of every `period` the child is ticked as usual,
+/// and for the rest of the period this node returns the idle status without
+/// ticking it, so that a scan can run for two seconds out of every ten, for
+/// example. A child which is still running when the "on" part ends is halted
+/// by resetting it. Time is taken from a `Clock`, which is the system clock
+/// unless another is given.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running, or while idle if the idle status is
+/// `Running`.
+///
+/// **Succeeded:** When the child succeeds, or while idle if the idle status is
+/// `Succeeded`.
+///
+/// **Failed:** When the child fails, or while idle if the idle status is
+/// `Failed`.
+///
+/// # Children
+///
+/// One, which is ticked during the "on" part of each period and reset whenever
+/// this node is reset or the "on" part ends.
+///
+/// # Panics
+///
+/// The constructors panic if `on` is longer than `period`.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::clock::ManualClock;
+/// # use std::time::Duration;
+/// let clock = ManualClock::new();
+/// let mut node = DutyCycle::with_clock(
+///     Duration::from_secs(10),
+///     Duration::from_secs(2),
+///     Status::Running,
+///     AlwaysSucceed::new(),
+///     clock.clone(),
+/// );
+///
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// clock.advance(Duration::from_secs(5));
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// clock.advance(Duration::from_secs(5));
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// ```
+pub struct DutyCycle<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The length of each period.
+    period: Duration,
+
+    /// How long the child is ticked for at the start of each period.
+    on: Duration,
+
+    /// The status returned for the rest of each period.
+    idle: Status,
+
+    /// The source of the current time.
+    clock: Box<dyn Clock + 'a>,
+
+    /// When the first period started.
+    started: Option<Instant>,
+}
+impl<'a, W> DutyCycle<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `DutyCycle` node using the system clock.
+    pub fn new(period: Duration, on: Duration, idle: Status, child: Node<'a, W>) -> Node<'a, W> {
+        DutyCycle::with_clock(period, on, idle, child, SystemClock)
+    }
+
+    /// Creates a new `DutyCycle` node using the given clock.
+    pub fn with_clock<C>(
+        period: Duration,
+        on: Duration,
+        idle: Status,
+        child: Node<'a, W>,
+        clock: C,
+    ) -> Node<'a, W>
+    where
+        C: Clock + 'a,
+    {
+        assert!(on <= period, "the on time must fit in the period");
+        let internals = DutyCycle {
+            child,
+            period,
+            on,
+            idle,
+            clock: Box::new(clock),
+            started: None,
+        };
+        Node::new(internals)
+    }
+
+    /// Returns `true` if the given time is in the "on" part of a period.
+    fn is_on(&self, now: Instant) -> bool {
+        let started = match self.started {
+            Some(t) => t,
+            None => return true,
+        };
+        if self.period == Duration::from_secs(0) {
+            return true;
+        }
+
+        let elapsed = now.saturating_duration_since(started).as_nanos();
+        elapsed % self.period.as_nanos() < self.on.as_nanos()
+    }
+}
+impl<'a, W> Tickable<W> for DutyCycle<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let now = self.clock.now();
+        if self.started.is_none() {
+            self.started = Some(now);
+        }
+
+        if self.is_on(now) {
+            return self.child.tick(world);
+        }
+
+        if self.child.status() == Some(Status::Running) {
+            debug!("Pausing {} until the next period", self.child.name());
+            self.child.reset();
+        }
+        self.idle
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if run.after_reset() || self.is_on(self.clock.now()) {
+            Some(run.tick(&self.child, world))
+        } else {
+            Some(self.idle)
+        }
+    }
+
+    fn reset(&mut self) {
+        self.started = None;
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "DutyCycle".
+    fn type_name(&self) -> &'static str {
+        "DutyCycle"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::ManualClock,
+        node::Tickable,
+        status::Status,
+        std_nodes::{CountedTick, DutyCycle},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn child_is_halted_while_off() {
+        let clock = ManualClock::new();
+        let child = CountedTick::resetable(Status::Running, 1, true);
+        let mut node = DutyCycle::with_clock(
+            Duration::from_secs(10),
+            Duration::from_secs(2),
+            Status::Failed,
+            child,
+            clock.clone(),
+        );
+
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+        assert_eq!(node.children()[0].status(), None);
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(node.tick(&mut ()), Status::Running);
+    }
+}
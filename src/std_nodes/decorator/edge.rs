@@ -0,0 +1,231 @@
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+
+/// Returns the status of an edge node which looks for its child completing
+/// with `edge`, given the status of the child and the status it last
+/// completed with.
+fn edge_status(edge: Status, status: Status, last: Option<Status>) -> Status {
+    if status == Status::Running {
+        Status::Running
+    } else if status == edge && last != Some(edge) {
+        Status::Succeeded
+    } else {
+        Status::Failed
+    }
+}
+
+/// A node that succeeds only on the tick where its child starts succeeding.
+///
+/// The child is ticked every time this node is. This node succeeds when the
+/// child succeeds having last completed with some other status, or never
+/// having completed before, and fails when the child completes otherwise. A
+/// side effect placed after this node in a `Sequence` is therefore run once
+/// each time the child goes from failing to succeeding, rather than on every
+/// tick that it succeeds.
+///
+/// The last completed status of the child is kept when this node is reset, so
+/// that an edge is only seen once, however often the tree is restarted.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running.
+///
+/// **Succeeded:** When the child succeeds after last completing with
+/// another status.
+///
+/// **Failed:** When the child fails, or succeeds as it did last time.
+///
+/// # Children
+///
+/// One, which is ticked every time this node is and reset whenever this node
+/// is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = OnSuccessEdge::new(Condition::new(|&pressed: &bool| pressed));
+///
+/// assert_eq!(node.tick(&mut true), Status::Succeeded);
+/// assert_eq!(node.tick(&mut true), Status::Failed);
+/// assert_eq!(node.tick(&mut false), Status::Failed);
+/// assert_eq!(node.tick(&mut true), Status::Succeeded);
+/// ```
+pub struct OnSuccessEdge<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The status the child last completed with.
+    last: Option<Status>,
+}
+impl<'a, W> OnSuccessEdge<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `OnSuccessEdge` node.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(OnSuccessEdge { child, last: None })
+    }
+}
+impl<'a, W> Tickable<W> for OnSuccessEdge<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let status = self.child.tick(world);
+        let edge = edge_status(Status::Succeeded, status, self.last);
+        if status.is_done() {
+            self.last = Some(status);
+        }
+        edge
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let status = run.tick(&self.child, world);
+        Some(edge_status(Status::Succeeded, status, self.last))
+    }
+
+    fn reset(&mut self) {
+        // The last status is kept so that edges are not seen twice
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "OnSuccessEdge".
+    fn type_name(&self) -> &'static str {
+        "OnSuccessEdge"
+    }
+}
+
+/// A node that succeeds only on the tick where its child starts failing.
+///
+/// This is the same as `OnSuccessEdge`, but looking for the child changing
+/// to failing. The child is ticked every time this node is. This node
+/// succeeds when the child fails having last completed with some other
+/// status, or never having completed before, and fails when the child
+/// completes otherwise.
+///
+/// The last completed status of the child is kept when this node is reset.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running.
+///
+/// **Succeeded:** When the child fails after last completing with another
+/// status.
+///
+/// **Failed:** When the child succeeds, or fails as it did last time.
+///
+/// # Children
+///
+/// One, which is ticked every time this node is and reset whenever this node
+/// is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = OnFailureEdge::new(Condition::new(|&connected: &bool| connected));
+///
+/// assert_eq!(node.tick(&mut true), Status::Failed);
+/// assert_eq!(node.tick(&mut false), Status::Succeeded);
+/// assert_eq!(node.tick(&mut false), Status::Failed);
+/// ```
+pub struct OnFailureEdge<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The status the child last completed with.
+    last: Option<Status>,
+}
+impl<'a, W> OnFailureEdge<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `OnFailureEdge` node.
+    pub fn new(child: Node<'a, W>) -> Node<'a, W> {
+        Node::new(OnFailureEdge { child, last: None })
+    }
+}
+impl<'a, W> Tickable<W> for OnFailureEdge<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let status = self.child.tick(world);
+        let edge = edge_status(Status::Failed, status, self.last);
+        if status.is_done() {
+            self.last = Some(status);
+        }
+        edge
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let status = run.tick(&self.child, world);
+        Some(edge_status(Status::Failed, status, self.last))
+    }
+
+    fn reset(&mut self) {
+        // The last status is kept so that edges are not seen twice
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "OnFailureEdge".
+    fn type_name(&self) -> &'static str {
+        "OnFailureEdge"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{InlineAction, OnFailureEdge, OnSuccessEdge},
+    };
+
+    #[test]
+    fn edges_survive_running_and_resets() {
+        // The child returns whatever status the world holds
+        let child = || InlineAction::new(|s: &mut Status| *s);
+        let mut success = OnSuccessEdge::new(child());
+        let mut failure = OnFailureEdge::new(child());
+
+        let statuses = [
+            Status::Running,
+            Status::Failed,
+            Status::Running,
+            Status::Failed,
+            Status::Succeeded,
+        ];
+        let mut seen = Vec::new();
+        for &status in &statuses {
+            let mut world = status;
+            seen.push((success.tick(&mut world), failure.tick(&mut world)));
+            success.reset();
+            failure.reset();
+        }
+
+        assert_eq!(
+            seen,
+            vec![
+                (Status::Running, Status::Running),
+                (Status::Failed, Status::Succeeded),
+                (Status::Running, Status::Running),
+                (Status::Failed, Status::Failed),
+                (Status::Succeeded, Status::Failed),
+            ]
+        );
+    }
+}
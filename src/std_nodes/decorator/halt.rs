@@ -0,0 +1,219 @@
+use crate::{
+    node::{self, DryRun, Node, Tickable},
+    status::Status,
+};
+use std::cell::{Cell, RefCell};
+
+/// A node that runs a cleanup node when its child is halted while running.
+///
+/// A running child is halted when it is reset, which happens when a parent
+/// preempts it for a sibling, or when the tree itself is reset. Halting stops
+/// the child at once, which leaves hardware in whatever state it was in, so
+/// this node then runs its cleanup to put things right, such as retracting an
+/// arm which was part way through grasping.
+///
+/// The cleanup is ticked until it completes, over as many ticks as it takes.
+/// Since the parent has usually moved on, a `BehaviorTree` ticks pending
+/// cleanups itself after each of its ticks. If this node is ticked again
+/// before its cleanup is done, it ticks the cleanup instead of its child and
+/// is running until the cleanup completes. The cleanup is not started when
+/// the child completes by itself, or when a child which has not started is
+/// reset.
+///
+/// `Node::on_halt` is a shorter way to create this node.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running, or while the cleanup is running.
+///
+/// **Succeeded:** When the child succeeds.
+///
+/// **Failed:** When the child fails.
+///
+/// # Children
+///
+/// One, which is ticked every time this node is unless a cleanup is pending,
+/// and reset whenever this node is reset. The cleanup is not counted as a
+/// child.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::{BehaviorTree, Status};
+/// let mut retracted = false;
+/// let grasp = AlwaysRunning::new().on_halt(InlineAction::new(|retracted: &mut bool| {
+///     *retracted = true;
+///     Status::Succeeded
+/// }));
+/// let mut tree = BehaviorTree::new(grasp);
+///
+/// assert_eq!(tree.tick(&mut retracted), Status::Running);
+/// tree.reset();
+/// assert!(!retracted);
+/// tree.tick(&mut retracted);
+/// assert!(retracted);
+/// ```
+pub struct OnHalt<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The node run when the child is halted.
+    cleanup: RefCell<Node<'a, W>>,
+
+    /// Whether the cleanup has been started and has not yet finished.
+    pending: Cell<bool>,
+
+    /// Whether the cleanup was ticked by this node since the tree last ticked
+    /// the pending cleanups.
+    ticked: Cell<bool>,
+}
+impl<'a, W> OnHalt<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `OnHalt` node which runs `cleanup` whenever `child` is
+    /// halted while running.
+    pub fn new(child: Node<'a, W>, cleanup: Node<'a, W>) -> Node<'a, W> {
+        let internals = OnHalt {
+            child,
+            cleanup: RefCell::new(cleanup),
+            pending: Cell::new(false),
+            ticked: Cell::new(false),
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> OnHalt<'a, W> {
+    /// Ticks the pending cleanup once.
+    fn run_cleanup(&self, world: &mut W) {
+        let mut cleanup = self.cleanup.borrow_mut();
+        let status = cleanup.tick(world);
+        if !status.is_done() {
+            return;
+        }
+
+        if status == Status::Failed {
+            warn!("Cleanup of halted node {} failed", self.child.name());
+        } else {
+            debug!("Cleanup of halted node {} finished", self.child.name());
+        }
+        cleanup.reset();
+        self.pending.set(false);
+        node::finish_cleanup();
+    }
+}
+impl<'a, W> Tickable<W> for OnHalt<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.pending.get() {
+            self.run_cleanup(world);
+            if self.pending.get() {
+                self.ticked.set(true);
+                return Status::Running;
+            }
+        }
+
+        self.child.tick(world)
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if self.pending.get() {
+            None
+        } else {
+            Some(run.tick(&self.child, world))
+        }
+    }
+
+    fn reset(&mut self) {
+        if self.child.status() == Some(Status::Running) && !self.pending.get() {
+            debug!("Halted node {}, starting its cleanup", self.child.name());
+            self.pending.set(true);
+            node::start_cleanup();
+        }
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    fn tick_cleanup(&self, world: &mut W) {
+        if self.ticked.replace(false) {
+            return;
+        }
+        if self.pending.get() {
+            self.run_cleanup(world);
+        }
+    }
+
+    /// Returns the string "OnHalt".
+    fn type_name(&self) -> &'static str {
+        "OnHalt"
+    }
+}
+impl<'a, W> Drop for OnHalt<'a, W> {
+    fn drop(&mut self) {
+        if self.pending.get() {
+            node::finish_cleanup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        status::Status,
+        std_nodes::{
+            AlwaysRunning, AlwaysSucceed, Condition, CountedTick, InlineAction, NoTick, Selector,
+        },
+        BehaviorTree,
+    };
+
+    #[test]
+    fn preempted_child_is_cleaned_up() {
+        // The world is whether to preempt, and how often the cleanup was ticked
+        let cleanup = InlineAction::new(|w: &mut (bool, u32)| {
+            w.1 += 1;
+            if w.1 < 2 {
+                Status::Running
+            } else {
+                Status::Succeeded
+            }
+        });
+        let mut tree = BehaviorTree::new(Selector::new(vec![
+            Condition::new(|w: &(bool, u32)| w.0),
+            AlwaysRunning::new().on_halt(cleanup),
+        ]));
+        let mut world = (false, 0);
+        assert_eq!(tree.tick(&mut world), Status::Running);
+        world.0 = true;
+        assert_eq!(tree.tick(&mut world), Status::Succeeded);
+        assert_eq!(world.1, 1);
+        tree.tick(&mut world);
+        tree.tick(&mut world);
+        assert_eq!(world.1, 2);
+
+        // Ticking the node again runs the cleanup rather than the child, and
+        // the tree does not tick the cleanup a second time
+        let mut tree = BehaviorTree::new(
+            CountedTick::new(Status::Running, 1, true).on_halt(CountedTick::new(
+                Status::Running,
+                2,
+                true,
+            )),
+        );
+        assert_eq!(tree.tick(&mut ()), Status::Running);
+        tree.reset();
+        assert_eq!(tree.tick(&mut ()), Status::Running);
+        assert_eq!(tree.tick(&mut ()), Status::Running);
+        drop(tree);
+
+        // Nothing is cleaned up when the child completes by itself
+        let mut tree = BehaviorTree::new(AlwaysSucceed::new().on_halt(NoTick::new()));
+        assert_eq!(tree.tick(&mut ()), Status::Succeeded);
+        tree.reset();
+        assert_eq!(tree.tick(&mut ()), Status::Succeeded);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::borrow::Cow;
+
+/// Function which checks an invariant of the world.
+type InvariantFn<'a, W> = dyn Fn(&W) -> bool + 'a;
+
+/// A node that checks an invariant of the world before and after ticking its
+/// child.
+///
+/// This is a lightweight form of design by contract: the predicate states
+/// something that must always hold while the child runs, such as "the gripper
+/// is never open while moving". If the predicate does not hold before or after
+/// the child is ticked, the violation is reported with the description of the
+/// invariant and the name of the child.
+///
+/// In debug builds, a violation panics, so it is caught straight away in tests.
+/// In release builds, it is logged as an error, the child is reset, and this
+/// node fails.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running and the invariant holds.
+///
+/// **Succeeded:** When the child succeeds and the invariant holds.
+///
+/// **Failed:** When the child fails, or, in release builds, when the invariant
+/// is violated.
+///
+/// # Children
+///
+/// One, which is not ticked if the invariant is violated beforehand.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = AssertInvariant::new(
+///     "speed is never negative",
+///     |&speed: &i32| speed >= 0,
+///     InlineAction::new(|speed: &mut i32| {
+///         *speed -= 1;
+///         Status::Succeeded
+///     }),
+/// );
+///
+/// assert_eq!(node.tick(&mut 1), Status::Succeeded);
+/// ```
+pub struct AssertInvariant<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// States the invariant, for error messages.
+    description: Cow<'static, str>,
+
+    /// Checks the invariant.
+    predicate: Box<InvariantFn<'a, W>>,
+}
+impl<'a, W> AssertInvariant<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `AssertInvariant` node which checks `predicate` around
+    /// each tick of `child`.
+    pub fn new<D, F>(description: D, predicate: F, child: Node<'a, W>) -> Node<'a, W>
+    where
+        D: Into<Cow<'static, str>>,
+        F: Fn(&W) -> bool + 'a,
+    {
+        let internals = AssertInvariant {
+            child,
+            description: description.into(),
+            predicate: Box::new(predicate),
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> AssertInvariant<'a, W> {
+    /// Reports a violation of the invariant, which happened `when` the child
+    /// was ticked.
+    fn violated(&mut self, when: &str) -> Status {
+        let message = format!(
+            "Invariant \"{}\" violated {} ticking {}",
+            self.description,
+            when,
+            self.child.name()
+        );
+        if cfg!(debug_assertions) {
+            panic!("{}", message);
+        }
+
+        error!("{}", message);
+        self.child.reset();
+        Status::Failed
+    }
+}
+impl<'a, W> Tickable<W> for AssertInvariant<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if !(*self.predicate)(world) {
+            return self.violated("before");
+        }
+
+        let status = self.child.tick(world);
+        if !(*self.predicate)(world) {
+            return self.violated("after");
+        }
+
+        status
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if !(*self.predicate)(world) {
+            return Some(Status::Failed);
+        }
+
+        Some(run.tick(&self.child, world))
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "AssertInvariant".
+    fn type_name(&self) -> &'static str {
+        "AssertInvariant"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{AssertInvariant, InlineAction, YesTick},
+    };
+
+    #[test]
+    fn passes_through_while_invariant_holds() {
+        let mut node = AssertInvariant::new("always", |_: &()| true, YesTick::new(Status::Running));
+        let status = node.tick(&mut ());
+        drop(node);
+        assert_eq!(status, Status::Running);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Invariant \"n is even\" violated after ticking Bump")]
+    fn violation_panics_in_debug_builds() {
+        let mut node = AssertInvariant::new(
+            "n is even",
+            |n: &u32| n % 2 == 0,
+            InlineAction::new(|n: &mut u32| {
+                *n += 1;
+                Status::Succeeded
+            })
+            .named(Some("Bump")),
+        );
+        node.tick(&mut 0);
+    }
+}
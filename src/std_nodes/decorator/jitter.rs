@@ -0,0 +1,207 @@
+use crate::{
+    clock::{Clock, SystemClock},
+    node::{DryRun, Node, Tickable},
+    rng::Rng,
+    status::Status,
+};
+use std::time::{Duration, Instant};
+
+/// A node that waits for a random time before first ticking its child.
+///
+/// On the first tick after the node is created or reset, a delay is picked
+/// between `min` and `max`. Until it has passed this node is running without
+/// ticking its child, and from then on it has the same status as its child.
+/// Giving identical trees across a fleet different seeds spreads out their
+/// start times, so that they do not all hit a shared resource at once.
+///
+/// The delays come from a pseudo-random generator seeded with `seed`, so the
+/// same seed always gives the same sequence of delays. The sequence carries on
+/// across resets. Time is taken from a `Clock`, which is the system clock
+/// unless another is given.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While waiting, or while the child is running.
+///
+/// **Succeeded:** When the child succeeds.
+///
+/// **Failed:** When the child fails.
+///
+/// # Children
+///
+/// One, which is ticked once the delay has passed and reset whenever this node
+/// is reset.
+///
+/// # Panics
+///
+/// The constructors panic if `min` is longer than `max`.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::clock::ManualClock;
+/// # use std::time::Duration;
+/// let clock = ManualClock::new();
+/// let mut node = Jitter::with_clock(
+///     Duration::from_secs(1),
+///     Duration::from_secs(3),
+///     42,
+///     AlwaysSucceed::new(),
+///     clock.clone(),
+/// );
+///
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// clock.advance(Duration::from_secs(3));
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// ```
+pub struct Jitter<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The shortest delay.
+    min: Duration,
+
+    /// The longest delay.
+    max: Duration,
+
+    /// The generator of delays.
+    rng: Rng,
+
+    /// The source of the current time.
+    clock: Box<dyn Clock + 'a>,
+
+    /// When the child may first be ticked, once a delay has been picked.
+    ready_at: Option<Instant>,
+}
+impl<'a, W> Jitter<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Jitter` node using the system clock.
+    pub fn new(min: Duration, max: Duration, seed: u64, child: Node<'a, W>) -> Node<'a, W> {
+        Jitter::with_clock(min, max, seed, child, SystemClock)
+    }
+
+    /// Creates a new `Jitter` node using the given clock.
+    pub fn with_clock<C>(
+        min: Duration,
+        max: Duration,
+        seed: u64,
+        child: Node<'a, W>,
+        clock: C,
+    ) -> Node<'a, W>
+    where
+        C: Clock + 'a,
+    {
+        assert!(
+            min <= max,
+            "the shortest delay must not be longer than the longest"
+        );
+        let internals = Jitter {
+            child,
+            min,
+            max,
+            rng: Rng::new(seed),
+            clock: Box::new(clock),
+            ready_at: None,
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Tickable<W> for Jitter<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let now = self.clock.now();
+        let ready_at = match self.ready_at {
+            Some(t) => t,
+            None => {
+                let delay = self.min + (self.max - self.min).mul_f64(self.rng.unit());
+                trace!("Delaying {} by {:?}", self.child.name(), delay);
+                self.ready_at = Some(now + delay);
+                now + delay
+            }
+        };
+
+        if now < ready_at {
+            Status::Running
+        } else {
+            self.child.tick(world)
+        }
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let zero = Duration::from_secs(0);
+        let waiting = match self.ready_at {
+            Some(t) if !run.after_reset() => self.clock.now() < t,
+            // A new delay would be picked, which is only certain to be zero or
+            // not at the ends of the range
+            _ if self.min > zero => true,
+            _ if self.max == zero => false,
+            _ => return None,
+        };
+        if waiting {
+            Some(Status::Running)
+        } else {
+            Some(run.tick(&self.child, world))
+        }
+    }
+
+    fn reset(&mut self) {
+        self.ready_at = None;
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "Jitter".
+    fn type_name(&self) -> &'static str {
+        "Jitter"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::ManualClock,
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysSucceed, Jitter},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn delays_are_in_range_and_seeded() {
+        let delays = |seed| {
+            let clock = ManualClock::new();
+            let mut node = Jitter::with_clock(
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                seed,
+                AlwaysSucceed::new(),
+                clock.clone(),
+            );
+            let mut delays = Vec::new();
+            for _ in 0..5 {
+                let mut waited = 0;
+                while node.tick(&mut ()) == Status::Running {
+                    clock.advance(Duration::from_millis(1));
+                    waited += 1;
+                }
+                node.reset();
+                delays.push(waited);
+            }
+            delays
+        };
+
+        let first = delays(1);
+        assert!(first.iter().all(|&d| (100..=200).contains(&d)));
+        assert_eq!(first, delays(1));
+        assert_ne!(first, delays(2));
+    }
+}
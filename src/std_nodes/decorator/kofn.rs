@@ -0,0 +1,188 @@
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::collections::VecDeque;
+
+/// A node that succeeds when at least `k` of the last `n` runs of its child
+/// succeeded.
+///
+/// The outcomes of the latest `n` runs of the child are kept in a sliding
+/// window. Every tick, the child is ticked once, and when it completes its
+/// outcome is added to the window, pushing out the oldest one once the window
+/// is full. This node then succeeds if at least `k` outcomes in the window are
+/// successes, and fails if that has become impossible. While there are too few
+/// outcomes to decide, it is running and the child is run again.
+///
+/// Since the window is kept between runs of this node, a perception check
+/// which fails now and then does not make this node fail, as long as enough
+/// of the recent checks succeeded.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running, or while the window has too few
+/// outcomes to decide.
+///
+/// **Succeeded:** When at least `k` outcomes in the window are successes.
+///
+/// **Failed:** When the window cannot hold `k` successes even if every
+/// outcome still to come succeeds.
+///
+/// # Children
+///
+/// One, which is ticked every time this node is and reset whenever this node
+/// is reset, which also empties the window.
+///
+/// # Panics
+///
+/// The constructor panics if `k` is larger than `n`.
+///
+/// # Examples
+///
+/// A check which succeeds three times out of every four:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = KofN::new(2, 3, Condition::new(|&reading: &u32| reading % 4 != 0));
+///
+/// assert_eq!(node.tick(&mut 1), Status::Running);
+/// assert_eq!(node.tick(&mut 2), Status::Succeeded);
+/// assert_eq!(node.tick(&mut 4), Status::Succeeded);
+/// assert_eq!(node.tick(&mut 8), Status::Failed);
+/// ```
+pub struct KofN<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The number of successes needed.
+    k: usize,
+
+    /// The size of the window.
+    n: usize,
+
+    /// Whether each of the latest runs succeeded, oldest first.
+    window: VecDeque<bool>,
+}
+impl<'a, W> KofN<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `KofN` node which needs `k` successes among the last `n`
+    /// runs of its child.
+    pub fn new(k: usize, n: usize, child: Node<'a, W>) -> Node<'a, W> {
+        assert!(k <= n, "k must not be larger than n");
+        let internals = KofN {
+            child,
+            k,
+            n,
+            window: VecDeque::with_capacity(n),
+        };
+        Node::new(internals)
+    }
+
+    /// Returns the status for a window with the given number of successes and
+    /// outcomes.
+    fn outcome(&self, successes: usize, outcomes: usize) -> Status {
+        if successes >= self.k {
+            Status::Succeeded
+        } else if successes + (self.n - outcomes) < self.k {
+            Status::Failed
+        } else {
+            Status::Running
+        }
+    }
+}
+impl<'a, W> Tickable<W> for KofN<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let status = self.child.tick(world);
+        if !status.is_done() {
+            return Status::Running;
+        }
+
+        if self.window.len() == self.n {
+            self.window.pop_front();
+        }
+        if self.n > 0 {
+            self.window.push_back(status == Status::Succeeded);
+        }
+
+        let successes = self.window.iter().filter(|&&s| s).count();
+        self.outcome(successes, self.window.len())
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let status = run.tick(&self.child, world);
+        if !status.is_done() {
+            return Some(Status::Running);
+        }
+
+        let mut window: Vec<_> = if run.after_reset() {
+            Vec::new()
+        } else {
+            self.window.iter().copied().collect()
+        };
+        if window.len() == self.n && self.n > 0 {
+            window.remove(0);
+        }
+        if self.n > 0 {
+            window.push(status == Status::Succeeded);
+        }
+
+        let successes = window.iter().filter(|&&s| s).count();
+        Some(self.outcome(successes, window.len()))
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "KofN".
+    fn type_name(&self) -> &'static str {
+        "KofN"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysRunning, Condition, KofN},
+    };
+
+    #[test]
+    fn window_slides() {
+        let mut node = KofN::new(3, 4, Condition::new(|&ok: &bool| ok));
+        let statuses: Vec<_> = [true, false, true, true, false, false, true]
+            .iter()
+            .map(|&ok| node.tick(&mut { ok }))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Status::Running,
+                Status::Running,
+                Status::Running,
+                Status::Succeeded,
+                Status::Failed,
+                Status::Failed,
+                Status::Failed
+            ]
+        );
+
+        node.reset();
+        assert_eq!(node.tick(&mut true), Status::Running);
+
+        let mut node = KofN::new(1, 1, AlwaysRunning::new());
+        assert_eq!(node.tick(&mut ()), Status::Running);
+    }
+}
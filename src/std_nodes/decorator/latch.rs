@@ -0,0 +1,136 @@
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+    std_nodes::Trigger,
+};
+
+/// A node that keeps succeeding once its child has succeeded, until it is
+/// released.
+///
+/// Until the latch is closed, this node has the same status as its child. The
+/// first time the child succeeds the latch closes, and from then on this node
+/// succeeds without ticking the child, even after it is reset. Firing the
+/// latch's `Trigger` opens it again, so that the child is run on the next tick.
+/// This suits work which only needs doing once until something in the world
+/// changes, such as homing an arm until it is power cycled.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the latch is open and the child is running.
+///
+/// **Succeeded:** When the child succeeds, and after that until the latch is
+/// released.
+///
+/// **Failed:** When the latch is open and the child fails.
+///
+/// # Children
+///
+/// One, which is ticked while the latch is open and reset whenever this node
+/// is reset or the latch is released.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let release = Trigger::new();
+/// let mut node = Latch::new(&release, Condition::new(|&ready: &bool| ready));
+///
+/// assert_eq!(node.tick(&mut true), Status::Succeeded);
+/// node.reset();
+/// assert_eq!(node.tick(&mut false), Status::Succeeded);
+///
+/// release.set();
+/// assert_eq!(node.tick(&mut false), Status::Failed);
+/// ```
+pub struct Latch<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The trigger which releases the latch.
+    release: Trigger,
+
+    /// Whether the child has succeeded since the latch was last released.
+    closed: bool,
+}
+impl<'a, W> Latch<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Latch` node which is released by `release`.
+    pub fn new(release: &Trigger, child: Node<'a, W>) -> Node<'a, W> {
+        let internals = Latch {
+            child,
+            release: release.clone(),
+            closed: false,
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Tickable<W> for Latch<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.release.take() && self.closed {
+            debug!("Releasing the latch on {}", self.child.name());
+            self.closed = false;
+            self.child.reset();
+        }
+        if self.closed {
+            return Status::Succeeded;
+        }
+
+        let status = self.child.tick(world);
+        self.closed = status == Status::Succeeded;
+        status
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if self.closed && !self.release.is_set() {
+            Some(Status::Succeeded)
+        } else {
+            Some(run.tick(&self.child, world))
+        }
+    }
+
+    fn reset(&mut self) {
+        // The latch stays closed
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "Latch".
+    fn type_name(&self) -> &'static str {
+        "Latch"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{CountedTick, Latch, Trigger},
+    };
+
+    #[test]
+    fn child_is_only_run_again_once_released() {
+        let release = Trigger::new();
+        let mut node = Latch::new(&release, CountedTick::new(Status::Succeeded, 2, true));
+
+        // Firing an open latch has no effect
+        release.set();
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        node.reset();
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+
+        release.set();
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        assert!(!release.is_set());
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+    }
+}
@@ -1,11 +1,44 @@
 //! Nodes that have a single child and modify the behavior of that child in some
 //! way.
 
+#[allow(clippy::module_inception)]
 mod decorator;
-pub use self::decorator::{Decorator, Invert};
+pub use self::decorator::{Decorator, Invert, InvertPolicy};
+
+#[cfg(feature = "chaos")]
+mod chaos;
+#[cfg(feature = "chaos")]
+pub use self::chaos::{Chaos, ChaosConfig};
+
+mod duty;
+pub use self::duty::DutyCycle;
+
+mod edge;
+pub use self::edge::{OnFailureEdge, OnSuccessEdge};
+
+mod halt;
+pub use self::halt::OnHalt;
+
+mod invariant;
+pub use self::invariant::AssertInvariant;
+
+mod jitter;
+pub use self::jitter::Jitter;
+
+mod kofn;
+pub use self::kofn::KofN;
+
+mod latch;
+pub use self::latch::Latch;
 
 mod repeat;
 pub use self::repeat::Repeat;
 
+mod throttle;
+pub use self::throttle::ThrottleAction;
+
+mod timeout;
+pub use self::timeout::Timeout;
+
 mod until;
 pub use self::until::{UntilFail, UntilSuccess};
@@ -1,5 +1,5 @@
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
     status::Status,
 };
 
@@ -109,6 +109,21 @@ impl<'a, W> Tickable<W> for Repeat<'a, W> {
         Status::Running
     }
 
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let child_status = run.tick(&self.child, world);
+        let limit = match self.attempt_limit {
+            Some(limit) => limit,
+            None => return Some(Status::Running),
+        };
+
+        let attempts = if run.after_reset() { 0 } else { self.attempts };
+        if child_status.is_done() && attempts + 1 >= limit {
+            Some(Status::Succeeded)
+        } else {
+            Some(Status::Running)
+        }
+    }
+
     fn reset(&mut self) {
         // Reset our attempt count
         self.attempts = 0;
@@ -117,7 +132,7 @@ impl<'a, W> Tickable<W> for Repeat<'a, W> {
         self.child.reset();
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         vec![&self.child]
     }
 
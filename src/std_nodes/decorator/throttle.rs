@@ -0,0 +1,188 @@
+use crate::{
+    clock::{Clock, SystemClock},
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::time::{Duration, Instant};
+
+/// A node that starts its child at most once per period.
+///
+/// When this node is ticked and its child has not been started within the
+/// last `period`, the child is reset and run until it completes, and the
+/// status it completes with is kept. Otherwise this node returns that kept
+/// status without ticking the child, however often the branch is activated.
+/// This suits side effects which must not be repeated too often, such as
+/// sending a status message at most every ten minutes. Time is taken from a
+/// `Clock`, which is the system clock unless another is given.
+///
+/// The time the child was last started and the status it completed with are
+/// kept when this node is reset, since they are about how recently the side
+/// effect happened rather than about the current attempt. If the child is
+/// reset before it completes, the attempt still counts towards the period.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running.
+///
+/// **Succeeded:** When the child succeeds, and after that until it is started
+/// again.
+///
+/// **Failed:** When the child fails, and after that until it is started
+/// again. Also when the child has been started in this period but has not yet
+/// completed with any status.
+///
+/// # Children
+///
+/// One, which is ticked when it is running or due to be started again, and
+/// reset whenever this node is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::clock::ManualClock;
+/// # use std::time::Duration;
+/// let clock = ManualClock::new();
+/// let send = InlineAction::new(|sent: &mut u32| {
+///     *sent += 1;
+///     Status::Succeeded
+/// });
+/// let mut node = ThrottleAction::with_clock(Duration::from_secs(600), send, clock.clone());
+///
+/// let mut sent = 0;
+/// assert_eq!(node.tick(&mut sent), Status::Succeeded);
+/// assert_eq!(node.tick(&mut sent), Status::Succeeded);
+/// assert_eq!(sent, 1);
+///
+/// clock.advance(Duration::from_secs(600));
+/// assert_eq!(node.tick(&mut sent), Status::Succeeded);
+/// assert_eq!(sent, 2);
+/// ```
+pub struct ThrottleAction<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// The shortest time between starts of the child.
+    period: Duration,
+
+    /// The source of the current time.
+    clock: Box<dyn Clock + 'a>,
+
+    /// When the child was last started.
+    started: Option<Instant>,
+
+    /// The status the child last completed with.
+    last: Option<Status>,
+}
+impl<'a, W> ThrottleAction<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `ThrottleAction` node using the system clock.
+    pub fn new(period: Duration, child: Node<'a, W>) -> Node<'a, W> {
+        ThrottleAction::with_clock(period, child, SystemClock)
+    }
+
+    /// Creates a new `ThrottleAction` node using the given clock.
+    pub fn with_clock<C>(period: Duration, child: Node<'a, W>, clock: C) -> Node<'a, W>
+    where
+        C: Clock + 'a,
+    {
+        let internals = ThrottleAction {
+            child,
+            period,
+            clock: Box::new(clock),
+            started: None,
+            last: None,
+        };
+        Node::new(internals)
+    }
+
+    /// Returns `true` if the child may be started at the given time.
+    fn is_due(&self, now: Instant) -> bool {
+        match self.started {
+            Some(started) => now.saturating_duration_since(started) >= self.period,
+            None => true,
+        }
+    }
+
+    /// Returns the status given while the child is not being ticked.
+    fn held(&self) -> Status {
+        self.last.unwrap_or(Status::Failed)
+    }
+}
+impl<'a, W> Tickable<W> for ThrottleAction<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.child.status() != Some(Status::Running) {
+            let now = self.clock.now();
+            if !self.is_due(now) {
+                return self.held();
+            }
+
+            trace!("Starting throttled {}", self.child.name());
+            self.started = Some(now);
+            self.child.reset();
+        }
+
+        let status = self.child.tick(world);
+        if status.is_done() {
+            self.last = Some(status);
+        }
+        status
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let running = !run.after_reset() && self.child.status() == Some(Status::Running);
+        if running || self.is_due(self.clock.now()) {
+            Some(run.tick(&self.child, world))
+        } else {
+            Some(self.held())
+        }
+    }
+
+    fn reset(&mut self) {
+        // The start time and last status are kept so the period is honoured
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "ThrottleAction".
+    fn type_name(&self) -> &'static str {
+        "ThrottleAction"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::ManualClock,
+        node::Tickable,
+        status::Status,
+        std_nodes::{CountedTick, ThrottleAction},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn halted_runs_count_towards_the_period() {
+        let clock = ManualClock::new();
+        let child = CountedTick::new(Status::Running, 3, true);
+        let mut node = ThrottleAction::with_clock(Duration::from_secs(10), child, clock.clone());
+
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(node.tick(&mut ()), Status::Running);
+
+        // Halting it does not let it start again within the period
+        node.reset();
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(node.tick(&mut ()), Status::Running);
+    }
+}
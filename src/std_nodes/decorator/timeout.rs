@@ -0,0 +1,169 @@
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::time::{Duration, Instant};
+
+/// A node that fails if its child takes too long to complete.
+///
+/// The time limit starts on the first tick after the node is created or reset.
+/// Until it runs out, this node has the same status as its child. Once it has
+/// run out, the child is halted by resetting it and this node fails without
+/// ticking it again.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running and the time limit has not run
+/// out.
+///
+/// **Succeeded:** When the child succeeds within the time limit.
+///
+/// **Failed:** When the child fails, or when the time limit runs out.
+///
+/// # Children
+///
+/// One, which is ticked while there is time left and reset whenever this node
+/// is reset or times out.
+///
+/// # Examples
+///
+/// A child that never finishes will eventually fail:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use std::time::Duration;
+/// let mut node = Timeout::new(Duration::from_millis(10), AlwaysRunning::new());
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+///
+/// std::thread::sleep(Duration::from_millis(10));
+/// assert_eq!(node.tick(&mut ()), Status::Failed);
+/// ```
+pub struct Timeout<'a, W> {
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// How long the child is given to complete.
+    limit: Duration,
+
+    /// When the first tick happened.
+    started: Option<Instant>,
+}
+impl<'a, W> Timeout<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Timeout` node which gives its child `limit` to complete.
+    pub fn new(limit: Duration, child: Node<'a, W>) -> Node<'a, W> {
+        let internals = Timeout {
+            child,
+            limit,
+            started: None,
+        };
+        Node::new(internals)
+    }
+
+    /// Returns `true` if the time limit has run out.
+    fn expired(&self) -> bool {
+        self.started.map_or(false, |t| t.elapsed() >= self.limit)
+    }
+}
+impl<'a, W> Tickable<W> for Timeout<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+
+        if self.expired() {
+            debug!("{} timed out", self.child.name());
+            self.child.reset();
+            return Status::Failed;
+        }
+
+        self.child.tick(world)
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if self.expired() && !run.after_reset() {
+            return Some(Status::Failed);
+        }
+
+        Some(run.tick(&self.child, world))
+    }
+
+    fn reset(&mut self) {
+        self.started = None;
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "Timeout".
+    fn type_name(&self) -> &'static str {
+        "Timeout"
+    }
+}
+
+/// Convenience macro for creating Timeout nodes.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate aspen;
+/// # use std::time::Duration;
+/// # fn main() {
+/// let timeout = Timeout! { Duration::from_secs(5),
+///     Condition!{ |&(a, b): &(u32, u32)| a < b }
+/// };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! Timeout {
+    ( $d:expr, $e:expr ) => {
+        $crate::std_nodes::Timeout::new($d, $e)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{CountedTick, NoTick, Timeout, YesTick},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn passes_through_within_limit() {
+        let mut node = Timeout::new(Duration::from_secs(60), YesTick::new(Status::Succeeded));
+        let status = node.tick(&mut ());
+        drop(node);
+        assert_eq!(status, Status::Succeeded);
+    }
+
+    #[test]
+    fn zero_limit_never_ticks_child() {
+        let mut node = Timeout::new(Duration::from_secs(0), NoTick::new());
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+    }
+
+    #[test]
+    fn reset_restarts_the_limit() {
+        let mut node = Timeout::new(
+            Duration::from_millis(5),
+            CountedTick::new(Status::Running, 2, true),
+        );
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+
+        node.reset();
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        drop(node);
+    }
+}
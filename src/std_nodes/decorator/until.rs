@@ -1,5 +1,5 @@
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
     status::Status,
 };
 
@@ -133,6 +133,19 @@ impl<'a, W> Tickable<W> for UntilFail<'a, W> {
         Status::Running
     }
 
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let child_status = run.tick(&self.child, world);
+        if child_status == Status::Failed {
+            return Some(Status::Succeeded);
+        }
+
+        let attempts = if run.after_reset() { 0 } else { self.attempts };
+        match self.attempt_limit {
+            Some(limit) if child_status.is_done() && attempts + 1 >= limit => Some(Status::Failed),
+            _ => Some(Status::Running),
+        }
+    }
+
     fn reset(&mut self) {
         // Reset our own status
         self.attempts = 0;
@@ -141,7 +154,7 @@ impl<'a, W> Tickable<W> for UntilFail<'a, W> {
         self.child.reset();
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         vec![&self.child]
     }
 
@@ -307,6 +320,19 @@ impl<'a, W> Tickable<W> for UntilSuccess<'a, W> {
         Status::Running
     }
 
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let child_status = run.tick(&self.child, world);
+        if child_status == Status::Succeeded {
+            return Some(Status::Succeeded);
+        }
+
+        let attempts = if run.after_reset() { 0 } else { self.attempts };
+        match self.attempt_limit {
+            Some(limit) if child_status.is_done() && attempts + 1 >= limit => Some(Status::Failed),
+            _ => Some(Status::Running),
+        }
+    }
+
     fn reset(&mut self) {
         // Reset our own status
         self.attempts = 0;
@@ -315,7 +341,7 @@ impl<'a, W> Tickable<W> for UntilSuccess<'a, W> {
         self.child.reset();
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         vec![&self.child]
     }
 
@@ -0,0 +1,178 @@
+//! Composite nodes whose children are stored in fixed-size arrays.
+//!
+//! These behave identically to their `Vec`-backed counterparts but have the
+//! number of children fixed at compile time. The children are held inline as
+//! an array of any one `Tickable` type and the composites are not boxed into a
+//! `Node`, so a tree built from them and from other unboxed nodes, such as
+//! those in `typed`, never allocates.
+//!
+//! As with the `typed` composites, the children are not reported by
+//! `children`, so they do not take part in monitoring or dry runs. A composite
+//! can be turned into a `Node` as a whole with `Tickable::into_node`.
+use crate::{
+    node::Tickable,
+    std_nodes::{selector::tick_active_selector, sequence::tick_sequence},
+    Status,
+};
+
+/// A `Sequence` with exactly `N` children.
+///
+/// See [`Sequence`](crate::std_nodes::Sequence) for the details of how the
+/// children are ticked.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::typed::Condition;
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// fn in_range(n: &u32) -> bool {
+///     (1..10).contains(n)
+/// }
+/// fn is_even(n: &u32) -> bool {
+///     n % 2 == 0
+/// }
+///
+/// // The children must all have the same type, such as a function pointer
+/// let mut node = SequenceN::new([
+///     Condition::new(in_range as fn(&u32) -> bool),
+///     Condition::new(is_even),
+/// ]);
+/// assert_eq!(node.tick(&mut 4), Status::Succeeded);
+///
+/// // Boxed nodes can be children too
+/// let mut node = SequenceN::new([AlwaysSucceed::new(), AlwaysRunning::new()]);
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// ```
+pub struct SequenceN<C, const N: usize> {
+    /// The children of this node.
+    children: [C; N],
+
+    /// The next child to be ticked.
+    next_child: usize,
+}
+impl<C, const N: usize> SequenceN<C, N> {
+    /// Creates a new `SequenceN` node from an array of children.
+    pub fn new(children: [C; N]) -> Self {
+        SequenceN {
+            children,
+            next_child: 0,
+        }
+    }
+}
+impl<W, C, const N: usize> Tickable<W> for SequenceN<C, N>
+where
+    C: Tickable<W>,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_sequence(&mut self.children, &mut self.next_child, world)
+    }
+
+    fn reset(&mut self) {
+        // Reset all of our children
+        for child in &mut self.children {
+            child.reset();
+        }
+
+        self.next_child = 0;
+    }
+
+    /// Returns the string "Sequence".
+    fn type_name(&self) -> &'static str {
+        "Sequence"
+    }
+}
+
+/// A `Selector` with exactly `N` children.
+///
+/// See [`Selector`](crate::std_nodes::Selector) for the details of how the
+/// children are ticked.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = SelectorN::new([AlwaysFail::new(), AlwaysSucceed::new()]);
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// ```
+pub struct SelectorN<C, const N: usize> {
+    /// The children of this node.
+    children: [C; N],
+}
+impl<C, const N: usize> SelectorN<C, N> {
+    /// Creates a new `SelectorN` node from an array of children.
+    pub fn new(children: [C; N]) -> Self {
+        SelectorN { children }
+    }
+}
+impl<W, C, const N: usize> Tickable<W> for SelectorN<C, N>
+where
+    C: Tickable<W>,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        tick_active_selector(&mut self.children, world)
+    }
+
+    fn reset(&mut self) {
+        // Reset all of our children
+        for child in &mut self.children {
+            child.reset();
+        }
+    }
+
+    /// Returns the string "Selector".
+    fn type_name(&self) -> &'static str {
+        "Selector"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        std_nodes::{CountedTick, InlineAction, NoTick, SelectorN, SequenceN},
+        Status,
+    };
+
+    #[test]
+    fn sequence_resumes() {
+        let mut seq = SequenceN::new([
+            CountedTick::new(Status::Succeeded, 1, true),
+            CountedTick::new(Status::Running, 2, true),
+            NoTick::new(),
+        ]);
+        assert_eq!(seq.tick(&mut ()), Status::Running);
+        assert_eq!(seq.tick(&mut ()), Status::Running);
+        drop(seq);
+    }
+
+    #[test]
+    fn selector_resets_later_children() {
+        // Fails on the first and third ticks, and succeeds on the second
+        let first = InlineAction::new(|n: &mut u32| {
+            *n += 1;
+            if *n == 2 {
+                Status::Succeeded
+            } else {
+                Status::Failed
+            }
+        });
+        let mut sel = SelectorN::new([
+            first,
+            // Can only be ticked a second time if it was reset in between
+            CountedTick::resetable(Status::Running, 1, true),
+            NoTick::new(),
+        ]);
+
+        let mut n = 0;
+        assert_eq!(sel.tick(&mut n), Status::Running);
+        assert_eq!(sel.tick(&mut n), Status::Succeeded);
+        assert_eq!(sel.tick(&mut n), Status::Running);
+        drop(sel);
+    }
+}
@@ -0,0 +1,315 @@
+//! A node that runs a finite state machine whose states are nodes.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+
+/// Function which decides whether a transition is taken.
+type GuardFn<'a, W> = dyn Fn(&W) -> bool + 'a;
+
+/// A state of an `Fsm`.
+struct State<'a, W> {
+    /// The name of the state.
+    name: String,
+
+    /// The node which is ticked while in the state.
+    node: Node<'a, W>,
+}
+
+/// A transition which is taken as soon as its guard holds.
+struct Guarded<'a, W> {
+    /// The index of the state the transition leaves.
+    from: usize,
+
+    /// The index of the state the transition enters.
+    to: usize,
+
+    /// Whether to take the transition.
+    guard: Box<GuardFn<'a, W>>,
+}
+
+/// A transition which is taken when the node of a state completes.
+struct Exit {
+    /// The index of the state the transition leaves.
+    from: usize,
+
+    /// The status the node of the state has to complete with.
+    status: Status,
+
+    /// The index of the state the transition enters.
+    to: usize,
+}
+
+/// A node that runs a finite state machine, with a node for each state.
+///
+/// The machine starts in the first state which was added. Every tick, the
+/// guarded transitions out of the current state are checked in the order they
+/// were added, and the first whose guard holds is taken, halting the node of
+/// the state which is left. The node of the current state is then ticked.
+///
+/// When the node of a state completes and there is an exit transition for its
+/// status, the machine moves to the next state, which is ticked from the next
+/// tick on, and this node is running. Otherwise, the machine has finished, and
+/// this node completes with the same status. This suits behaviors which are
+/// naturally protocols, such as a docking handshake, which would otherwise
+/// need nested selectors to route between their steps.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the node of the current state is running, or after it
+/// took an exit transition.
+///
+/// **Succeeded:** When the node of a state succeeded with no exit transition
+/// for success.
+///
+/// **Failed:** When the node of a state failed with no exit transition for
+/// failure, or if there are no states.
+///
+/// # Children
+///
+/// The nodes of the states, in the order they were added. Only the node of the
+/// current state is ticked. A node is reset when its state is left, and all of
+/// them are reset when this node is reset, which also returns the machine to
+/// its first state.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// // The world is whether the dock has acknowledged
+/// let mut node = Fsm::new()
+///     .with_state("request", AlwaysSucceed::new())
+///     .with_state("wait", AlwaysRunning::new())
+///     .with_state("docked", AlwaysSucceed::new())
+///     .with_exit("request", Status::Succeeded, "wait")
+///     .with_transition("wait", "docked", |&acked: &bool| acked)
+///     .into_node();
+///
+/// assert_eq!(node.tick(&mut false), Status::Running);
+/// assert_eq!(node.tick(&mut false), Status::Running);
+/// assert_eq!(node.tick(&mut true), Status::Succeeded);
+/// ```
+pub struct Fsm<'a, W> {
+    /// The states, in the order they were added.
+    states: Vec<State<'a, W>>,
+
+    /// The guarded transitions, in the order they were added.
+    guarded: Vec<Guarded<'a, W>>,
+
+    /// The exit transitions.
+    exits: Vec<Exit>,
+
+    /// The index of the current state.
+    current: usize,
+}
+impl<'a, W> Fsm<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Fsm` with no states.
+    pub fn new() -> Self {
+        Fsm {
+            states: Vec::new(),
+            guarded: Vec::new(),
+            exits: Vec::new(),
+            current: 0,
+        }
+    }
+
+    /// Adds a state, which runs `child` while the machine is in it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is already a state with the same name.
+    pub fn with_state<N, T>(mut self, name: N, child: T) -> Self
+    where
+        N: Into<String>,
+        T: Tickable<W> + 'a,
+    {
+        let name = name.into();
+        assert!(
+            self.states.iter().all(|s| s.name != name),
+            "duplicate state {}",
+            name
+        );
+        self.states.push(State {
+            name,
+            node: child.into_node(),
+        });
+        self
+    }
+
+    /// Adds a transition from one state to another which is taken as soon as
+    /// `guard` holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either state has not been added.
+    pub fn with_transition<F>(mut self, from: &str, to: &str, guard: F) -> Self
+    where
+        F: Fn(&W) -> bool + 'a,
+    {
+        let (from, to) = (self.index(from), self.index(to));
+        self.guarded.push(Guarded {
+            from,
+            to,
+            guard: Box::new(guard),
+        });
+        self
+    }
+
+    /// Adds a transition from one state to another which is taken when the
+    /// node of the first state completes with `status`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either state has not been added, or if `status` is
+    /// `Running`.
+    pub fn with_exit(mut self, from: &str, status: Status, to: &str) -> Self {
+        assert!(status.is_done(), "exit transitions need a completed status");
+        let (from, to) = (self.index(from), self.index(to));
+        self.exits.push(Exit { from, status, to });
+        self
+    }
+}
+impl<'a, W> Fsm<'a, W> {
+    /// Returns the index of the state with the given name.
+    fn index(&self, name: &str) -> usize {
+        self.states
+            .iter()
+            .position(|s| s.name == name)
+            .unwrap_or_else(|| panic!("unknown state {}", name))
+    }
+
+    /// Returns the state entered by the first guarded transition out of
+    /// `state` which is taken.
+    fn guarded_target(&self, state: usize, world: &W) -> Option<usize> {
+        self.guarded
+            .iter()
+            .find(|t| t.from == state && (t.guard)(world))
+            .map(|t| t.to)
+    }
+
+    /// Returns the state entered when the node of `state` completes with
+    /// `status`.
+    fn exit_target(&self, state: usize, status: Status) -> Option<usize> {
+        self.exits
+            .iter()
+            .find(|t| t.from == state && t.status == status)
+            .map(|t| t.to)
+    }
+
+    /// Moves to the state with the given index, halting the current one.
+    fn enter(&mut self, state: usize) {
+        debug!(
+            "State machine moving from {} to {}",
+            self.states[self.current].name, self.states[state].name
+        );
+        self.states[self.current].node.reset();
+        self.current = state;
+    }
+}
+impl<'a, W> Default for Fsm<'a, W>
+where
+    W: 'a,
+{
+    fn default() -> Self {
+        Fsm::new()
+    }
+}
+impl<'a, W> Tickable<W> for Fsm<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.states.is_empty() {
+            return Status::Failed;
+        }
+
+        if let Some(to) = self.guarded_target(self.current, world) {
+            self.enter(to);
+        }
+
+        let status = self.states[self.current].node.tick(world);
+        if !status.is_done() {
+            return status;
+        }
+        match self.exit_target(self.current, status) {
+            Some(to) => {
+                self.enter(to);
+                Status::Running
+            }
+            None => status,
+        }
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if self.states.is_empty() {
+            return Some(Status::Failed);
+        }
+
+        let mut current = if run.after_reset() { 0 } else { self.current };
+        if let Some(to) = self.guarded_target(current, world) {
+            current = to;
+        }
+
+        let status = run.tick(&self.states[current].node, world);
+        if status.is_done() && self.exit_target(current, status).is_some() {
+            Some(Status::Running)
+        } else {
+            Some(status)
+        }
+    }
+
+    fn reset(&mut self) {
+        for state in &mut self.states {
+            state.node.reset();
+        }
+        self.current = 0;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.states.iter().map(|s| &s.node).collect()
+    }
+
+    /// Returns the string "Fsm".
+    fn type_name(&self) -> &'static str {
+        "Fsm"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysFail, CountedTick, Fsm, NoTick, YesTick},
+    };
+
+    #[test]
+    fn transitions_are_followed() {
+        // A failing handshake is retried once the world allows it
+        let mut node = Fsm::new()
+            .with_state("handshake", CountedTick::new(Status::Failed, 2, true))
+            .with_state("backoff", CountedTick::new(Status::Running, 1, true))
+            .with_state("unused", NoTick::new())
+            .with_exit("handshake", Status::Failed, "backoff")
+            .with_transition("backoff", "handshake", |&retry: &bool| retry)
+            .with_transition("handshake", "unused", |_| false);
+        assert_eq!(node.tick(&mut false), Status::Running);
+        assert_eq!(node.tick(&mut false), Status::Running);
+        assert_eq!(node.tick(&mut true), Status::Running);
+        assert_eq!(node.children()[0].status(), None);
+        drop(node);
+
+        // A completed state with no exit finishes the machine
+        let mut node = Fsm::new()
+            .with_state("only", YesTick::new(Status::Succeeded))
+            .with_exit("only", Status::Failed, "only");
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        assert_eq!(Fsm::<()>::new().tick(&mut ()), Status::Failed);
+        let mut node = Fsm::new().with_state("fail", AlwaysFail::new());
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+    }
+}
@@ -0,0 +1,354 @@
+//! A node that plans a sequence of actions to reach a goal.
+//!
+//! This is goal-oriented action planning, as used for game characters. The
+//! state of the world is described to the planner as a set of facts which
+//! hold, and each action which can be planned lists the facts it requires and
+//! the facts it changes. This module is experimental, and the planner is a
+//! simple search which suits a few dozen actions.
+use crate::{
+    node::{Node, Tickable},
+    status::Status,
+    std_nodes::Sequence,
+};
+use std::{cmp::Ordering, collections::BTreeSet};
+
+/// The facts which hold in a state of the world.
+pub type Facts = BTreeSet<String>;
+
+/// Function which describes the world as facts.
+type ObserveFn<'a, W> = dyn Fn(&W) -> Facts + 'a;
+
+/// Function which decides whether the goal has been reached.
+type GoalFn<'a> = dyn Fn(&Facts) -> bool + 'a;
+
+/// Function which creates the node of a planned action.
+type FactoryFn<'a, W> = dyn Fn() -> Node<'a, W> + 'a;
+
+/// An action which a `GoalNode` can plan with.
+pub struct PlannedAction<'a, W> {
+    /// The name of the action.
+    name: String,
+
+    /// The facts which have to hold, or not hold, for the action to be used.
+    preconditions: Vec<(String, bool)>,
+
+    /// The facts which the action makes hold, or stop holding.
+    effects: Vec<(String, bool)>,
+
+    /// The cost of the action.
+    cost: f64,
+
+    /// Creates the node which carries out the action.
+    factory: Box<FactoryFn<'a, W>>,
+}
+impl<'a, W> PlannedAction<'a, W>
+where
+    W: 'a,
+{
+    /// Creates an action with no preconditions, no effects and a cost of one,
+    /// which is carried out by a node created by `factory`.
+    ///
+    /// A new node is created each time the action is planned.
+    pub fn new<N, F>(name: N, factory: F) -> Self
+    where
+        N: Into<String>,
+        F: Fn() -> Node<'a, W> + 'a,
+    {
+        PlannedAction {
+            name: name.into(),
+            preconditions: Vec::new(),
+            effects: Vec::new(),
+            cost: 1.0,
+            factory: Box::new(factory),
+        }
+    }
+
+    /// Adds a precondition that `fact` holds if `value` is `true`, or does not
+    /// hold if it is `false`.
+    pub fn requires<T: Into<String>>(mut self, fact: T, value: bool) -> Self {
+        self.preconditions.push((fact.into(), value));
+        self
+    }
+
+    /// Adds an effect which makes `fact` hold if `value` is `true`, or stop
+    /// holding if it is `false`.
+    pub fn effect<T: Into<String>>(mut self, fact: T, value: bool) -> Self {
+        self.effects.push((fact.into(), value));
+        self
+    }
+
+    /// Sets the cost of the action, which the planner keeps as low as
+    /// possible.
+    pub fn cost(mut self, cost: f64) -> Self {
+        self.cost = cost;
+        self
+    }
+}
+impl<'a, W> PlannedAction<'a, W> {
+    /// Returns `true` if the action can be used when `facts` hold.
+    fn applies(&self, facts: &Facts) -> bool {
+        self.preconditions
+            .iter()
+            .all(|(fact, value)| facts.contains(fact) == *value)
+    }
+
+    /// Returns the facts which hold after the action is used.
+    fn apply(&self, facts: &Facts) -> Facts {
+        let mut next = facts.clone();
+        for (fact, value) in &self.effects {
+            if *value {
+                next.insert(fact.clone());
+            } else {
+                next.remove(fact);
+            }
+        }
+        next
+    }
+}
+
+/// A node that plans a sequence of actions to reach a goal, and runs it.
+///
+/// When it is first ticked after being created or reset, this node describes
+/// the world as facts and searches for the cheapest sequence of its actions
+/// which leads to facts that meet the goal. The nodes of those actions are
+/// then created and run as a `Sequence`, and this node finishes with the
+/// status of the sequence. The next run plans again from the new state of the
+/// world.
+///
+/// Plans are limited to a maximum number of steps, which is 8 unless set with
+/// `with_max_steps`. The plan is not checked again while it runs, so a step
+/// which can fail should fail rather than carry on regardless, which makes
+/// this node fail and plan again the next time it is run.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the plan is running.
+///
+/// **Succeeded:** When the plan succeeded, or if the goal was already met.
+///
+/// **Failed:** When the plan failed, or if no plan reaches the goal.
+///
+/// # Children
+///
+/// None. The plan is owned by this node, and is reset and dropped when this
+/// node is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// // The world is the number of logs which have been chopped
+/// let mut node = GoalNode::new(|_| Facts::new(), |f: &Facts| f.contains("has_wood"))
+///     .with_action(
+///         PlannedAction::new("fetch_axe", || AlwaysSucceed::new()).effect("has_axe", true),
+///     )
+///     .with_action(
+///         PlannedAction::new("chop", || {
+///             InlineAction::new(|logs: &mut u32| {
+///                 *logs += 1;
+///                 Status::Succeeded
+///             })
+///         })
+///         .requires("has_axe", true)
+///         .effect("has_wood", true),
+///     )
+///     .into_node();
+///
+/// let mut logs = 0;
+/// assert_eq!(node.tick(&mut logs), Status::Succeeded);
+/// assert_eq!(logs, 1);
+/// ```
+pub struct GoalNode<'a, W> {
+    /// Describes the world as facts.
+    observe: Box<ObserveFn<'a, W>>,
+
+    /// Decides whether the goal has been reached.
+    goal: Box<GoalFn<'a>>,
+
+    /// The actions which can be planned with.
+    actions: Vec<PlannedAction<'a, W>>,
+
+    /// The most actions a plan can have.
+    max_steps: usize,
+
+    /// The plan which is running.
+    plan: Option<Node<'a, W>>,
+}
+impl<'a, W> GoalNode<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `GoalNode` with no actions, which describes the world
+    /// with `observe` and plans until `goal` holds.
+    pub fn new<O, G>(observe: O, goal: G) -> Self
+    where
+        O: Fn(&W) -> Facts + 'a,
+        G: Fn(&Facts) -> bool + 'a,
+    {
+        GoalNode {
+            observe: Box::new(observe),
+            goal: Box::new(goal),
+            actions: Vec::new(),
+            max_steps: 8,
+            plan: None,
+        }
+    }
+
+    /// Adds an action which can be planned with.
+    pub fn with_action(mut self, action: PlannedAction<'a, W>) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Sets the most actions a plan can have.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Finds the cheapest plan from `start` to the goal, returning the indices
+    /// of its actions.
+    fn search(&self, start: Facts) -> Option<Vec<usize>> {
+        let mut frontier = vec![(0.0, start, Vec::new())];
+        let mut visited = BTreeSet::new();
+        while !frontier.is_empty() {
+            let cheapest = (0..frontier.len())
+                .min_by(|&a, &b| {
+                    frontier[a]
+                        .0
+                        .partial_cmp(&frontier[b].0)
+                        .unwrap_or(Ordering::Equal)
+                })
+                .unwrap();
+            let (cost, facts, steps) = frontier.swap_remove(cheapest);
+            if (self.goal)(&facts) {
+                return Some(steps);
+            }
+            if steps.len() >= self.max_steps || visited.contains(&facts) {
+                continue;
+            }
+
+            for (i, action) in self.actions.iter().enumerate() {
+                if !action.applies(&facts) {
+                    continue;
+                }
+                let next = action.apply(&facts);
+                if !visited.contains(&next) {
+                    let mut next_steps = steps.clone();
+                    next_steps.push(i);
+                    frontier.push((cost + action.cost, next, next_steps));
+                }
+            }
+            visited.insert(facts);
+        }
+
+        None
+    }
+}
+impl<'a, W> Tickable<W> for GoalNode<'a, W>
+where
+    W: 'a,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        let plan = match self.plan {
+            Some(ref mut plan) => plan,
+            None => {
+                let steps = match self.search((self.observe)(world)) {
+                    Some(s) => s,
+                    None => {
+                        warn!("No plan reaches the goal");
+                        return Status::Failed;
+                    }
+                };
+                if steps.is_empty() {
+                    return Status::Succeeded;
+                }
+
+                let names: Vec<_> = steps.iter().map(|&i| &self.actions[i].name).collect();
+                debug!("Planned {:?}", names);
+                let nodes = steps.iter().map(|&i| (self.actions[i].factory)()).collect();
+                self.plan.get_or_insert(Sequence::new(nodes))
+            }
+        };
+
+        let status = plan.tick(world);
+        if status.is_done() {
+            self.plan = None;
+        }
+        status
+    }
+
+    fn reset(&mut self) {
+        if let Some(mut plan) = self.plan.take() {
+            plan.reset();
+        }
+    }
+
+    /// Returns the string "GoalNode".
+    fn type_name(&self) -> &'static str {
+        "GoalNode"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Facts, PlannedAction};
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{GoalNode, InlineAction},
+    };
+
+    /// Returns an action which records its name in the world.
+    fn logged(name: &'static str) -> PlannedAction<'static, Vec<&'static str>> {
+        PlannedAction::new(name, move || {
+            InlineAction::new(move |log: &mut Vec<&'static str>| {
+                log.push(name);
+                Status::Succeeded
+            })
+        })
+    }
+
+    #[test]
+    fn cheapest_plan_is_run() {
+        let mut node = GoalNode::new(
+            |log: &Vec<&'static str>| {
+                let mut facts = Facts::new();
+                if log.contains(&"chop") {
+                    facts.insert("has_wood".to_owned());
+                }
+                facts
+            },
+            |f: &Facts| f.contains("has_wood"),
+        )
+        .with_action(logged("gather").effect("has_wood", true).cost(5.0))
+        .with_action(logged("fetch_axe").effect("has_axe", true).cost(2.0))
+        .with_action(
+            logged("chop")
+                .requires("has_axe", true)
+                .effect("has_wood", true),
+        );
+
+        let mut log = Vec::new();
+        assert_eq!(node.tick(&mut log), Status::Succeeded);
+        assert_eq!(log, vec!["fetch_axe", "chop"]);
+
+        // The goal is met now, so nothing more is done
+        assert_eq!(node.tick(&mut log), Status::Succeeded);
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn unreachable_goal_fails() {
+        let mut node = GoalNode::new(
+            |_: &Vec<&'static str>| Facts::new(),
+            |f: &Facts| f.contains("flying"),
+        )
+        .with_action(logged("jump").effect("airborne", true));
+        assert_eq!(node.tick(&mut Vec::new()), Status::Failed);
+    }
+}
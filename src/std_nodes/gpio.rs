@@ -0,0 +1,158 @@
+//! Nodes which read digital I/O pins.
+use crate::{
+    node::{Node, Tickable},
+    status::Status,
+};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// A digital input pin.
+///
+/// This is implemented for `SysfsPin`, which works on any Linux system that
+/// exposes its GPIOs through sysfs. Pins from other libraries, such as
+/// `gpio-cdev` or `rppal`, can be used by implementing this trait for them.
+pub trait DigitalInput {
+    /// Reads the current level of the pin, returning `true` if it is high.
+    fn is_high(&mut self) -> io::Result<bool>;
+}
+
+/// A GPIO pin read through the Linux sysfs interface.
+///
+/// The pin must already have been exported and configured as an input.
+#[derive(Debug)]
+pub struct SysfsPin {
+    /// The open `value` file of the pin.
+    file: File,
+}
+impl SysfsPin {
+    /// Opens the pin with the given number.
+    pub fn open(pin: u32) -> io::Result<Self> {
+        Self::from_path(format!("/sys/class/gpio/gpio{}/value", pin))
+    }
+
+    /// Opens the pin whose `value` file is at the given path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(SysfsPin {
+            file: File::open(path)?,
+        })
+    }
+}
+impl DigitalInput for SysfsPin {
+    fn is_high(&mut self) -> io::Result<bool> {
+        let mut level = [0];
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_exact(&mut level)?;
+        match level[0] {
+            b'0' => Ok(false),
+            b'1' => Ok(true),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected GPIO value",
+            )),
+        }
+    }
+}
+
+/// A node that checks the level of a digital input pin.
+///
+/// Errors reading the pin are logged and treated as a failure.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** When the pin is at the expected level.
+///
+/// **Failed:** When the pin is not at the expected level, or cannot be read.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// Checking that a bumper switch on GPIO 17 is not pressed:
+///
+/// ```no_run
+/// # use aspen::std_nodes::*;
+/// # use aspen::node::Node;
+/// let bumper = SysfsPin::open(17).unwrap();
+/// let clear: Node<()> = GpioCondition::new(bumper, false);
+/// ```
+pub struct GpioCondition<P> {
+    /// The pin to read.
+    pin: P,
+
+    /// Whether the pin is expected to be high.
+    high: bool,
+}
+impl<P> GpioCondition<P>
+where
+    P: DigitalInput,
+{
+    /// Creates a new `GpioCondition` node which succeeds when the pin is high
+    /// if `high` is `true`, or when it is low otherwise.
+    pub fn new<'a, W>(pin: P, high: bool) -> Node<'a, W>
+    where
+        P: 'a,
+    {
+        Node::new(GpioCondition { pin, high })
+    }
+}
+impl<W, P> Tickable<W> for GpioCondition<P>
+where
+    P: DigitalInput,
+{
+    fn tick(&mut self, _: &mut W) -> Status {
+        match self.pin.is_high() {
+            Ok(level) if level == self.high => Status::Succeeded,
+            Ok(_) => Status::Failed,
+            Err(e) => {
+                warn!("Unable to read GPIO: {}", e);
+                Status::Failed
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "GpioCondition".
+    fn type_name(&self) -> &'static str {
+        "GpioCondition"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{GpioCondition, SysfsPin},
+    };
+    use std::{env, fs, process};
+
+    #[test]
+    fn reads_sysfs_value() {
+        let path = env::temp_dir().join(format!("aspen-gpio-{}", process::id()));
+        fs::write(&path, "1\n").unwrap();
+
+        let mut high = GpioCondition::new(SysfsPin::from_path(&path).unwrap(), true);
+        let mut low = GpioCondition::new(SysfsPin::from_path(&path).unwrap(), false);
+        assert_eq!(high.tick(&mut ()), Status::Succeeded);
+        assert_eq!(low.tick(&mut ()), Status::Failed);
+
+        // The level is read again on every tick
+        fs::write(&path, "0\n").unwrap();
+        assert_eq!(high.tick(&mut ()), Status::Failed);
+        assert_eq!(low.tick(&mut ()), Status::Succeeded);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
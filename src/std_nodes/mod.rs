@@ -1,22 +1,118 @@
 //! Contains a set of commonly used behavior tree nodes.
 
-mod sequence;
+pub(crate) mod sequence;
 pub use self::sequence::{ActiveSequence, Sequence};
 
-mod selector;
+pub(crate) mod selector;
 pub use self::selector::{Selector, StatefulSelector};
 
+mod sticky;
+pub use self::sticky::StickySelector;
+
+mod alternate;
+pub use self::alternate::{Alternate, AlternatePolicy};
+
+mod budget;
+pub use self::budget::BudgetedSelector;
+
+mod cooldown;
+pub use self::cooldown::CooldownSelector;
+
+mod fixed;
+pub use self::fixed::{SelectorN, SequenceN};
+
 mod parallel;
-pub use self::parallel::Parallel;
+pub use self::parallel::{Parallel, ParallelGroup, TickOrder};
+
+mod phased;
+pub use self::phased::Phased;
+
+mod checkpoint;
+pub use self::checkpoint::{Checkpoint, ResumeFrom, CHECKPOINT_KEY};
+
+mod fsm;
+pub use self::fsm::Fsm;
+
+mod goap;
+pub use self::goap::{Facts, GoalNode, PlannedAction};
+
+mod monitored;
+pub use self::monitored::Monitored;
+
+mod recovery;
+pub use self::recovery::Recovery;
+
+mod reserve;
+pub use self::reserve::Reserve;
 
 mod decorator;
-pub use self::decorator::{Decorator, Invert, Repeat, UntilFail, UntilSuccess};
+pub use self::decorator::{
+    AssertInvariant, Decorator, DutyCycle, Invert, InvertPolicy, Jitter, KofN, Latch,
+    OnFailureEdge, OnHalt, OnSuccessEdge, Repeat, ThrottleAction, Timeout, UntilFail,
+    UntilSuccess,
+};
+#[cfg(feature = "chaos")]
+pub use self::decorator::{Chaos, ChaosConfig};
 
 mod action;
-pub use self::action::{Action, InlineAction};
+pub use self::action::InlineAction;
+#[cfg(feature = "action")]
+pub use self::action::{
+    Action, ActionConfig, CrashPolicy, DropPolicy, HaltSignal, RestartPolicy, TaskContext,
+};
 
 mod condition;
-pub use self::condition::Condition;
+pub use self::condition::{Condition, Flag};
+
+mod blackboard;
+pub use self::blackboard::{
+    OnBlackboardChange, SaveBlackboard, ScopedBlackboard, SyncBlackboard,
+};
+
+mod trigger;
+pub use self::trigger::{OnTrigger, Trigger};
+
+mod timer;
+pub use self::timer::{CheckTimer, StartTimer};
+
+#[cfg(feature = "gpio")]
+mod gpio;
+#[cfg(feature = "gpio")]
+pub use self::gpio::{DigitalInput, GpioCondition, SysfsPin};
+
+#[cfg(feature = "robotics")]
+mod robotics;
+#[cfg(feature = "robotics")]
+pub use self::robotics::{
+    BatteryAbove, EStopClear, Geofence, ReleasePolicy, TeleopOverride, WithinGeofence,
+};
+
+#[cfg(feature = "serial")]
+mod serial;
+#[cfg(feature = "serial")]
+pub use self::serial::SerialCommandAction;
+
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "remote")]
+pub use self::remote::{
+    RemoteAction, RemoteCondition, RemoteLink, Reply, Request, RequestKind, TcpTransport, Transport,
+};
+
+mod coroutine;
+pub use self::coroutine::{Coroutine, Step};
+
+mod pipeline;
+pub use self::pipeline::Pipeline;
+
+mod spawn;
+pub use self::spawn::{BlackboardScope, SpawnTree};
+
+pub mod combinators;
+
+pub mod compat;
+
+pub mod typed;
 
 mod constants;
 pub use self::constants::{AlwaysFail, AlwaysRunning, AlwaysSucceed};
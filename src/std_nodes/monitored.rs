@@ -0,0 +1,246 @@
+//! Nodes that guard a behavior with an always-checked safety monitor.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+
+/// A node that runs a main behavior while a monitor reports that it is safe to
+/// do so, and a recovery behavior when it is not.
+///
+/// The monitor is ticked first on every tick, before anything else. While it
+/// does not fail, the main behavior is ticked and this node has the same
+/// status. As soon as the monitor fails, the main behavior is halted by
+/// resetting it and the recovery behavior takes over. Recovery always runs to
+/// completion: once it succeeds, the main behavior starts again from the
+/// beginning on the next tick, and if it fails then this node fails.
+///
+/// The monitor is reset before each tick once it has completed, so it is
+/// evaluated afresh every time. This codifies the common safety architecture
+/// of a small, always-ticked monitor tree alongside the main tree.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the main behavior is running, or while recovering.
+///
+/// **Succeeded:** When the main behavior succeeds while the monitor has not
+/// failed.
+///
+/// **Failed:** When the main behavior fails while the monitor has not failed,
+/// or when the recovery behavior fails.
+///
+/// # Children
+///
+/// Three: the monitor, the main behavior, and the recovery behavior. The
+/// monitor is ticked every time this node is ticked. All three are reset when
+/// this node is reset.
+///
+/// # Examples
+///
+/// Stopping the motors when they get too hot:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// struct Robot {
+///     temperature: u32,
+///     driving: bool,
+/// }
+///
+/// let mut node = Monitored::new(
+///     Condition::new(|r: &Robot| r.temperature < 80),
+///     InlineAction::new(|r: &mut Robot| {
+///         r.driving = true;
+///         Status::Running
+///     }),
+///     InlineAction::new(|r: &mut Robot| {
+///         r.driving = false;
+///         Status::Succeeded
+///     }),
+/// );
+///
+/// let mut robot = Robot {
+///     temperature: 20,
+///     driving: false,
+/// };
+/// assert_eq!(node.tick(&mut robot), Status::Running);
+/// assert!(robot.driving);
+///
+/// robot.temperature = 95;
+/// assert_eq!(node.tick(&mut robot), Status::Running);
+/// assert!(!robot.driving);
+/// ```
+pub struct Monitored<'a, W> {
+    /// The node which decides whether it is safe to run the main behavior.
+    monitor: Node<'a, W>,
+
+    /// The behavior run while it is safe to do so.
+    main: Node<'a, W>,
+
+    /// The behavior run after the monitor has failed.
+    recovery: Node<'a, W>,
+
+    /// Whether the recovery behavior is running.
+    recovering: bool,
+}
+impl<'a, W> Monitored<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Monitored` node.
+    pub fn new(monitor: Node<'a, W>, main: Node<'a, W>, recovery: Node<'a, W>) -> Node<'a, W> {
+        let internals = Monitored {
+            monitor,
+            main,
+            recovery,
+            recovering: false,
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Tickable<W> for Monitored<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.monitor.status().map_or(false, |s| s.is_done()) {
+            self.monitor.reset();
+        }
+        let safe = self.monitor.tick(world) != Status::Failed;
+
+        if !safe && !self.recovering {
+            warn!("Monitor failed, halting {}", self.main.name());
+            self.main.reset();
+            self.recovering = true;
+        }
+
+        if !self.recovering {
+            return self.main.tick(world);
+        }
+
+        match self.recovery.tick(world) {
+            Status::Succeeded => {
+                debug!("Recovered, restarting {}", self.main.name());
+                self.recovery.reset();
+                self.recovering = false;
+                Status::Running
+            }
+            s => s,
+        }
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let safe = run.tick(&self.monitor, world) != Status::Failed;
+        let recovering = self.recovering && !run.after_reset();
+        if safe && !recovering {
+            return Some(run.tick(&self.main, world));
+        }
+
+        match run.tick(&self.recovery, world) {
+            Status::Succeeded => Some(Status::Running),
+            s => Some(s),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.monitor.reset();
+        self.main.reset();
+        self.recovery.reset();
+        self.recovering = false;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.monitor, &self.main, &self.recovery]
+    }
+
+    /// Returns the string "Monitored".
+    fn type_name(&self) -> &'static str {
+        "Monitored"
+    }
+}
+
+/// Convenience macro for creating Monitored nodes.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate aspen;
+/// # fn main() {
+/// let monitored = Monitored! {
+///     Condition!{ |&t: &u32| t < 80 },
+///     InlineAction!{ |_| aspen::Status::Running },
+///     AlwaysSucceed!{}
+/// };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! Monitored {
+    ( $m:expr, $e:expr, $r:expr ) => {
+        $crate::std_nodes::Monitored::new($m, $e, $r)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{Condition, Coroutine, Monitored, NoTick, Step, YesTick},
+    };
+
+    #[test]
+    fn main_runs_while_safe() {
+        let mut node = Monitored::new(
+            YesTick::new(Status::Succeeded),
+            YesTick::new(Status::Running),
+            NoTick::new(),
+        );
+        let status = node.tick(&mut ());
+        drop(node);
+        assert_eq!(status, Status::Running);
+    }
+
+    #[test]
+    fn failure_halts_main_until_recovered() {
+        // The world is (safe, main steps, recovery steps)
+        let mut node = Monitored::new(
+            Condition::new(|w: &(bool, u32, u32)| w.0),
+            Coroutine::new((), |_, w: &mut (bool, u32, u32)| {
+                w.1 += 1;
+                Step::Yield(())
+            }),
+            Coroutine::new(0, |n, w: &mut (bool, u32, u32)| {
+                w.2 += 1;
+                if n == 1 {
+                    Step::Succeed
+                } else {
+                    Step::Yield(n + 1)
+                }
+            }),
+        );
+
+        let mut world = (true, 0, 0);
+        assert_eq!(node.tick(&mut world), Status::Running);
+        world.0 = false;
+        assert_eq!(node.tick(&mut world), Status::Running);
+
+        // Recovery continues even once the monitor is happy again
+        world.0 = true;
+        assert_eq!(node.tick(&mut world), Status::Running);
+        assert_eq!(world, (true, 1, 2));
+
+        assert_eq!(node.tick(&mut world), Status::Running);
+        assert_eq!(world, (true, 2, 2));
+    }
+
+    #[test]
+    fn failed_recovery_fails() {
+        let mut node = Monitored::new(
+            YesTick::new(Status::Failed),
+            NoTick::new(),
+            YesTick::new(Status::Failed),
+        );
+        let status = node.tick(&mut ());
+        drop(node);
+        assert_eq!(status, Status::Failed);
+    }
+}
@@ -1,8 +1,40 @@
 //! Nodes that tick their children in parallel
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
+    rng::Rng,
     status::Status,
 };
+use std::borrow::Cow;
+
+/// The order in which a `Parallel` node ticks its children each cycle.
+///
+/// Children which are ticked earlier see the world before their siblings have
+/// changed it, which can give them a systematic advantage. Changing the order
+/// from one tick to the next shares that advantage out.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TickOrder {
+    /// The children are ticked from first to last.
+    Fixed,
+
+    /// The children are ticked from last to first.
+    Reversed,
+
+    /// The child ticked first moves along by one each tick, wrapping around
+    /// to the first child after the last one.
+    Rotating,
+
+    /// The children are ticked in a random order, which is the same for the
+    /// same seed.
+    Random {
+        /// The seed of the random number generator.
+        seed: u64,
+    },
+}
+impl Default for TickOrder {
+    fn default() -> Self {
+        TickOrder::Fixed
+    }
+}
 
 /// A node that handles "concurrent" behavior.
 ///
@@ -13,6 +45,19 @@ use crate::{
 /// remaining children to bring the success count to the required threshold then
 /// the node fails. Otherwise it is considered running.
 ///
+/// The children are ticked from first to last unless a different `TickOrder`
+/// is given to `Parallel::with_order`.
+///
+/// The children can also be split into groups with `Parallel::with_groups`,
+/// each with its own threshold. A group succeeds or fails by its own threshold
+/// in the same way, and the threshold of the node then counts groups rather
+/// than children. This keeps the children of the groups directly below the
+/// node rather than behind a nested `Parallel` for each group.
+///
+/// Alternatively, each child can be given a weight with `Parallel::weighted`,
+/// in which case the threshold is the total weight of the children which must
+/// succeed, such as when some sensors are trusted more than others.
+///
 /// Note that a threshold of zero means this node always succeeds on the first
 /// tick and a threshold greater than the number of children means this node
 /// always fails on the first tick.
@@ -106,12 +151,47 @@ use crate::{
 ///
 /// assert_eq!(node.tick(&mut ()), Status::Failed);
 /// ```
+///
+/// A node that needs at least one camera and at least one lidar to succeed:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let mut node = Parallel::with_groups(
+///     TickOrder::Fixed,
+///     2,
+///     vec![
+///         ParallelGroup::new(1, vec![AlwaysFail::new(), AlwaysSucceed::new()]),
+///         ParallelGroup::new(1, vec![AlwaysRunning::new()]),
+///     ],
+/// );
+///
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// assert_eq!(node.children().len(), 3);
+/// ```
 pub struct Parallel<'a, W> {
     /// Child nodes.
     children: Vec<Node<'a, W>>,
 
-    /// Number of child nodes required to succeed.
-    required_successes: usize,
+    /// How many of the children are required to succeed.
+    threshold: Threshold,
+
+    /// The status of each child on the current tick.
+    statuses: Vec<Status>,
+
+    /// The order the children are ticked in.
+    order: TickOrder,
+
+    /// The number of times this node has been ticked, which sets the first
+    /// child of a rotating order.
+    cycle: usize,
+
+    /// The generator of random orders.
+    rng: Rng,
+
+    /// The indices of the children in the order they are ticked this cycle.
+    indices: Vec<usize>,
 }
 impl<'a, W> Parallel<'a, W>
 where
@@ -120,48 +200,188 @@ where
     /// Creates a `Parallel` node with the given children an required number of
     /// successes.
     pub fn new(required_successes: usize, children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Parallel::with_order(TickOrder::default(), required_successes, children)
+    }
+
+    /// Creates a `Parallel` node which ticks its children in the given order.
+    ///
+    /// The rotation and the random sequence carry on across resets, so that
+    /// the children keep taking turns to go first.
+    pub fn with_order(
+        order: TickOrder,
+        required_successes: usize,
+        children: Vec<Node<'a, W>>,
+    ) -> Node<'a, W> {
+        Parallel::build(order, Threshold::Count(required_successes), children)
+    }
+
+    /// Creates a `Parallel` node whose children are split into groups.
+    ///
+    /// The node succeeds when `required_groups` of the groups have succeeded.
+    /// The children of all of the groups are ticked in the given order, as
+    /// though they were not grouped.
+    pub fn with_groups(
+        order: TickOrder,
+        required_groups: usize,
+        groups: Vec<ParallelGroup<'a, W>>,
+    ) -> Node<'a, W> {
+        let mut sizes = Vec::with_capacity(groups.len());
+        let mut children = Vec::new();
+        for group in groups {
+            sizes.push((group.children.len(), group.required_successes));
+            children.extend(group.children);
+        }
+        Parallel::build(order, Threshold::Groups(required_groups, sizes), children)
+    }
+
+    /// Creates a `Parallel` node whose children are each given a weight.
+    ///
+    /// The node succeeds once the total weight of the children which have
+    /// succeeded reaches `required_weight`, and fails once the children which
+    /// have not failed do not weigh enough to reach it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aspen::std_nodes::*;
+    /// # use aspen::Status;
+    /// # use aspen::node::Tickable;
+    /// let mut node = Parallel::weighted(
+    ///     TickOrder::Fixed,
+    ///     1.5,
+    ///     vec![
+    ///         (1.0, AlwaysSucceed::new()),
+    ///         (0.25, AlwaysSucceed::new()),
+    ///         (0.5, AlwaysFail::new()),
+    ///     ],
+    /// );
+    ///
+    /// assert_eq!(node.tick(&mut ()), Status::Failed);
+    /// ```
+    pub fn weighted(
+        order: TickOrder,
+        required_weight: f64,
+        children: Vec<(f64, Node<'a, W>)>,
+    ) -> Node<'a, W> {
+        let (weights, children) = children.into_iter().unzip();
+        Parallel::build(
+            order,
+            Threshold::Weighted(required_weight, weights),
+            children,
+        )
+    }
+
+    /// Creates a `Parallel` node.
+    fn build(order: TickOrder, threshold: Threshold, children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        let seed = match order {
+            TickOrder::Random { seed } => seed,
+            _ => 0,
+        };
         let internals = Parallel {
+            indices: (0..children.len()).collect(),
+            statuses: Vec::with_capacity(children.len()),
             children,
-            required_successes,
+            threshold,
+            order,
+            cycle: 0,
+            rng: Rng::new(seed),
         };
         Node::new(internals)
     }
 }
+impl<'a, W> Parallel<'a, W> {
+    /// Returns the status of this node given the status of each child.
+    fn outcome(&self, statuses: &[Status]) -> Status {
+        match self.threshold {
+            Threshold::Count(required) => threshold(required, statuses),
+            Threshold::Groups(required, ref groups) => {
+                let mut start = 0;
+                let groups: Vec<Status> = groups
+                    .iter()
+                    .map(|&(len, required)| {
+                        start += len;
+                        threshold(required, &statuses[start - len..start])
+                    })
+                    .collect();
+                threshold(required, &groups)
+            }
+            Threshold::Weighted(required, ref weights) => {
+                let mut succeeded = 0.0;
+                let mut possible = 0.0;
+                for (&status, &weight) in statuses.iter().zip(weights) {
+                    if status == Status::Succeeded {
+                        succeeded += weight;
+                    }
+                    if status != Status::Failed {
+                        possible += weight;
+                    }
+                }
+
+                if succeeded >= required {
+                    Status::Succeeded
+                } else if possible < required {
+                    Status::Failed
+                } else {
+                    Status::Running
+                }
+            }
+        }
+    }
+
+    /// Arranges `indices` in the order the children are ticked this cycle.
+    fn arrange(&mut self) {
+        let count = self.children.len();
+        self.indices.clear();
+        match self.order {
+            TickOrder::Fixed => self.indices.extend(0..count),
+            TickOrder::Reversed => self.indices.extend((0..count).rev()),
+            TickOrder::Rotating => {
+                let first = if count == 0 { 0 } else { self.cycle % count };
+                self.indices.extend((first..count).chain(0..first));
+            }
+            TickOrder::Random { .. } => {
+                // A Fisher-Yates shuffle
+                self.indices.extend(0..count);
+                for i in (1..count).rev() {
+                    let j = self.rng.below(i + 1);
+                    self.indices.swap(i, j);
+                }
+            }
+        }
+        self.cycle = self.cycle.wrapping_add(1);
+    }
+}
 impl<'a, W> Tickable<W> for Parallel<'a, W> {
     fn tick(&mut self, world: &mut W) -> Status {
-        let mut successes = 0;
-        let mut failures = 0;
+        self.arrange();
+        self.statuses.clear();
+        self.statuses.resize(self.children.len(), Status::Running);
 
         // Go through all the children to determine success or failure
-        for child in &mut self.children {
+        for &i in &self.indices {
+            let child = &mut self.children[i];
             // Check if this child has already completed
-            let s = match child.status() {
+            self.statuses[i] = match child.status() {
                 Some(Status::Succeeded) => Status::Succeeded,
                 Some(Status::Failed) => Status::Failed,
                 _ => child.tick(world),
             };
-
-            if s == Status::Succeeded {
-                successes += 1;
-            } else if s == Status::Failed {
-                failures += 1;
-            }
         }
 
-        // Return a result based on the children
-        if successes >= self.required_successes {
-            // Enough children succeeded
-            Status::Succeeded
-        } else if failures + self.required_successes > self.children.len() {
-            // Too many children failed - it is impossible to succeed. I
-            // suspect the overflow condition to be significantly less likely
-            // than the underflow, which is why I've written the condition this
-            // way.
-            Status::Failed
-        } else {
-            // Status is still undetermined
-            Status::Running
-        }
+        self.outcome(&self.statuses)
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let statuses: Vec<Status> = self
+            .children
+            .iter()
+            .map(|child| match child.status() {
+                Some(s) if s.is_done() && !run.after_reset() => s,
+                _ => run.tick(child, world),
+            })
+            .collect();
+
+        Some(self.outcome(&statuses))
     }
 
     fn reset(&mut self) {
@@ -171,7 +391,7 @@ impl<'a, W> Tickable<W> for Parallel<'a, W> {
         }
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         self.children.iter().collect()
     }
 
@@ -179,6 +399,96 @@ impl<'a, W> Tickable<W> for Parallel<'a, W> {
     fn type_name(&self) -> &'static str {
         "Parallel"
     }
+
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        // The node succeeds straight away, whatever its children do
+        let message = Cow::Borrowed("cannot affect the outcome, since no successes are required");
+        let all = || {
+            (0..self.children.len())
+                .map(|i| (i, message.clone()))
+                .collect()
+        };
+        match self.threshold {
+            Threshold::Count(0) | Threshold::Groups(0, _) => all(),
+            Threshold::Weighted(required, _) if required <= 0.0 => all(),
+            Threshold::Count(_) => Vec::new(),
+            Threshold::Groups(_, ref groups) => {
+                // As do groups which require no successes
+                let mut unreachable = Vec::new();
+                let mut start = 0;
+                for &(len, required) in groups {
+                    if required == 0 {
+                        unreachable.extend((start..start + len).map(|i| (i, message.clone())));
+                    }
+                    start += len;
+                }
+                unreachable
+            }
+            Threshold::Weighted(_, ref weights) => weights
+                .iter()
+                .enumerate()
+                .filter(|&(_, &weight)| weight == 0.0)
+                .map(|(i, _)| {
+                    (
+                        i,
+                        Cow::Borrowed("cannot affect the outcome, since it has no weight"),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// How many of the children of a `Parallel` node are required to succeed.
+enum Threshold {
+    /// A number of children.
+    Count(usize),
+
+    /// A number of groups, along with the number of children in each group
+    /// and how many of them are required to succeed.
+    Groups(usize, Vec<(usize, usize)>),
+
+    /// A total weight, along with the weight of each child.
+    Weighted(f64, Vec<f64>),
+}
+
+/// A group of the children of a `Parallel` node with its own threshold.
+pub struct ParallelGroup<'a, W> {
+    /// The children in the group.
+    children: Vec<Node<'a, W>>,
+
+    /// Number of children in the group required to succeed.
+    required_successes: usize,
+}
+impl<'a, W> ParallelGroup<'a, W> {
+    /// Creates a group which succeeds once `required_successes` of its
+    /// children have.
+    pub fn new(required_successes: usize, children: Vec<Node<'a, W>>) -> Self {
+        ParallelGroup {
+            children,
+            required_successes,
+        }
+    }
+}
+
+/// Returns the status of a set of nodes given their statuses and how many of
+/// them are required to succeed.
+fn threshold(required: usize, statuses: &[Status]) -> Status {
+    let successes = statuses.iter().filter(|&&s| s == Status::Succeeded).count();
+    let failures = statuses.iter().filter(|&&s| s == Status::Failed).count();
+    if successes >= required {
+        // Enough children succeeded
+        Status::Succeeded
+    } else if failures + required > statuses.len() {
+        // Too many children failed - it is impossible to succeed. I
+        // suspect the overflow condition to be significantly less likely
+        // than the underflow, which is why I've written the condition this
+        // way.
+        Status::Failed
+    } else {
+        // Status is still undetermined
+        Status::Running
+    }
 }
 
 /// Convenience macro for creating Parallel nodes.
@@ -206,10 +516,11 @@ macro_rules! Parallel
 
 #[cfg(test)]
 mod tests {
+    use super::TickOrder;
     use crate::{
         node::Tickable,
         status::Status,
-        std_nodes::{Parallel, YesTick},
+        std_nodes::{InlineAction, Parallel, ParallelGroup, YesTick},
     };
 
     #[test]
@@ -259,4 +570,103 @@ mod tests {
         drop(parallel);
         assert_eq!(status, Status::Running);
     }
+
+    #[test]
+    fn tick_order() {
+        let orders = |order| {
+            let children = (0..3)
+                .map(|i| {
+                    InlineAction::new(move |w: &mut Vec<usize>| {
+                        w.push(i);
+                        Status::Running
+                    })
+                })
+                .collect();
+            let mut parallel = Parallel::with_order(order, 3, children);
+            let mut ticked = Vec::new();
+            let mut cycles = Vec::new();
+            for _ in 0..4 {
+                parallel.tick(&mut ticked);
+                cycles.push(ticked.split_off(0));
+            }
+            cycles
+        };
+
+        assert_eq!(orders(TickOrder::Fixed), vec![vec![0, 1, 2]; 4]);
+        assert_eq!(orders(TickOrder::Reversed), vec![vec![2, 1, 0]; 4]);
+        assert_eq!(
+            orders(TickOrder::Rotating),
+            vec![vec![0, 1, 2], vec![1, 2, 0], vec![2, 0, 1], vec![0, 1, 2]]
+        );
+
+        let random = orders(TickOrder::Random { seed: 7 });
+        assert_eq!(random, orders(TickOrder::Random { seed: 7 }));
+        for mut cycle in random {
+            cycle.sort_unstable();
+            assert_eq!(cycle, vec![0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn groups() {
+        let mut parallel = Parallel::with_groups(
+            TickOrder::Fixed,
+            2,
+            vec![
+                ParallelGroup::new(
+                    1,
+                    vec![YesTick::new(Status::Failed), YesTick::new(Status::Running)],
+                ),
+                ParallelGroup::new(2, vec![YesTick::new(Status::Succeeded)]),
+                ParallelGroup::new(0, vec![YesTick::new(Status::Failed)]),
+            ],
+        );
+        // The second group can never succeed, but the third already has
+        assert_eq!(parallel.tick(&mut ()), Status::Running);
+        assert_eq!(parallel.children().len(), 4);
+        assert_eq!(
+            parallel
+                .unreachable_children()
+                .into_iter()
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+
+        let mut parallel = Parallel::with_groups(
+            TickOrder::Fixed,
+            1,
+            vec![
+                ParallelGroup::new(2, vec![YesTick::new(Status::Succeeded)]),
+                ParallelGroup::new(1, vec![YesTick::new(Status::Failed)]),
+            ],
+        );
+        assert_eq!(parallel.tick(&mut ()), Status::Failed);
+    }
+
+    #[test]
+    fn weights() {
+        let mut parallel = Parallel::weighted(
+            TickOrder::Fixed,
+            2.0,
+            vec![
+                (1.5, YesTick::new(Status::Succeeded)),
+                (0.5, YesTick::new(Status::Running)),
+                (1.0, YesTick::new(Status::Failed)),
+                (0.0, YesTick::new(Status::Running)),
+            ],
+        );
+        assert_eq!(parallel.tick(&mut ()), Status::Running);
+        assert_eq!(parallel.unreachable_children().len(), 1);
+
+        let mut parallel = Parallel::weighted(
+            TickOrder::Fixed,
+            2.0,
+            vec![
+                (1.5, YesTick::new(Status::Succeeded)),
+                (0.5, YesTick::new(Status::Succeeded)),
+            ],
+        );
+        assert_eq!(parallel.tick(&mut ()), Status::Succeeded);
+    }
 }
@@ -0,0 +1,228 @@
+//! A composite that runs several lanes of nodes through the same phases in
+//! lockstep.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::borrow::Cow;
+
+/// A node that runs several lanes in lockstep, one phase at a time.
+///
+/// Each lane is a list of nodes, one for each phase, such as the steps taken by
+/// one arm or one robot. Every tick, this node ticks the nodes of the current
+/// phase in every lane which have not yet completed. Once all of them have
+/// succeeded the next phase starts, in the same tick, so no lane ever starts
+/// phase `k + 1` before every lane has finished phase `k`. This is the
+/// barrier that `Parallel` cannot express, since its threshold only counts
+/// children.
+///
+/// A lane signals the end of a phase by its node succeeding. Lanes with fewer
+/// nodes than the others have nothing to do in the later phases.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While a node of the current phase is running.
+///
+/// **Succeeded:** Every lane has finished every phase.
+///
+/// **Failed:** A node of the current phase failed.
+///
+/// # Children
+///
+/// The nodes of every lane, lane by lane. A node is only ticked during its
+/// phase, and the nodes of every phase are reset when this node is.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let left = vec![AlwaysSucceed::new(), AlwaysSucceed::new()];
+/// let right = vec![AlwaysRunning::new(), AlwaysSucceed::new()];
+/// let mut node = Phased::new(vec![left, right]);
+///
+/// // The left arm waits for the right one to finish the first phase
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// assert_eq!(node.children()[1].status(), None);
+/// ```
+pub struct Phased<'a, W> {
+    /// The nodes of each lane, one for each phase.
+    lanes: Vec<Vec<Node<'a, W>>>,
+
+    /// The number of phases, which is the length of the longest lane.
+    phases: usize,
+
+    /// The current phase.
+    phase: usize,
+}
+impl<'a, W> Phased<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a `Phased` node from the nodes of each lane.
+    pub fn new(lanes: Vec<Vec<Node<'a, W>>>) -> Node<'a, W> {
+        let phases = lanes.iter().map(Vec::len).max().unwrap_or(0);
+        let internals = Phased {
+            lanes,
+            phases,
+            phase: 0,
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Tickable<W> for Phased<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        while self.phase < self.phases {
+            let phase = self.phase;
+            let mut running = false;
+            for node in self.lanes.iter_mut().filter_map(|l| l.get_mut(phase)) {
+                let s = match node.status() {
+                    Some(s) if s.is_done() => s,
+                    _ => node.tick(world),
+                };
+
+                match s {
+                    Status::Failed => return Status::Failed,
+                    Status::Running => running = true,
+                    Status::Succeeded => {}
+                }
+            }
+
+            if running {
+                return Status::Running;
+            }
+            self.phase += 1;
+        }
+
+        Status::Succeeded
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let mut phase = if run.after_reset() { 0 } else { self.phase };
+        while phase < self.phases {
+            let mut running = false;
+            for node in self.lanes.iter().filter_map(|l| l.get(phase)) {
+                let s = match node.status() {
+                    Some(s) if s.is_done() && !run.after_reset() => s,
+                    _ => run.tick(node, world),
+                };
+
+                match s {
+                    Status::Failed => return Some(Status::Failed),
+                    Status::Running => running = true,
+                    Status::Succeeded => {}
+                }
+            }
+
+            if running {
+                return Some(Status::Running);
+            }
+            phase += 1;
+        }
+
+        Some(Status::Succeeded)
+    }
+
+    fn reset(&mut self) {
+        for node in self.lanes.iter_mut().flatten() {
+            node.reset();
+        }
+
+        self.phase = 0;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.lanes.iter().flatten().collect()
+    }
+
+    /// Returns the string "Phased".
+    fn type_name(&self) -> &'static str {
+        "Phased"
+    }
+
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        // The first phase with a node which never succeeds is never finished
+        let blocker = (0..self.phases).find_map(|phase| {
+            self.lanes.iter().find_map(|l| {
+                l.get(phase)
+                    .filter(|n| matches!(n.type_name(), "AlwaysFail" | "AlwaysRunning"))
+                    .map(|n| (phase, n.name().to_owned()))
+            })
+        });
+        let (blocked, name) = match blocker {
+            Some(b) => b,
+            None => return Vec::new(),
+        };
+
+        let message = format!("unreachable, since {} never finishes its phase", name);
+        let mut unreachable = Vec::new();
+        let mut index = 0;
+        for lane in &self.lanes {
+            for phase in 0..lane.len() {
+                if phase > blocked {
+                    unreachable.push((index, Cow::Owned(message.clone())));
+                }
+                index += 1;
+            }
+        }
+        unreachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysFail, AlwaysSucceed, InlineAction, NoTick, Phased, YesTick},
+    };
+
+    #[test]
+    fn phases_run_in_lockstep() {
+        let step = |name: &'static str, running: u32| {
+            let mut ticks = 0;
+            InlineAction::new(move |log: &mut Vec<&'static str>| {
+                log.push(name);
+                ticks += 1;
+                if ticks > running {
+                    Status::Succeeded
+                } else {
+                    Status::Running
+                }
+            })
+        };
+        let left = vec![step("left 0", 0), step("left 1", 0)];
+        let right = vec![step("right 0", 1), step("right 1", 0)];
+        let mut phased = Phased::new(vec![left, right]);
+
+        let mut log = Vec::new();
+        assert_eq!(phased.tick(&mut log), Status::Running);
+        assert_eq!(log, ["left 0", "right 0"]);
+        assert_eq!(phased.tick(&mut log), Status::Succeeded);
+        assert_eq!(log, ["left 0", "right 0", "right 0", "left 1", "right 1"]);
+    }
+
+    #[test]
+    fn failures_and_blockers() {
+        let left = vec![YesTick::new(Status::Succeeded), NoTick::new()];
+        let right = vec![YesTick::new(Status::Failed), NoTick::new()];
+        let mut phased = Phased::new(vec![left, right]);
+        assert_eq!(phased.tick(&mut ()), Status::Failed);
+        drop(phased);
+
+        let left = vec![AlwaysFail::<()>::new(), NoTick::new()];
+        let right = vec![AlwaysSucceed::new()];
+        let phased = Phased::new(vec![left, right]);
+        assert_eq!(
+            phased
+                .unreachable_children()
+                .into_iter()
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+}
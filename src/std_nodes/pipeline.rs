@@ -0,0 +1,300 @@
+//! Nodes that pass values from one stage to the next.
+use crate::{
+    blackboard::Blackboard,
+    node::{DryRun, Node, Tickable},
+    status::Status,
+    std_nodes::sequence::dry_tick_sequence,
+};
+use std::{
+    any::{self, Any, TypeId},
+    borrow::Cow,
+};
+
+/// The blackboard entry one stage of a `Pipeline` passes to the next.
+struct Link {
+    /// The key of the entry.
+    key: Cow<'static, str>,
+
+    /// The type of the value.
+    type_id: TypeId,
+
+    /// The name of the type of the value.
+    type_name: &'static str,
+}
+
+/// A node that runs its children as a chain of stages, each passing a value to
+/// the next through the blackboard.
+///
+/// The children are ticked in order, the same as a `Sequence`. Each stage but
+/// the last writes its output to a blackboard entry, which the next stage
+/// reads as its input. When a stage succeeds, the pipeline checks that it
+/// wrote a value of the declared type to its entry since it started, so that
+/// a stale value left over from an earlier run is never passed on. A stage
+/// which succeeds without doing so fails the pipeline, as does any stage
+/// which fails.
+///
+/// This models data-processing chains such as sense, plan, act explicitly.
+/// The stages are ordinary nodes, so they are seen by monitors, traces, dry
+/// runs and exports, and the entries are declared with `Node::writes` and
+/// `Node::reads` on the stages, so `validate` and dataflow graphs see them
+/// too.
+///
+/// The pipeline is built up a stage at a time, starting with `Pipeline::new`,
+/// and is turned into a node with `into_node`.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While a stage is running.
+///
+/// **Succeeded:** When the last stage succeeds.
+///
+/// **Failed:** When any stage fails, or succeeds without writing its output.
+///
+/// # Children
+///
+/// Any number, which are the stages of the pipeline. A dry run predicts the
+/// status as for a `Sequence`, without checking the outputs.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::blackboard::Blackboard;
+/// let sense = InlineAction::new(|b: &mut Blackboard| {
+///     b.set("range", 1.5f32);
+///     Status::Succeeded
+/// });
+/// let plan = InlineAction::new(|b: &mut Blackboard| match b.get::<f32>("range") {
+///     Some(&range) => {
+///         b.set("speed", (range - 1.0).max(0.0).min(2.0));
+///         Status::Succeeded
+///     },
+///     None => Status::Failed,
+/// });
+/// let act = InlineAction::new(|b: &mut Blackboard| match b.get::<f32>("speed") {
+///     Some(&speed) => {
+///         b.set("motor", speed);
+///         Status::Succeeded
+///     },
+///     None => Status::Failed,
+/// });
+///
+/// let mut node = Pipeline::new(sense)
+///     .then::<f32, _>("range", plan)
+///     .then::<f32, _>("speed", act)
+///     .into_node();
+///
+/// let mut blackboard = Blackboard::new();
+/// assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+/// assert_eq!(blackboard.get::<f32>("motor"), Some(&0.5));
+/// ```
+pub struct Pipeline<'a, W> {
+    /// The stages of the pipeline.
+    children: Vec<Node<'a, W>>,
+
+    /// The entries passed between the stages, where the entry at index `i` is
+    /// the output of stage `i`.
+    links: Vec<Link>,
+
+    /// The stage which is currently running or will be run next.
+    next_child: usize,
+
+    /// The revision of the output of the current stage when it started, if it
+    /// has started.
+    started: Option<Option<u64>>,
+}
+impl<'a, W> Pipeline<'a, W>
+where
+    W: AsRef<Blackboard> + 'a,
+{
+    /// Creates a new `Pipeline` whose first stage is `first`.
+    pub fn new(first: Node<'a, W>) -> Self {
+        Pipeline {
+            children: vec![first],
+            links: Vec::new(),
+            next_child: 0,
+            started: None,
+        }
+    }
+
+    /// Adds a stage which reads the output of the current last stage.
+    ///
+    /// The current last stage must write its output to the blackboard entry
+    /// with the given key, which is a `T`, and `stage` reads it from there.
+    pub fn then<T, K>(mut self, key: K, stage: Node<'a, W>) -> Self
+    where
+        T: Any,
+        K: Into<Cow<'static, str>>,
+    {
+        let key = key.into();
+        let last = self.children.pop().expect("a pipeline always has a stage");
+        self.children.push(last.writes::<T, _>(key.clone()));
+        self.children.push(stage.reads::<T, _>(key.clone()));
+        self.links.push(Link {
+            key,
+            type_id: TypeId::of::<T>(),
+            type_name: any::type_name::<T>(),
+        });
+        self
+    }
+}
+impl<'a, W> Tickable<W> for Pipeline<'a, W>
+where
+    W: AsRef<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        while self.next_child < self.children.len() {
+            let link = self.links.get(self.next_child);
+            let started = *self
+                .started
+                .get_or_insert_with(|| link.and_then(|l| world.as_ref().revision(&l.key)));
+
+            match self.children[self.next_child].tick(world) {
+                Status::Running => return Status::Running,
+                Status::Failed => return Status::Failed,
+                Status::Succeeded => {
+                    if let Some(link) = link {
+                        let blackboard = world.as_ref();
+                        if blackboard.revision(&link.key) == started
+                            || blackboard.type_of(&link.key) != Some(link.type_id)
+                        {
+                            warn!(
+                                "Pipeline stage {} succeeded without writing {} as a {}",
+                                self.next_child, link.key, link.type_name
+                            );
+                            return Status::Failed;
+                        }
+                    }
+
+                    self.next_child += 1;
+                    self.started = None;
+                }
+            }
+        }
+
+        Status::Succeeded
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(dry_tick_sequence(
+            &self.children,
+            self.next_child,
+            world,
+            run,
+        ))
+    }
+
+    fn reset(&mut self) {
+        for child in &mut self.children {
+            child.reset();
+        }
+
+        self.next_child = 0;
+        self.started = None;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "Pipeline".
+    fn type_name(&self) -> &'static str {
+        "Pipeline"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        blackboard::{Access, Blackboard},
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysSucceed, InlineAction, Pipeline},
+    };
+
+    #[test]
+    fn values_are_passed_along() {
+        let mut node = Pipeline::new(InlineAction::new(|b: &mut Blackboard| {
+            b.set("n", 7u32);
+            Status::Succeeded
+        }))
+        .then::<u32, _>(
+            "n",
+            InlineAction::new(|b: &mut Blackboard| {
+                let n = *b.get::<u32>("n").unwrap();
+                let ticks = b.get::<u32>("ticks").copied().unwrap_or(0) + 1;
+                b.set("ticks", ticks);
+                if ticks < 2 {
+                    Status::Running
+                } else {
+                    b.set("doubled", n * 2);
+                    Status::Succeeded
+                }
+            }),
+        )
+        .then::<u32, _>("doubled", AlwaysSucceed::new())
+        .into_node();
+
+        let mut blackboard = Blackboard::new();
+        assert_eq!(node.tick(&mut blackboard), Status::Running);
+        assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+        assert_eq!(blackboard.get::<u32>("doubled"), Some(&14));
+
+        let children = node.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].status(), Some(Status::Succeeded));
+        assert_eq!(children[1].key_uses()[0].access(), Access::Read);
+        assert_eq!(children[1].key_uses()[1].access(), Access::Write);
+        assert_eq!(children[1].key_uses()[1].key(), "doubled");
+    }
+
+    #[test]
+    fn fails_fast() {
+        let mut node = Pipeline::new(InlineAction::new(|_: &mut Blackboard| Status::Failed))
+            .then::<u32, _>("n", InlineAction::new(|_| panic!("stage was run")))
+            .into_node();
+
+        assert_eq!(node.tick(&mut Blackboard::new()), Status::Failed);
+    }
+
+    #[test]
+    fn stale_or_mistyped_outputs_fail() {
+        let mut node = Pipeline::new(AlwaysSucceed::new())
+            .then::<u32, _>("n", AlwaysSucceed::new())
+            .into_node();
+
+        // The value was written before the stage started
+        let mut blackboard = Blackboard::new();
+        blackboard.set("n", 1u32);
+        assert_eq!(node.tick(&mut blackboard), Status::Failed);
+
+        let mut node = Pipeline::new(InlineAction::new(|b: &mut Blackboard| {
+            b.set("n", 1i64);
+            Status::Succeeded
+        }))
+        .then::<u32, _>("n", AlwaysSucceed::new())
+        .into_node();
+        assert_eq!(node.tick(&mut blackboard), Status::Failed);
+    }
+
+    #[test]
+    fn reset_starts_over() {
+        let mut node = Pipeline::new(InlineAction::new(|b: &mut Blackboard| {
+            let runs = b.get::<u32>("runs").copied().unwrap_or(0) + 1;
+            b.set("runs", runs);
+            Status::Succeeded
+        }))
+        .then::<u32, _>("runs", AlwaysSucceed::new())
+        .into_node();
+
+        let mut blackboard = Blackboard::new();
+        assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+        node.reset();
+        assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+        assert_eq!(blackboard.get::<u32>("runs"), Some(&2));
+    }
+}
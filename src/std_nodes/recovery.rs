@@ -0,0 +1,277 @@
+//! Nodes that retry a behavior after running recovery behaviors.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+
+/// A node that retries its main child after running a recovery behavior each
+/// time it fails.
+///
+/// This node ticks its main child and, as long as the main child does not fail,
+/// has the same status. Each time the main child fails, the next recovery child
+/// is run to completion and then the main child is retried from the beginning.
+/// Recoveries are used in order, so they can escalate from cheap to drastic
+/// measures. If a recovery fails, the next one is run straight away instead of
+/// retrying the main child.
+///
+/// Once every recovery has been used, the list starts again from the first one,
+/// up to the configured number of rounds. The node only fails when the main
+/// child fails after every recovery has been exhausted.
+///
+/// Recovery and retries happen within the same tick until a child reports that
+/// it is running.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the main child or a recovery child is running.
+///
+/// **Succeeded:** When the main child succeeds.
+///
+/// **Failed:** When the main child fails and there are no recoveries left.
+///
+/// # Children
+///
+/// The main child, followed by any number of recovery children. Children are
+/// reset before they are run again, and all of them are reset when this node
+/// is reset.
+///
+/// # Examples
+///
+/// A navigation behavior which clears its map and then backs up before giving
+/// up:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// #[derive(Default)]
+/// struct Robot {
+///     blocked: u32,
+///     recoveries: Vec<&'static str>,
+/// }
+///
+/// let mut node = Recovery::new(
+///     Condition::new(|r: &Robot| r.blocked == 0),
+///     vec![
+///         InlineAction::new(|r: &mut Robot| {
+///             r.recoveries.push("clear map");
+///             Status::Succeeded
+///         }),
+///         InlineAction::new(|r: &mut Robot| {
+///             r.recoveries.push("back up");
+///             r.blocked -= 1;
+///             Status::Succeeded
+///         }),
+///     ],
+/// );
+///
+/// let mut robot = Robot {
+///     blocked: 1,
+///     ..Default::default()
+/// };
+/// assert_eq!(node.tick(&mut robot), Status::Succeeded);
+/// assert_eq!(robot.recoveries, vec!["clear map", "back up"]);
+/// ```
+pub struct Recovery<'a, W> {
+    /// The behavior which is retried.
+    main: Node<'a, W>,
+
+    /// The behaviors run after the main child fails, in order.
+    recoveries: Vec<Node<'a, W>>,
+
+    /// The number of times the list of recoveries can be used.
+    rounds: u32,
+
+    /// The number of recoveries that have been used.
+    used: usize,
+
+    /// Whether a recovery is running.
+    recovering: bool,
+}
+impl<'a, W> Recovery<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Recovery` node which uses each recovery at most once.
+    pub fn new(main: Node<'a, W>, recoveries: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Self::with_rounds(1, main, recoveries)
+    }
+
+    /// Creates a new `Recovery` node which goes through the list of recoveries
+    /// at most `rounds` times.
+    pub fn with_rounds(
+        rounds: u32,
+        main: Node<'a, W>,
+        recoveries: Vec<Node<'a, W>>,
+    ) -> Node<'a, W> {
+        let internals = Recovery {
+            main,
+            recoveries,
+            rounds,
+            used: 0,
+            recovering: false,
+        };
+        Node::new(internals)
+    }
+
+    /// Returns `true` if every recovery has been used.
+    fn exhausted(&self, used: usize) -> bool {
+        used >= self.recoveries.len() * self.rounds as usize
+    }
+}
+impl<'a, W> Tickable<W> for Recovery<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        loop {
+            if !self.recovering {
+                match self.main.tick(world) {
+                    Status::Failed if !self.exhausted(self.used) => {
+                        debug!("{} failed, recovering", self.main.name());
+                        self.recovering = true;
+                    }
+                    s => return s,
+                }
+            }
+
+            let index = self.used % self.recoveries.len();
+            let status = self.recoveries[index].tick(world);
+            if status == Status::Running {
+                return Status::Running;
+            }
+
+            self.recoveries[index].reset();
+            self.used += 1;
+            if status == Status::Failed && !self.exhausted(self.used) {
+                debug!("Recovery failed, trying the next one");
+                continue;
+            }
+
+            // Retry the main behavior, which fails if there are no recoveries
+            // left and the last one failed
+            self.recovering = false;
+            self.main.reset();
+        }
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let (used, recovering) = if run.after_reset() {
+            (0, false)
+        } else {
+            (self.used, self.recovering)
+        };
+
+        if !recovering {
+            match run.tick(&self.main, world) {
+                Status::Failed if !self.exhausted(used) => {}
+                s => return Some(s),
+            }
+        }
+
+        // Whatever the recovery does, the main child is retried or the next
+        // recovery run, so the outcome is not known until later
+        run.tick(&self.recoveries[used % self.recoveries.len()], world);
+        Some(Status::Running)
+    }
+
+    fn reset(&mut self) {
+        self.main.reset();
+        for recovery in &mut self.recoveries {
+            recovery.reset();
+        }
+
+        self.used = 0;
+        self.recovering = false;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        let mut children = vec![&self.main];
+        children.extend(self.recoveries.iter());
+        children
+    }
+
+    /// Returns the string "Recovery".
+    fn type_name(&self) -> &'static str {
+        "Recovery"
+    }
+}
+
+/// Convenience macro for creating Recovery nodes.
+///
+/// The first node is the main child and the rest are the recoveries.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate aspen;
+/// # fn main() {
+/// let recovery = Recovery! {
+///     Condition!{ |&(a, _): &(u32, u32)| a < 12 },
+///     InlineAction!{ |s: &mut (u32, u32)| { s.0 -= 1; aspen::Status::Succeeded } },
+///     InlineAction!{ |s: &mut (u32, u32)| { s.0 = 0; aspen::Status::Succeeded } }
+/// };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! Recovery {
+    ( $m:expr $(, $e:expr )* ) => {
+        $crate::std_nodes::Recovery::new($m, vec![$( $e ),*])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{CountedTick, NoTick, Recovery, YesTick},
+    };
+
+    #[test]
+    fn success_needs_no_recovery() {
+        let mut node = Recovery::new(YesTick::new(Status::Succeeded), vec![NoTick::new()]);
+        let status = node.tick(&mut ());
+        drop(node);
+        assert_eq!(status, Status::Succeeded);
+    }
+
+    #[test]
+    fn fails_once_recoveries_are_exhausted() {
+        let mut node = Recovery::with_rounds(
+            2,
+            CountedTick::new(Status::Failed, 5, true),
+            vec![
+                CountedTick::new(Status::Succeeded, 2, true),
+                CountedTick::new(Status::Succeeded, 2, true),
+            ],
+        );
+        let status = node.tick(&mut ());
+        drop(node);
+        assert_eq!(status, Status::Failed);
+    }
+
+    #[test]
+    fn failed_recovery_moves_to_the_next() {
+        let mut node = Recovery::new(
+            CountedTick::new(Status::Failed, 2, true),
+            vec![
+                CountedTick::new(Status::Failed, 1, true),
+                CountedTick::new(Status::Succeeded, 1, true),
+            ],
+        );
+        let status = node.tick(&mut ());
+        drop(node);
+        assert_eq!(status, Status::Failed);
+    }
+
+    #[test]
+    fn running_recovery_resumes() {
+        let mut node = Recovery::new(
+            CountedTick::new(Status::Failed, 1, true),
+            vec![CountedTick::new(Status::Running, 2, true)],
+        );
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        drop(node);
+    }
+}
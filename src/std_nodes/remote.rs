@@ -0,0 +1,762 @@
+//! Leaves which are run by another process or robot.
+use crate::{
+    node::{Node, Tickable},
+    status::Status,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// What a request asks the remote side to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RequestKind {
+    /// Start running an action.
+    Action,
+
+    /// Evaluate a condition.
+    Condition,
+
+    /// Stop the action of an earlier request.
+    Halt,
+}
+impl RequestKind {
+    /// Returns the name of the kind, as used by `TcpTransport`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RequestKind::Action => "action",
+            RequestKind::Condition => "condition",
+            RequestKind::Halt => "halt",
+        }
+    }
+}
+
+/// A request sent to the remote side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    /// Identifies the request, and the replies to it.
+    id: u64,
+
+    /// What to do.
+    kind: RequestKind,
+
+    /// The name of the leaf on the remote side.
+    leaf: String,
+}
+impl Request {
+    /// Returns the ID of the request, which replies must carry.
+    ///
+    /// A request may be sent again with the same ID after reconnecting, so
+    /// the remote side should ignore IDs it has already started.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns what the request asks for.
+    pub fn kind(&self) -> RequestKind {
+        self.kind
+    }
+
+    /// Returns the name of the leaf on the remote side.
+    pub fn leaf(&self) -> &str {
+        &self.leaf
+    }
+}
+
+/// A reply from the remote side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reply {
+    /// The ID of the request this answers.
+    id: u64,
+
+    /// The status of the leaf.
+    status: Status,
+}
+impl Reply {
+    /// Creates a reply to the request with the given ID.
+    ///
+    /// A `Running` reply tells the node that the request was delivered and is
+    /// still being worked on, which restarts its delivery timeout.
+    pub fn new(id: u64, status: Status) -> Self {
+        Reply { id, status }
+    }
+
+    /// Returns the ID of the request this answers.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the status of the leaf.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+}
+
+/// A way of exchanging requests and replies with another process or robot.
+///
+/// This is implemented by `TcpTransport`, and can be implemented on top of
+/// LCM, gRPC, MQTT or any other messaging system which can carry a request
+/// and its replies.
+pub trait Transport {
+    /// Sends a request.
+    ///
+    /// A request which the transport cannot carry, such as one for a leaf
+    /// whose name it cannot encode, should be rejected with an error of kind
+    /// `InvalidInput`. This fails the leaf which made it rather than being
+    /// treated as a lost connection.
+    fn send(&mut self, request: &Request) -> io::Result<()>;
+
+    /// Returns the next reply which has arrived, without blocking.
+    fn poll(&mut self) -> io::Result<Option<Reply>>;
+
+    /// Connects again after the transport returned an error.
+    fn reconnect(&mut self) -> io::Result<()>;
+}
+
+/// The state of a `RemoteLink`.
+struct LinkState<T> {
+    /// The transport.
+    transport: T,
+
+    /// Whether the transport is believed to be working.
+    connected: bool,
+
+    /// How many times the transport has reconnected.
+    generation: u64,
+
+    /// How long to wait between attempts to reconnect.
+    reconnect_interval: Duration,
+
+    /// When the last attempt to reconnect was made.
+    last_attempt: Option<Instant>,
+
+    /// The ID of the next request.
+    next_id: u64,
+
+    /// The latest status received for each request which is still waited on.
+    replies: HashMap<u64, Option<Status>>,
+}
+impl<T: Transport> LinkState<T> {
+    /// Marks the transport as broken after an error.
+    fn disconnect(&mut self, e: io::Error) {
+        if self.connected {
+            warn!("Lost connection to remote leaves: {}", e);
+            self.connected = false;
+            self.last_attempt = Some(Instant::now());
+        }
+    }
+
+    /// Tries to reconnect, if the transport is broken and it is time to.
+    fn maintain(&mut self) {
+        if self.connected
+            || self
+                .last_attempt
+                .map_or(false, |t| t.elapsed() < self.reconnect_interval)
+        {
+            return;
+        }
+
+        self.last_attempt = Some(Instant::now());
+        match self.transport.reconnect() {
+            Ok(()) => {
+                info!("Reconnected to remote leaves");
+                self.connected = true;
+                self.generation += 1;
+            }
+            Err(e) => debug!("Unable to reconnect to remote leaves: {}", e),
+        }
+    }
+
+    /// Sends a request, returning `true` if it was sent.
+    ///
+    /// An error is only returned if the transport rejected the request.
+    fn send(&mut self, request: &Request) -> io::Result<bool> {
+        if !self.connected {
+            return Ok(false);
+        }
+        match self.transport.send(request) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::InvalidInput => Err(e),
+            Err(e) => {
+                self.disconnect(e);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Collects the replies which have arrived.
+    fn receive(&mut self) {
+        while self.connected {
+            match self.transport.poll() {
+                Ok(Some(reply)) => {
+                    // Replies to requests which are no longer waited on are
+                    // dropped, as are updates after the final status
+                    if let Some(latest) = self.replies.get_mut(&reply.id) {
+                        if !latest.map_or(false, |s| s.is_done()) {
+                            *latest = Some(reply.status);
+                        }
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => self.disconnect(e),
+            }
+        }
+    }
+}
+
+/// A connection to another process or robot, shared by the remote leaves which
+/// run there.
+///
+/// Replies for every leaf arrive over the same transport, so the link keeps
+/// them until the leaf they belong to is next ticked. When the transport
+/// returns an error, the link tries to reconnect every time a leaf is ticked,
+/// at most once per reconnect interval. After reconnecting, leaves which were
+/// waiting send their requests again.
+pub struct RemoteLink<T> {
+    /// The shared state.
+    state: Rc<RefCell<LinkState<T>>>,
+}
+impl<T: Transport> RemoteLink<T> {
+    /// Creates a link over the given transport, which should be connected.
+    pub fn new(transport: T) -> Self {
+        RemoteLink {
+            state: Rc::new(RefCell::new(LinkState {
+                transport,
+                connected: true,
+                generation: 0,
+                reconnect_interval: Duration::from_secs(1),
+                last_attempt: None,
+                next_id: 1,
+                replies: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Sets how long to wait between attempts to reconnect.
+    ///
+    /// The default is one second.
+    pub fn with_reconnect_interval(self, interval: Duration) -> Self {
+        self.state.borrow_mut().reconnect_interval = interval;
+        self
+    }
+
+    /// Returns `true` if the transport is believed to be working.
+    pub fn is_connected(&self) -> bool {
+        self.state.borrow().connected
+    }
+}
+impl<T> Clone for RemoteLink<T> {
+    fn clone(&self) -> Self {
+        RemoteLink {
+            state: Rc::clone(&self.state),
+        }
+    }
+}
+
+/// A request which has been made and not yet answered.
+struct Pending {
+    /// The ID of the request.
+    id: u64,
+
+    /// The generation of the link when the request was last sent.
+    sent: Option<u64>,
+
+    /// When the request was made, or a reply last arrived.
+    heard: Instant,
+}
+
+/// The shared implementation of `RemoteAction` and `RemoteCondition`.
+struct RemoteLeaf<T> {
+    /// The link to the remote side.
+    link: RemoteLink<T>,
+
+    /// The kind of request to make.
+    kind: RequestKind,
+
+    /// The name of the leaf on the remote side.
+    leaf: String,
+
+    /// How long to wait for a reply before failing.
+    timeout: Duration,
+
+    /// The request in flight.
+    pending: Option<Pending>,
+}
+impl<T: Transport> RemoteLeaf<T> {
+    /// Creates a leaf which makes the given kind of request.
+    fn node<'a, W>(
+        link: RemoteLink<T>,
+        kind: RequestKind,
+        leaf: &str,
+        timeout: Duration,
+    ) -> Node<'a, W>
+    where
+        T: 'a,
+    {
+        Node::new(RemoteLeaf {
+            link,
+            kind,
+            leaf: leaf.to_owned(),
+            timeout,
+            pending: None,
+        })
+    }
+
+    /// Returns the request with the given ID.
+    fn request(&self, id: u64, kind: RequestKind) -> Request {
+        Request {
+            id,
+            kind,
+            leaf: self.leaf.clone(),
+        }
+    }
+}
+impl<W, T: Transport> Tickable<W> for RemoteLeaf<T> {
+    fn tick(&mut self, _: &mut W) -> Status {
+        let link = Rc::clone(&self.link.state);
+        let mut link = link.borrow_mut();
+        link.maintain();
+
+        let mut pending = match self.pending.take() {
+            Some(p) => p,
+            None => {
+                let id = link.next_id;
+                link.next_id += 1;
+                link.replies.insert(id, None);
+                Pending {
+                    id,
+                    sent: None,
+                    heard: Instant::now(),
+                }
+            }
+        };
+
+        // Send the request if it has not reached the current connection
+        if pending.sent != Some(link.generation) {
+            let request = self.request(pending.id, self.kind);
+            match link.send(&request) {
+                Ok(true) => pending.sent = Some(link.generation),
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("Unable to request remote leaf {}: {}", self.leaf, e);
+                    link.replies.remove(&pending.id);
+                    return Status::Failed;
+                }
+            }
+        }
+
+        link.receive();
+        match link.replies.get(&pending.id).copied().flatten() {
+            Some(status) if status.is_done() => {
+                link.replies.remove(&pending.id);
+                return status;
+            }
+            Some(_) => {
+                pending.heard = Instant::now();
+                link.replies.insert(pending.id, None);
+            }
+            None => {}
+        }
+
+        if pending.heard.elapsed() >= self.timeout {
+            warn!("Timed out waiting for remote leaf {}", self.leaf);
+            link.replies.remove(&pending.id);
+            return Status::Failed;
+        }
+        self.pending = Some(pending);
+        Status::Running
+    }
+
+    fn reset(&mut self) {
+        let pending = match self.pending.take() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let mut link = self.link.state.borrow_mut();
+        link.replies.remove(&pending.id);
+        if self.kind == RequestKind::Action && pending.sent.is_some() {
+            let halt = self.request(pending.id, RequestKind::Halt);
+            if let Err(e) = link.send(&halt) {
+                warn!("Unable to halt remote leaf {}: {}", self.leaf, e);
+            }
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self.kind {
+            RequestKind::Condition => "RemoteCondition",
+            _ => "RemoteAction",
+        }
+    }
+}
+
+/// A node that runs an action on another process or robot.
+///
+/// Every time this node starts, it sends an action request for the named leaf
+/// over the link, and then waits for the final status in a reply. The remote
+/// side can send `Running` replies while it works, and the node fails if
+/// nothing is heard for longer than the timeout, including while the link is
+/// reconnecting. If the node is reset while it is waiting, it asks the remote
+/// side to halt the action.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While waiting for the final status.
+///
+/// **Succeeded:** If the remote action succeeded.
+///
+/// **Failed:** If the remote action failed, or if nothing was heard from it
+/// within the timeout.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// A coordinator asking a teammate to pick up a part:
+///
+/// ```no_run
+/// # use aspen::std_nodes::*;
+/// # use aspen::node::Node;
+/// # use std::time::Duration;
+/// let teammate = RemoteLink::new(TcpTransport::connect("10.0.0.2:7200".parse().unwrap())?);
+/// let pick: Node<()> = RemoteAction::new(&teammate, "pick_part", Duration::from_secs(5));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct RemoteAction;
+impl RemoteAction {
+    /// Creates a node which runs the named leaf over the link.
+    pub fn new<'a, W, T>(link: &RemoteLink<T>, leaf: &str, timeout: Duration) -> Node<'a, W>
+    where
+        T: Transport + 'a,
+    {
+        RemoteLeaf::node(link.clone(), RequestKind::Action, leaf, timeout)
+    }
+}
+
+/// A node that evaluates a condition on another process or robot.
+///
+/// This works like `RemoteAction`, except that it makes condition requests,
+/// and does not ask the remote side to halt when it is reset. Since the answer
+/// has to travel over the link, the node is running until it arrives.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While waiting for the answer.
+///
+/// **Succeeded:** If the condition holds.
+///
+/// **Failed:** If the condition does not hold, or if no answer arrived within
+/// the timeout.
+///
+/// # Children
+///
+/// None.
+pub struct RemoteCondition;
+impl RemoteCondition {
+    /// Creates a node which evaluates the named leaf over the link.
+    pub fn new<'a, W, T>(link: &RemoteLink<T>, leaf: &str, timeout: Duration) -> Node<'a, W>
+    where
+        T: Transport + 'a,
+    {
+        RemoteLeaf::node(link.clone(), RequestKind::Condition, leaf, timeout)
+    }
+}
+
+/// A transport which exchanges lines of text over TCP.
+///
+/// Each request is written as a line holding its ID, kind and leaf, separated
+/// by spaces, such as `7 action pick_part`. Each reply is a line holding the
+/// ID of its request and a status of `running`, `succeeded` or `failed`, such
+/// as `7 succeeded`. Reconnecting opens a new connection to the same address.
+///
+/// Leaf names must not contain whitespace, and requests for leaves whose names
+/// do have any are rejected. The connection never blocks: requests which
+/// cannot be written straight away are buffered and written as the remote
+/// side catches up, every time the transport is sent to or polled.
+pub struct TcpTransport {
+    /// The address of the remote side.
+    address: SocketAddr,
+
+    /// The connection.
+    stream: TcpStream,
+
+    /// Bytes received which do not yet make a whole line.
+    received: Vec<u8>,
+
+    /// Bytes of requests which have yet to be written.
+    outgoing: Vec<u8>,
+}
+impl TcpTransport {
+    /// Connects to the given address.
+    pub fn connect(address: SocketAddr) -> io::Result<Self> {
+        Ok(TcpTransport {
+            address,
+            stream: TcpTransport::open(address)?,
+            received: Vec::new(),
+            outgoing: Vec::new(),
+        })
+    }
+
+    /// Opens a connection which does not block.
+    fn open(address: SocketAddr) -> io::Result<TcpStream> {
+        let stream = TcpStream::connect_timeout(&address, Duration::from_secs(1))?;
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(stream)
+    }
+
+    /// Takes the next whole line from the received bytes and parses it.
+    fn next_reply(&mut self) -> io::Result<Option<Reply>> {
+        let end = match self.received.iter().position(|&b| b == b'\n') {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let line: Vec<_> = self.received.drain(..=end).collect();
+        let line = String::from_utf8_lossy(&line);
+        let invalid =
+            || io::Error::new(io::ErrorKind::InvalidData, format!("bad reply {:?}", line));
+
+        let mut words = line.split_whitespace();
+        let id = words
+            .next()
+            .and_then(|w| w.parse().ok())
+            .ok_or_else(invalid)?;
+        let status = match words.next() {
+            Some("running") => Status::Running,
+            Some("succeeded") => Status::Succeeded,
+            Some("failed") => Status::Failed,
+            _ => return Err(invalid()),
+        };
+        Ok(Some(Reply::new(id, status)))
+    }
+}
+impl Transport for TcpTransport {
+    fn send(&mut self, request: &Request) -> io::Result<()> {
+        if request.leaf.is_empty() || request.leaf.contains(char::is_whitespace) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("leaf name {:?} is empty or has whitespace", request.leaf),
+            ));
+        }
+
+        let line = format!(
+            "{} {} {}\n",
+            request.id,
+            request.kind.as_str(),
+            request.leaf
+        );
+        self.outgoing.extend_from_slice(line.as_bytes());
+        write_some(&mut self.stream, &mut self.outgoing)
+    }
+
+    fn poll(&mut self) -> io::Result<Option<Reply>> {
+        write_some(&mut self.stream, &mut self.outgoing)?;
+        if let Some(reply) = self.next_reply()? {
+            return Ok(Some(reply));
+        }
+
+        let mut buffer = [0; 256];
+        match self.stream.read(&mut buffer) {
+            Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                self.received.extend_from_slice(&buffer[..n]);
+                self.next_reply()
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = TcpTransport::open(self.address)?;
+        self.received.clear();
+        // The link sends the requests which are still waited on again
+        self.outgoing.clear();
+        Ok(())
+    }
+}
+
+/// Writes as much of `buffer` as can be written without blocking, and removes
+/// what was written from it.
+fn write_some<S: Write>(stream: &mut S, buffer: &mut Vec<u8>) -> io::Result<()> {
+    let mut written = 0;
+    let result = loop {
+        if written == buffer.len() {
+            break Ok(());
+        }
+        match stream.write(&buffer[written..]) {
+            Ok(0) => break Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => written += n,
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => break Err(e),
+        }
+    };
+    buffer.drain(..written);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_some, Reply, Request, RequestKind, Transport};
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{RemoteAction, RemoteCondition, RemoteLink, TcpTransport},
+    };
+    use std::{
+        cell::RefCell,
+        io::{self, BufRead, BufReader, Write},
+        net::TcpListener,
+        rc::Rc,
+        thread,
+        time::Duration,
+    };
+
+    /// A transport which records requests and hands out scripted replies.
+    #[derive(Clone, Default)]
+    struct Script {
+        sent: Rc<RefCell<Vec<(u64, RequestKind)>>>,
+        replies: Rc<RefCell<Vec<io::Result<Reply>>>>,
+    }
+    impl Transport for Script {
+        fn send(&mut self, request: &Request) -> io::Result<()> {
+            self.sent.borrow_mut().push((request.id(), request.kind()));
+            Ok(())
+        }
+
+        fn poll(&mut self) -> io::Result<Option<Reply>> {
+            let mut replies = self.replies.borrow_mut();
+            if replies.is_empty() {
+                return Ok(None);
+            }
+            replies.remove(0).map(Some)
+        }
+
+        fn reconnect(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn requests_are_resent_after_reconnecting() {
+        let script = Script::default();
+        let link = RemoteLink::new(script.clone()).with_reconnect_interval(Duration::from_secs(0));
+        let mut pick = RemoteAction::new(&link, "pick", Duration::from_secs(60));
+        let mut clear = RemoteCondition::new(&link, "clear", Duration::from_secs(60));
+
+        assert_eq!(pick.tick(&mut ()), Status::Running);
+        script
+            .replies
+            .borrow_mut()
+            .push(Err(io::ErrorKind::ConnectionReset.into()));
+        assert_eq!(clear.tick(&mut ()), Status::Running);
+        assert!(!link.is_connected());
+
+        script.replies.borrow_mut().extend(vec![
+            Ok(Reply::new(2, Status::Succeeded)),
+            Ok(Reply::new(1, Status::Running)),
+        ]);
+        assert_eq!(pick.tick(&mut ()), Status::Running);
+        assert_eq!(clear.tick(&mut ()), Status::Succeeded);
+        pick.reset();
+
+        assert_eq!(
+            *script.sent.borrow(),
+            vec![
+                (1, RequestKind::Action),
+                (2, RequestKind::Condition),
+                (1, RequestKind::Action),
+                (2, RequestKind::Condition),
+                (1, RequestKind::Halt)
+            ]
+        );
+    }
+
+    /// A stream which accepts a few bytes at a time, then would block.
+    struct Trickle {
+        written: Vec<u8>,
+        budget: usize,
+    }
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.budget == 0 {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(3).min(self.budget);
+            self.budget -= n;
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn partial_writes_are_kept() {
+        let mut stream = Trickle {
+            written: Vec::new(),
+            budget: 5,
+        };
+        let mut buffer = b"1 action pick\n".to_vec();
+        write_some(&mut stream, &mut buffer).unwrap();
+        assert_eq!(stream.written, b"1 act");
+        assert_eq!(buffer, b"ion pick\n");
+
+        stream.budget = usize::MAX;
+        write_some(&mut stream, &mut buffer).unwrap();
+        assert_eq!(stream.written, b"1 action pick\n");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn unencodable_leaves_fail() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let link = RemoteLink::new(TcpTransport::connect(listener.local_addr().unwrap()).unwrap());
+        let mut pick = RemoteAction::new(&link, "pick part\n2 halt", Duration::from_secs(60));
+        assert_eq!(pick.tick(&mut ()), Status::Failed);
+        assert!(link.is_connected());
+    }
+
+    #[test]
+    fn silence_times_out() {
+        let link = RemoteLink::new(Script::default());
+        let mut pick = RemoteAction::new(&link, "pick", Duration::from_secs(0));
+        assert_eq!(pick.tick(&mut ()), Status::Failed);
+    }
+
+    #[test]
+    fn talks_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            let id = line.split(' ').next().unwrap().to_owned();
+            writeln!(&stream, "{} running\n{} succeeded", id, id).unwrap();
+            line
+        });
+
+        let link = RemoteLink::new(TcpTransport::connect(address).unwrap());
+        let mut pick = RemoteAction::new(&link, "pick", Duration::from_secs(60));
+        while pick.tick(&mut ()) == Status::Running {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pick.status(), Some(Status::Succeeded));
+        assert_eq!(server.join().unwrap(), "1 action pick\n");
+    }
+}
@@ -0,0 +1,286 @@
+//! A node that holds resources for the duration of a branch.
+use crate::{
+    node::{self, DryRun, Node, Tickable},
+    status::Status,
+};
+use std::cell::{Cell, RefCell};
+
+/// How far a `Reserve` node has got.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Phase {
+    /// The resources are being acquired.
+    Acquire,
+
+    /// The resources are held and the body is running.
+    Body,
+
+    /// The body finished with the given status and the resources are being
+    /// released.
+    Release(Status),
+
+    /// The resources have been released.
+    Done,
+}
+
+/// A node that acquires resources, runs a branch which uses them, and always
+/// releases them afterwards.
+///
+/// This node has three parts. The reservation acquires the resources, such as
+/// a lock on a shared arm or a slot at a charging station. Once it succeeds,
+/// the body is run. When the body finishes, whether it succeeded or failed,
+/// the release is run to completion, and this node then finishes with the
+/// status of the body. Failing to release is logged, but does not change the
+/// status.
+///
+/// If this node is halted while the resources are held, the release is still
+/// run, like the cleanup of an `OnHalt` node: it is ticked by the
+/// `BehaviorTree` after each tick until it completes, or by this node if it is
+/// ticked again first. If the reservation itself fails or is halted, nothing
+/// was acquired and nothing is released.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While any of the parts is running.
+///
+/// **Succeeded:** When the body succeeded and the resources were released.
+///
+/// **Failed:** When the reservation failed, or when the body failed and the
+/// resources were released.
+///
+/// # Children
+///
+/// The reservation and the body, which are reset whenever this node is reset.
+/// The release is not counted as a child.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// // The world is whether the charger is reserved
+/// let mut node = Reserve::new(
+///     InlineAction::new(|reserved: &mut bool| {
+///         *reserved = true;
+///         Status::Succeeded
+///     }),
+///     AlwaysFail::new(),
+///     InlineAction::new(|reserved: &mut bool| {
+///         *reserved = false;
+///         Status::Succeeded
+///     }),
+/// );
+///
+/// let mut reserved = false;
+/// assert_eq!(node.tick(&mut reserved), Status::Failed);
+/// assert!(!reserved);
+/// ```
+pub struct Reserve<'a, W> {
+    /// Acquires the resources.
+    acquire: Node<'a, W>,
+
+    /// Uses the resources.
+    body: Node<'a, W>,
+
+    /// Releases the resources.
+    release: RefCell<Node<'a, W>>,
+
+    /// How far this node has got.
+    phase: Phase,
+
+    /// Whether a release was started by a halt and has not yet finished.
+    pending: Cell<bool>,
+
+    /// Whether the pending release was ticked by this node since the tree
+    /// last ticked the pending cleanups.
+    ticked: Cell<bool>,
+}
+impl<'a, W> Reserve<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `Reserve` node from its reservation, body and release.
+    pub fn new(acquire: Node<'a, W>, body: Node<'a, W>, release: Node<'a, W>) -> Node<'a, W> {
+        let internals = Reserve {
+            acquire,
+            body,
+            release: RefCell::new(release),
+            phase: Phase::Acquire,
+            pending: Cell::new(false),
+            ticked: Cell::new(false),
+        };
+        Node::new(internals)
+    }
+}
+impl<'a, W> Reserve<'a, W> {
+    /// Ticks the release once, returning its status.
+    ///
+    /// The release is reset once it completes.
+    fn tick_release(&self, world: &mut W) -> Status {
+        let mut release = self.release.borrow_mut();
+        let status = release.tick(world);
+        if status.is_done() {
+            if status == Status::Failed {
+                warn!("Unable to release the resources of {}", self.body.name());
+            }
+            release.reset();
+        }
+        status
+    }
+
+    /// Ticks the release left pending by a halt once.
+    fn run_pending(&self, world: &mut W) {
+        if self.tick_release(world).is_done() {
+            self.pending.set(false);
+            node::finish_cleanup();
+        }
+    }
+}
+impl<'a, W> Tickable<W> for Reserve<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if self.pending.get() {
+            self.run_pending(world);
+            if self.pending.get() {
+                self.ticked.set(true);
+                return Status::Running;
+            }
+        }
+
+        if self.phase == Phase::Acquire {
+            match self.acquire.tick(world) {
+                Status::Succeeded => self.phase = Phase::Body,
+                status => return status,
+            }
+        }
+        if self.phase == Phase::Body {
+            match self.body.tick(world) {
+                Status::Running => return Status::Running,
+                status => self.phase = Phase::Release(status),
+            }
+        }
+        if let Phase::Release(status) = self.phase {
+            if !self.tick_release(world).is_done() {
+                return Status::Running;
+            }
+            self.phase = Phase::Done;
+            return status;
+        }
+
+        unreachable!("Reserve ticked after it completed")
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if self.pending.get() {
+            return None;
+        }
+
+        let mut phase = if run.after_reset() {
+            Phase::Acquire
+        } else {
+            self.phase
+        };
+        if phase == Phase::Acquire {
+            match run.tick(&self.acquire, world) {
+                Status::Succeeded => phase = Phase::Body,
+                status => return Some(status),
+            }
+        }
+        if phase == Phase::Body {
+            match run.tick(&self.body, world) {
+                Status::Running => return Some(Status::Running),
+                status => phase = Phase::Release(status),
+            }
+        }
+        match phase {
+            Phase::Release(status) => match run.tick(&self.release.borrow(), world) {
+                Status::Running => Some(Status::Running),
+                _ => Some(status),
+            },
+            _ => None,
+        }
+    }
+
+    fn reset(&mut self) {
+        let held = matches!(self.phase, Phase::Body | Phase::Release(_));
+        if held && !self.pending.get() {
+            debug!(
+                "Halted {} while holding resources, releasing them",
+                self.body.name()
+            );
+            self.pending.set(true);
+            node::start_cleanup();
+        }
+
+        self.acquire.reset();
+        self.body.reset();
+        self.phase = Phase::Acquire;
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.acquire, &self.body]
+    }
+
+    fn tick_cleanup(&self, world: &mut W) {
+        if self.ticked.replace(false) {
+            return;
+        }
+        if self.pending.get() {
+            self.run_pending(world);
+        }
+    }
+
+    /// Returns the string "Reserve".
+    fn type_name(&self) -> &'static str {
+        "Reserve"
+    }
+}
+impl<'a, W> Drop for Reserve<'a, W> {
+    fn drop(&mut self) {
+        if self.pending.get() {
+            node::finish_cleanup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        status::Status,
+        std_nodes::{
+            AlwaysFail, AlwaysRunning, AlwaysSucceed, CountedTick, NoTick, Reserve, YesTick,
+        },
+        BehaviorTree,
+    };
+
+    #[test]
+    fn resources_are_always_released() {
+        let mut tree = BehaviorTree::new(Reserve::new(
+            AlwaysSucceed::new(),
+            YesTick::new(Status::Succeeded),
+            CountedTick::new(Status::Running, 1, true),
+        ));
+        assert_eq!(tree.tick(&mut ()), Status::Running);
+        drop(tree);
+
+        let mut tree = BehaviorTree::new(Reserve::new(
+            AlwaysFail::new(),
+            NoTick::new(),
+            NoTick::new(),
+        ));
+        assert_eq!(tree.tick(&mut ()), Status::Failed);
+        drop(tree);
+
+        // A halted body is released by the tree over two ticks
+        let mut tree = BehaviorTree::new(Reserve::new(
+            AlwaysSucceed::new(),
+            AlwaysRunning::new(),
+            CountedTick::new(Status::Running, 2, true),
+        ));
+        assert_eq!(tree.tick(&mut ()), Status::Running);
+        tree.reset();
+        assert_eq!(tree.tick(&mut ()), Status::Running);
+        assert_eq!(tree.tick(&mut ()), Status::Running);
+    }
+}
@@ -0,0 +1,468 @@
+//! Common guards for robots.
+//!
+//! These nodes are conditions and decorators with standard names and
+//! semantics, so that trees from different teams read the same way and show up
+//! consistently in monitoring tools. Each one is given a closure which reads
+//! the relevant value from the world, so they work with any world type.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+
+/// Function which reads a value from the world.
+type AccessorFn<'a, W, T> = dyn Fn(&W) -> T + 'a;
+
+/// Converts a condition into a status.
+fn status(condition: bool) -> Status {
+    if condition {
+        Status::Succeeded
+    } else {
+        Status::Failed
+    }
+}
+
+/// A node that succeeds while the battery charge is above a threshold.
+///
+/// The node is named after its threshold, such as `BatteryAbove(20%)`.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** When the charge is strictly above the threshold.
+///
+/// **Failed:** When the charge is at or below the threshold.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// struct Robot {
+///     charge: f32,
+/// }
+///
+/// let mut node = BatteryAbove::new(20.0, |r: &Robot| r.charge);
+/// assert_eq!(node.name(), "BatteryAbove(20%)");
+/// assert_eq!(node.tick(&mut Robot { charge: 55.0 }), Status::Succeeded);
+/// assert_eq!(node.tick(&mut Robot { charge: 20.0 }), Status::Failed);
+/// ```
+pub struct BatteryAbove<'a, W> {
+    /// The threshold, as a percentage.
+    percent: f32,
+
+    /// Reads the charge, as a percentage.
+    charge: Box<AccessorFn<'a, W, f32>>,
+}
+impl<'a, W> BatteryAbove<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `BatteryAbove` node with a threshold in percent.
+    pub fn new<F>(percent: f32, charge: F) -> Node<'a, W>
+    where
+        F: Fn(&W) -> f32 + 'a,
+    {
+        let name = format!("BatteryAbove({}%)", percent);
+        Node::new(BatteryAbove {
+            percent,
+            charge: Box::new(charge),
+        })
+        .named(Some(name))
+    }
+}
+impl<'a, W> Tickable<W> for BatteryAbove<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        status((*self.charge)(world) > self.percent)
+    }
+
+    fn dry_tick(&self, world: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(status((*self.charge)(world) > self.percent))
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "BatteryAbove".
+    fn type_name(&self) -> &'static str {
+        "BatteryAbove"
+    }
+}
+
+/// An area that a robot must stay within.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Geofence {
+    /// A circle with the given center and radius.
+    Circle {
+        /// The center of the circle.
+        center: (f64, f64),
+
+        /// The radius of the circle.
+        radius: f64,
+    },
+
+    /// A simple polygon with the given vertices, in order.
+    Polygon(Vec<(f64, f64)>),
+}
+impl Geofence {
+    /// Returns `true` if `point` is inside the fence.
+    ///
+    /// Points on the boundary of a circle are inside it. Points exactly on the
+    /// boundary of a polygon may be considered to be on either side.
+    pub fn contains(&self, (x, y): (f64, f64)) -> bool {
+        match self {
+            Geofence::Circle { center, radius } => {
+                let (dx, dy) = (x - center.0, y - center.1);
+                dx * dx + dy * dy <= radius * radius
+            }
+            Geofence::Polygon(vertices) => {
+                // Count the edges crossed by a ray from the point
+                let mut inside = false;
+                let mut j = vertices.len().wrapping_sub(1);
+                for (i, &(xi, yi)) in vertices.iter().enumerate() {
+                    let (xj, yj) = vertices[j];
+                    if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                        inside = !inside;
+                    }
+                    j = i;
+                }
+                inside
+            }
+        }
+    }
+}
+
+/// A node that succeeds while the robot is within a geofence.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** When the position is inside the fence.
+///
+/// **Failed:** When the position is outside the fence.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let fence = Geofence::Polygon(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 5.0), (0.0, 5.0)]);
+/// let mut node = WithinGeofence::new(fence, |&p: &(f64, f64)| p);
+///
+/// assert_eq!(node.tick(&mut (3.0, 4.0)), Status::Succeeded);
+/// assert_eq!(node.tick(&mut (3.0, 6.0)), Status::Failed);
+/// ```
+pub struct WithinGeofence<'a, W> {
+    /// The area to stay within.
+    fence: Geofence,
+
+    /// Reads the position.
+    position: Box<AccessorFn<'a, W, (f64, f64)>>,
+}
+impl<'a, W> WithinGeofence<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `WithinGeofence` node.
+    pub fn new<F>(fence: Geofence, position: F) -> Node<'a, W>
+    where
+        F: Fn(&W) -> (f64, f64) + 'a,
+    {
+        Node::new(WithinGeofence {
+            fence,
+            position: Box::new(position),
+        })
+    }
+}
+impl<'a, W> Tickable<W> for WithinGeofence<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        status(self.fence.contains((*self.position)(world)))
+    }
+
+    fn dry_tick(&self, world: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(status(self.fence.contains((*self.position)(world))))
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "WithinGeofence".
+    fn type_name(&self) -> &'static str {
+        "WithinGeofence"
+    }
+}
+
+/// A node that succeeds while the emergency stop is not engaged.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** When the emergency stop is clear.
+///
+/// **Failed:** When the emergency stop is engaged.
+///
+/// # Children
+///
+/// None.
+pub struct EStopClear<'a, W> {
+    /// Reads whether the emergency stop is engaged.
+    engaged: Box<AccessorFn<'a, W, bool>>,
+}
+impl<'a, W> EStopClear<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `EStopClear` node from a closure returning `true` while
+    /// the emergency stop is engaged.
+    pub fn new<F>(engaged: F) -> Node<'a, W>
+    where
+        F: Fn(&W) -> bool + 'a,
+    {
+        Node::new(EStopClear {
+            engaged: Box::new(engaged),
+        })
+    }
+}
+impl<'a, W> Tickable<W> for EStopClear<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        status(!(*self.engaged)(world))
+    }
+
+    fn dry_tick(&self, world: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(status(!(*self.engaged)(world)))
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "EStopClear".
+    fn type_name(&self) -> &'static str {
+        "EStopClear"
+    }
+}
+
+/// What a `TeleopOverride` node does with its child when manual control is
+/// taken.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ReleasePolicy {
+    /// The child is paused, and carries on from where it was when manual
+    /// control is released. Work which the child has handed off, such as the
+    /// task of an `Action`, is not stopped.
+    Resume,
+
+    /// The child is halted, and starts again from the beginning when manual
+    /// control is released.
+    Restart,
+}
+impl Default for ReleasePolicy {
+    fn default() -> Self {
+        ReleasePolicy::Restart
+    }
+}
+
+/// A node that suspends its autonomous child while a human has manual
+/// control.
+///
+/// While the closure reports that manual control is active, such as when a
+/// safety driver holds the dead man's switch of a joystick, the child is not
+/// ticked and this node is running. When manual control is released, the child
+/// is ticked again, either carrying on or starting over depending on the
+/// `ReleasePolicy`, which is `Restart` for `TeleopOverride::new`.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the child is running, or while manual control is active.
+///
+/// **Succeeded:** When the child succeeds.
+///
+/// **Failed:** When the child fails.
+///
+/// # Children
+///
+/// One, which is ticked while manual control is not active, and reset whenever
+/// this node is reset. With `ReleasePolicy::Restart`, it is also reset when
+/// manual control is taken.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+/// let manual = Arc::new(AtomicBool::new(false));
+/// let flag = Arc::clone(&manual);
+/// let mut node = TeleopOverride::new(move |_| flag.get(), AlwaysRunning::new());
+///
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// manual.store(true, Ordering::SeqCst);
+/// assert_eq!(node.tick(&mut ()), Status::Running);
+/// assert_eq!(node.children()[0].status(), None);
+/// ```
+pub struct TeleopOverride<'a, W> {
+    /// Reads whether manual control is active.
+    manual: Box<AccessorFn<'a, W, bool>>,
+
+    /// Child node.
+    child: Node<'a, W>,
+
+    /// What happens to the child when manual control is taken.
+    policy: ReleasePolicy,
+
+    /// Whether manual control was active on the previous tick.
+    overridden: bool,
+}
+impl<'a, W> TeleopOverride<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `TeleopOverride` node which restarts its child after
+    /// manual control, from a closure returning `true` while manual control
+    /// is active.
+    pub fn new<F>(manual: F, child: Node<'a, W>) -> Node<'a, W>
+    where
+        F: Fn(&W) -> bool + 'a,
+    {
+        TeleopOverride::with_policy(manual, ReleasePolicy::default(), child)
+    }
+
+    /// Creates a new `TeleopOverride` node with the given policy.
+    pub fn with_policy<F>(manual: F, policy: ReleasePolicy, child: Node<'a, W>) -> Node<'a, W>
+    where
+        F: Fn(&W) -> bool + 'a,
+    {
+        Node::new(TeleopOverride {
+            manual: Box::new(manual),
+            child,
+            policy,
+            overridden: false,
+        })
+    }
+}
+impl<'a, W> Tickable<W> for TeleopOverride<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        if (*self.manual)(world) {
+            if !self.overridden {
+                info!("Manual control taken from {}", self.child.name());
+                self.overridden = true;
+                if self.policy == ReleasePolicy::Restart {
+                    self.child.reset();
+                }
+            }
+            return Status::Running;
+        }
+
+        if self.overridden {
+            info!("Manual control released to {}", self.child.name());
+            self.overridden = false;
+        }
+        self.child.tick(world)
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        if (*self.manual)(world) {
+            Some(Status::Running)
+        } else {
+            Some(run.tick(&self.child, world))
+        }
+    }
+
+    fn reset(&mut self) {
+        self.overridden = false;
+        self.child.reset();
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        vec![&self.child]
+    }
+
+    /// Returns the string "TeleopOverride".
+    fn type_name(&self) -> &'static str {
+        "TeleopOverride"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Geofence, ReleasePolicy};
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{CountedTick, EStopClear, TeleopOverride},
+    };
+
+    #[test]
+    fn geofence_contains() {
+        let circle = Geofence::Circle {
+            center: (1.0, 1.0),
+            radius: 2.0,
+        };
+        assert!(circle.contains((3.0, 1.0)));
+        assert!(!circle.contains((3.0, 3.0)));
+
+        // An L shape, whose notch is outside
+        let l = Geofence::Polygon(vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+        ]);
+        assert!(l.contains((0.5, 3.0)));
+        assert!(l.contains((3.0, 0.5)));
+        assert!(!l.contains((3.0, 3.0)));
+        assert!(!Geofence::Polygon(Vec::new()).contains((0.0, 0.0)));
+    }
+
+    #[test]
+    fn estop_engaged_fails() {
+        let mut node = EStopClear::new(|&engaged: &bool| engaged);
+        assert_eq!(node.tick(&mut false), Status::Succeeded);
+        assert_eq!(node.tick(&mut true), Status::Failed);
+    }
+
+    #[test]
+    fn manual_control_suspends_child() {
+        let mut node = TeleopOverride::with_policy(
+            |&manual: &bool| manual,
+            ReleasePolicy::Resume,
+            CountedTick::new(Status::Running, 2, true),
+        );
+        assert_eq!(node.tick(&mut false), Status::Running);
+        assert_eq!(node.tick(&mut true), Status::Running);
+        assert_eq!(node.children()[0].status(), Some(Status::Running));
+        assert_eq!(node.tick(&mut false), Status::Running);
+
+        let mut node = TeleopOverride::new(
+            |&manual: &bool| manual,
+            CountedTick::resetable(Status::Running, 1, true),
+        );
+        assert_eq!(node.tick(&mut false), Status::Running);
+        assert_eq!(node.tick(&mut true), Status::Running);
+        assert_eq!(node.tick(&mut false), Status::Running);
+    }
+}
@@ -1,9 +1,11 @@
 //! Nodes that have children and tick them in a sequential order as long as they
 //! fail.
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
+    validate::unreachable_after,
     Status,
 };
+use std::borrow::Cow;
 
 /// A node that ticks its children sequentially as long as they fail.
 ///
@@ -100,21 +102,11 @@ where
 }
 impl<'a, W> Tickable<W> for Selector<'a, W> {
     fn tick(&mut self, world: &mut W) -> Status {
-        // Tick the children in order
-        let mut ret_status = Status::Failed;
-        for child in &mut self.children {
-            // What we want to do is tick our children until we find one that
-            // is either running or successful. If we find either of those, all
-            // children after that node need to be reset
-            if ret_status == Status::Failed {
-                ret_status = child.tick(world);
-            } else {
-                child.reset();
-            }
-        }
+        tick_active_selector(&mut self.children, world)
+    }
 
-        // Return the status that we found
-        ret_status
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(dry_tick_active_selector(&self.children, world, run))
     }
 
     fn reset(&mut self) {
@@ -124,7 +116,7 @@ impl<'a, W> Tickable<W> for Selector<'a, W> {
         }
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         self.children.iter().collect()
     }
 
@@ -132,6 +124,50 @@ impl<'a, W> Tickable<W> for Selector<'a, W> {
     fn type_name(&self) -> &'static str {
         "Selector"
     }
+
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        unreachable_after(&self.children, &["AlwaysSucceed", "AlwaysRunning"])
+    }
+}
+
+/// Ticks `children` in order until one of them does not fail, resetting all of
+/// the children after it.
+pub(crate) fn tick_active_selector<W, C>(children: &mut [C], world: &mut W) -> Status
+where
+    C: Tickable<W>,
+{
+    // Tick the children in order
+    let mut ret_status = Status::Failed;
+    for child in children {
+        // What we want to do is tick our children until we find one that
+        // is either running or successful. If we find either of those, all
+        // children after that node need to be reset
+        if ret_status == Status::Failed {
+            ret_status = child.tick(world);
+        } else {
+            child.reset();
+        }
+    }
+
+    // Return the status that we found
+    ret_status
+}
+
+/// Predicts the status of an active selector during a dry run.
+pub(crate) fn dry_tick_active_selector<W>(
+    children: &[Node<'_, W>],
+    world: &W,
+    run: &mut DryRun<'_, W>,
+) -> Status {
+    let mut ret_status = Status::Failed;
+    for child in children {
+        if ret_status != Status::Failed {
+            break;
+        }
+        ret_status = run.tick(child, world);
+    }
+
+    ret_status
 }
 
 /// Convenience macro for creating Selector nodes.
@@ -271,6 +307,24 @@ where
         ret_status
     }
 
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let mut next_child = if run.after_reset() {
+            0
+        } else {
+            self.next_child
+        };
+        let mut ret_status = Status::Failed;
+        while next_child < self.children.len() && ret_status == Status::Failed {
+            ret_status = run.tick(&self.children[next_child], world);
+
+            if ret_status.is_done() {
+                next_child += 1;
+            }
+        }
+
+        Some(ret_status)
+    }
+
     fn reset(&mut self) {
         // Reset all of our children
         for child in &mut self.children {
@@ -280,7 +334,7 @@ where
         self.next_child = 0;
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         self.children.iter().collect()
     }
 
@@ -288,6 +342,10 @@ where
     fn type_name(&self) -> &'static str {
         "Selector"
     }
+
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        unreachable_after(&self.children, &["AlwaysSucceed", "AlwaysRunning"])
+    }
 }
 
 /// Convenience macro for creating StatefulSelector nodes.
@@ -1,9 +1,11 @@
 //! Nodes that have children and tick them in a sequential order as long as they
 //! succeed.
 use crate::{
-    node::{Node, Tickable},
+    node::{DryRun, Node, Tickable},
+    validate::unreachable_after,
     Status,
 };
+use std::borrow::Cow;
 
 /// A node that will tick its children in order as long as they succeed.
 ///
@@ -116,6 +118,14 @@ where
         self
     }
 }
+impl<'a, W> Default for ActiveSequence<'a, W>
+where
+    W: 'a,
+{
+    fn default() -> Self {
+        ActiveSequence::new()
+    }
+}
 impl<'a, W> Tickable<W> for ActiveSequence<'a, W> {
     fn tick(&mut self, world: &mut W) -> Status {
         // Tick all of our children as long as they succeed
@@ -132,6 +142,18 @@ impl<'a, W> Tickable<W> for ActiveSequence<'a, W> {
         ret_status
     }
 
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let mut ret_status = Status::Succeeded;
+        for child in &self.children {
+            if ret_status != Status::Succeeded {
+                break;
+            }
+            ret_status = run.tick(child, world);
+        }
+
+        Some(ret_status)
+    }
+
     fn reset(&mut self) {
         // Reset all of our children
         for child in &mut self.children {
@@ -139,7 +161,7 @@ impl<'a, W> Tickable<W> for ActiveSequence<'a, W> {
         }
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         self.children.iter().collect()
     }
 
@@ -147,6 +169,10 @@ impl<'a, W> Tickable<W> for ActiveSequence<'a, W> {
     fn type_name(&self) -> &'static str {
         "ActiveSequence"
     }
+
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        unreachable_after(&self.children, &["AlwaysFail", "AlwaysRunning"])
+    }
 }
 
 /// Convenience macro for creating ActiveSequence nodes.
@@ -267,17 +293,16 @@ where
 }
 impl<'a, W> Tickable<W> for Sequence<'a, W> {
     fn tick(&mut self, world: &mut W) -> Status {
-        // Tick the children as long as they keep failing
-        let mut ret_status = Status::Succeeded;
-        while self.next_child < self.children.len() && ret_status == Status::Succeeded {
-            ret_status = self.children[self.next_child].tick(world);
-
-            if ret_status.is_done() {
-                self.next_child += 1;
-            }
-        }
+        tick_sequence(&mut self.children, &mut self.next_child, world)
+    }
 
-        ret_status
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(dry_tick_sequence(
+            &self.children,
+            self.next_child,
+            world,
+            run,
+        ))
     }
 
     fn reset(&mut self) {
@@ -289,7 +314,7 @@ impl<'a, W> Tickable<W> for Sequence<'a, W> {
         self.next_child = 0;
     }
 
-    fn children(&self) -> Vec<&Node<W>> {
+    fn children(&self) -> Vec<&Node<'_, W>> {
         self.children.iter().collect()
     }
 
@@ -297,6 +322,52 @@ impl<'a, W> Tickable<W> for Sequence<'a, W> {
     fn type_name(&self) -> &'static str {
         "Sequence"
     }
+
+    fn unreachable_children(&self) -> Vec<(usize, Cow<'static, str>)> {
+        unreachable_after(&self.children, &["AlwaysFail", "AlwaysRunning"])
+    }
+}
+
+/// Ticks `children` in order, starting at `next_child`, as long as they
+/// succeed.
+///
+/// `next_child` is advanced past every child that completes.
+pub(crate) fn tick_sequence<W, C: Tickable<W>>(
+    children: &mut [C],
+    next_child: &mut usize,
+    world: &mut W,
+) -> Status {
+    // Tick the children as long as they keep succeeding
+    let mut ret_status = Status::Succeeded;
+    while *next_child < children.len() && ret_status == Status::Succeeded {
+        ret_status = children[*next_child].tick(world);
+
+        if ret_status.is_done() {
+            *next_child += 1;
+        }
+    }
+
+    ret_status
+}
+
+/// Predicts the status of a sequence during a dry run.
+pub(crate) fn dry_tick_sequence<W>(
+    children: &[Node<'_, W>],
+    next_child: usize,
+    world: &W,
+    run: &mut DryRun<'_, W>,
+) -> Status {
+    let mut next_child = if run.after_reset() { 0 } else { next_child };
+    let mut ret_status = Status::Succeeded;
+    while next_child < children.len() && ret_status == Status::Succeeded {
+        ret_status = run.tick(&children[next_child], world);
+
+        if ret_status.is_done() {
+            next_child += 1;
+        }
+    }
+
+    ret_status
 }
 
 /// Convenience macro for creating Selector nodes.
@@ -0,0 +1,212 @@
+//! Nodes which talk to devices over a serial link.
+use crate::{
+    node::{Node, Tickable},
+    status::Status,
+};
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+/// A node that writes a command to a serial port and waits for an
+/// acknowledgement.
+///
+/// The port can be anything which implements `Read` and `Write`. On the first
+/// tick the command is written, and on every tick a single read is made to
+/// look for the acknowledgement. The port should therefore be configured to
+/// return from reads quickly when there is no data, for example with a short
+/// read timeout, so that the tick rate is not affected. Reads which time out
+/// or would block are treated as there being no data yet.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While waiting for the acknowledgement.
+///
+/// **Succeeded:** Once the acknowledgement has been received.
+///
+/// **Failed:** If the acknowledgement did not arrive within the timeout, or if
+/// the port returned an error.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// Homing a stepper controller attached to a USB serial adapter:
+///
+/// ```no_run
+/// # use aspen::std_nodes::*;
+/// # use aspen::node::Node;
+/// # use std::{fs::OpenOptions, time::Duration};
+/// let port = OpenOptions::new()
+///     .read(true)
+///     .write(true)
+///     .open("/dev/ttyUSB0")
+///     .unwrap();
+/// let home: Node<()> =
+///     SerialCommandAction::new(port, &b"G28\n"[..], &b"ok"[..], Duration::from_secs(10));
+/// ```
+pub struct SerialCommandAction<P> {
+    /// The serial port.
+    port: P,
+
+    /// The command to write.
+    command: Vec<u8>,
+
+    /// The acknowledgement to wait for.
+    ack: Vec<u8>,
+
+    /// How long to wait for the acknowledgement.
+    timeout: Duration,
+
+    /// When the command was written, if it has been.
+    sent_at: Option<Instant>,
+
+    /// The bytes received since the command was written.
+    received: Vec<u8>,
+}
+impl<P> SerialCommandAction<P>
+where
+    P: Read + Write,
+{
+    /// Creates a new `SerialCommandAction` node.
+    pub fn new<'a, W, C, A>(port: P, command: C, ack: A, timeout: Duration) -> Node<'a, W>
+    where
+        P: 'a,
+        C: Into<Vec<u8>>,
+        A: Into<Vec<u8>>,
+    {
+        let internals = SerialCommandAction {
+            port,
+            command: command.into(),
+            ack: ack.into(),
+            timeout,
+            sent_at: None,
+            received: Vec::new(),
+        };
+        Node::new(internals)
+    }
+
+    /// Returns `true` if the acknowledgement has been received.
+    fn acknowledged(&self) -> bool {
+        self.received
+            .windows(self.ack.len().max(1))
+            .any(|w| w == &self.ack[..])
+    }
+}
+impl<W, P> Tickable<W> for SerialCommandAction<P>
+where
+    P: Read + Write,
+{
+    fn tick(&mut self, _: &mut W) -> Status {
+        let sent_at = match self.sent_at {
+            Some(t) => t,
+            None => {
+                if let Err(e) = self
+                    .port
+                    .write_all(&self.command)
+                    .and_then(|_| self.port.flush())
+                {
+                    warn!("Unable to write serial command: {}", e);
+                    return Status::Failed;
+                }
+                let now = Instant::now();
+                self.sent_at = Some(now);
+                now
+            }
+        };
+
+        let mut buffer = [0; 64];
+        match self.port.read(&mut buffer) {
+            Ok(n) => self.received.extend_from_slice(&buffer[..n]),
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock
+                    || e.kind() == io::ErrorKind::TimedOut
+                    || e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                warn!("Unable to read serial acknowledgement: {}", e);
+                return Status::Failed;
+            }
+        }
+
+        if self.acknowledged() {
+            Status::Succeeded
+        } else if sent_at.elapsed() >= self.timeout {
+            warn!("Timed out waiting for serial acknowledgement");
+            Status::Failed
+        } else {
+            Status::Running
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sent_at = None;
+        self.received.clear();
+    }
+
+    /// Returns the string "SerialCommandAction".
+    fn type_name(&self) -> &'static str {
+        "SerialCommandAction"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{node::Tickable, status::Status, std_nodes::SerialCommandAction};
+    use std::{
+        io::{self, Read, Write},
+        time::Duration,
+    };
+
+    /// A port which replies with a chunk of data on each read.
+    struct Port {
+        written: Vec<u8>,
+        replies: Vec<&'static [u8]>,
+    }
+    impl Read for Port {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.replies.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let reply = self.replies.remove(0);
+            buf[..reply.len()].copy_from_slice(reply);
+            Ok(reply.len())
+        }
+    }
+    impl Write for Port {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn waits_for_split_ack() {
+        let port = Port {
+            written: Vec::new(),
+            replies: vec![b"o", b"k\n"],
+        };
+        let mut node =
+            SerialCommandAction::new(port, &b"G28\n"[..], &b"ok"[..], Duration::from_secs(60));
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+    }
+
+    #[test]
+    fn times_out() {
+        let port = Port {
+            written: Vec::new(),
+            replies: Vec::new(),
+        };
+        let mut node =
+            SerialCommandAction::new(port, &b"G28\n"[..], &b"ok"[..], Duration::from_secs(0));
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+    }
+}
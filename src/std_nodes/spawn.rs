@@ -0,0 +1,248 @@
+//! A node that runs a whole tree as one of its steps.
+use crate::{
+    blackboard::Blackboard,
+    loader::Prototype,
+    node::{Node, Tickable},
+    status::Status,
+};
+
+/// Which blackboard a spawned tree reads and writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlackboardScope {
+    /// The blackboard of the world the `SpawnTree` node is ticked with.
+    Shared,
+
+    /// A new, empty blackboard for each spawned tree, which is dropped when
+    /// the tree finishes.
+    Scoped,
+}
+
+/// Function which maps the final status of a spawned tree to the status of the
+/// node.
+type MapFn = dyn Fn(Status) -> Status;
+
+/// A tree which has been spawned, with its own state.
+struct Spawned {
+    /// The root of the tree.
+    root: Node<'static, Blackboard>,
+
+    /// The blackboard of the tree, if it is scoped.
+    blackboard: Option<Blackboard>,
+}
+
+/// A node that builds a new copy of a tree and runs it to completion.
+///
+/// On its first tick, this node builds a copy of the tree from its prototype,
+/// with its own state, and then ticks it every time it is ticked until it
+/// finishes. The final status of the spawned tree, passed through the mapping
+/// if there is one, becomes the status of this node. This allows missions to be
+/// built from phases which are whole trees, chosen and started as the mission
+/// unfolds.
+///
+/// Spawned trees use a `Blackboard` as their world. Depending on the scope,
+/// they either share the blackboard of the world this node is ticked with, or
+/// get a new one which is dropped when they finish.
+///
+/// If the mapped status is `Running`, the tree is built again and restarted on
+/// the next tick, which allows a phase to be retried.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** While the spawned tree is running.
+///
+/// **Succeeded:** When the mapped status of the spawned tree is success.
+///
+/// **Failed:** When the mapped status of the spawned tree is failure, or if
+/// the tree could not be built.
+///
+/// # Children
+///
+/// None. The spawned tree is owned by this node, and is reset and dropped when
+/// this node is reset.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::blackboard::Blackboard;
+/// # use aspen::loader::Loader;
+/// # use aspen::registry::NodeRegistry;
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let definition = Loader::new().load_str(
+///     r#"<root><BehaviorTree ID="Dock"><Repeat limit="2"><AlwaysSucceed /></Repeat></BehaviorTree></root>"#,
+/// )?;
+/// let dock = definition.prototype(&NodeRegistry::with_std_nodes())?;
+///
+/// let mut node = SpawnTree::new(dock, BlackboardScope::Scoped);
+/// let mut blackboard = Blackboard::new();
+/// assert_eq!(node.tick(&mut blackboard), Status::Running);
+/// assert_eq!(node.tick(&mut blackboard), Status::Succeeded);
+/// # Ok::<(), aspen::loader::LoadError>(())
+/// ```
+pub struct SpawnTree {
+    /// The tree to spawn.
+    prototype: Prototype<Blackboard>,
+
+    /// Which blackboard the spawned tree uses.
+    scope: BlackboardScope,
+
+    /// Maps the final status of the spawned tree.
+    map: Option<Box<MapFn>>,
+
+    /// The tree which is running.
+    spawned: Option<Spawned>,
+}
+impl SpawnTree {
+    /// Creates a node which spawns the given tree, with its final status as
+    /// the status of the node.
+    pub fn new<'a, W>(prototype: Prototype<Blackboard>, scope: BlackboardScope) -> Node<'a, W>
+    where
+        W: AsMut<Blackboard>,
+    {
+        Node::new(SpawnTree {
+            prototype,
+            scope,
+            map: None,
+            spawned: None,
+        })
+    }
+
+    /// Creates a node which spawns the given tree, passing its final status
+    /// through `map`.
+    pub fn with_mapping<'a, W, F>(
+        prototype: Prototype<Blackboard>,
+        scope: BlackboardScope,
+        map: F,
+    ) -> Node<'a, W>
+    where
+        W: AsMut<Blackboard>,
+        F: Fn(Status) -> Status + 'static,
+    {
+        Node::new(SpawnTree {
+            prototype,
+            scope,
+            map: Some(Box::new(map)),
+            spawned: None,
+        })
+    }
+}
+impl<W> Tickable<W> for SpawnTree
+where
+    W: AsMut<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        let spawned = match self.spawned {
+            Some(ref mut s) => s,
+            None => {
+                let root = match self.prototype.build() {
+                    Ok(root) => root,
+                    Err(e) => {
+                        warn!("Unable to spawn tree {}: {}", self.prototype.id(), e);
+                        return Status::Failed;
+                    }
+                };
+                debug!("Spawning tree {}", self.prototype.id());
+                let blackboard = match self.scope {
+                    BlackboardScope::Shared => None,
+                    BlackboardScope::Scoped => Some(Blackboard::new()),
+                };
+                self.spawned.get_or_insert(Spawned { root, blackboard })
+            }
+        };
+
+        let blackboard = match spawned.blackboard {
+            Some(ref mut b) => b,
+            None => world.as_mut(),
+        };
+        let status = spawned.root.tick(blackboard);
+        if !status.is_done() {
+            return Status::Running;
+        }
+
+        debug!(
+            "Spawned tree {} finished: {:?}",
+            self.prototype.id(),
+            status
+        );
+        self.spawned = None;
+        match self.map {
+            Some(ref map) => map(status),
+            None => status,
+        }
+    }
+
+    fn reset(&mut self) {
+        if let Some(mut spawned) = self.spawned.take() {
+            spawned.root.reset();
+        }
+    }
+
+    /// Returns the string "SpawnTree".
+    fn type_name(&self) -> &'static str {
+        "SpawnTree"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlackboardScope;
+    use crate::{
+        blackboard::Blackboard,
+        loader::{Loader, Prototype},
+        node::Tickable,
+        registry::NodeRegistry,
+        std_nodes::{InlineAction, SpawnTree},
+        Status,
+    };
+
+    /// Returns a prototype of a tree with a leaf which counts its ticks on the
+    /// blackboard, and fails on the second.
+    fn counter() -> Prototype<Blackboard> {
+        let mut registry = NodeRegistry::with_std_nodes();
+        registry.register("Count", |_, _| {
+            Ok(InlineAction::new(|b: &mut Blackboard| {
+                let count = b.get::<u32>("count").copied().unwrap_or(0) + 1;
+                b.set("count", count);
+                if count < 2 {
+                    Status::Running
+                } else {
+                    Status::Failed
+                }
+            }))
+        });
+        Loader::new()
+            .load_str(r#"<root><BehaviorTree ID="Count"><Count /></BehaviorTree></root>"#)
+            .unwrap()
+            .prototype(&registry)
+            .unwrap()
+    }
+
+    #[test]
+    fn spawned_trees_use_their_scope() {
+        let mut blackboard = Blackboard::new();
+        let mut shared = SpawnTree::new(counter(), BlackboardScope::Shared);
+        assert_eq!(shared.tick(&mut blackboard), Status::Running);
+        assert_eq!(shared.tick(&mut blackboard), Status::Failed);
+        assert_eq!(blackboard.get::<u32>("count"), Some(&2));
+
+        let mut blackboard = Blackboard::new();
+        let mut scoped = SpawnTree::with_mapping(counter(), BlackboardScope::Scoped, |s| {
+            if s == Status::Failed {
+                Status::Succeeded
+            } else {
+                s
+            }
+        });
+        assert_eq!(scoped.tick(&mut blackboard), Status::Running);
+        assert_eq!(scoped.tick(&mut blackboard), Status::Succeeded);
+        assert!(!blackboard.contains("count"));
+
+        // A reset drops the spawned tree, so the next tick starts a new one
+        assert_eq!(scoped.tick(&mut blackboard), Status::Running);
+        scoped.reset();
+        assert_eq!(scoped.tick(&mut blackboard), Status::Running);
+    }
+}
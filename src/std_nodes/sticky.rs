@@ -0,0 +1,189 @@
+//! A selector which sticks with the child it chose.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+    std_nodes::selector::{dry_tick_active_selector, tick_active_selector},
+};
+
+/// Function which decides from the world whether to choose a child again.
+type GuardFn<'a, W> = dyn Fn(&W) -> bool + 'a;
+
+/// A selector that keeps ticking the child it chose until that child completes.
+///
+/// With nothing chosen, this node ticks its children in order until one of
+/// them does not fail, like a `Selector`. If that child is running, this node
+/// commits to it: later ticks go straight to that child without trying the
+/// children before it, even after this node has been reset, until the child
+/// succeeds or fails. If it fails, the children after it are tried in the
+/// same tick.
+///
+/// This stops the node switching back and forth between two children which
+/// are nearly as good as each other. A guard can be given with
+/// `StickySelector::with_guard`, which is checked every tick and, when it
+/// returns `true`, has the children chosen between again from the first.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** The chosen child is running.
+///
+/// **Succeeded:** The latest ticked child succeeded.
+///
+/// **Failed:** Every child that was tried failed.
+///
+/// # Children
+///
+/// Any number. Children after the one which did not fail are reset, as are
+/// all children when this node is reset. Resets do not change which child is
+/// chosen.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// // The world says whether the first child can run
+/// let mut node = StickySelector::new(vec![
+///     Condition::new(|&ready: &bool| ready),
+///     AlwaysRunning::new(),
+/// ]);
+///
+/// assert_eq!(node.tick(&mut false), Status::Running);
+/// node.reset();
+///
+/// // The second child is still chosen, even though the first could succeed
+/// assert_eq!(node.tick(&mut true), Status::Running);
+/// assert_eq!(node.children()[0].status(), None);
+/// ```
+pub struct StickySelector<'a, W> {
+    /// Vector containing the children of this node.
+    children: Vec<Node<'a, W>>,
+
+    /// The index of the chosen child, if there is one.
+    chosen: Option<usize>,
+
+    /// Decides whether to choose again.
+    guard: Option<Box<GuardFn<'a, W>>>,
+}
+impl<'a, W> StickySelector<'a, W>
+where
+    W: 'a,
+{
+    /// Creates a new `StickySelector` which only chooses again once the chosen
+    /// child completes.
+    pub fn new(children: Vec<Node<'a, W>>) -> Node<'a, W> {
+        Node::new(StickySelector {
+            children,
+            chosen: None,
+            guard: None,
+        })
+    }
+
+    /// Creates a new `StickySelector` which also chooses again whenever the
+    /// guard returns `true`.
+    pub fn with_guard<F>(guard: F, children: Vec<Node<'a, W>>) -> Node<'a, W>
+    where
+        F: Fn(&W) -> bool + 'a,
+    {
+        Node::new(StickySelector {
+            children,
+            chosen: None,
+            guard: Some(Box::new(guard)),
+        })
+    }
+}
+impl<'a, W> StickySelector<'a, W> {
+    /// Returns the index of the first child to tick.
+    fn start(&self, world: &W) -> usize {
+        match (self.chosen, &self.guard) {
+            (Some(_), Some(guard)) if guard(world) => 0,
+            (Some(chosen), _) => chosen,
+            (None, _) => 0,
+        }
+    }
+}
+impl<'a, W> Tickable<W> for StickySelector<'a, W> {
+    fn tick(&mut self, world: &mut W) -> Status {
+        let start = self.start(world);
+        let children = &mut self.children[start..];
+        let status = tick_active_selector(children, world);
+
+        self.chosen = if status == Status::Running {
+            children
+                .iter()
+                .position(|c| c.status() == Some(Status::Running))
+                .map(|i| start + i)
+        } else {
+            None
+        };
+        status
+    }
+
+    fn dry_tick(&self, world: &W, run: &mut DryRun<'_, W>) -> Option<Status> {
+        let start = self.start(world);
+        Some(dry_tick_active_selector(
+            &self.children[start..],
+            world,
+            run,
+        ))
+    }
+
+    fn reset(&mut self) {
+        // Reset all of our children, but remember the choice
+        for child in &mut self.children {
+            child.reset();
+        }
+    }
+
+    fn children(&self) -> Vec<&Node<'_, W>> {
+        self.children.iter().collect()
+    }
+
+    /// Returns the string "StickySelector".
+    fn type_name(&self) -> &'static str {
+        "StickySelector"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{AlwaysRunning, InlineAction, StickySelector},
+    };
+
+    #[test]
+    fn guard_chooses_again() {
+        // The world says whether the first child can run and whether to
+        // choose again
+        let mut node = StickySelector::with_guard(
+            |&(_, again): &(bool, bool)| again,
+            vec![
+                InlineAction::new(|&mut (ready, _): &mut (bool, bool)| {
+                    if ready {
+                        Status::Running
+                    } else {
+                        Status::Failed
+                    }
+                }),
+                AlwaysRunning::new(),
+            ],
+        );
+
+        assert_eq!(node.tick(&mut (false, false)), Status::Running);
+        assert_eq!(node.tick(&mut (true, false)), Status::Running);
+        assert_eq!(node.children()[0].status(), Some(Status::Failed));
+
+        assert_eq!(node.tick(&mut (true, true)), Status::Running);
+        assert_eq!(node.children()[0].status(), Some(Status::Running));
+        assert_eq!(node.children()[1].status(), None);
+
+        // When the chosen child fails, the ones after it are tried
+        assert_eq!(node.tick(&mut (false, false)), Status::Running);
+        assert_eq!(node.children()[0].status(), Some(Status::Failed));
+        assert_eq!(node.children()[1].status(), Some(Status::Running));
+    }
+}
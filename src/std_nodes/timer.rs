@@ -0,0 +1,240 @@
+//! Nodes that measure mission time with named timers on the blackboard.
+use crate::{
+    blackboard::Blackboard,
+    clock::{Clock, SystemClock},
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::{borrow::Cow, time::Duration};
+
+/// A node that starts, or restarts, a named timer.
+///
+/// The time the timer was started is written to the blackboard under the
+/// timer's name, where `CheckTimer` nodes can read it. Time is taken from a
+/// `Clock`, which is the system clock unless another is given.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** Every time it is ticked.
+///
+/// **Failed:** Never.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// A search that is abandoned after five minutes:
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// # use aspen::blackboard::Blackboard;
+/// # use aspen::clock::ManualClock;
+/// # use std::time::Duration;
+/// let clock = ManualClock::new();
+/// let mut node = Sequence::new(vec![
+///     StartTimer::with_clock("search", clock.clone()),
+///     ActiveSequence::new()
+///         .with_children(vec![
+///             CheckTimer::with_clock("search", Duration::from_secs(300), clock.clone()),
+///             AlwaysRunning::new(),
+///         ])
+///         .into_node(),
+/// ]);
+///
+/// let mut blackboard = Blackboard::new();
+/// assert_eq!(node.tick(&mut blackboard), Status::Running);
+///
+/// clock.advance(Duration::from_secs(300));
+/// assert_eq!(node.tick(&mut blackboard), Status::Failed);
+/// ```
+pub struct StartTimer<'a> {
+    /// The name of the timer.
+    name: Cow<'static, str>,
+
+    /// The source of the current time.
+    clock: Box<dyn Clock + 'a>,
+}
+impl<'a> StartTimer<'a> {
+    /// Creates a new `StartTimer` node using the system clock.
+    pub fn new<W, N>(name: N) -> Node<'a, W>
+    where
+        W: AsMut<Blackboard>,
+        N: Into<Cow<'static, str>>,
+    {
+        StartTimer::with_clock(name, SystemClock)
+    }
+
+    /// Creates a new `StartTimer` node using the given clock.
+    pub fn with_clock<W, N, C>(name: N, clock: C) -> Node<'a, W>
+    where
+        W: AsMut<Blackboard>,
+        N: Into<Cow<'static, str>>,
+        C: Clock + 'a,
+    {
+        Node::new(StartTimer {
+            name: name.into(),
+            clock: Box::new(clock),
+        })
+    }
+}
+impl<'a, W> Tickable<W> for StartTimer<'a>
+where
+    W: AsMut<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        trace!("Starting timer {}", self.name);
+        let now = self.clock.now();
+        world.as_mut().set(self.name.clone().into_owned(), now);
+        Status::Succeeded
+    }
+
+    fn dry_tick(&self, _: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(Status::Succeeded)
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "StartTimer".
+    fn type_name(&self) -> &'static str {
+        "StartTimer"
+    }
+}
+
+/// A node that checks whether a named timer is within its limit.
+///
+/// This node succeeds while less than the limit has passed since the timer was
+/// last started by a `StartTimer` node, and fails once it has run out. It also
+/// fails if the timer has not been started.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** While the timer is within its limit.
+///
+/// **Failed:** When the limit has run out or the timer was never started.
+///
+/// # Children
+///
+/// None.
+pub struct CheckTimer<'a> {
+    /// The name of the timer.
+    name: Cow<'static, str>,
+
+    /// How long the timer may run for.
+    limit: Duration,
+
+    /// The source of the current time.
+    clock: Box<dyn Clock + 'a>,
+}
+impl<'a> CheckTimer<'a> {
+    /// Creates a new `CheckTimer` node using the system clock.
+    pub fn new<W, N>(name: N, limit: Duration) -> Node<'a, W>
+    where
+        W: AsRef<Blackboard>,
+        N: Into<Cow<'static, str>>,
+    {
+        CheckTimer::with_clock(name, limit, SystemClock)
+    }
+
+    /// Creates a new `CheckTimer` node using the given clock.
+    pub fn with_clock<W, N, C>(name: N, limit: Duration, clock: C) -> Node<'a, W>
+    where
+        W: AsRef<Blackboard>,
+        N: Into<Cow<'static, str>>,
+        C: Clock + 'a,
+    {
+        Node::new(CheckTimer {
+            name: name.into(),
+            limit,
+            clock: Box::new(clock),
+        })
+    }
+
+    /// Returns the status for the timers on `blackboard`.
+    fn check(&self, blackboard: &Blackboard) -> Status {
+        let started = match blackboard.get(&self.name) {
+            Some(&started) => started,
+            None => {
+                warn!("Timer {} has not been started", self.name);
+                return Status::Failed;
+            }
+        };
+
+        if self.clock.now().saturating_duration_since(started) < self.limit {
+            Status::Succeeded
+        } else {
+            Status::Failed
+        }
+    }
+}
+impl<'a, W> Tickable<W> for CheckTimer<'a>
+where
+    W: AsRef<Blackboard>,
+{
+    fn tick(&mut self, world: &mut W) -> Status {
+        self.check(world.as_ref())
+    }
+
+    fn dry_tick(&self, world: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        Some(self.check(world.as_ref()))
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "CheckTimer".
+    fn type_name(&self) -> &'static str {
+        "CheckTimer"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        blackboard::Blackboard,
+        clock::ManualClock,
+        node::Tickable,
+        status::Status,
+        std_nodes::{CheckTimer, StartTimer},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn unstarted_timer_fails() {
+        let mut node = CheckTimer::new("t", Duration::from_secs(1));
+        assert_eq!(node.tick(&mut Blackboard::new()), Status::Failed);
+    }
+
+    #[test]
+    fn restarting_extends_the_limit() {
+        let clock = ManualClock::new();
+        let mut start = StartTimer::with_clock("t", clock.clone());
+        let mut check = CheckTimer::with_clock("t", Duration::from_secs(10), clock.clone());
+        let mut blackboard = Blackboard::new();
+
+        start.tick(&mut blackboard);
+        clock.advance(Duration::from_secs(8));
+        assert_eq!(check.tick(&mut blackboard), Status::Succeeded);
+
+        start.tick(&mut blackboard);
+        clock.advance(Duration::from_secs(8));
+        assert_eq!(check.tick(&mut blackboard), Status::Succeeded);
+
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(check.tick(&mut blackboard), Status::Failed);
+    }
+}
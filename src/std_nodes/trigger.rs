@@ -0,0 +1,139 @@
+//! Nodes which react to events fired from outside the tree.
+use crate::{
+    node::{DryRun, Node, Tickable},
+    status::Status,
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A handle which fires an event for `OnTrigger` nodes.
+///
+/// Triggers can be cloned and sent to other threads, such as the one running a
+/// user interface. Firing a trigger which has already been fired, but not yet
+/// seen by its node, has no further effect.
+#[derive(Clone, Debug, Default)]
+pub struct Trigger {
+    /// Whether the trigger has been fired and not yet seen.
+    fired: Arc<AtomicBool>,
+}
+impl Trigger {
+    /// Creates a new trigger which has not been fired.
+    pub fn new() -> Self {
+        Trigger::default()
+    }
+
+    /// Fires the trigger.
+    pub fn set(&self) {
+        self.fired.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the trigger has been fired and not yet seen.
+    pub fn is_set(&self) -> bool {
+        self.fired.load(Ordering::SeqCst)
+    }
+
+    /// Clears the trigger, returning `true` if it had been fired.
+    pub(crate) fn take(&self) -> bool {
+        self.fired.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// A node that succeeds once each time a trigger is fired.
+///
+/// When ticked, this node succeeds if its trigger has been fired since the
+/// last time it succeeded, and clears the trigger. Otherwise, it fails. This
+/// allows events such as button presses to start a branch exactly once.
+///
+/// # State
+///
+/// **Initialized:** Before being ticked after either being created or reset.
+///
+/// **Running:** Never.
+///
+/// **Succeeded:** When the trigger was fired.
+///
+/// **Failed:** When the trigger was not fired.
+///
+/// # Children
+///
+/// None.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::Status;
+/// # use aspen::node::Tickable;
+/// let trigger = Trigger::new();
+/// let mut node = OnTrigger::new(&trigger);
+/// assert_eq!(node.tick(&mut ()), Status::Failed);
+///
+/// trigger.set();
+/// assert_eq!(node.tick(&mut ()), Status::Succeeded);
+/// assert_eq!(node.tick(&mut ()), Status::Failed);
+/// ```
+pub struct OnTrigger {
+    /// The trigger which is watched.
+    trigger: Trigger,
+}
+impl OnTrigger {
+    /// Creates a new `OnTrigger` node which is fired by `trigger`.
+    pub fn new<'a, W>(trigger: &Trigger) -> Node<'a, W> {
+        Node::new(OnTrigger {
+            trigger: trigger.clone(),
+        })
+    }
+}
+impl<W> Tickable<W> for OnTrigger {
+    fn tick(&mut self, _: &mut W) -> Status {
+        if self.trigger.take() {
+            Status::Succeeded
+        } else {
+            Status::Failed
+        }
+    }
+
+    fn dry_tick(&self, _: &W, _: &mut DryRun<'_, W>) -> Option<Status> {
+        if self.trigger.is_set() {
+            Some(Status::Succeeded)
+        } else {
+            Some(Status::Failed)
+        }
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "OnTrigger".
+    fn type_name(&self) -> &'static str {
+        "OnTrigger"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Trigger;
+    use crate::{node::Tickable, status::Status, std_nodes::OnTrigger};
+    use std::thread;
+
+    #[test]
+    fn fires_once_per_set() {
+        let trigger = Trigger::new();
+        let mut node = OnTrigger::new(&trigger);
+
+        let remote = trigger.clone();
+        thread::spawn(move || {
+            remote.set();
+            remote.set();
+        })
+        .join()
+        .unwrap();
+        assert!(trigger.is_set());
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        assert_eq!(node.tick(&mut ()), Status::Failed);
+        assert!(!trigger.is_set());
+    }
+}
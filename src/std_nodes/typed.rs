@@ -0,0 +1,321 @@
+//! Statically typed nodes for trees which are known at compile time.
+//!
+//! The nodes in this module are generic over their children and functions
+//! instead of boxing them, so ticking a tree built from them involves no
+//! dynamic dispatch and can be inlined by the compiler. Composites take their
+//! children as a tuple, which may mix these nodes, any other `Tickable`, and
+//! ordinary `Node`s.
+//!
+//! The trade-off is that the type of a tree spells out its whole structure,
+//! and the children of these composites are not `Node`s, so they are not
+//! reported by `children` and do not take part in monitoring, dry runs, or
+//! status tracking. A typed tree can be turned into a `Node` as a whole with
+//! `Tickable::into_node` to use it as part of a larger, dynamic tree.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::std_nodes::typed::*;
+//! # use aspen::node::Tickable;
+//! # use aspen::Status;
+//! let mut node = Selector::new((
+//!     Condition::new(|&n: &u32| n >= 10),
+//!     Sequence::new((
+//!         Condition::new(|&n: &u32| n % 2 == 0),
+//!         InlineAction::new(|n: &mut u32| {
+//!             *n += 2;
+//!             Status::Succeeded
+//!         }),
+//!     )),
+//! ));
+//!
+//! let mut count = 4;
+//! assert_eq!(node.tick(&mut count), Status::Succeeded);
+//! assert_eq!(count, 6);
+//! ```
+use crate::{node::Tickable, status::Status};
+
+/// A fixed collection of children which can be ticked by index.
+///
+/// This is implemented for tuples of up to twelve `Tickable`s.
+pub trait Children<W> {
+    /// Returns the number of children.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no children.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ticks the child with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn tick(&mut self, index: usize, world: &mut W) -> Status;
+
+    /// Resets the child with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn reset(&mut self, index: usize);
+}
+
+macro_rules! impl_children {
+    ( $len:expr; $( $idx:tt $t:ident ),+ ) => {
+        impl<W, $( $t ),+> Children<W> for ( $( $t, )+ )
+        where
+            $( $t: Tickable<W> ),+
+        {
+            #[inline]
+            fn len(&self) -> usize {
+                $len
+            }
+
+            #[inline]
+            fn tick(&mut self, index: usize, world: &mut W) -> Status {
+                match index {
+                    $( $idx => self.$idx.tick(world), )+
+                    _ => panic!("child index {} out of bounds", index),
+                }
+            }
+
+            #[inline]
+            fn reset(&mut self, index: usize) {
+                match index {
+                    $( $idx => self.$idx.reset(), )+
+                    _ => panic!("child index {} out of bounds", index),
+                }
+            }
+        }
+    };
+}
+
+impl_children!(1; 0 A);
+impl_children!(2; 0 A, 1 B);
+impl_children!(3; 0 A, 1 B, 2 C);
+impl_children!(4; 0 A, 1 B, 2 C, 3 D);
+impl_children!(5; 0 A, 1 B, 2 C, 3 D, 4 E);
+impl_children!(6; 0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+impl_children!(7; 0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+impl_children!(8; 0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
+impl_children!(9; 0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I);
+impl_children!(10; 0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J);
+impl_children!(11; 0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K);
+impl_children!(12; 0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K, 11 L);
+
+/// A statically typed version of `std_nodes::Sequence`.
+///
+/// Ticks its children in order as long as they succeed, resuming at the last
+/// running child on the next tick.
+pub struct Sequence<C> {
+    /// The children of this node.
+    children: C,
+
+    /// The index of the child to be ticked next.
+    next_child: usize,
+}
+impl<C> Sequence<C> {
+    /// Creates a new `Sequence` from a tuple of children.
+    pub fn new(children: C) -> Self {
+        Sequence {
+            children,
+            next_child: 0,
+        }
+    }
+}
+impl<W, C> Tickable<W> for Sequence<C>
+where
+    C: Children<W>,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        let mut ret_status = Status::Succeeded;
+        while self.next_child < self.children.len() && ret_status == Status::Succeeded {
+            ret_status = self.children.tick(self.next_child, world);
+
+            if ret_status.is_done() {
+                self.next_child += 1;
+            }
+        }
+
+        ret_status
+    }
+
+    fn reset(&mut self) {
+        for index in 0..self.children.len() {
+            self.children.reset(index);
+        }
+
+        self.next_child = 0;
+    }
+
+    /// Returns the string "Sequence".
+    fn type_name(&self) -> &'static str {
+        "Sequence"
+    }
+}
+
+/// A statically typed version of `std_nodes::Selector`.
+///
+/// Ticks its children in order until one of them does not fail, starting from
+/// the first child on every tick and resetting the children after it.
+pub struct Selector<C> {
+    /// The children of this node.
+    children: C,
+}
+impl<C> Selector<C> {
+    /// Creates a new `Selector` from a tuple of children.
+    pub fn new(children: C) -> Self {
+        Selector { children }
+    }
+}
+impl<W, C> Tickable<W> for Selector<C>
+where
+    C: Children<W>,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        let mut ret_status = Status::Failed;
+        for index in 0..self.children.len() {
+            if ret_status == Status::Failed {
+                ret_status = self.children.tick(index, world);
+            } else {
+                self.children.reset(index);
+            }
+        }
+
+        ret_status
+    }
+
+    fn reset(&mut self) {
+        for index in 0..self.children.len() {
+            self.children.reset(index);
+        }
+    }
+
+    /// Returns the string "Selector".
+    fn type_name(&self) -> &'static str {
+        "Selector"
+    }
+}
+
+/// A statically typed version of `std_nodes::Condition`.
+pub struct Condition<F> {
+    /// The function which is evaluated on every tick.
+    func: F,
+}
+impl<F> Condition<F> {
+    /// Creates a new `Condition` which succeeds when `func` returns `true`.
+    pub fn new(func: F) -> Self {
+        Condition { func }
+    }
+}
+impl<W, F> Tickable<W> for Condition<F>
+where
+    F: Fn(&W) -> bool,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        if (self.func)(world) {
+            Status::Succeeded
+        } else {
+            Status::Failed
+        }
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "Condition".
+    fn type_name(&self) -> &'static str {
+        "Condition"
+    }
+}
+
+/// A statically typed version of `std_nodes::InlineAction`.
+pub struct InlineAction<F> {
+    /// The function which is run on every tick.
+    task: F,
+}
+impl<F> InlineAction<F> {
+    /// Creates a new `InlineAction` which runs `task` on every tick.
+    pub fn new(task: F) -> Self {
+        InlineAction { task }
+    }
+}
+impl<W, F> Tickable<W> for InlineAction<F>
+where
+    F: FnMut(&mut W) -> Status,
+{
+    #[inline]
+    fn tick(&mut self, world: &mut W) -> Status {
+        (self.task)(world)
+    }
+
+    fn reset(&mut self) {
+        // No-op
+    }
+
+    /// Returns the string "InlineAction".
+    fn type_name(&self) -> &'static str {
+        "InlineAction"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InlineAction, Selector, Sequence};
+    use crate::{
+        node::Tickable,
+        status::Status,
+        std_nodes::{CountedTick, NoTick, YesTick},
+    };
+
+    #[test]
+    fn sequence_resumes_at_running_child() {
+        let mut node = Sequence::new((
+            CountedTick::new(Status::Succeeded, 1, true),
+            CountedTick::new(Status::Running, 2, true),
+            NoTick::new(),
+        ));
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        assert_eq!(node.tick(&mut ()), Status::Running);
+        drop(node);
+    }
+
+    #[test]
+    fn selector_resets_later_children() {
+        // Fails on the first and third ticks, and succeeds on the second
+        let first = InlineAction::new(|n: &mut u32| {
+            *n += 1;
+            if *n == 2 {
+                Status::Succeeded
+            } else {
+                Status::Failed
+            }
+        });
+        let mut node = Selector::new((
+            first,
+            // Can only be ticked a second time if it was reset in between
+            CountedTick::resetable(Status::Running, 1, true),
+            NoTick::new(),
+        ));
+
+        let mut n = 0;
+        assert_eq!(node.tick(&mut n), Status::Running);
+        assert_eq!(node.tick(&mut n), Status::Succeeded);
+        assert_eq!(node.tick(&mut n), Status::Running);
+        drop(node);
+    }
+
+    #[test]
+    fn typed_tree_can_be_a_node() {
+        let mut node =
+            Sequence::new((Sequence::new((YesTick::new(Status::Succeeded),)),)).into_node();
+        assert_eq!(node.tick(&mut ()), Status::Succeeded);
+        assert_eq!(node.type_name(), "Sequence");
+    }
+}
@@ -0,0 +1,637 @@
+//! Generators of random trees for property-based testing.
+//!
+//! A `TreeSpec` describes a tree of standard composites and decorators whose
+//! leaves follow fixed scripts of statuses. Specs are generated from a seed
+//! within configurable bounds, and can be built into as many identical trees
+//! as needed. `check` runs a property against many random specs and reports
+//! the seed and spec of the first one it fails for, so the failure can be
+//! reproduced with `TreeSpec::random`.
+//!
+//! `TreeSpec` also implements `arbitrary::Arbitrary` for fuzzing, and
+//! `tree_spec` is a `proptest` strategy for the same trees, which shrinks a
+//! failing spec to a smaller one.
+//!
+//! Composite authors can use random specs as the children of their own nodes
+//! to test them against a wide range of child behavior.
+//!
+//! For end-to-end tests of hand-written trees, a `Scenario` changes the world
+//! on a script while the tree runs, and checks the statuses of its nodes at
+//! given ticks.
+//!
+//! This module is only available with the `test-utils` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::testing::{check, Bounds};
+//! # use aspen::{monitor, BehaviorTree};
+//! // Resetting a tree returns every node to its initial state
+//! check(200, 1, &Bounds::default(), |spec| {
+//!     let mut tree = BehaviorTree::new(spec.build::<()>());
+//!     tree.tick_n(3, &mut ());
+//!     tree.reset();
+//!
+//!     let mut statuses = Vec::new();
+//!     monitor::collect_statuses(tree.root(), &mut statuses);
+//!     assert!(statuses.iter().all(Option::is_none));
+//! });
+//! ```
+use crate::{
+    bt::BehaviorTree,
+    monitor,
+    node::{Node, Tickable},
+    rng::Rng,
+    status::Status,
+    std_nodes::{
+        ActiveSequence, Invert, Parallel, Repeat, Selector, Sequence, UntilFail, UntilSuccess,
+    },
+};
+use arbitrary::{Arbitrary, Unstructured};
+use proptest::{collection::vec, prelude::*};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Limits on the size of generated trees.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    /// The greatest depth of the tree, where a lone leaf has depth one.
+    pub max_depth: usize,
+
+    /// The greatest number of children of a composite.
+    pub max_children: usize,
+
+    /// The greatest length of a leaf's script.
+    pub max_script: usize,
+
+    /// The greatest limit of a `Repeat`, `UntilFail` or `UntilSuccess` node.
+    pub max_limit: u32,
+}
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds {
+            max_depth: 4,
+            max_children: 4,
+            max_script: 4,
+            max_limit: 3,
+        }
+    }
+}
+
+/// A description of a tree, which can be built any number of times.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeSpec {
+    /// A leaf which returns the statuses in its script in order, starting again
+    /// from the beginning when it reaches the end or is reset.
+    Leaf(Vec<Status>),
+
+    /// A `Sequence` node.
+    Sequence(Vec<TreeSpec>),
+
+    /// An `ActiveSequence` node.
+    ActiveSequence(Vec<TreeSpec>),
+
+    /// A `Selector` node.
+    Selector(Vec<TreeSpec>),
+
+    /// A `Parallel` node with the given number of required successes.
+    Parallel(usize, Vec<TreeSpec>),
+
+    /// An `Invert` node.
+    Invert(Box<TreeSpec>),
+
+    /// A `Repeat` node with the given limit.
+    Repeat(u32, Box<TreeSpec>),
+
+    /// An `UntilFail` node with the given limit.
+    UntilFail(u32, Box<TreeSpec>),
+
+    /// An `UntilSuccess` node with the given limit.
+    UntilSuccess(u32, Box<TreeSpec>),
+}
+impl TreeSpec {
+    /// Generates a random spec within `bounds` from the given seed.
+    ///
+    /// The same seed and bounds always generate the same spec.
+    pub fn random(seed: u64, bounds: &Bounds) -> TreeSpec {
+        let mut rng = Rng::new(seed);
+        TreeSpec::generate(&mut rng, bounds, bounds.max_depth.max(1))
+    }
+
+    /// Generates a random spec of at most the given depth.
+    fn generate<C: Choices>(rng: &mut C, bounds: &Bounds, depth: usize) -> TreeSpec {
+        if depth <= 1 || rng.chance(0.3) {
+            let len = 1 + rng.below(bounds.max_script.max(1));
+            let script = (0..len)
+                .map(|_| match rng.below(3) {
+                    0 => Status::Running,
+                    1 => Status::Succeeded,
+                    _ => Status::Failed,
+                })
+                .collect();
+            return TreeSpec::Leaf(script);
+        }
+
+        let limit = 1 + rng.below(bounds.max_limit.max(1) as usize) as u32;
+        let count = 1 + rng.below(bounds.max_children.max(1));
+        let kind = rng.below(8);
+        if kind < 4 {
+            let children: Vec<_> = (0..count)
+                .map(|_| TreeSpec::generate(rng, bounds, depth - 1))
+                .collect();
+            return match kind {
+                0 => TreeSpec::Sequence(children),
+                1 => TreeSpec::ActiveSequence(children),
+                2 => TreeSpec::Selector(children),
+                _ => TreeSpec::Parallel(1 + rng.below(count), children),
+            };
+        }
+
+        let child = Box::new(TreeSpec::generate(rng, bounds, depth - 1));
+        match kind {
+            4 => TreeSpec::Invert(child),
+            5 => TreeSpec::Repeat(limit, child),
+            6 => TreeSpec::UntilFail(limit, child),
+            _ => TreeSpec::UntilSuccess(limit, child),
+        }
+    }
+
+    /// Builds a tree matching this spec.
+    ///
+    /// The leaves ignore the world, so the tree can be ticked with any world.
+    pub fn build<'a, W: 'a>(&self) -> Node<'a, W> {
+        let build_all = |specs: &[TreeSpec]| specs.iter().map(TreeSpec::build).collect();
+        match self {
+            TreeSpec::Leaf(script) => Node::new(Scripted {
+                script: script.clone(),
+                next: 0,
+            }),
+            TreeSpec::Sequence(c) => Sequence::new(build_all(c)),
+            TreeSpec::ActiveSequence(c) => ActiveSequence::new()
+                .with_children(build_all(c))
+                .into_node(),
+            TreeSpec::Selector(c) => Selector::new(build_all(c)),
+            TreeSpec::Parallel(n, c) => Parallel::new(*n, build_all(c)),
+            TreeSpec::Invert(c) => Invert::new(c.build()),
+            TreeSpec::Repeat(n, c) => Repeat::with_limit(*n, c.build()),
+            TreeSpec::UntilFail(n, c) => UntilFail::with_limit(*n, c.build()),
+            TreeSpec::UntilSuccess(n, c) => UntilSuccess::with_limit(*n, c.build()),
+        }
+    }
+
+    /// Returns the number of nodes in the tree.
+    pub fn len(&self) -> usize {
+        1 + self.children().iter().map(|c| c.len()).sum::<usize>()
+    }
+
+    /// Returns `true` if the tree has no nodes, which is never the case.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the specs of the children of the root.
+    pub fn children(&self) -> Vec<&TreeSpec> {
+        match self {
+            TreeSpec::Leaf(_) => Vec::new(),
+            TreeSpec::Sequence(c)
+            | TreeSpec::ActiveSequence(c)
+            | TreeSpec::Selector(c)
+            | TreeSpec::Parallel(_, c) => c.iter().collect(),
+            TreeSpec::Invert(c)
+            | TreeSpec::Repeat(_, c)
+            | TreeSpec::UntilFail(_, c)
+            | TreeSpec::UntilSuccess(_, c) => vec![&**c],
+        }
+    }
+}
+
+/// Generates a spec within the default `Bounds` from the fuzzer's data.
+///
+/// Running out of data ends every branch with a leaf, so any input makes a
+/// valid spec.
+impl<'a> Arbitrary<'a> for TreeSpec {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bounds = Bounds::default();
+        Ok(TreeSpec::generate(u, &bounds, bounds.max_depth))
+    }
+}
+
+/// The random choices made while generating a spec.
+trait Choices {
+    /// Returns a number less than `n`, which must not be zero.
+    fn below(&mut self, n: usize) -> usize;
+
+    /// Returns `true` with the given probability.
+    fn chance(&mut self, probability: f64) -> bool;
+}
+impl Choices for Rng {
+    fn below(&mut self, n: usize) -> usize {
+        Rng::below(self, n)
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        Rng::chance(self, probability)
+    }
+}
+impl<'a> Choices for Unstructured<'a> {
+    fn below(&mut self, n: usize) -> usize {
+        self.int_in_range(0..=n - 1).unwrap_or(0)
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        // Out of data reads as zero, which is always a hit
+        let percent = self.int_in_range(0..=99u8).unwrap_or(0);
+        f64::from(percent) < probability * 100.0
+    }
+}
+
+/// Returns a `proptest` strategy which generates specs within `bounds`.
+///
+/// Unlike `TreeSpec::random`, failing specs found by the strategy are shrunk
+/// towards smaller trees and shorter scripts.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::testing::{tree_spec, Bounds};
+/// # use aspen::{monitor, BehaviorTree};
+/// # use proptest::prelude::*;
+/// proptest!(|(spec in tree_spec(&Bounds::default()))| {
+///     // Every node in the spec is built
+///     let tree = BehaviorTree::new(spec.build::<()>());
+///     prop_assert_eq!(monitor::preorder(tree.root()).len(), spec.len());
+/// });
+/// ```
+pub fn tree_spec(bounds: &Bounds) -> impl Strategy<Value = TreeSpec> {
+    let bounds = *bounds;
+    let status = prop_oneof![
+        Just(Status::Running),
+        Just(Status::Succeeded),
+        Just(Status::Failed),
+    ];
+    let leaf = vec(status, 1..=bounds.max_script.max(1)).prop_map(TreeSpec::Leaf);
+
+    let depth = bounds.max_depth.max(1) - 1;
+    let children = bounds.max_children.max(1);
+    let limit = 1..=bounds.max_limit.max(1);
+    leaf.prop_recursive(depth as u32, 256, children as u32, move |inner| {
+        let all = vec(inner.clone(), 1..=children);
+        let parallel = all
+            .clone()
+            .prop_flat_map(|c| (1..=c.len(), Just(c)))
+            .prop_map(|(n, c)| TreeSpec::Parallel(n, c));
+        let child = inner.prop_map(Box::new);
+        prop_oneof![
+            all.clone().prop_map(TreeSpec::Sequence),
+            all.clone().prop_map(TreeSpec::ActiveSequence),
+            all.prop_map(TreeSpec::Selector),
+            parallel,
+            child.clone().prop_map(TreeSpec::Invert),
+            (limit.clone(), child.clone()).prop_map(|(n, c)| TreeSpec::Repeat(n, c)),
+            (limit.clone(), child.clone()).prop_map(|(n, c)| TreeSpec::UntilFail(n, c)),
+            (limit.clone(), child).prop_map(|(n, c)| TreeSpec::UntilSuccess(n, c)),
+        ]
+    })
+}
+
+/// Checks that `property` holds for `cases` random specs within `bounds`.
+///
+/// The specs are generated from seeds derived from `seed`, so the same call
+/// always checks the same specs.
+///
+/// # Panics
+///
+/// Panics if the property panics for any spec, giving the seed and the spec.
+pub fn check<F>(cases: usize, seed: u64, bounds: &Bounds, mut property: F)
+where
+    F: FnMut(&TreeSpec),
+{
+    let mut seeds = Rng::new(seed);
+    for _ in 0..cases {
+        let case = seeds.next_u64();
+        let spec = TreeSpec::random(case, bounds);
+        if panic::catch_unwind(AssertUnwindSafe(|| property(&spec))).is_err() {
+            panic!("property failed for seed {} with {:?}", case, spec);
+        }
+    }
+}
+
+/// A script of changes to the world and checks on the tree, for end-to-end
+/// tests.
+///
+/// Ticks are counted from one. The changes for a tick are made just before it,
+/// and the checks for a tick are made just after it.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::testing::Scenario;
+/// # use aspen::{BehaviorTree, Status};
+/// struct Robot {
+///     battery: u32,
+/// }
+///
+/// let mut tree = BehaviorTree::new(Selector::new(vec![
+///     Condition::new(|r: &Robot| r.battery < 20).named(Some("LowBattery")),
+///     AlwaysRunning::new().named(Some("Patrol")),
+/// ]));
+///
+/// let mut robot = Robot { battery: 100 };
+/// Scenario::new()
+///     .expect(1, "Patrol", Status::Running)
+///     .at(5, |r: &mut Robot| r.battery = 10)
+///     .expect(4, "LowBattery", Status::Failed)
+///     .expect(5, "LowBattery", Status::Succeeded)
+///     .run(&mut tree, &mut robot, 10);
+/// ```
+pub struct Scenario<'s, W> {
+    /// The changes to make to the world, with the ticks to make them before.
+    changes: Vec<(u64, Box<ChangeFn<'s, W>>)>,
+
+    /// The checks to make, with the ticks to make them after.
+    checks: Vec<(u64, Check<'s, W>)>,
+}
+impl<'s, W> Scenario<'s, W> {
+    /// Creates an empty scenario.
+    pub fn new() -> Self {
+        Scenario {
+            changes: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Changes the world just before the given tick.
+    pub fn at<F>(mut self, tick: u64, change: F) -> Self
+    where
+        F: FnMut(&mut W) + 's,
+    {
+        self.changes.push((tick, Box::new(change)));
+        self
+    }
+
+    /// Checks that the first node with the given name, in pre-order, has the
+    /// given status just after the given tick.
+    pub fn expect<N: Into<String>>(mut self, tick: u64, node: N, status: Status) -> Self {
+        self.checks
+            .push((tick, Check::Status(node.into(), Some(status))));
+        self
+    }
+
+    /// Checks that the first node with the given name, in pre-order, was not
+    /// ticked since it was last reset, just after the given tick.
+    pub fn expect_idle<N: Into<String>>(mut self, tick: u64, node: N) -> Self {
+        self.checks.push((tick, Check::Status(node.into(), None)));
+        self
+    }
+
+    /// Makes an arbitrary check just after the given tick.
+    ///
+    /// The check should panic if it fails.
+    pub fn check<F>(mut self, tick: u64, check: F) -> Self
+    where
+        F: FnMut(&BehaviorTree<'_, W>, &W) + 's,
+    {
+        self.checks.push((tick, Check::Custom(Box::new(check))));
+        self
+    }
+
+    /// Runs the scenario, ticking the tree up to `ticks` times or until it
+    /// completes, and returns the status of its last tick.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a check fails, or if the tree completes before a tick with a
+    /// change or check.
+    pub fn run<'a>(&mut self, tree: &mut BehaviorTree<'a, W>, world: &mut W, ticks: u64) -> Status {
+        let mut status = Status::Running;
+        let mut tick = 0;
+        while tick < ticks && !status.is_done() {
+            tick += 1;
+            for (_, change) in self.changes.iter_mut().filter(|(t, _)| *t == tick) {
+                change(world);
+            }
+
+            status = tree.tick(world);
+
+            for (_, check) in self.checks.iter_mut().filter(|(t, _)| *t == tick) {
+                check.run(tick, tree, world);
+            }
+        }
+
+        let last = self
+            .changes
+            .iter()
+            .map(|(t, _)| *t)
+            .chain(self.checks.iter().map(|(t, _)| *t))
+            .max();
+        if let Some(last) = last {
+            if last > tick {
+                panic!(
+                    "scenario stopped after tick {} with status {:?}, before tick {}",
+                    tick, status, last
+                );
+            }
+        }
+
+        status
+    }
+}
+impl<'s, W> Default for Scenario<'s, W> {
+    fn default() -> Self {
+        Scenario::new()
+    }
+}
+
+/// A check made by a `Scenario`.
+enum Check<'s, W> {
+    /// The named node has the given status.
+    Status(String, Option<Status>),
+
+    /// The function does not panic.
+    Custom(Box<CheckFn<'s, W>>),
+}
+impl<'s, W> Check<'s, W> {
+    /// Makes the check just after the given tick.
+    fn run(&mut self, tick: u64, tree: &BehaviorTree<'_, W>, world: &W) {
+        match self {
+            Check::Status(name, expected) => {
+                let node = monitor::preorder(tree.root())
+                    .into_iter()
+                    .find(|n| n.name() == name.as_str())
+                    .unwrap_or_else(|| panic!("tick {}: no node is named {}", tick, name));
+                assert_eq!(
+                    node.status(),
+                    *expected,
+                    "tick {}: unexpected status of {}",
+                    tick,
+                    name
+                );
+            }
+            Check::Custom(check) => check(tree, world),
+        }
+    }
+}
+
+/// A change made to the world by a `Scenario`.
+type ChangeFn<'s, W> = dyn FnMut(&mut W) + 's;
+
+/// A custom check made by a `Scenario`.
+type CheckFn<'s, W> = dyn FnMut(&BehaviorTree<'_, W>, &W) + 's;
+
+/// A leaf which follows a script of statuses.
+struct Scripted {
+    /// The statuses to return.
+    script: Vec<Status>,
+
+    /// The index of the status to return next.
+    next: usize,
+}
+impl<W> Tickable<W> for Scripted {
+    fn tick(&mut self, _: &mut W) -> Status {
+        let status = self.script[self.next % self.script.len()];
+        self.next += 1;
+        status
+    }
+
+    fn reset(&mut self) {
+        self.next = 0;
+    }
+
+    /// Returns the string "Scripted".
+    fn type_name(&self) -> &'static str {
+        "Scripted"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, tree_spec, Bounds, Scenario, TreeSpec};
+    use crate::{
+        monitor,
+        node::Tickable,
+        std_nodes::{AlwaysRunning, AlwaysSucceed, InlineAction, Sequence},
+        BehaviorTree, Status,
+    };
+    use arbitrary::{Arbitrary, Unstructured};
+    use proptest::prelude::*;
+
+    /// Returns the depth of the tree, where a lone leaf has depth one.
+    fn depth(spec: &TreeSpec) -> usize {
+        1 + spec.children().into_iter().map(depth).max().unwrap_or(0)
+    }
+
+    #[test]
+    #[should_panic(expected = "tick 2: unexpected status of Leaf")]
+    fn scenario_reports_failed_checks() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            InlineAction::new(|n: &mut u32| {
+                if *n > 0 {
+                    Status::Succeeded
+                } else {
+                    Status::Running
+                }
+            })
+            .named(Some("Leaf")),
+            AlwaysRunning::new(),
+        ]));
+
+        Scenario::new()
+            .expect(1, "Leaf", Status::Running)
+            .at(2, |n: &mut u32| *n = 1)
+            .expect(2, "Leaf", Status::Running)
+            .run(&mut tree, &mut 0, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "before tick 4")]
+    fn scenario_reports_unreached_ticks() {
+        let mut tree = BehaviorTree::new(AlwaysSucceed::new());
+        Scenario::new()
+            .check(4, |_, _: &()| {})
+            .run(&mut tree, &mut (), 10);
+    }
+
+    #[test]
+    fn specs_respect_bounds() {
+        let bounds = Bounds {
+            max_depth: 3,
+            max_children: 2,
+            ..Bounds::default()
+        };
+        check(500, 7, &bounds, |spec| {
+            assert!(depth(spec) <= 3);
+            assert!(spec.len() <= 1 + 2 + 4);
+        });
+        assert_eq!(TreeSpec::random(3, &bounds), TreeSpec::random(3, &bounds));
+    }
+
+    #[test]
+    fn reset_restores_initial_state() {
+        check(500, 11, &Bounds::default(), |spec| {
+            let mut tree = BehaviorTree::new(spec.build::<()>());
+            let first: Vec<_> = (0..5).map(|_| tree.tick(&mut ())).collect();
+
+            tree.reset();
+            let mut statuses = Vec::new();
+            monitor::collect_statuses(tree.root(), &mut statuses);
+            assert!(statuses.iter().all(Option::is_none));
+
+            let again: Vec<_> = (0..5).map(|_| tree.tick(&mut ())).collect();
+            assert_eq!(first, again);
+        });
+    }
+
+    #[test]
+    fn completed_sequences_agree_with_their_children() {
+        check(500, 13, &Bounds::default(), |spec| {
+            let mut node = spec.build::<()>();
+            let status = node.tick(&mut ());
+            if let TreeSpec::Sequence(_) = spec {
+                if status == Status::Succeeded {
+                    let children = node.children();
+                    assert!(children
+                        .iter()
+                        .all(|c| c.status() == Some(Status::Succeeded)));
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn arbitrary_specs_respect_bounds() {
+        let bounds = Bounds::default();
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let spec = TreeSpec::arbitrary(&mut u).unwrap();
+            assert!(depth(&spec) <= bounds.max_depth);
+            assert!(spec.children().len() <= bounds.max_children);
+        }
+
+        // Without data, the spec is a lone leaf
+        let spec = TreeSpec::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert_eq!(spec, TreeSpec::Leaf(vec![Status::Running]));
+    }
+
+    proptest! {
+        #[test]
+        fn strategy_respects_bounds(spec in tree_spec(&Bounds {
+            max_depth: 3,
+            max_children: 2,
+            ..Bounds::default()
+        })) {
+            prop_assert!(depth(&spec) <= 3);
+            prop_assert!(spec.len() <= 1 + 2 + 4);
+        }
+
+        #[test]
+        fn reset_clears_statuses(spec in tree_spec(&Bounds::default())) {
+            let mut tree = BehaviorTree::new(spec.build::<()>());
+            tree.tick_n(3, &mut ());
+            tree.reset();
+
+            let mut statuses = Vec::new();
+            monitor::collect_statuses(tree.root(), &mut statuses);
+            prop_assert!(statuses.iter().all(Option::is_none));
+        }
+    }
+}
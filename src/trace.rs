@@ -0,0 +1,1433 @@
+//! Recording what trees do, and learning from the recordings.
+//!
+//! A `TraceRecorder` writes down the structure of a tree and which nodes were
+//! ticked on every tick, with the status they were left in. Traces can be
+//! saved as text and read back with `Trace::parse`, so that recordings from
+//! many runs, or many robots, can be gathered in one place.
+//!
+//! A trace can be replayed as a series of `TraceFrame`s, one per tick, which
+//! can be rendered as text or as DOT graphs. The `aspen` binary uses these to
+//! animate recorded traces. A whole trace can also be drawn as an SVG Gantt
+//! chart of when each node was running.
+//!
+//! A `TraceAnalysis` of one or more traces gives the hit rate of every branch
+//! and how often each node failed, suggestions for trees which have grown
+//! organically, such as branches which are never reached and the nodes which
+//! fail most, and a skeleton tree file holding just the nodes which were
+//! used.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::std_nodes::*;
+//! # use aspen::trace::{Trace, TraceAnalysis, TraceRecorder};
+//! # use aspen::BehaviorTree;
+//! let mut tree = BehaviorTree::new(Selector::new(vec![
+//!     Condition::new(|&ready: &bool| ready).named(Some("Ready")),
+//!     AlwaysSucceed::new().named(Some("Prepare")),
+//! ]));
+//! let mut recorder = TraceRecorder::new(&tree);
+//! for &ready in &[false, false, true, false] {
+//!     tree.tick(&mut { ready });
+//!     recorder.observe(&tree);
+//! }
+//!
+//! let trace = Trace::parse(&recorder.trace().to_text()).unwrap();
+//! let analysis = TraceAnalysis::of(&[trace]);
+//! assert_eq!(analysis.hit_rate(2), Some(0.75));
+//! assert_eq!(
+//!     analysis.suggestions(),
+//!     vec!["#1 Selector > Ready failed 3 of 4 times"]
+//! );
+//! ```
+use crate::{
+    loader::xml::push_escaped,
+    monitor::Transition,
+    node::{Node, Tickable},
+    stats::PhaseStats,
+    BehaviorTree, Status,
+};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The first line of a trace file.
+const HEADER: &str = "aspen-trace 1";
+
+/// The fewest completions after which a leaf which never failed is reported.
+const REDUNDANT_AFTER: u64 = 10;
+
+/// A node of a traced tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TracedNode {
+    /// The index of the node in pre-order.
+    id: usize,
+
+    /// The ID of the parent of the node.
+    parent: Option<usize>,
+
+    /// The type of the node.
+    type_name: String,
+
+    /// The name of the node.
+    name: String,
+
+    /// The phase the node was tagged with.
+    phase: Option<String>,
+}
+impl TracedNode {
+    /// Returns the index of the node in pre-order.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the ID of the parent of the node, which is `None` for the root.
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// Returns the type of the node.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the name of the node.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the phase the node was tagged with, if any.
+    pub fn phase(&self) -> Option<&str> {
+        self.phase.as_deref()
+    }
+}
+
+/// A node being ticked during a tick of a traced tree.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TraceEvent {
+    /// The tick count of the tree after the tick.
+    pub tick: u64,
+
+    /// The pre-order index of the node.
+    pub node: usize,
+
+    /// The status the node was left in, which is `None` if it was reset after
+    /// being ticked.
+    pub status: Option<Status>,
+
+    /// How long after the recording started the tick was observed, to the
+    /// microsecond, if it is known.
+    pub elapsed: Option<Duration>,
+}
+
+/// A recording of the nodes a tree ticked.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    /// The nodes of the tree, in pre-order.
+    nodes: Vec<TracedNode>,
+
+    /// The nodes which were ticked, in the order of the ticks.
+    events: Vec<TraceEvent>,
+}
+impl Trace {
+    /// Returns the nodes of the tree, in pre-order.
+    pub fn nodes(&self) -> &[TracedNode] {
+        &self.nodes
+    }
+
+    /// Returns the nodes which were ticked, in the order of the ticks and then
+    /// in pre-order.
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Returns the trace as text, which `parse` reads back.
+    ///
+    /// After a header line, each node is listed as `node`, its ID, the ID of
+    /// its parent or `-`, its type and its name if it differs from the type.
+    /// A node tagged with a phase is followed by `phase` and the phase. Then
+    /// each tick is given as `tick`, the tick count and, if it is known,
+    /// the number of microseconds since the recording started, followed by a
+    /// line for each node which was ticked with its ID and its status, or `-`
+    /// if it was reset.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+        for node in &self.nodes {
+            text.push_str(&format!("node {} ", node.id));
+            match node.parent {
+                Some(parent) => text.push_str(&parent.to_string()),
+                None => text.push('-'),
+            }
+            text.push(' ');
+            text.push_str(&node.type_name);
+            if node.name != node.type_name {
+                text.push(' ');
+                text.push_str(&node.name);
+            }
+            text.push('\n');
+            if let Some(ref phase) = node.phase {
+                text.push_str(&format!("phase {}\n", phase));
+            }
+        }
+
+        let mut tick = None;
+        for event in &self.events {
+            if tick != Some(event.tick) {
+                tick = Some(event.tick);
+                match event.elapsed {
+                    Some(elapsed) => {
+                        let micros = elapsed.as_micros();
+                        text.push_str(&format!("tick {} {}\n", event.tick, micros));
+                    }
+                    None => text.push_str(&format!("tick {}\n", event.tick)),
+                }
+            }
+            text.push_str(&format!("{} {}\n", event.node, status_word(event.status)));
+        }
+        text
+    }
+
+    /// Reads a trace written by `to_text`.
+    pub fn parse(text: &str) -> Result<Trace, TraceError> {
+        let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(TraceError::new(1, "not a trace")),
+        }
+
+        let mut trace = Trace::default();
+        let mut tick = None;
+        let mut elapsed = None;
+        for (line, text) in lines.filter(|(_, l)| !l.is_empty()) {
+            let error = |message: &str| TraceError::new(line, message);
+            let mut words = text.splitn(2, ' ');
+            let first = words.next().unwrap_or("");
+            let rest = words.next().unwrap_or("");
+            match first {
+                "node" => {
+                    let mut words = rest.splitn(4, ' ');
+                    let id = words.next().and_then(|w| w.parse().ok());
+                    let parent = match words.next() {
+                        Some("-") => Some(None),
+                        Some(w) => w.parse().ok().map(Some),
+                        None => None,
+                    };
+                    let type_name = words.next().filter(|w| !w.is_empty());
+                    let (id, parent, type_name) = match (id, parent, type_name) {
+                        (Some(i), Some(p), Some(t)) => (i, p, t),
+                        _ => return Err(error("invalid node")),
+                    };
+                    if id != trace.nodes.len() || parent.map_or(false, |p| p >= id) {
+                        return Err(error("nodes must be listed in pre-order"));
+                    }
+                    trace.nodes.push(TracedNode {
+                        id,
+                        parent,
+                        type_name: type_name.to_owned(),
+                        name: words.next().unwrap_or(type_name).to_owned(),
+                        phase: None,
+                    });
+                }
+                "phase" => match trace.nodes.last_mut() {
+                    Some(node) if node.phase.is_none() && !rest.is_empty() => {
+                        node.phase = Some(rest.to_owned())
+                    }
+                    _ => return Err(error("invalid phase")),
+                },
+                "tick" => {
+                    let mut words = rest.split(' ');
+                    match words.next().map(str::parse) {
+                        Some(Ok(t)) => tick = Some(t),
+                        _ => return Err(error("invalid tick count")),
+                    }
+                    elapsed = match words.next().map(str::parse) {
+                        Some(Ok(micros)) => Some(Duration::from_micros(micros)),
+                        Some(Err(_)) => return Err(error("invalid time")),
+                        None => None,
+                    };
+                }
+                _ => {
+                    let tick = tick.ok_or_else(|| error("node ticked outside a tick"))?;
+                    let node = match first.parse() {
+                        Ok(n) if n < trace.nodes.len() => n,
+                        _ => return Err(error("unknown node")),
+                    };
+                    let status = match rest {
+                        "-" => None,
+                        "running" => Some(Status::Running),
+                        "succeeded" => Some(Status::Succeeded),
+                        "failed" => Some(Status::Failed),
+                        _ => return Err(error("invalid status")),
+                    };
+                    trace.events.push(TraceEvent {
+                        tick,
+                        node,
+                        status,
+                        elapsed,
+                    });
+                }
+            }
+        }
+
+        Ok(trace)
+    }
+
+    /// Returns the state of the tree after each tick in the trace.
+    pub fn frames(&self) -> Vec<TraceFrame> {
+        let mut frames: Vec<TraceFrame> = Vec::new();
+        let mut statuses = vec![None; self.nodes.len()];
+        for event in &self.events {
+            if frames.last().map_or(true, |f| f.tick != event.tick) {
+                frames.push(TraceFrame {
+                    tick: event.tick,
+                    elapsed: event.elapsed,
+                    statuses: Vec::new(),
+                    ticked: vec![false; self.nodes.len()],
+                });
+            }
+            statuses[event.node] = event.status;
+            let frame = frames.last_mut().unwrap();
+            frame.statuses.clone_from(&statuses);
+            frame.ticked[event.node] = true;
+        }
+        frames
+    }
+
+    /// Renders a frame as an indented list of the nodes and their statuses.
+    ///
+    /// Nodes which were ticked during the frame are marked with a `*`.
+    pub fn render_text(&self, frame: &TraceFrame) -> String {
+        let mut text = String::new();
+        for node in &self.nodes {
+            let depth = self.depth(node.id);
+            let marker = if frame.was_ticked(node.id) { '*' } else { ' ' };
+            let status = frame.statuses.get(node.id).cloned().flatten();
+            text.push_str(&format!(
+                "{} {}{} [{}]\n",
+                marker,
+                "  ".repeat(depth),
+                node.name,
+                status_word(status)
+            ));
+        }
+        text
+    }
+
+    /// Renders a frame as a DOT graph, with nodes coloured by their status.
+    ///
+    /// Nodes which were ticked during the frame are drawn with a thick border.
+    pub fn render_dot(&self, frame: &TraceFrame) -> String {
+        let mut dot = format!("digraph \"tick {}\" {{\n", frame.tick);
+        dot.push_str("  node [shape=box, style=filled];\n");
+        for node in &self.nodes {
+            let colour = match frame.statuses.get(node.id).cloned().flatten() {
+                None => "white",
+                Some(Status::Running) => "gold",
+                Some(Status::Succeeded) => "palegreen",
+                Some(Status::Failed) => "salmon",
+            };
+            let label = node.name.replace('\\', "\\\\").replace('"', "\\\"");
+            dot.push_str(&format!(
+                "  n{} [label=\"{}\", fillcolor={}",
+                node.id, label, colour
+            ));
+            if frame.was_ticked(node.id) {
+                dot.push_str(", penwidth=3");
+            }
+            dot.push_str("];\n");
+            if let Some(parent) = node.parent {
+                dot.push_str(&format!("  n{} -> n{};\n", parent, node.id));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns an SVG Gantt chart of the intervals in which each node was
+    /// running.
+    ///
+    /// There is a row for each node, in pre-order and indented by depth, with
+    /// the subtrees of the children of the root shaded alternately. The time
+    /// axis is in seconds if the trace recorded the time of every tick, and in
+    /// ticks otherwise. Each bar is coloured by how the interval ended:
+    /// succeeded, failed, halted, or still running at the end of the trace.
+    /// Bars have a class of the same name, and a tooltip with their times.
+    ///
+    /// A running node which is not ticked on a tick of the tree is taken to
+    /// have been halted after the last tick it was ticked on.
+    pub fn to_gantt_svg(&self) -> String {
+        let frames = self.frames();
+        let timed = frames.iter().all(|f| f.elapsed.is_some());
+        let x = |frame: &TraceFrame| match frame.elapsed {
+            Some(elapsed) if timed => elapsed.as_secs_f64(),
+            _ => frame.tick as f64,
+        };
+
+        // The intervals of every node, as start, end and how they ended
+        let mut intervals = vec![Vec::new(); self.nodes.len()];
+        let mut open: Vec<Option<(f64, f64)>> = vec![None; self.nodes.len()];
+        for frame in &frames {
+            let now = x(frame);
+            for id in 0..self.nodes.len() {
+                let status = frame.statuses[id];
+                match open[id] {
+                    None if frame.was_ticked(id) && status == Some(Status::Running) => {
+                        open[id] = Some((now, now));
+                    }
+                    Some((start, _)) if frame.was_ticked(id) => match status {
+                        Some(Status::Running) => open[id] = Some((start, now)),
+                        outcome => {
+                            let class = outcome.map_or("halted", |s| status_word(Some(s)));
+                            intervals[id].push((start, now, class));
+                            open[id] = None;
+                        }
+                    },
+                    Some((start, last)) => {
+                        intervals[id].push((start, last, "halted"));
+                        open[id] = None;
+                    }
+                    None => {}
+                }
+            }
+        }
+        for (id, interval) in open.into_iter().enumerate() {
+            if let Some((start, last)) = interval {
+                intervals[id].push((start, last, "running"));
+            }
+        }
+
+        let (label_width, chart_width, row) = (220.0, 640.0, 20.0);
+        let first = frames.first().map_or(0.0, &x);
+        let span = frames.last().map_or(0.0, &x) - first;
+        let scale = if span > 0.0 { chart_width / span } else { 0.0 };
+        let height = row * (self.nodes.len() + 2) as f64;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             font-family=\"sans-serif\" font-size=\"12\">\n",
+            label_width + chart_width + 20.0,
+            height
+        );
+        svg.push_str(
+            "  <style>.succeeded{fill:#7c7}.failed{fill:#e66}.halted{fill:#aaa}.running{fill:#\
+             fc3}</style>\n",
+        );
+        for (i, node) in self.nodes.iter().enumerate() {
+            let y = row * i as f64;
+            let shaded = self.subtree(node.id).map_or(false, |s| s % 2 == 1);
+            if shaded {
+                svg.push_str(&format!(
+                    "  <rect x=\"0\" y=\"{}\" width=\"100%\" height=\"{}\" fill=\"#f0f0f0\" />\n",
+                    y, row
+                ));
+            }
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\">",
+                4 + 12 * self.depth(node.id),
+                y + 14.0
+            ));
+            push_escaped(&mut svg, &node.name);
+            svg.push_str("</text>\n");
+
+            for &(start, end, class) in &intervals[i] {
+                let left = label_width + (start - first) * scale;
+                let width = ((end - start) * scale).max(1.0);
+                svg.push_str(&format!(
+                    "  <rect class=\"{}\" x=\"{:.1}\" y=\"{}\" width=\"{:.1}\" \
+                     height=\"{}\"><title>",
+                    class,
+                    left,
+                    y + 3.0,
+                    width,
+                    row - 6.0
+                ));
+                push_escaped(&mut svg, &node.name);
+                svg.push_str(&format!(": {} to {}</title></rect>\n", start, end));
+            }
+        }
+
+        let axis = row * self.nodes.len() as f64 + 4.0;
+        let label = |x: f64| {
+            if timed {
+                format!("{:.3}s", x)
+            } else {
+                format!("tick {}", x)
+            }
+        };
+        svg.push_str(&format!(
+            "  <line x1=\"{0}\" y1=\"{1}\" x2=\"{2}\" y2=\"{1}\" stroke=\"black\" />\n",
+            label_width,
+            axis,
+            label_width + chart_width
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\">{}</text>\n",
+            label_width,
+            axis + 14.0,
+            label(first)
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n",
+            label_width + chart_width,
+            axis + 14.0,
+            label(first + span)
+        ));
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Returns a post-mortem of the last run of the tree, if it failed.
+    ///
+    /// The failing path is followed from the root through the last child
+    /// which failed on the final tick, down to the leaf where the failure
+    /// started. The nodes next to the path which were also ticked on that tick,
+    /// such as the guards of a sequence or the alternatives of a selector, are
+    /// reported with the status they were left in, together with the last
+    /// `transitions` changes of status before and during the failure.
+    ///
+    /// Returns `None` if the root was not left failed by the final tick.
+    pub fn post_mortem(&self, transitions: usize) -> Option<PostMortem<'_>> {
+        let frames = self.frames();
+        let frame = frames.last()?;
+        if frame.statuses.first() != Some(&Some(Status::Failed)) || !frame.was_ticked(0) {
+            return None;
+        }
+
+        let mut path = vec![0];
+        let mut guards = Vec::new();
+        loop {
+            let current = *path.last().unwrap();
+            let children: Vec<_> = self
+                .nodes
+                .iter()
+                .filter(|n| n.parent == Some(current) && frame.was_ticked(n.id))
+                .map(|n| n.id)
+                .collect();
+            let failed = children
+                .iter()
+                .rev()
+                .find(|&&id| frame.statuses[id] == Some(Status::Failed));
+            match failed {
+                Some(&next) => {
+                    guards.extend(
+                        children
+                            .iter()
+                            .filter(|&&id| id != next)
+                            .map(|&id| (id, frame.statuses[id])),
+                    );
+                    path.push(next);
+                }
+                None => break,
+            }
+        }
+
+        let mut recent = Vec::new();
+        let mut statuses = vec![None; self.nodes.len()];
+        for event in &self.events {
+            if statuses[event.node] != event.status {
+                recent.push((
+                    event.tick,
+                    Transition {
+                        node: event.node,
+                        from: statuses[event.node],
+                        to: event.status,
+                    },
+                ));
+                statuses[event.node] = event.status;
+            }
+        }
+        let skip = recent.len().saturating_sub(transitions);
+        recent.drain(..skip);
+
+        Some(PostMortem {
+            trace: self,
+            tick: frame.tick,
+            path,
+            guards,
+            recent,
+        })
+    }
+
+    /// Compares this trace with another of the same tree, returning where
+    /// their executions first diverged, or `None` if they never did.
+    ///
+    /// The traces are aligned tick by tick from their first recorded tick, so
+    /// they can start from different tick counts, such as a run in simulation
+    /// and one on the robot. On each tick, the nodes which were ticked and the
+    /// statuses they were left in are compared in pre-order. If one trace
+    /// ends before the other without diverging, the divergence is where the
+    /// shorter one ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the traces are of trees with different structures.
+    pub fn diff<'t>(&'t self, other: &'t Trace) -> Option<Divergence<'t>> {
+        assert!(
+            self.nodes == other.nodes,
+            "cannot compare traces of trees with different structures"
+        );
+
+        let (ours, theirs) = (self.frames(), other.frames());
+        let divergence =
+            |index: usize, node, ours: Option<&TraceFrame>, theirs: Option<&TraceFrame>| {
+                let seen = |frame: Option<&TraceFrame>, id: Option<usize>| match (frame, id) {
+                    (Some(frame), Some(id)) if frame.was_ticked(id) => Some(frame.statuses[id]),
+                    _ => None,
+                };
+                Divergence {
+                    trace: self,
+                    index,
+                    ticks: (ours.map(|f| f.tick), theirs.map(|f| f.tick)),
+                    node,
+                    statuses: (seen(ours, node), seen(theirs, node)),
+                }
+            };
+
+        for i in 0..ours.len().max(theirs.len()) {
+            let (a, b) = match (ours.get(i), theirs.get(i)) {
+                (Some(a), Some(b)) => (a, b),
+                (a, b) => return Some(divergence(i, None, a, b)),
+            };
+            let differs = |id: usize| {
+                a.was_ticked(id) != b.was_ticked(id)
+                    || (a.was_ticked(id) && a.statuses[id] != b.statuses[id])
+            };
+            if let Some(id) = (0..self.nodes.len()).find(|&id| differs(id)) {
+                return Some(divergence(i, Some(id), Some(a), Some(b)));
+            }
+        }
+        None
+    }
+
+    /// Returns the depth of the node with the given ID, which is zero for the
+    /// root.
+    fn depth(&self, id: usize) -> usize {
+        let mut depth = 0;
+        let mut parent = self.nodes[id].parent;
+        while let Some(p) = parent {
+            depth += 1;
+            parent = self.nodes[p].parent;
+        }
+        depth
+    }
+
+    /// Returns the position among the children of the root of the child whose
+    /// subtree holds the node with the given ID, or `None` for the root.
+    fn subtree(&self, id: usize) -> Option<usize> {
+        let mut id = id;
+        while let Some(parent) = self.nodes[id].parent {
+            if self.nodes[parent].parent.is_none() {
+                let mut children = self.nodes.iter().filter(|n| n.parent == Some(parent));
+                return children.position(|n| n.id == id);
+            }
+            id = parent;
+        }
+        None
+    }
+}
+
+/// The state of a traced tree after one of its ticks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceFrame {
+    /// The tick count of the tree after the tick.
+    tick: u64,
+
+    /// How long after the recording started the tick was observed.
+    elapsed: Option<Duration>,
+
+    /// The last recorded status of every node, in pre-order.
+    statuses: Vec<Option<Status>>,
+
+    /// Whether each node was ticked during the tick, in pre-order.
+    ticked: Vec<bool>,
+}
+impl TraceFrame {
+    /// Returns the tick count of the tree after the tick.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns how long after the recording started the tick was observed, if
+    /// it is known.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    /// Returns the last recorded status of every node, in pre-order.
+    ///
+    /// Nodes which were not ticked keep the status they were left in when they
+    /// last were, as resets are not recorded.
+    pub fn statuses(&self) -> &[Option<Status>] {
+        &self.statuses
+    }
+
+    /// Returns `true` if the node with the given ID was ticked during the
+    /// tick.
+    pub fn was_ticked(&self, id: usize) -> bool {
+        self.ticked.get(id).cloned().unwrap_or(false)
+    }
+}
+
+/// The failure of the last run of a traced tree, from `Trace::post_mortem`.
+///
+/// Displaying a post-mortem gives a report for operators, with nodes named by
+/// their path from the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PostMortem<'t> {
+    /// The trace which failed.
+    trace: &'t Trace,
+
+    /// The tick count of the tree after the failing tick.
+    tick: u64,
+
+    /// The IDs of the nodes on the failing path, from the root to the leaf.
+    path: Vec<usize>,
+
+    /// The other nodes next to the path which were ticked, with their status.
+    guards: Vec<(usize, Option<Status>)>,
+
+    /// The last changes of status, with the tick they happened on.
+    recent: Vec<(u64, Transition)>,
+}
+impl<'t> PostMortem<'t> {
+    /// Returns the tick count of the tree after the failing tick.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the IDs of the nodes on the failing path, from the root to the
+    /// leaf which failed.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Returns the ID of the leaf which failed.
+    pub fn failing_leaf(&self) -> usize {
+        *self.path.last().unwrap()
+    }
+
+    /// Returns the nodes next to the failing path which were also ticked on
+    /// the failing tick, with the status they were left in.
+    pub fn guards(&self) -> &[(usize, Option<Status>)] {
+        &self.guards
+    }
+
+    /// Returns the last changes of status, with the tick count of the tree
+    /// after the tick they happened on.
+    pub fn recent_transitions(&self) -> &[(u64, Transition)] {
+        &self.recent
+    }
+}
+impl<'t> fmt::Display for PostMortem<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nodes = &self.trace.nodes;
+        writeln!(f, "Failed on tick {}", self.tick)?;
+        writeln!(f, "Failing path: {}", node_path(nodes, self.failing_leaf()))?;
+        if !self.guards.is_empty() {
+            writeln!(f, "Nodes beside the path:")?;
+            for &(id, status) in &self.guards {
+                writeln!(f, "  {}: {}", node_path(nodes, id), status_word(status))?;
+            }
+        }
+        writeln!(f, "Last {} transitions:", self.recent.len())?;
+        for (tick, transition) in &self.recent {
+            writeln!(
+                f,
+                "  tick {}: {} {} -> {}",
+                tick,
+                node_path(nodes, transition.node),
+                status_word(transition.from),
+                status_word(transition.to)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the executions of two traces first diverged, from `Trace::diff`.
+///
+/// Displaying a divergence describes it, with nodes named by their path from
+/// the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence<'t> {
+    /// The trace which was compared with the other.
+    trace: &'t Trace,
+
+    /// The number of ticks into the traces at which they diverged.
+    index: usize,
+
+    /// The tick counts of the diverging tick in each trace, or `None` for a
+    /// trace which had ended.
+    ticks: (Option<u64>, Option<u64>),
+
+    /// The first node which behaved differently.
+    node: Option<usize>,
+
+    /// The statuses the node was left in by each trace on the tick, or `None`
+    /// for a trace which did not tick it.
+    statuses: (Option<Option<Status>>, Option<Option<Status>>),
+}
+impl<'t> Divergence<'t> {
+    /// Returns the number of recorded ticks into the traces at which they
+    /// diverged, starting from zero.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the tick count of the diverging tick in this trace and in the
+    /// other, or `None` for a trace which ended before it.
+    pub fn ticks(&self) -> (Option<u64>, Option<u64>) {
+        self.ticks
+    }
+
+    /// Returns the ID of the first node, in pre-order, which behaved
+    /// differently, or `None` if one of the traces had ended.
+    pub fn node(&self) -> Option<usize> {
+        self.node
+    }
+
+    /// Returns the status the node was left in by this trace and by the
+    /// other, or `None` for a trace which did not tick it.
+    pub fn statuses(&self) -> (Option<Option<Status>>, Option<Option<Status>>) {
+        self.statuses
+    }
+}
+impl<'t> fmt::Display for Divergence<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |status: Option<Option<Status>>| match status {
+            Some(status) => status_word(status),
+            None => "not ticked",
+        };
+        match (self.ticks, self.node) {
+            ((Some(ours), Some(theirs)), Some(node)) => write!(
+                f,
+                "Diverged after {} ticks, on tick {} here and {} there, at {}: {} here, {} there",
+                self.index,
+                ours,
+                theirs,
+                node_path(&self.trace.nodes, node),
+                describe(self.statuses.0),
+                describe(self.statuses.1)
+            ),
+            ((None, _), _) => write!(f, "This trace ended after {} ticks", self.index),
+            _ => write!(f, "The other trace ended after {} ticks", self.index),
+        }
+    }
+}
+
+/// Returns the path of a node from the root, with names separated by `>`.
+fn node_path(nodes: &[TracedNode], id: usize) -> String {
+    let node = &nodes[id];
+    match node.parent {
+        Some(parent) => format!("{} > {}", node_path(nodes, parent), node.name),
+        None => node.name.clone(),
+    }
+}
+
+/// Returns the word for a status used in traces.
+fn status_word(status: Option<Status>) -> &'static str {
+    match status {
+        None => "-",
+        Some(Status::Running) => "running",
+        Some(Status::Succeeded) => "succeeded",
+        Some(Status::Failed) => "failed",
+    }
+}
+
+/// An error from reading a trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceError {
+    /// The line the error is on, starting from one.
+    line: usize,
+
+    /// What went wrong.
+    message: String,
+}
+impl TraceError {
+    /// Creates an error on the given line.
+    fn new(line: usize, message: &str) -> Self {
+        TraceError {
+            line,
+            message: message.to_owned(),
+        }
+    }
+
+    /// Returns the line the error is on, starting from one.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns what went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+impl Error for TraceError {}
+
+/// Records the nodes a tree ticks.
+///
+/// The recorder should be created before the tree is first ticked, and be
+/// given the tree after every tick, such as from the hook passed to
+/// `BehaviorTree::run`.
+pub struct TraceRecorder {
+    /// The trace so far.
+    trace: Trace,
+
+    /// The tick count of every node at the previous observation.
+    tick_counts: Vec<u64>,
+
+    /// When the recording started.
+    started: Instant,
+
+    /// The hash of the structure of the tree being recorded.
+    structure_hash: u64,
+}
+impl TraceRecorder {
+    /// Creates a recorder for the given tree.
+    pub fn new<W>(tree: &BehaviorTree<'_, W>) -> Self {
+        let mut recorder = TraceRecorder {
+            trace: Trace::default(),
+            tick_counts: Vec::new(),
+            started: Instant::now(),
+            structure_hash: crate::monitor::structure_hash(tree.root()),
+        };
+        recorder.push_nodes(tree.root(), None);
+        recorder
+    }
+
+    /// Appends `node` and its descendants, in pre-order.
+    fn push_nodes<W>(&mut self, node: &Node<'_, W>, parent: Option<usize>) {
+        let id = self.trace.nodes.len();
+        self.trace.nodes.push(TracedNode {
+            id,
+            parent,
+            type_name: node.type_name().to_owned(),
+            name: node.name().to_owned(),
+            phase: node.phase().map(str::to_owned),
+        });
+        self.tick_counts.push(node.tick_count());
+        for child in node.children() {
+            self.push_nodes(child, Some(id));
+        }
+    }
+
+    /// Records the nodes which were ticked since the previous observation.
+    ///
+    /// Nothing is recorded if the structure of the tree has changed.
+    pub fn observe<W>(&mut self, tree: &BehaviorTree<'_, W>) {
+        if crate::monitor::structure_hash(tree.root()) != self.structure_hash {
+            warn!("Not tracing a tree whose structure has changed");
+            return;
+        }
+
+        let nodes = crate::monitor::preorder(tree.root());
+        let elapsed = Duration::from_micros(self.started.elapsed().as_micros() as u64);
+        for (id, node) in nodes.into_iter().enumerate() {
+            if node.tick_count() != self.tick_counts[id] {
+                self.tick_counts[id] = node.tick_count();
+                self.trace.events.push(TraceEvent {
+                    tick: tree.tick_count(),
+                    node: id,
+                    status: node.status(),
+                    elapsed: Some(elapsed),
+                });
+            }
+        }
+    }
+
+    /// Returns the trace so far.
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    /// Returns the trace.
+    pub fn into_trace(self) -> Trace {
+        self.trace
+    }
+}
+
+/// How a node behaved over the traced ticks.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct NodeStats {
+    /// The number of ticks on which the node was ticked.
+    ticks: u64,
+
+    /// The number of times the node was started.
+    activations: u64,
+
+    /// The number of ticks after which the node had succeeded.
+    successes: u64,
+
+    /// The number of ticks after which the node had failed.
+    failures: u64,
+}
+impl NodeStats {
+    /// Returns the number of ticks on which the node was ticked.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the number of times the node was started, which is the number
+    /// of ticks on which it was ticked without having been running.
+    pub fn activations(&self) -> u64 {
+        self.activations
+    }
+
+    /// Returns the number of times the node succeeded.
+    pub fn successes(&self) -> u64 {
+        self.successes
+    }
+
+    /// Returns the number of times the node failed.
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+}
+
+/// Statistics and suggestions drawn from traces of a tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceAnalysis {
+    /// The nodes of the tree, in pre-order.
+    nodes: Vec<TracedNode>,
+
+    /// The statistics of every node, in pre-order.
+    stats: Vec<NodeStats>,
+
+    /// The statistics of every phase, by name.
+    phases: BTreeMap<String, PhaseStats>,
+}
+impl TraceAnalysis {
+    /// Analyses traces of trees with the same structure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the traces are of trees with different structures.
+    pub fn of(traces: &[Trace]) -> Self {
+        let nodes = traces.first().map(|t| t.nodes.clone()).unwrap_or_default();
+        let mut stats = vec![NodeStats::default(); nodes.len()];
+        let mut phases: BTreeMap<String, PhaseStats> = nodes
+            .iter()
+            .filter_map(|n| n.phase.clone())
+            .map(|phase| (phase, PhaseStats::default()))
+            .collect();
+        for trace in traces {
+            assert!(
+                trace.nodes == nodes,
+                "cannot analyse traces of trees with different structures"
+            );
+            let mut last = vec![None; nodes.len()];
+            let mut tick = None;
+            let mut previous = Duration::from_secs(0);
+            let mut current = Duration::from_secs(0);
+            let mut timed: Vec<&str> = Vec::new();
+            for event in &trace.events {
+                if tick != Some(event.tick) {
+                    tick = Some(event.tick);
+                    previous = current;
+                    current = event.elapsed.unwrap_or(previous);
+                    timed.clear();
+                }
+                if let Some(ref phase) = nodes[event.node].phase {
+                    let phase_stats = phases.entry(phase.clone()).or_default();
+                    phase_stats.record(last[event.node], event.status);
+                    // Each phase is given the time since the previous tick once
+                    let elapsed = event.elapsed.filter(|_| !timed.contains(&phase.as_str()));
+                    if let Some(elapsed) = elapsed {
+                        timed.push(phase);
+                        phase_stats.add_time(elapsed.checked_sub(previous).unwrap_or_default());
+                    }
+                }
+
+                let stats = &mut stats[event.node];
+                stats.ticks += 1;
+                if last[event.node] != Some(Status::Running) {
+                    stats.activations += 1;
+                }
+                match event.status {
+                    Some(Status::Succeeded) => stats.successes += 1,
+                    Some(Status::Failed) => stats.failures += 1,
+                    _ => {}
+                }
+                last[event.node] = event.status;
+            }
+        }
+
+        TraceAnalysis {
+            nodes,
+            stats,
+            phases,
+        }
+    }
+
+    /// Returns the nodes of the tree, in pre-order.
+    pub fn nodes(&self) -> &[TracedNode] {
+        &self.nodes
+    }
+
+    /// Returns the statistics of the node with the given ID.
+    pub fn stats(&self, id: usize) -> Option<&NodeStats> {
+        self.stats.get(id)
+    }
+
+    /// Returns the statistics of the given phase, if any node of the traced
+    /// tree was tagged with it.
+    ///
+    /// Each tick on which a node of the phase was ticked adds the time since
+    /// the previous tick to the phase, as the `PhaseMonitor` does.
+    pub fn phase(&self, phase: &str) -> Option<&PhaseStats> {
+        self.phases.get(phase)
+    }
+
+    /// Returns an iterator over the statistics of every phase of the traced
+    /// tree, in order of their names.
+    pub fn phases(&self) -> impl Iterator<Item = (&str, &PhaseStats)> {
+        self.phases.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Returns how often the node with the given ID was started when its
+    /// parent was, or `None` if the parent was never started.
+    ///
+    /// The hit rate of the root is always one once it has been started.
+    pub fn hit_rate(&self, id: usize) -> Option<f64> {
+        let node = self.nodes.get(id)?;
+        let parent = node.parent.map_or(id, |p| p);
+        let parent = self.stats[parent].activations;
+        if parent == 0 {
+            None
+        } else {
+            Some(self.stats[id].activations as f64 / parent as f64)
+        }
+    }
+
+    /// Returns the path of a node from the root, with names separated by
+    /// `>`.
+    fn path(&self, id: usize) -> String {
+        node_path(&self.nodes, id)
+    }
+
+    /// Returns suggestions for improving the tree, one per line.
+    ///
+    /// Nodes which were never ticked although their parent was are reported
+    /// first, followed by the leaves which failed at least half of the time,
+    /// most failures first, and then leaves which completed many times and
+    /// never failed, which may be redundant.
+    pub fn suggestions(&self) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        for node in &self.nodes {
+            let parent_ticked = node.parent.map_or(true, |p| self.stats[p].ticks > 0);
+            if self.stats[node.id].ticks == 0 && parent_ticked {
+                suggestions.push(format!(
+                    "#{} {} was never ticked; consider removing it",
+                    node.id,
+                    self.path(node.id)
+                ));
+            }
+        }
+
+        let leaves: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|n| self.nodes.iter().all(|c| c.parent != Some(n.id)))
+            .map(|n| (n.id, self.stats[n.id]))
+            .collect();
+        let mut failing: Vec<_> = leaves
+            .iter()
+            .filter(|(_, s)| s.failures > 0 && s.failures >= s.successes)
+            .collect();
+        failing.sort_by_key(|(id, s)| (std::cmp::Reverse(s.failures), *id));
+        for (id, stats) in failing {
+            suggestions.push(format!(
+                "#{} {} failed {} of {} times",
+                id,
+                self.path(*id),
+                stats.failures,
+                stats.failures + stats.successes
+            ));
+        }
+
+        for (id, stats) in &leaves {
+            if stats.failures == 0 && stats.successes >= REDUNDANT_AFTER {
+                suggestions.push(format!(
+                    "#{} {} succeeded all {} times; it may be redundant",
+                    id,
+                    self.path(*id),
+                    stats.successes
+                ));
+            }
+        }
+        suggestions
+    }
+
+    /// Returns a tree file holding the nodes which were ticked, in the format
+    /// read by the `loader`.
+    ///
+    /// Each node is preceded by a comment with its statistics. Parameters are
+    /// not traced, so nodes which need them have to be filled in by hand.
+    pub fn skeleton(&self) -> String {
+        let mut xml = String::from("<root format=\"1\" main_tree_to_execute=\"Main\">\n");
+        xml.push_str("  <BehaviorTree ID=\"Main\">\n");
+        if !self.nodes.is_empty() {
+            self.push_skeleton(0, 2, &mut xml);
+        }
+        xml.push_str("  </BehaviorTree>\n</root>\n");
+        xml
+    }
+
+    /// Appends the skeleton of the node with the given ID and its ticked
+    /// descendants.
+    fn push_skeleton(&self, id: usize, depth: usize, xml: &mut String) {
+        let node = &self.nodes[id];
+        let stats = &self.stats[id];
+        let indent = "  ".repeat(depth);
+        xml.push_str(&format!(
+            "{}<!-- {} activations, {} succeeded, {} failed",
+            indent, stats.activations, stats.successes, stats.failures
+        ));
+        if let (Some(_), Some(rate)) = (node.parent, self.hit_rate(id)) {
+            xml.push_str(&format!(", hit {:.0}%", 100.0 * rate));
+        }
+        xml.push_str(" -->\n");
+
+        xml.push_str(&indent);
+        xml.push('<');
+        xml.push_str(&node.type_name);
+        if node.name != node.type_name {
+            xml.push_str(" name=\"");
+            push_escaped(xml, &node.name);
+            xml.push('"');
+        }
+
+        let children: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|c| c.parent == Some(id) && self.stats[c.id].ticks > 0)
+            .collect();
+        if children.is_empty() {
+            xml.push_str(" />\n");
+            return;
+        }
+        xml.push_str(">\n");
+        for child in children {
+            self.push_skeleton(child.id, depth + 1, xml);
+        }
+        xml.push_str(&format!("{}</{}>\n", indent, node.type_name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Trace, TraceAnalysis, TraceRecorder};
+    use crate::{
+        loader::Loader,
+        registry::NodeRegistry,
+        std_nodes::{AlwaysFail, AlwaysSucceed, Condition, Repeat, Selector, Sequence},
+        BehaviorTree,
+    };
+
+    #[test]
+    fn other_structures_are_not_traced() {
+        let tree: BehaviorTree<()> = BehaviorTree::new(Selector::new(vec![AlwaysFail::new()]));
+        let mut recorder = TraceRecorder::new(&tree);
+
+        let mut other = BehaviorTree::new(Sequence::new(vec![AlwaysSucceed::new()]));
+        other.tick(&mut ());
+        recorder.observe(&other);
+        assert!(recorder.trace().events().is_empty());
+    }
+
+    #[test]
+    fn phases_are_traced() {
+        let mut tree = BehaviorTree::new(Selector::new(vec![
+            Condition::new(|&arrived: &bool| arrived).in_phase("Navigation"),
+            AlwaysSucceed::new().in_phase("Recovery"),
+        ]));
+        let mut recorder = TraceRecorder::new(&tree);
+        for &arrived in &[false, true, true] {
+            tree.tick(&mut { arrived });
+            recorder.observe(&tree);
+        }
+
+        let text = recorder.trace().to_text();
+        assert!(text.contains("node 1 0 Condition\nphase Navigation\n"));
+        let trace = Trace::parse(&text).unwrap();
+        assert_eq!(trace.nodes()[2].phase(), Some("Recovery"));
+        let error = Trace::parse(&format!("{}phase Again\n", text)).unwrap_err();
+        assert_eq!(error.message(), "invalid phase");
+
+        let analysis = TraceAnalysis::of(&[trace]);
+        let navigation = analysis.phase("Navigation").unwrap();
+        assert_eq!(navigation.activations(), 3);
+        assert_eq!(navigation.successes(), 2);
+        assert_eq!(analysis.phase("Recovery").unwrap().successes(), 1);
+        let names: Vec<&str> = analysis.phases().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["Navigation", "Recovery"]);
+    }
+
+    #[test]
+    fn traces_round_trip_and_analyse() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            Selector::new(vec![
+                Condition::new(|&n: &u32| n % 3 != 0).named(Some("Odd one")),
+                AlwaysFail::new(),
+                AlwaysSucceed::new().named(Some("Fallback")),
+                AlwaysSucceed::new().named(Some("Unused")),
+            ]),
+            AlwaysSucceed::new(),
+        ]));
+        let mut recorder = TraceRecorder::new(&tree);
+        for n in 0..12 {
+            tree.tick(&mut { n });
+            recorder.observe(&tree);
+        }
+
+        let text = recorder.trace().to_text();
+        let trace = Trace::parse(&text).unwrap();
+        assert_eq!(&trace, recorder.trace());
+        assert_eq!(trace.nodes()[2].name(), "Odd one");
+        assert_eq!(Trace::parse("node 0 - A").unwrap_err().line(), 1);
+        let error = Trace::parse(&text.replace("succeeded", "done")).unwrap_err();
+        assert_eq!(error.message(), "invalid status");
+
+        let analysis = TraceAnalysis::of(&[trace.clone(), trace]);
+        assert_eq!(analysis.stats(2).unwrap().activations(), 24);
+        assert_eq!(analysis.hit_rate(3), Some(1.0 / 3.0));
+        assert_eq!(
+            analysis.suggestions(),
+            vec![
+                "#5 Sequence > Selector > Unused was never ticked; consider removing it",
+                "#3 Sequence > Selector > AlwaysFail failed 8 of 8 times",
+                "#6 Sequence > AlwaysSucceed succeeded all 24 times; it may be redundant",
+            ]
+        );
+
+        // The skeleton leaves out the unused node, and can be loaded
+        let skeleton = analysis.skeleton();
+        assert!(!skeleton.contains("Unused"));
+        assert!(skeleton.contains("<Condition name=\"Odd one\" />"));
+        let definition = Loader::new().load_str(&skeleton).unwrap();
+        let mut registry = NodeRegistry::<()>::with_std_nodes();
+        registry.register("Condition", |_, _| Ok(AlwaysSucceed::new()));
+        assert!(definition.build(&registry).is_ok());
+    }
+
+    #[test]
+    fn frames_replay_the_trace() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            AlwaysSucceed::new().named(Some("Check \"a\"")),
+            Condition::new(|&ready: &bool| ready),
+        ]));
+        let mut recorder = TraceRecorder::new(&tree);
+        for &ready in &[false, true] {
+            tree.tick(&mut { ready });
+            recorder.observe(&tree);
+        }
+
+        let trace = Trace::parse(&recorder.trace().to_text()).unwrap();
+        let frames = trace.frames();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[1].elapsed() >= frames[0].elapsed());
+        assert_eq!(
+            trace.render_text(&frames[0]),
+            "* Sequence [failed]\n*   Check \"a\" [succeeded]\n*   Condition [failed]\n"
+        );
+
+        let dot = trace.render_dot(&frames[1]);
+        assert!(dot.starts_with("digraph \"tick 2\" {"));
+        assert!(dot.contains("n1 [label=\"Check \\\"a\\\"\", fillcolor=palegreen, penwidth=3];"));
+        assert!(dot.contains("n0 -> n2;"));
+    }
+
+    #[test]
+    fn gantt_chart_has_a_bar_per_interval() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            Repeat::with_limit(2, AlwaysSucceed::new()).named(Some("Drive")),
+            Repeat::with_limit(2, AlwaysSucceed::new()).named(Some("Dock <1>")),
+        ]));
+        let mut recorder = TraceRecorder::new(&tree);
+        for _ in 0..4 {
+            tree.tick(&mut ());
+            recorder.observe(&tree);
+        }
+
+        let mut trace = recorder.into_trace();
+        for event in &mut trace.events {
+            event.elapsed = None;
+        }
+        let svg = trace.to_gantt_svg();
+        assert!(svg.contains("Dock &lt;1&gt;</text>"));
+        assert_eq!(svg.matches("class=\"running\"").count(), 2);
+        assert_eq!(svg.matches("class=\"succeeded\"").count(), 3);
+        assert!(svg.contains(">tick 4</text>"));
+    }
+
+    #[test]
+    fn post_mortem_follows_the_failure() {
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            Condition::new(|&charged: &bool| charged).named(Some("Charged")),
+            Selector::new(vec![
+                Condition::new(|_: &bool| false).named(Some("Docked")),
+                AlwaysFail::new().named(Some("Dock")),
+            ]),
+        ]));
+        let mut recorder = TraceRecorder::new(&tree);
+        tree.tick(&mut false);
+        recorder.observe(&tree);
+        assert_eq!(recorder.trace().post_mortem(3).unwrap().failing_leaf(), 1);
+
+        tree.tick(&mut true);
+        recorder.observe(&tree);
+        let trace = recorder.into_trace();
+        let report = trace.post_mortem(2).unwrap();
+        assert_eq!(report.path(), &[0, 2, 4]);
+        assert_eq!(
+            report.to_string(),
+            "Failed on tick 2\n\
+             Failing path: Sequence > Selector > Dock\n\
+             Nodes beside the path:\n  \
+             Sequence > Charged: succeeded\n  \
+             Sequence > Selector > Docked: failed\n\
+             Last 2 transitions:\n  \
+             tick 2: Sequence > Selector > Docked - -> failed\n  \
+             tick 2: Sequence > Selector > Dock - -> failed\n"
+        );
+    }
+
+    #[test]
+    fn diff_finds_the_first_divergence() {
+        let record = |worlds: &[u32]| {
+            let mut tree = BehaviorTree::new(Selector::new(vec![
+                Condition::new(|&n: &u32| n > 2).named(Some("Far")),
+                AlwaysSucceed::new().named(Some("Approach")),
+            ]));
+            let mut recorder = TraceRecorder::new(&tree);
+            for &n in worlds {
+                tree.tick(&mut { n });
+                recorder.observe(&tree);
+            }
+            recorder.into_trace()
+        };
+
+        let sim = record(&[5, 4, 3, 2]);
+        let real = record(&[5, 4, 1, 1, 1]);
+        assert_eq!(sim.diff(&sim), None);
+        let divergence = sim.diff(&real).unwrap();
+        assert_eq!(divergence.index(), 2);
+        assert_eq!(divergence.node(), Some(1));
+        assert_eq!(
+            divergence.to_string(),
+            "Diverged after 2 ticks, on tick 3 here and 3 there, at Selector > Far: succeeded \
+             here, failed there"
+        );
+        let short = record(&[5, 4, 1, 1]);
+        let divergence = short.diff(&real).unwrap();
+        assert_eq!(divergence.to_string(), "This trace ended after 4 ticks");
+    }
+}
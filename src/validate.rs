@@ -0,0 +1,287 @@
+//! Static checks for mistakes in the structure of a tree.
+//!
+//! `validate` walks a tree without ticking it and reports anything which is
+//! almost certainly an authoring mistake, such as a branch which can never be
+//! reached. This is most useful for large or generated trees, where such
+//! mistakes are easy to make and hard to spot.
+//!
+//! When nodes declare the blackboard keys they read and write, with
+//! `Node::reads` and `Node::writes`, the wiring between them is checked too:
+//! every key which is read must be written by some node, or be on the initial
+//! blackboard, as the type it is read as.
+//!
+//! # Examples
+//!
+//! ```
+//! # use aspen::std_nodes::*;
+//! # use aspen::BehaviorTree;
+//! let tree: BehaviorTree<()> = BehaviorTree::new(Selector::new(vec![
+//!     AlwaysSucceed::new(),
+//!     AlwaysFail::new().named(Some("Recover")),
+//! ]));
+//!
+//! let warnings = tree.validate();
+//! assert_eq!(warnings.len(), 1);
+//! assert_eq!(warnings[0].path(), ["Selector", "Recover"]);
+//! ```
+use crate::{
+    blackboard::{Access, Blackboard, KeyUse},
+    node::{Node, Tickable},
+    registry::short_type_name,
+};
+use std::{borrow::Cow, fmt};
+
+/// A likely mistake found in a tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    /// The names of the nodes from the root to the node the warning is about.
+    path: Vec<String>,
+
+    /// What is wrong.
+    message: Cow<'static, str>,
+}
+impl Warning {
+    /// Returns the names of the nodes from the root to the node the warning is
+    /// about.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Returns what is wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.join(" > "), self.message)
+    }
+}
+
+/// Checks the tree with the given root for likely mistakes.
+pub fn validate<W>(root: &Node<'_, W>) -> Vec<Warning> {
+    check(root, None)
+}
+
+/// Checks the tree with the given root for likely mistakes, given the
+/// blackboard it will start with.
+///
+/// Keys which are on the initial blackboard count as written, as the type of
+/// their value.
+pub fn validate_with_blackboard<W>(root: &Node<'_, W>, initial: &Blackboard) -> Vec<Warning> {
+    check(root, Some(initial))
+}
+
+/// Checks the tree with the given root, and optionally its initial
+/// blackboard.
+fn check<W>(root: &Node<'_, W>, initial: Option<&Blackboard>) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut path = Vec::new();
+    let mut keys = Vec::new();
+    visit(root, &mut path, &mut keys, &mut warnings);
+    check_keys(&keys, initial, &mut warnings);
+    warnings
+}
+
+/// Checks the subtree rooted at `node`, whose parent has the given path, and
+/// collects the keys its nodes declare.
+fn visit<W>(
+    node: &Node<'_, W>,
+    path: &mut Vec<String>,
+    keys: &mut Vec<(Vec<String>, KeyUse)>,
+    warnings: &mut Vec<Warning>,
+) {
+    path.push(node.name().to_owned());
+    for key in node.key_uses() {
+        keys.push((path.clone(), key.clone()));
+    }
+
+    let children = node.children();
+    for (index, message) in node.unreachable_children() {
+        let mut child_path = path.clone();
+        if let Some(child) = children.get(index) {
+            child_path.push(child.name().to_owned());
+        }
+        warnings.push(Warning {
+            path: child_path,
+            message,
+        });
+    }
+
+    for child in children {
+        visit(child, path, keys, warnings);
+    }
+    path.pop();
+}
+
+/// Reports keys which are read without being written as the same type, and
+/// keys which are written as different types.
+fn check_keys(
+    keys: &[(Vec<String>, KeyUse)],
+    initial: Option<&Blackboard>,
+    warnings: &mut Vec<Warning>,
+) {
+    for (index, (path, used)) in keys.iter().enumerate() {
+        let key = used.key();
+        let writers: Vec<&KeyUse> = keys
+            .iter()
+            .map(|(_, k)| k)
+            .filter(|k| k.access() == Access::Write && k.key() == key)
+            .collect();
+
+        let message = match used.access() {
+            Access::Read => {
+                let initial = initial.and_then(|b| b.type_of(key));
+                if initial == Some(used.type_id())
+                    || writers.iter().any(|w| w.type_id() == used.type_id())
+                {
+                    continue;
+                }
+                match (writers.first(), initial) {
+                    (None, None) => format!("reads blackboard key {}, which nothing writes", key),
+                    (Some(writer), _) => format!(
+                        "reads blackboard key {} as {}, but it is written as {}",
+                        key,
+                        short_type_name(used.type_name()),
+                        short_type_name(writer.type_name())
+                    ),
+                    (None, Some(_)) => format!(
+                        "reads blackboard key {} as {}, but its initial value is another type",
+                        key,
+                        short_type_name(used.type_name())
+                    ),
+                }
+            }
+            Access::Write => {
+                // Every later writer of a key is compared with the first
+                let later = keys[..index]
+                    .iter()
+                    .any(|(_, k)| k.access() == Access::Write && k.key() == key);
+                match writers.first() {
+                    Some(writer) if later && writer.type_id() != used.type_id() => format!(
+                        "writes blackboard key {} as {}, but it is also written as {}",
+                        key,
+                        short_type_name(used.type_name()),
+                        short_type_name(writer.type_name())
+                    ),
+                    _ => continue,
+                }
+            }
+        };
+        warnings.push(Warning {
+            path: path.clone(),
+            message: Cow::Owned(message),
+        });
+    }
+}
+
+/// Reports the children after the first one of the given types as
+/// unreachable.
+///
+/// Composites which stop at the first child not to return a particular status
+/// can use this with the types of the nodes which never return it.
+pub(crate) fn unreachable_after<W>(
+    children: &[Node<'_, W>],
+    blockers: &[&str],
+) -> Vec<(usize, Cow<'static, str>)> {
+    let first = match children
+        .iter()
+        .position(|c| blockers.contains(&c.type_name()))
+    {
+        Some(i) => i,
+        None => return Vec::new(),
+    };
+
+    let message = format!(
+        "unreachable, since it comes after {}",
+        children[first].name()
+    );
+    (first + 1..children.len())
+        .map(|i| (i, Cow::Owned(message.clone())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, validate_with_blackboard};
+    use crate::{
+        blackboard::Blackboard,
+        node::Tickable,
+        std_nodes::{
+            ActiveSequence, AlwaysFail, AlwaysRunning, AlwaysSucceed, Invert, Parallel, Selector,
+            Sequence,
+        },
+    };
+
+    #[test]
+    fn reports_children_after_blockers() {
+        let root = Sequence::new(vec![
+            Selector::new(vec![AlwaysFail::new(), AlwaysSucceed::new()]),
+            ActiveSequence::new()
+                .with_children(vec![AlwaysRunning::new(), AlwaysSucceed::new()])
+                .into_node(),
+            Sequence::new(vec![AlwaysFail::new(), Invert::new(AlwaysSucceed::new())]),
+        ]);
+
+        let warnings: Vec<_> = validate::<()>(&root)
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Sequence > ActiveSequence > AlwaysSucceed: unreachable, since it comes after \
+                 AlwaysRunning",
+                "Sequence > Sequence > Invert: unreachable, since it comes after AlwaysFail",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_parallel_without_threshold() {
+        let root = Parallel::new(0, vec![AlwaysSucceed::new(), AlwaysFail::new()]);
+        assert_eq!(validate::<()>(&root).len(), 2);
+
+        let root = Parallel::new(1, vec![AlwaysSucceed::new(), AlwaysFail::new()]);
+        assert!(validate::<()>(&root).is_empty());
+    }
+
+    #[test]
+    fn reports_miswired_keys() {
+        let root = Sequence::new(vec![
+            AlwaysSucceed::new()
+                .named(Some("Plan"))
+                .writes::<Vec<u32>, _>("path")
+                .reads::<u32, _>("goal"),
+            AlwaysSucceed::new()
+                .named(Some("Follow"))
+                .reads::<Vec<u32>, _>("path")
+                .reads::<f32, _>("speed")
+                .writes::<String, _>("path"),
+        ]);
+
+        let warnings: Vec<_> = validate::<()>(&root)
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Sequence > Plan: reads blackboard key goal, which nothing writes",
+                "Sequence > Follow: reads blackboard key speed, which nothing writes",
+                "Sequence > Follow: writes blackboard key path as String, but it is also written \
+                 as Vec<u32>",
+            ]
+        );
+
+        let mut initial = Blackboard::new();
+        initial.set("goal", 3u32);
+        initial.set("speed", 1.0f64);
+        let warnings = validate_with_blackboard::<()>(&root, &initial);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].message(),
+            "reads blackboard key speed as f32, but its initial value is another type"
+        );
+    }
+}
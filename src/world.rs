@@ -0,0 +1,298 @@
+//! Wrappers around the world a tree is ticked with.
+use crate::{bt::BehaviorTree, status::Status};
+use std::{
+    mem,
+    sync::{Arc, Mutex, RwLock},
+};
+
+/// A world with a back buffer which is modified by the tree and a front buffer
+/// which other threads can read.
+///
+/// The tree is ticked with the back buffer, which is then copied into a new
+/// front buffer. Readers get the front buffer as an `Arc`, so they always see
+/// the complete state of the world as of the end of a tick, and can keep
+/// reading it for as long as they like without holding up the tick thread.
+///
+/// The only lock is around the pointer to the front buffer. It is held just
+/// long enough to clone or replace the `Arc`, never while the world is being
+/// read or ticked, so readers and the tick thread do not contend with each
+/// other. Front buffers which are no longer read by anyone are reused rather
+/// than reallocated.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::world::DoubleBufferedWorld;
+/// # use aspen::{BehaviorTree, Status};
+/// # use std::thread;
+/// let mut tree = BehaviorTree::new(InlineAction::new(|n: &mut u32| {
+///     *n += 1;
+///     Status::Running
+/// }));
+/// let mut world = DoubleBufferedWorld::new(0);
+/// let front = world.front();
+///
+/// world.tick(&mut tree);
+/// world.tick(&mut tree);
+/// thread::spawn(move || assert_eq!(*front.read(), 2))
+///     .join()
+///     .unwrap();
+/// ```
+pub struct DoubleBufferedWorld<W> {
+    /// The world modified by the tree.
+    back: W,
+
+    /// The world as of the last swap.
+    front: Arc<Mutex<Arc<W>>>,
+
+    /// The previous front buffer, which is reused if nobody is reading it.
+    spare: Option<Arc<W>>,
+}
+impl<W> DoubleBufferedWorld<W>
+where
+    W: Clone,
+{
+    /// Creates a new double-buffered world, with both buffers set to `world`.
+    pub fn new(world: W) -> Self {
+        DoubleBufferedWorld {
+            front: Arc::new(Mutex::new(Arc::new(world.clone()))),
+            back: world,
+            spare: None,
+        }
+    }
+
+    /// Returns a handle to the front buffer, which can be sent to other
+    /// threads if the world can.
+    pub fn front(&self) -> FrontBuffer<W> {
+        FrontBuffer {
+            front: Arc::clone(&self.front),
+        }
+    }
+
+    /// Returns a reference to the back buffer.
+    pub fn back(&self) -> &W {
+        &self.back
+    }
+
+    /// Returns a mutable reference to the back buffer.
+    ///
+    /// Changes are not visible to readers until the next swap.
+    pub fn back_mut(&mut self) -> &mut W {
+        &mut self.back
+    }
+
+    /// Ticks `tree` with the back buffer and then swaps the buffers.
+    pub fn tick(&mut self, tree: &mut BehaviorTree<'_, W>) -> Status {
+        let status = tree.tick(&mut self.back);
+        self.swap();
+        status
+    }
+
+    /// Publishes the back buffer as the new front buffer.
+    pub fn swap(&mut self) {
+        let next = match self.spare.take() {
+            Some(mut spare) => match Arc::get_mut(&mut spare) {
+                Some(world) => {
+                    world.clone_from(&self.back);
+                    spare
+                }
+                None => Arc::new(self.back.clone()),
+            },
+            None => Arc::new(self.back.clone()),
+        };
+
+        let mut front = self.front.lock().unwrap_or_else(|e| e.into_inner());
+        self.spare = Some(mem::replace(&mut *front, next));
+    }
+
+    /// Returns the back buffer.
+    pub fn into_inner(self) -> W {
+        self.back
+    }
+}
+
+/// A handle to the front buffer of a `DoubleBufferedWorld`.
+pub struct FrontBuffer<W> {
+    /// The pointer to the current front buffer.
+    front: Arc<Mutex<Arc<W>>>,
+}
+impl<W> FrontBuffer<W> {
+    /// Returns the world as of the last swap.
+    ///
+    /// The returned buffer does not change, even if the buffers are swapped
+    /// again while it is being read.
+    pub fn read(&self) -> Arc<W> {
+        let front = self.front.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(&front)
+    }
+}
+impl<W> Clone for FrontBuffer<W> {
+    fn clone(&self) -> Self {
+        FrontBuffer {
+            front: Arc::clone(&self.front),
+        }
+    }
+}
+
+/// State which can be copied as it is at one instant.
+///
+/// This is implemented for state shared behind a lock, which worker threads
+/// may change at any time.
+pub trait Snapshot {
+    /// The copy of the state.
+    type Frozen;
+
+    /// Returns a copy of the state as it is now.
+    fn snapshot(&self) -> Self::Frozen;
+}
+impl<T: Clone> Snapshot for Arc<Mutex<T>> {
+    type Frozen = T;
+
+    fn snapshot(&self) -> T {
+        self.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+impl<T: Clone> Snapshot for Arc<RwLock<T>> {
+    type Frozen = T;
+
+    fn snapshot(&self) -> T {
+        self.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// A world which freezes a copy of shared state at the start of every tick.
+///
+/// When the tree reads state which `Action` worker threads are changing, two
+/// conditions in the same tick can see different values, so a reactive branch
+/// can flap within a single traversal. Conditions should instead read the
+/// snapshot, which is taken before each tick and does not change until the
+/// next one, while actions change the live state.
+///
+/// # Examples
+///
+/// ```
+/// # use aspen::std_nodes::*;
+/// # use aspen::world::SnapshotWorld;
+/// # use aspen::{BehaviorTree, Status};
+/// # use std::sync::{Arc, Mutex};
+/// let battery = Arc::new(Mutex::new(50u32));
+/// let mut tree = BehaviorTree::new(Sequence::new(vec![
+///     Condition::new(|w: &SnapshotWorld<Arc<Mutex<u32>>>| *w.snapshot() > 20),
+///     InlineAction::new(|w: &mut SnapshotWorld<Arc<Mutex<u32>>>| {
+///         *w.live().lock().unwrap() = 10;
+///         Status::Succeeded
+///     }),
+///     Condition::new(|w: &SnapshotWorld<Arc<Mutex<u32>>>| *w.snapshot() > 20),
+/// ]));
+/// let mut world = SnapshotWorld::new(Arc::clone(&battery));
+///
+/// assert_eq!(world.tick(&mut tree), Status::Succeeded);
+/// assert_eq!(world.tick(&mut tree), Status::Failed);
+/// ```
+pub struct SnapshotWorld<S: Snapshot> {
+    /// The live state.
+    live: S,
+
+    /// The state as of the start of the latest tick.
+    snapshot: S::Frozen,
+}
+impl<S: Snapshot> SnapshotWorld<S> {
+    /// Creates a new world over the given state, taking a first snapshot.
+    pub fn new(live: S) -> Self {
+        SnapshotWorld {
+            snapshot: live.snapshot(),
+            live,
+        }
+    }
+
+    /// Returns the state as of the start of the latest tick.
+    pub fn snapshot(&self) -> &S::Frozen {
+        &self.snapshot
+    }
+
+    /// Returns the live state.
+    pub fn live(&self) -> &S {
+        &self.live
+    }
+
+    /// Takes a new snapshot of the live state.
+    pub fn refresh(&mut self) {
+        self.snapshot = self.live.snapshot();
+    }
+
+    /// Takes a new snapshot and then ticks `tree` once.
+    pub fn tick(&mut self, tree: &mut BehaviorTree<'_, Self>) -> Status {
+        self.refresh();
+        tree.tick(self)
+    }
+
+    /// Returns the live state.
+    pub fn into_inner(self) -> S {
+        self.live
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DoubleBufferedWorld, SnapshotWorld};
+    use crate::{
+        std_nodes::{Condition, InlineAction, Sequence},
+        BehaviorTree, Status,
+    };
+    use std::{
+        sync::{Arc, RwLock},
+        thread,
+    };
+
+    #[test]
+    fn readers_see_only_swapped_state() {
+        let mut world = DoubleBufferedWorld::new(vec![1]);
+        let front = world.front();
+
+        world.back_mut().push(2);
+        assert_eq!(*front.read(), vec![1]);
+
+        let old = front.read();
+        world.swap();
+        assert_eq!(*front.read(), vec![1, 2]);
+        assert_eq!(*old, vec![1]);
+    }
+
+    #[test]
+    fn unread_buffers_are_reused() {
+        let mut world = DoubleBufferedWorld::new(0u32);
+        let front = world.front();
+        world.swap();
+        let first = Arc::as_ptr(&front.read());
+
+        *world.back_mut() = 1;
+        world.swap();
+        *world.back_mut() = 2;
+        world.swap();
+        assert_eq!(Arc::as_ptr(&front.read()), first);
+        assert_eq!(*front.read(), 2);
+    }
+
+    #[test]
+    fn snapshot_is_fixed_for_a_tick() {
+        type World = SnapshotWorld<Arc<RwLock<u32>>>;
+        let shared = Arc::new(RwLock::new(0));
+        let mut tree = BehaviorTree::new(Sequence::new(vec![
+            Condition::new(|w: &World| *w.snapshot() == 0),
+            InlineAction::new(|w: &mut World| {
+                let live = Arc::clone(w.live());
+                thread::spawn(move || *live.write().unwrap() += 1)
+                    .join()
+                    .unwrap();
+                Status::Succeeded
+            }),
+            Condition::new(|w: &World| *w.snapshot() == 0),
+        ]));
+        let mut world = SnapshotWorld::new(Arc::clone(&shared));
+
+        assert_eq!(world.tick(&mut tree), Status::Succeeded);
+        assert_eq!(*shared.read().unwrap(), 1);
+        assert_eq!(world.tick(&mut tree), Status::Failed);
+    }
+}
@@ -0,0 +1,162 @@
+// Records the traces in traces.txt from BehaviorTree.CPP 4.x.
+//
+// Every case builds a tree of scripted leaves with BT.CPP, ticks its root a
+// fixed number of times and writes down which leaves were ticked and halted on
+// each tick, in the format read by the tests of `std_nodes::compat`. With
+// BT.CPP 4.x installed, the traces are recorded with:
+//
+//     g++ -std=c++17 record.cpp -lbehaviortree_cpp -o record
+//     ./record > traces.txt
+//
+// The trees here must be kept the same as those built by the tests.
+#include <behaviortree_cpp/bt_factory.h>
+
+#include <iostream>
+#include <string>
+#include <vector>
+
+namespace
+{
+// The leaf ticks and halts of the current tick.
+std::vector<std::string> events;
+
+// A leaf which returns the statuses in its `script` port in a loop, where `S`
+// is success, `F` is failure and anything else is running.
+class Scripted : public BT::ActionNodeBase
+{
+public:
+  Scripted(const std::string& name, const BT::NodeConfig& config)
+    : BT::ActionNodeBase(name, config)
+  {
+  }
+
+  static BT::PortsList providedPorts()
+  {
+    return { BT::InputPort<std::string>("script") };
+  }
+
+  BT::NodeStatus tick() override
+  {
+    if (script_.empty())
+    {
+      script_ = getInput<std::string>("script").value();
+    }
+    const char c = script_[next_++ % script_.size()];
+    events.push_back(name() + c);
+    switch (c)
+    {
+      case 'S':
+        return BT::NodeStatus::SUCCESS;
+      case 'F':
+        return BT::NodeStatus::FAILURE;
+      default:
+        return BT::NodeStatus::RUNNING;
+    }
+  }
+
+  void halt() override
+  {
+    if (status() == BT::NodeStatus::RUNNING)
+    {
+      events.push_back("halt " + name());
+    }
+  }
+
+private:
+  std::string script_;
+  size_t next_ = 0;
+};
+
+const char* const trees = R"(
+<root BTCPP_format="4">
+  <BehaviorTree ID="sequence_restarts_after_failure">
+    <Sequence>
+      <Scripted name="A" script="S"/>
+      <Scripted name="B" script="RF"/>
+    </Sequence>
+  </BehaviorTree>
+  <BehaviorTree ID="sequence_with_memory_resumes_at_failed_child">
+    <SequenceWithMemory>
+      <Scripted name="A" script="S"/>
+      <Scripted name="B" script="FS"/>
+    </SequenceWithMemory>
+  </BehaviorTree>
+  <BehaviorTree ID="reactive_sequence_halts_running_child_on_failure">
+    <ReactiveSequence>
+      <Scripted name="A" script="SSF"/>
+      <Scripted name="B" script="R"/>
+    </ReactiveSequence>
+  </BehaviorTree>
+  <BehaviorTree ID="fallback_resumes_running_child">
+    <Fallback>
+      <Scripted name="A" script="F"/>
+      <Scripted name="B" script="RS"/>
+    </Fallback>
+  </BehaviorTree>
+  <BehaviorTree ID="reactive_fallback_rechecks_earlier_children">
+    <ReactiveFallback>
+      <Scripted name="A" script="FS"/>
+      <Scripted name="B" script="R"/>
+    </ReactiveFallback>
+  </BehaviorTree>
+  <BehaviorTree ID="retry_stops_while_running">
+    <RetryUntilSuccessful num_attempts="3">
+      <Scripted name="A" script="FFR"/>
+    </RetryUntilSuccessful>
+  </BehaviorTree>
+  <BehaviorTree ID="retry_gives_up">
+    <RetryUntilSuccessful num_attempts="2">
+      <Scripted name="B" script="F"/>
+    </RetryUntilSuccessful>
+  </BehaviorTree>
+  <BehaviorTree ID="repeat_loops_within_a_tick">
+    <Repeat num_cycles="3">
+      <Scripted name="C" script="SSRS"/>
+    </Repeat>
+  </BehaviorTree>
+</root>
+)";
+
+// The trees to record, and how many times to tick each of them.
+const std::vector<std::pair<std::string, int>> cases = {
+  { "sequence_restarts_after_failure", 3 },
+  { "sequence_with_memory_resumes_at_failed_child", 3 },
+  { "reactive_sequence_halts_running_child_on_failure", 3 },
+  { "fallback_resumes_running_child", 3 },
+  { "reactive_fallback_rechecks_earlier_children", 2 },
+  { "retry_stops_while_running", 1 },
+  { "retry_gives_up", 1 },
+  { "repeat_loops_within_a_tick", 2 },
+};
+}  // namespace
+
+int main()
+{
+  BT::BehaviorTreeFactory factory;
+  factory.registerNodeType<Scripted>("Scripted");
+  factory.registerBehaviorTreeFromText(trees);
+
+  std::cout << "# Leaf ticks and halts for the trees in the tests of `std_nodes::compat`, in\n"
+               "# the format written by record.cpp.\n"
+               "#\n"
+               "# Each case starts with its name in brackets. Every line after it is one tick\n"
+               "# of the root of the tree: the leaves ticked, each followed by the status it\n"
+               "# returned, and the leaves halted, in order, then the status of the root.\n";
+
+  for (const auto& [id, ticks] : cases)
+  {
+    auto tree = factory.createTree(id);
+    std::cout << "\n[" << id << "]\n";
+    for (int i = 0; i < ticks; i++)
+    {
+      events.clear();
+      const BT::NodeStatus status = tree.tickOnce();
+      for (size_t e = 0; e < events.size(); e++)
+      {
+        std::cout << (e == 0 ? "" : " ") << events[e];
+      }
+      std::cout << " -> " << BT::toStr(status) << "\n";
+    }
+  }
+  return 0;
+}